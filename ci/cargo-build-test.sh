@@ -14,7 +14,7 @@ export RUSTBACKTRACE=1
 set -x
 
 # Build/test all host crates
-cargo +"$rust_stable" build --release
-cargo +"$rust_stable" test -- --nocapture
+cargo +"$rust_stable" build --release --features solana-geyser-plugin-scaffold/all-sinks
+cargo +"$rust_stable" test --features solana-geyser-plugin-scaffold/all-sinks -- --nocapture
 
 exit 0
@@ -68,7 +68,7 @@ mkdir -p "$installDir/lib"
 (
   set -x
   # shellcheck disable=SC2086 # Don't want to double quote $rust_version
-  cargo $maybeRustVersion build $maybeReleaseFlag --lib
+  cargo $maybeRustVersion build $maybeReleaseFlag --lib --features solana-geyser-plugin-scaffold/all-sinks
 )
 
 cp -fv "target/$buildVariant/libsolana_geyser_plugin_scaffold.$libExt" "$installDir"/lib/
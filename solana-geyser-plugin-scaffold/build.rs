@@ -0,0 +1,25 @@
+use std::{path::Path, process::Command};
+
+fn main() {
+    // builds outside of a git checkout (e.g. docker) can pass the commit explicitly
+    println!("cargo:rerun-if-env-changed=GIT_COMMIT");
+    for path in ["../.git/HEAD", "../.git/refs"] {
+        if Path::new(path).exists() {
+            println!("cargo:rerun-if-changed={}", path);
+        }
+    }
+
+    let commit = std::env::var("GIT_COMMIT")
+        .ok()
+        .or_else(|| {
+            Command::new("git")
+                .args(["rev-parse", "--short", "HEAD"])
+                .output()
+                .ok()
+                .filter(|output| output.status.success())
+                .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+
+    println!("cargo:rustc-env=GIT_COMMIT={}", commit);
+}
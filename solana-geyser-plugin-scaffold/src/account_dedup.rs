@@ -0,0 +1,91 @@
+use lru::LruCache;
+use solana_sdk::pubkey::Pubkey;
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    num::NonZeroUsize,
+    sync::Mutex,
+};
+
+pub const DEFAULT_DEDUP_CAPACITY: usize = 1_000_000;
+
+/// Spread the accounts over several locks, the validator notifies from many threads
+const SHARDS: usize = 16;
+
+/// Remembers a hash of the content last published per account, to leave out the updates
+/// rewriting an account with the same content. Up to `capacity` accounts are remembered, the
+/// least recently updated ones are forgotten first and their next update is published.
+pub struct AccountDedup {
+    shards: Vec<Mutex<LruCache<Pubkey, u64>>>,
+}
+
+impl AccountDedup {
+    pub fn new(capacity: usize) -> Self {
+        let per_shard = NonZeroUsize::new(capacity.div_ceil(SHARDS).max(1)).unwrap();
+        AccountDedup {
+            shards: (0..SHARDS)
+                .map(|_| Mutex::new(LruCache::new(per_shard)))
+                .collect(),
+        }
+    }
+
+    /// Whether the account holds the content last seen for it, recording it otherwise.
+    pub fn is_unchanged(
+        &self,
+        pubkey: &Pubkey,
+        lamports: u64,
+        owner: &Pubkey,
+        executable: bool,
+        data: &[u8],
+    ) -> bool {
+        let mut hasher = DefaultHasher::new();
+        lamports.hash(&mut hasher);
+        owner.hash(&mut hasher);
+        executable.hash(&mut hasher);
+        data.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let shard = &self.shards[pubkey.as_ref()[0] as usize % SHARDS];
+        let mut hashes = shard.lock().unwrap_or_else(|e| e.into_inner());
+        hashes.put(*pubkey, hash) == Some(hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_unchanged() {
+        let dedup = AccountDedup::new(100);
+        let (pubkey, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        assert!(!dedup.is_unchanged(&pubkey, 1, &owner, false, b"data"));
+        assert!(dedup.is_unchanged(&pubkey, 1, &owner, false, b"data"));
+
+        // any of the fields changing
+        assert!(!dedup.is_unchanged(&pubkey, 2, &owner, false, b"data"));
+        assert!(!dedup.is_unchanged(&pubkey, 2, &owner, false, b"other"));
+        assert!(!dedup.is_unchanged(&pubkey, 2, &Pubkey::new_unique(), false, b"other"));
+        assert!(!dedup.is_unchanged(&pubkey, 2, &owner, true, b"other"));
+        assert!(dedup.is_unchanged(&pubkey, 2, &owner, true, b"other"));
+
+        // accounts are apart
+        assert!(!dedup.is_unchanged(&Pubkey::new_unique(), 2, &owner, true, b"other"));
+    }
+
+    #[test]
+    fn test_least_recently_updated_are_forgotten() {
+        // a single account per shard
+        let dedup = AccountDedup::new(SHARDS);
+        let owner = Pubkey::new_unique();
+        let first = Pubkey::new_from_array([0; 32]);
+        let second = Pubkey::new_from_array([SHARDS as u8; 32]);
+
+        assert!(!dedup.is_unchanged(&first, 1, &owner, false, &[]));
+        assert!(!dedup.is_unchanged(&second, 1, &owner, false, &[]));
+        // evicted by the second account of its shard, published again
+        assert!(!dedup.is_unchanged(&first, 1, &owner, false, &[]));
+        assert!(dedup.is_unchanged(&first, 1, &owner, false, &[]));
+    }
+}
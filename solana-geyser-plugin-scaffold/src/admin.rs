@@ -0,0 +1,97 @@
+//! HTTP API for operating the plugin while the validator runs.
+use crate::geyser_plugin_hook::Inner;
+use crate::sinks::{server::spawn_server, SinkConfig};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{net::SocketAddr, sync::Arc};
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AdminConfig {
+    // e.g. "127.0.0.1:8990", the API is not authenticated so keep it off public interfaces
+    pub addr: String,
+}
+
+#[derive(Deserialize)]
+struct RebindRequest {
+    addr: SocketAddr,
+    // moves the vote listener instead of the main one
+    #[serde(default)]
+    votes: bool,
+}
+
+#[derive(Deserialize)]
+struct RemoveSinkRequest {
+    name: String,
+}
+
+/// Serves:
+/// - `GET /status`
+/// - `POST /listener`, moving the TCP listener to another address, e.g.
+///   `{"addr": "0.0.0.0:9001"}`, without disconnecting subscribers
+/// - `POST /sinks`, starting a sink from its config, e.g.
+///   `{"type": "parquet", "dir": "/tmp/capture"}`
+/// - `DELETE /sinks`, closing a sink by the name listed in the status, e.g.
+///   `{"name": "parquet /tmp/capture"}`
+pub fn spawn(config: &AdminConfig, plugin: Arc<Inner>) -> anyhow::Result<()> {
+    let router = Router::new()
+        .route("/status", get(status))
+        .route("/listener", post(rebind))
+        .route("/sinks", post(add_sink).delete(remove_sink))
+        .with_state(plugin);
+
+    spawn_server("admin", &config.addr, router, std::future::pending())?;
+
+    Ok(())
+}
+
+async fn status(State(plugin): State<Arc<Inner>>) -> Json<Value> {
+    Json(plugin.status())
+}
+
+async fn rebind(
+    State(plugin): State<Arc<Inner>>,
+    Json(request): Json<RebindRequest>,
+) -> Result<Json<Value>, (StatusCode, String)> {
+    // waking up the old acceptors connects to them, keep it off the runtime threads
+    let rebound =
+        tokio::task::spawn_blocking(move || plugin.rebind_listener(request.addr, request.votes))
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    match rebound {
+        Ok(addr) => Ok(Json(json!({ "addr": addr }))),
+        Err(e) => Err((StatusCode::BAD_REQUEST, e.to_string())),
+    }
+}
+
+async fn add_sink(
+    State(plugin): State<Arc<Inner>>,
+    Json(config): Json<SinkConfig>,
+) -> Result<Json<Value>, (StatusCode, String)> {
+    // sinks connect to their destination or bind their address when built
+    let added = tokio::task::spawn_blocking(move || plugin.add_sink(&config))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    match added {
+        Ok(name) => Ok(Json(json!({ "name": name }))),
+        Err(e) => Err((StatusCode::BAD_REQUEST, e.to_string())),
+    }
+}
+
+async fn remove_sink(
+    State(plugin): State<Arc<Inner>>,
+    Json(request): Json<RemoveSinkRequest>,
+) -> Result<Json<Value>, (StatusCode, String)> {
+    if plugin.remove_sink(&request.name) {
+        Ok(Json(json!({ "name": request.name })))
+    } else {
+        Err((StatusCode::NOT_FOUND, format!("no sink {}", request.name)))
+    }
+}
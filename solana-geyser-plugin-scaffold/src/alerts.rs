@@ -0,0 +1,200 @@
+use crate::metrics::Metrics;
+use log::{info, warn};
+use reqwest::blocking::Client;
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    collections::VecDeque,
+    sync::{atomic::Ordering, Arc},
+    thread,
+    time::{Duration, Instant},
+};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+const DEFAULT_WINDOW_SECS: u64 = 60;
+const DEFAULT_SOURCE: &str = "solana-geyser-plugin";
+const PAGERDUTY_EVENTS_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertMetric {
+    SendErrs,
+    DisconnectErrs,
+    SerializeErrs,
+    UntypedErrs,
+    /// Slots between the last processed and the last rooted slot
+    SlotLag,
+}
+
+impl AlertMetric {
+    fn is_counter(&self) -> bool {
+        *self != AlertMetric::SlotLag
+    }
+
+    fn value(&self, metrics: &Metrics) -> u64 {
+        match self {
+            AlertMetric::SendErrs => metrics.send_errs.load(Ordering::Relaxed),
+            AlertMetric::DisconnectErrs => metrics.disconnect_errs.load(Ordering::Relaxed),
+            AlertMetric::SerializeErrs => metrics.serialize_errs.load(Ordering::Relaxed),
+            AlertMetric::UntypedErrs => metrics.untyped_errs.load(Ordering::Relaxed),
+            AlertMetric::SlotLag => metrics
+                .processed_slot
+                .load(Ordering::Relaxed)
+                .saturating_sub(metrics.rooted_slot.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AlertRule {
+    pub metric: AlertMetric,
+    // fires when a counter increased by more than this over the window, or when slot_lag is
+    // above it
+    pub threshold: u64,
+    // 60 by default, unused by slot_lag
+    pub window_secs: Option<u64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertTarget {
+    /// Slack incoming webhook, or any other accepting `{"text": ...}`
+    Slack { url: String },
+    /// PagerDuty Events API v2, alerts are resolved once back under the threshold
+    Pagerduty {
+        routing_key: String,
+        url: Option<String>,
+    },
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AlertsConfig {
+    pub targets: Vec<AlertTarget>,
+    pub rules: Vec<AlertRule>,
+    // reported as the alert source, "solana-geyser-plugin" by default
+    pub source: Option<String>,
+}
+
+struct RuleState {
+    rule: AlertRule,
+    // counter values over the window, oldest first
+    samples: VecDeque<(Instant, u64)>,
+    firing: bool,
+}
+
+impl RuleState {
+    /// Value compared to the threshold: the increase over the window for counters.
+    fn observe(&mut self, metrics: &Metrics, now: Instant) -> u64 {
+        let value = self.rule.metric.value(metrics);
+        if !self.rule.metric.is_counter() {
+            return value;
+        }
+
+        let window = Duration::from_secs(self.rule.window_secs.unwrap_or(DEFAULT_WINDOW_SECS));
+        self.samples.push_back((now, value));
+        while self
+            .samples
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) > window)
+        {
+            self.samples.pop_front();
+        }
+
+        self.samples
+            .front()
+            .map_or(0, |(_, oldest)| value.saturating_sub(*oldest))
+    }
+}
+
+/// Checks the alert rules against the metrics every 10s, notifying the targets when a rule
+/// starts and stops firing.
+pub struct Alerter {
+    config: AlertsConfig,
+    client: Client,
+}
+
+impl Alerter {
+    pub fn new(config: AlertsConfig) -> Self {
+        Alerter {
+            config,
+            client: Client::new(),
+        }
+    }
+
+    pub fn spawn(self, metrics: Arc<Metrics>) {
+        thread::spawn(move || {
+            let mut states = self
+                .config
+                .rules
+                .iter()
+                .map(|rule| RuleState {
+                    rule: rule.clone(),
+                    samples: VecDeque::new(),
+                    firing: false,
+                })
+                .collect::<Vec<_>>();
+
+            loop {
+                let now = Instant::now();
+                for state in &mut states {
+                    let value = state.observe(&metrics, now);
+                    let firing = value > state.rule.threshold;
+                    if firing != state.firing {
+                        state.firing = firing;
+                        self.notify(&state.rule, value, firing);
+                    }
+                }
+
+                thread::sleep(CHECK_INTERVAL);
+            }
+        });
+    }
+
+    fn notify(&self, rule: &AlertRule, value: u64, firing: bool) {
+        let source = self.config.source.as_deref().unwrap_or(DEFAULT_SOURCE);
+        let summary = match (firing, rule.metric.is_counter()) {
+            (true, true) => format!(
+                "{}: {:?} increased by {} in {}s, above {}",
+                source,
+                rule.metric,
+                value,
+                rule.window_secs.unwrap_or(DEFAULT_WINDOW_SECS),
+                rule.threshold
+            ),
+            (true, false) => format!(
+                "{}: {:?} is {}, above {}",
+                source, rule.metric, value, rule.threshold
+            ),
+            (false, _) => format!(
+                "{}: {:?} is back under {}",
+                source, rule.metric, rule.threshold
+            ),
+        };
+        info!("[alerts] - {}", summary);
+
+        for target in &self.config.targets {
+            let request = match target {
+                AlertTarget::Slack { url } => self.client.post(url).json(&json!({
+                    "text": summary,
+                })),
+                AlertTarget::Pagerduty { routing_key, url } => self
+                    .client
+                    .post(url.as_deref().unwrap_or(PAGERDUTY_EVENTS_URL))
+                    .json(&json!({
+                        "routing_key": routing_key,
+                        "event_action": if firing { "trigger" } else { "resolve" },
+                        "dedup_key": format!("{}-{:?}", source, rule.metric),
+                        "payload": {
+                            "summary": summary,
+                            "source": source,
+                            "severity": "error",
+                        },
+                    })),
+            };
+
+            if let Err(e) = request.send().and_then(|r| r.error_for_status()) {
+                warn!("[alerts] - failed to notify: {}", e);
+            }
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use log::warn;
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_rpc_client_api::config::RpcBlockConfig;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_transaction_status::{TransactionDetails, UiConfirmedBlock, UiTransactionEncoding};
+use std::{
+    collections::BTreeMap,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Mutex,
+    },
+};
+
+/// Detects the slots which had transactions but no block metadata notified by the time they
+/// are rooted, and fetches their block from an RPC node so the block stream has no holes.
+pub struct BlockBackfill {
+    // slot -> whether the block metadata was notified
+    slots: Mutex<BTreeMap<u64, bool>>,
+    missing: Sender<u64>,
+}
+
+impl BlockBackfill {
+    /// The receiver gets the slots to fetch, see `BlockFetcher`.
+    pub fn new() -> (Self, Receiver<u64>) {
+        let (missing, receiver) = mpsc::channel();
+
+        let backfill = BlockBackfill {
+            slots: Mutex::new(BTreeMap::new()),
+            missing,
+        };
+
+        (backfill, receiver)
+    }
+
+    pub fn on_transaction(&self, slot: u64) {
+        let mut slots = self.slots.lock().unwrap_or_else(|e| e.into_inner());
+        slots.entry(slot).or_insert(false);
+    }
+
+    pub fn on_block(&self, slot: u64) {
+        let mut slots = self.slots.lock().unwrap_or_else(|e| e.into_inner());
+        slots.insert(slot, true);
+    }
+
+    /// Queues the slot for fetching if its block metadata is missing. Older slots are
+    /// forgotten, they were either rooted already or on a fork.
+    pub fn on_rooted(&self, slot: u64) {
+        let mut slots = self.slots.lock().unwrap_or_else(|e| e.into_inner());
+        let newer = slots.split_off(&(slot + 1));
+        let missing = slots.get(&slot) == Some(&false);
+        *slots = newer;
+
+        if missing {
+            let _ = self.missing.send(slot);
+        }
+    }
+}
+
+pub struct BlockFetcher {
+    client: RpcClient,
+}
+
+impl BlockFetcher {
+    pub fn new(rpc_url: String) -> Self {
+        BlockFetcher {
+            client: RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed()),
+        }
+    }
+
+    /// The block with its signatures and rewards, None if the node couldn't provide it.
+    pub fn fetch(&self, slot: u64) -> Option<UiConfirmedBlock> {
+        let config = RpcBlockConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            transaction_details: Some(TransactionDetails::Signatures),
+            rewards: Some(true),
+            commitment: Some(CommitmentConfig::confirmed()),
+            max_supported_transaction_version: Some(0),
+        };
+
+        match self.client.get_block_with_config(slot, config) {
+            Ok(block) => Some(block),
+            Err(e) => {
+                warn!("[block_backfill] - failed to fetch block {}: {}", slot, e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_on_rooted() {
+        let (backfill, missing) = BlockBackfill::new();
+
+        // transactions, then the block
+        backfill.on_transaction(10);
+        backfill.on_block(10);
+        // transactions without block
+        backfill.on_transaction(11);
+        backfill.on_transaction(11);
+        // the block before the transactions
+        backfill.on_block(12);
+        backfill.on_transaction(12);
+        // on a fork, never rooted
+        backfill.on_transaction(13);
+        backfill.on_transaction(15);
+
+        backfill.on_rooted(10);
+        backfill.on_rooted(11);
+        backfill.on_rooted(12);
+        // nothing notified for it
+        backfill.on_rooted(14);
+        backfill.on_rooted(15);
+
+        assert_eq!(missing.try_iter().collect::<Vec<_>>(), [11, 15]);
+    }
+}
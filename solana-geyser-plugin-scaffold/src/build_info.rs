@@ -0,0 +1,5 @@
+/// Version of the plugin crate
+pub const PLUGIN_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Git commit the plugin was built from, set by build.rs
+pub const GIT_COMMIT: &str = env!("GIT_COMMIT");
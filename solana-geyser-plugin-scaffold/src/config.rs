@@ -1,26 +1,320 @@
+use crate::{
+    admin::AdminConfig, alerts::AlertsConfig, pushgateway::PushgatewayConfig, sinks::SinkConfig,
+};
 use serde::Deserialize;
+use std::{collections::HashMap, net::IpAddr};
+use utils::{
+    compression::CompressionPolicy,
+    dead_letter::DeadLetterConfig,
+    filters::FilterState,
+    ip_filter::IpFilter,
+    message_type::MessageType,
+    quota::SubscriberQuota,
+    sender::{AddressFamily, GroupBalancing},
+    wal::WalConfig,
+};
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Transport {
+    // the framed TCP protocol, with handshake, filters and batching
+    #[default]
+    Tcp,
+    // a ZeroMQ PUB socket, one message per frame, its first byte being the topic (with 0x80
+    // set when compressed)
+    Zmq,
+    // a WebSocket server, one message per binary frame
+    Ws,
+    // a NATS publisher, one message per subject named after its type
+    Nats,
+    // a QUIC server on the UDP port, a stream per message type, requires tls
+    Quic,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TlsConfig {
+    // PEM certificate chain and private key of the plugin
+    pub cert_path: String,
+    pub key_path: String,
+    // if set, subscribers have to present a certificate signed by one of these PEM CA
+    // certificates, and are identified by its common name
+    pub client_ca_path: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct EndpointConfig {
+    // the port to listen on, or the unix socket path replacing it
+    pub port: Option<u16>,
+    pub uds_path: Option<String>,
+    // the main transport by default
+    pub transport: Option<Transport>,
+    // message types published on the endpoint, every one by default; messages without a
+    // type, like handshakes and diagnostics, are published on every endpoint
+    pub message_types: Option<Vec<MessageType>>,
+}
 
 #[derive(Deserialize)]
 pub struct Config {
     pub tcp_port: u16,
+    // address the listeners (tcp_port, vote_tcp_port) are bound on, every interface by
+    // default, e.g. "127.0.0.1" to keep the stream off a validator's public interface
+    pub tcp_bind_addr: Option<IpAddr>,
+    // "ipv4" by default, "ipv6" or "dual_stack" to bind on every IPv6 interface instead, the
+    // latter accepting IPv4 connections too; tcp_bind_addr can be an IPv6 address as well
+    pub tcp_address_family: Option<AddressFamily>,
+
+    // listeners served besides the main one (tcp_port or uds_path), which keeps getting every
+    // message, configured like it with tcp_* and sending only the given message types, e.g.
+    // [{"port": 2001, "message_types": ["account"]},
+    //  {"port": 2002, "message_types": ["transaction", "block"]},
+    //  {"uds_path": "/run/geyser/slots.sock", "message_types": ["slot"]}]
+    pub endpoints: Option<Vec<EndpointConfig>>,
+
+    // if set, accounts and transactions are also partitioned across listeners on these ports,
+    // with the main transport, by pubkey and signature (see utils::sender::shard_of), so
+    // each consumer of a pool gets a deterministic shard; other messages go to every shard
+    pub tcp_shard_ports: Option<Vec<u16>>,
     pub tcp_buffer_size: usize,
     pub tcp_batch_max_bytes: usize,
 
+    // message types batched apart from the rest of the TCP stream, with their own
+    // tcp_batch_max_bytes, e.g. {"slot": 0} so slot statuses are sent right away instead of
+    // waiting for account updates to fill a batch; topics may be delivered out of order
+    pub tcp_topics: Option<HashMap<MessageType, usize>>,
+
+    // "tcp" by default, with "zmq" a PUB socket is bound on tcp_port (and vote_tcp_port)
+    // instead, and with "ws" a WebSocket server, queuing up to tcp_buffer_size messages per
+    // subscriber; the other tcp_* options don't apply to them
+    // "quic" serves QUIC on the same port over UDP, for subscribers on lossy links, with the
+    // certificate of tls
+    pub transport: Option<Transport>,
+
+    // with the "nats" transport, the server to publish to, e.g. "nats://127.0.0.1:4222"
+    // messages go to <nats_subject_prefix>.<type>, e.g. geyser.account, and the vote stream
+    // (enabled by vote_tcp_port) to <nats_subject_prefix>.vote.<type>
+    pub nats_url: Option<String>,
+    // "geyser" by default
+    pub nats_subject_prefix: Option<String>,
+    // if set, the subjects are persisted in this JetStream stream, created if missing, so
+    // consumers can replay what they missed
+    pub nats_stream: Option<String>,
+
+    // if set, the main stream is served on this unix socket path instead of tcp_port, for
+    // consumers on the same host (with zmq, as an ipc:// endpoint, ignored by ws)
+    pub uds_path: Option<String>,
+
+    // if set, the TCP stream (and the vote one) is served over TLS, e.g.
+    // {"cert_path": "/etc/geyser/cert.pem", "key_path": "/etc/geyser/key.pem"}
+    pub tls: Option<TlsConfig>,
+
+    // if set, the messages of the TCP stream's batches are encrypted with ChaCha20-Poly1305
+    // and this 32 bytes key, written as hex, shared with the subscribers, for deployments
+    // which can't terminate TLS
+    pub tcp_encryption_key: Option<String>,
+
+    // if set, new connections are closed right away while this many subscribers are connected
+    // or handshaking, protecting the validator from connection floods
+    pub tcp_max_connections: Option<usize>,
+
+    // if set, TCP connections are only accepted from the client IP ranges it allows and
+    // doesn't deny, e.g. {"allow": ["10.0.0.0/8", "::1/128"], "deny": ["10.1.0.0/16"]};
+    // an empty allow list allows any address
+    pub tcp_ip_filter: Option<IpFilter>,
+
+    // number of threads accepting new subscribers, 1 by default
+    pub tcp_acceptor_threads: Option<usize>,
+
+    // threads of the runtime writing batches to subscribers, 4 by default
+    // subscribers get a task each on the runtime instead of a thread
+    pub tcp_writer_threads: Option<usize>,
+
+    // threads encoding the batches of subscribers with different filters, framing or
+    // compression, 1 by default, i.e. on the geyser callback's thread
+    pub tcp_fanout_threads: Option<usize>,
+
+    // how long new subscribers are given to send their filters before getting every
+    // message, 100ms by default
+    pub tcp_handshake_timeout_ms: Option<u64>,
+
+    // bandwidth quotas by subscriber name, as sent by the subscriber on connect, e.g.
+    // {"indexer": {"bytes_per_sec": 10485760, "messages_per_sec": 50000,
+    //  "action": "drop_accounts"}}
+    // action is one of throttle, drop_accounts or disconnect
+    pub tcp_subscriber_quotas: Option<HashMap<String, SubscriberQuota>>,
+    // quota of the subscribers not in tcp_subscriber_quotas, unnamed ones included
+    pub tcp_default_quota: Option<SubscriberQuota>,
+
+    // if set, messages are compressed with zstd according to the policy, e.g.
+    // {"level": 3, "message_types": ["account", "transaction", "block"], "min_bytes": 512}
+    // small frames like slots are better left uncompressed
+    pub compression: Option<CompressionPolicy>,
+
+    // how long the buffered messages and the subscribers' queues are given to be flushed when
+    // the plugin is unloaded, 5000ms by default
+    pub tcp_drain_timeout_ms: Option<u64>,
+
     // if set to true, messages will not be dropped when tcp_buffer_size is full
     // instead the application will reattempt to send until the buffer has enough space
     // NOTE: not to be used in production, but can be helpful for snapshot publishing
     pub tcp_strict_delivery: Option<bool>,
 
+    // with tcp_strict_delivery, how long a batch is retried before being given up on, written
+    // to tcp_dead_letters if set and counted as abandoned_batches; retried forever if not set
+    pub tcp_strict_delivery_timeout_ms: Option<u64>,
+
     // if set to positive number, the execution will hang until enough subscribers are available
     // NOTE: not to be used in production, but can be helpful for snapshot publishing
     pub tcp_min_subscribers: Option<usize>,
 
+    // subscribers the messages of these types wait for, on top of tcp_min_subscribers, e.g.
+    // {"account": 1} to publish a snapshot's accounts only once its consumer is connected
+    // while slots and transactions keep flowing with none
+    pub tcp_min_subscribers_by_type: Option<HashMap<MessageType, usize>>,
+
+    // if set, subscribers whose tcp_buffer_size is full with nothing written to them for this
+    // long are disconnected, e.g. clients gone without closing their connection
+    pub tcp_stall_timeout_ms: Option<u64>,
+
+    // subscribers a write to doesn't complete within this long are disconnected, e.g. peers
+    // which stopped reading but keep the connection open, 10000 by default
+    pub tcp_write_timeout_ms: Option<u64>,
+
+    // if set, a watchdog disconnects the subscribers with batches queued and none written to
+    // them for this long, e.g. held back by a client which stopped acking them
+    pub tcp_writer_watchdog_ms: Option<u64>,
+
+    // if set, messages waiting longer than this to be written, e.g. during a stall or while
+    // strict delivery retries, are dropped and counted instead of delivered late
+    pub tcp_message_ttl_ms: Option<u64>,
+
+    // batches a subscriber which acknowledges them (acks in its client hello) can have
+    // unacknowledged, 16 by default; the next ones wait on the sender for acks
+    pub tcp_ack_window: Option<u64>,
+
+    // how the subscriber of a consumer group (group in its client hello) each batch goes to
+    // is chosen, "least_loaded" (default) or "round_robin"
+    pub tcp_group_balancing: Option<GroupBalancing>,
+
+    // if set, messages larger than this many bytes are sent as chunks of at most this size,
+    // put back together by TcpReceiver, for consumers which can't take larger batches
+    pub tcp_max_message_bytes: Option<usize>,
+
+    // if set, every batch of the TCP stream is appended to rotating segment files before
+    // being sent, for consumers down longer than tcp_buffer_size covers and offline replay, e.g.
+    // {"dir": "/data/wal", "segment_bytes": 268435456, "max_bytes": 10737418240,
+    //  "max_age_secs": 86400}
+    // segments are read with utils::wal::read_segment
+    pub tcp_wal: Option<WalConfig>,
+
+    // if set, batches of the TCP stream no subscriber could be sent, with none connected or
+    // all of them failing, are written to this directory for reprocessing, a file per batch
+    // named after its slots, e.g. {"dir": "/data/dead-letters", "max_bytes": 1073741824}
+    // files are read with utils::wal::read_segment, listed with utils::dead_letter::dead_letters
+    pub tcp_dead_letters: Option<DeadLetterConfig>,
+
     pub send_transactions: bool,
     pub send_accounts: bool,
     pub send_blocks: bool,
 
+    // if set, blocks which had transactions but whose metadata was not notified by the time
+    // the slot is rooted are fetched from this RPC endpoint and published like the others
+    pub block_backfill_rpc_url: Option<String>,
+
+    // if set to true, staking and voting rewards are additionally published as a
+    // separate message, so consumers interested only in rewards don't need full blocks
+    pub send_rewards: Option<bool>,
+
     pub skip_vote_txs: bool,
     pub skip_deploy_txs: bool,
+
+    // if set to true, messages are not serialized while no subscriber is connected to their
+    // listener, unless they are kept for later ones (strict delivery, tcp_min_subscribers,
+    // tcp_wal or tcp_dead_letters), saving the validator the work
+    pub skip_without_subscribers: Option<bool>,
+
+    // if set, vote transactions are routed to a separate listener on this port instead of
+    // the main stream, so consensus monitoring consumers can get them without every other
+    // subscriber filtering them out (skip_vote_txs is ignored in that case)
+    pub vote_tcp_port: Option<u16>,
+    // transport of the vote listener, the main one by default
+    pub vote_transport: Option<Transport>,
+
+    // if set, the periodic metadata messages are published on a separate listener on this
+    // port, with the main transport, instead of the main stream, so consumers of chain data
+    // only don't have to skip them; with nats, under <nats_subject_prefix>.control
+    pub control_tcp_port: Option<u16>,
+
+    // if set to true, a lightweight SlotRooted message is published when a slot previously
+    // published as confirmed is rooted, so consumers following confirmed slots only know
+    // when their data became irreversible
+    pub send_slot_rooted: Option<bool>,
+
+    // if set to true, the out of order slots, write_versions or tx indices detected are also
+    // published in-band as diagnostic messages, at most one per kind and second, besides
+    // incrementing the warning metrics
+    pub send_order_diagnostics: Option<bool>,
+
+    // if set to true, a diagnostic message is published when two different blockhashes are
+    // notified for the same slot, besides incrementing the duplicate_block_warns metric
+    pub send_duplicate_block_diagnostics: Option<bool>,
+
+    // if set, startup accounts are written to zstd compressed segment files in this directory,
+    // along with an index.json, instead of being skipped, keeping the snapshot flood out of
+    // the TCP stream
+    pub startup_snapshot_dir: Option<String>,
+
+    // max uncompressed size of a startup snapshot segment, 64MiB by default
+    pub startup_snapshot_segment_bytes: Option<usize>,
+
+    // if set, the active filters (send_*, skip_*) are published in-band every this many
+    // seconds, so subscribers that connect at any time learn what they won't receive
+    pub filter_state_interval_secs: Option<u64>,
+
+    // if set, messages and bytes published per account owner and invoked program are
+    // counted, and the top N programs since the previous report are logged with the metrics
+    pub program_stats_top_n: Option<usize>,
+
+    // if set to true, account updates with the same data, lamports, owner and executable flag
+    // as the previous update of the account are not published, a hash being kept per account
+    pub skip_unchanged_accounts: Option<bool>,
+
+    // max number of accounts skip_unchanged_accounts remembers, the least recently updated
+    // being forgotten first, 1000000 by default
+    pub skip_unchanged_accounts_capacity: Option<usize>,
+
+    // sinks getting the published accounts, slots, transactions and blocks as JSON events,
+    // besides the TCP stream, e.g.
+    // [{"type": "webhook", "url": "https://...", "headers": {"Authorization": "Bearer ..."}}]
+    // [{"type": "clickhouse", "url": "http://localhost:8123", "database": "solana"}]
+    // [{"type": "s3", "bucket": "geyser-archive", "prefix": "mainnet", "batch_size": 100000}]
+    // [{"type": "parquet", "dir": "/data/parquet", "slot_range": 10000, "batch_size": 100000}]
+    // [{"type": "sqlite", "path": "/data/archive.db", "retention_slots": 10000}]
+    // [{"type": "elasticsearch", "url": "http://localhost:9200", "index": "txs-%Y.%m"}]
+    // [{"type": "postgres", "connection": "host=localhost user=geyser dbname=solana"}]
+    // [{"type": "graphql", "addr": "0.0.0.0:8900"}]
+    // [{"type": "pubsub", "addr": "0.0.0.0:8901"}] for Solana RPC PubSub clients
+    // batch_size, batch_interval_ms, queue_size and max_retries can be set on every sink but
+    // graphql and pubsub, which only take a queue_size
+    // all but webhook and pubsub need the plugin built with the cargo feature of their type
+    // (or all-sinks), e.g. --features clickhouse,s3
+    pub sinks: Option<Vec<SinkConfig>>,
+
+    // if set, alerts are sent when error counters increase too fast or the slot lag grows, e.g.
+    // {"targets": [{"type": "slack", "url": "https://hooks.slack.com/..."}],
+    //  "rules": [{"metric": "send_errs", "threshold": 1000, "window_secs": 60},
+    //            {"metric": "slot_lag", "threshold": 150}]}
+    // metric is one of send_errs, disconnect_errs, serialize_errs, untyped_errs or slot_lag
+    // targets can also be {"type": "pagerduty", "routing_key": "..."}
+    pub alerts: Option<AlertsConfig>,
+
+    // if set, the metrics are pushed to a Prometheus Pushgateway on an interval, e.g.
+    // {"url": "http://pushgateway:9091", "job": "geyser", "instance": "validator-1"}
+    pub pushgateway: Option<PushgatewayConfig>,
+
+    // if set, an HTTP API is served on this address to inspect the plugin, move the TCP
+    // listener and add or remove sinks without restarting the validator,
+    // e.g. {"addr": "127.0.0.1:8990"}
+    pub admin: Option<AdminConfig>,
 }
 
 impl Config {
@@ -30,4 +324,17 @@ impl Config {
 
         Ok(c)
     }
+
+    /// The filters applied to published data.
+    pub fn filter_state(&self) -> FilterState {
+        FilterState {
+            send_accounts: self.send_accounts,
+            send_transactions: self.send_transactions,
+            send_blocks: self.send_blocks,
+            send_rewards: self.send_rewards.unwrap_or(false),
+            skip_vote_txs: self.skip_vote_txs,
+            skip_deploy_txs: self.skip_deploy_txs,
+            vote_txs_rerouted: self.vote_tcp_port.is_some(),
+        }
+    }
 }
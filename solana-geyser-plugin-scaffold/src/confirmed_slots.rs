@@ -0,0 +1,43 @@
+use std::{collections::BTreeSet, sync::Mutex};
+
+/// Slots published as confirmed and not rooted yet.
+#[derive(Default)]
+pub struct ConfirmedSlots(Mutex<BTreeSet<u64>>);
+
+impl ConfirmedSlots {
+    pub fn confirm(&self, slot: u64) {
+        let mut slots = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        slots.insert(slot);
+    }
+
+    /// True if the slot was confirmed. Older slots are forgotten as well, they were on
+    /// a fork if they didn't root by now.
+    pub fn root(&self, slot: u64) -> bool {
+        let mut slots = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let newer = slots.split_off(&(slot + 1));
+        let confirmed = slots.contains(&slot);
+        *slots = newer;
+
+        confirmed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_root() {
+        let slots = ConfirmedSlots::default();
+        slots.confirm(10);
+        slots.confirm(11);
+        slots.confirm(13);
+
+        assert!(slots.root(11));
+        // 10 was forgotten with the older slots
+        assert!(!slots.root(10));
+        assert!(!slots.root(12));
+        assert!(slots.root(13));
+        assert!(!slots.root(13));
+    }
+}
@@ -0,0 +1,74 @@
+use std::{collections::BTreeMap, sync::Mutex};
+
+// slots older than this many slots behind the newest block are forgotten
+const TRACKED_SLOTS: u64 = 1024;
+
+/// Two different blocks notified for the same slot.
+pub struct DuplicateBlock {
+    pub slot: u64,
+    pub previous_blockhash: String,
+    pub blockhash: String,
+}
+
+/// Remembers the blockhash of the recent slots, to detect duplicate blocks: a leader
+/// producing several versions of its block, of which validators may replay more than one.
+#[derive(Default)]
+pub struct DuplicateBlockDetector {
+    blockhashes: Mutex<BTreeMap<u64, String>>,
+}
+
+impl DuplicateBlockDetector {
+    pub fn check(&self, slot: u64, blockhash: &str) -> Option<DuplicateBlock> {
+        let mut blockhashes = self.blockhashes.lock().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(previous) = blockhashes.get_mut(&slot) {
+            if previous == blockhash {
+                return None;
+            }
+
+            let previous_blockhash = std::mem::replace(previous, blockhash.to_string());
+            return Some(DuplicateBlock {
+                slot,
+                previous_blockhash,
+                blockhash: blockhash.to_string(),
+            });
+        }
+
+        blockhashes.insert(slot, blockhash.to_string());
+        if let Some(&newest) = blockhashes.keys().next_back() {
+            *blockhashes = blockhashes.split_off(&newest.saturating_sub(TRACKED_SLOTS));
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplicate_block() {
+        let detector = DuplicateBlockDetector::default();
+
+        assert!(detector.check(10, "a").is_none());
+        assert!(detector.check(10, "a").is_none());
+        assert!(detector.check(11, "b").is_none());
+
+        let duplicate = detector.check(10, "c").unwrap();
+        assert_eq!(duplicate.slot, 10);
+        assert_eq!(duplicate.previous_blockhash, "a");
+        assert_eq!(duplicate.blockhash, "c");
+        // the latest version is remembered
+        assert!(detector.check(10, "c").is_none());
+    }
+
+    #[test]
+    fn test_old_slots_are_forgotten() {
+        let detector = DuplicateBlockDetector::default();
+
+        assert!(detector.check(10, "a").is_none());
+        assert!(detector.check(10 + TRACKED_SLOTS + 1, "b").is_none());
+        assert!(detector.check(10, "c").is_none());
+    }
+}
@@ -0,0 +1,61 @@
+use flatbuffers::FlatBufferBuilder;
+use std::{
+    cell::RefCell,
+    ops::{Deref, DerefMut},
+};
+
+// serialize_account uses two builders at once, the rest one
+const MAX_POOLED_BUILDERS: usize = 2;
+// builders which served bigger messages are dropped instead of keeping the memory around
+const MAX_POOLED_BUILDER_BYTES: usize = 4 * 1024 * 1024;
+
+thread_local! {
+    static BUILDERS: RefCell<Vec<FlatBufferBuilder<'static>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A `FlatBufferBuilder` taken from the thread-local pool, reset and given back on drop.
+pub struct PooledBuilder(Option<FlatBufferBuilder<'static>>);
+
+impl PooledBuilder {
+    pub fn take() -> Self {
+        let builder = BUILDERS.with(|builders| builders.borrow_mut().pop());
+
+        PooledBuilder(Some(builder.unwrap_or_default()))
+    }
+}
+
+impl Drop for PooledBuilder {
+    fn drop(&mut self) {
+        let mut builder = match self.0.take() {
+            Some(builder) => builder,
+            None => return,
+        };
+
+        if builder.unfinished_data().len() > MAX_POOLED_BUILDER_BYTES {
+            return;
+        }
+
+        builder.reset();
+        BUILDERS.with(|builders| {
+            let mut builders = builders.borrow_mut();
+            if builders.len() < MAX_POOLED_BUILDERS {
+                builders.push(builder);
+            }
+        });
+    }
+}
+
+impl Deref for PooledBuilder {
+    type Target = FlatBufferBuilder<'static>;
+
+    fn deref(&self) -> &Self::Target {
+        // only None while dropping
+        self.0.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledBuilder {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.as_mut().unwrap()
+    }
+}
@@ -1,11 +1,14 @@
 //! FlatBuffer serialization module
 use utils::flatbuffer::account_info_generated::account_info::{AccountInfo, AccountInfoArgs};
+use utils::flatbuffer::account_info_v2_generated::account_info_v2;
 use utils::flatbuffer::consts::{
-    BYTE_PREFIX_ACCOUNT, BYTE_PREFIX_BLOCK, BYTE_PREFIX_METADATA, BYTE_PREFIX_SLOT, BYTE_PREFIX_TX,
+    BYTE_PREFIX_ACCOUNT, BYTE_PREFIX_ACCOUNT_V2, BYTE_PREFIX_BLOCK, BYTE_PREFIX_DIAGNOSTIC,
+    BYTE_PREFIX_METADATA, BYTE_PREFIX_REWARDS, BYTE_PREFIX_SLOT, BYTE_PREFIX_SLOT_ROOTED,
+    BYTE_PREFIX_TX,
 };
 
-use flatbuffers::FlatBufferBuilder;
 use solana_geyser_plugin_interface::geyser_plugin_interface::SlotStatus;
+use solana_transaction_status::RewardType;
 use update_types::{AccountUpdate, BlockUpdate, TransactionUpdate};
 use utils::{
     errors::GeyserError,
@@ -14,26 +17,31 @@ use utils::{
     },
 };
 
+use crate::build_info::{GIT_COMMIT, PLUGIN_VERSION};
+use crate::duplicate_blocks::DuplicateBlock;
+use crate::fb_serializers::builder_pool::PooledBuilder;
 use crate::fb_serializers::extractors::{
     extract_rewards, extract_tx_info_args, extract_tx_meta_args,
 };
+use crate::monotonicity::OrderViolation;
 use utils::flatbuffer::account_data_generated::account_data::{AccountData, AccountDataArgs};
+use utils::flatbuffer::diagnostic_generated::diagnostic::{Diagnostic, DiagnosticArgs, Kind};
 use utils::flatbuffer::metadata_generated::metadata::{Metadata, MetadataArgs};
+use utils::flatbuffer::rewards_generated::rewards::{Rewards, RewardsArgs};
+use utils::flatbuffer::slot_rooted_generated::slot_rooted::{SlotRooted, SlotRootedArgs};
 use utils::flatbuffer::{
     block_info_generated::block_info::{BlockInfo, BlockInfoArgs},
     slot_generated::slot::{Slot, SlotArgs, Status},
+    SCHEMA_HASH,
 };
 
+mod builder_pool;
 mod extractors;
 pub mod update_types;
 
-/// Struct which implements FlatBuffer serialization for accounts, block metadata and transactions data
-#[derive(Debug, Copy, Clone)]
-pub struct FlatBufferSerialization {}
-
 pub fn serialize_account(account: &AccountUpdate) -> Vec<u8> {
-    let mut data_builder = FlatBufferBuilder::new();
-    let data = Some(data_builder.create_vector(account.data.as_ref()));
+    let mut data_builder = PooledBuilder::take();
+    let data = Some(data_builder.create_vector(account.data));
     let account_data = AccountData::create(
         &mut data_builder,
         &AccountDataArgs {
@@ -46,10 +54,13 @@ pub fn serialize_account(account: &AccountUpdate) -> Vec<u8> {
     );
     data_builder.finish(account_data, None);
 
-    let mut builder = FlatBufferBuilder::new();
+    let mut builder = PooledBuilder::take();
     let pubkey = Some(builder.create_string(account.key.to_string().as_ref()));
     let owner = Some(builder.create_string(account.owner.to_string().as_ref()));
     let account_data = Some(builder.create_vector(data_builder.finished_data()));
+    let txn_signature = account
+        .txn_signature
+        .map(|signature| builder.create_string(signature.to_string().as_ref()));
 
     let account_info = AccountInfo::create(
         &mut builder,
@@ -58,16 +69,47 @@ pub fn serialize_account(account: &AccountUpdate) -> Vec<u8> {
             owner,
             slot: account.slot,
             account_data,
+            txn_signature,
+        },
+    );
+
+    builder.finish(account_info, None);
+
+    build_output(BYTE_PREFIX_ACCOUNT, builder.finished_data())
+}
+
+/// Same as `serialize_account`, with the `AccountData` fields inlined instead of nested.
+pub fn serialize_account_v2(account: &AccountUpdate) -> Vec<u8> {
+    let mut builder = PooledBuilder::take();
+    let pubkey = Some(builder.create_string(account.key.to_string().as_ref()));
+    let owner = Some(builder.create_string(account.owner.to_string().as_ref()));
+    let data = Some(builder.create_vector(account.data));
+    let txn_signature = account
+        .txn_signature
+        .map(|signature| builder.create_string(signature.to_string().as_ref()));
+
+    let account_info = account_info_v2::AccountInfo::create(
+        &mut builder,
+        &account_info_v2::AccountInfoArgs {
+            pubkey,
+            owner,
+            slot: account.slot,
+            lamports: account.lamports,
+            rent_epoch: account.rent_epoch,
+            executable: account.executable,
+            write_version: account.write_version,
+            data,
+            txn_signature,
         },
     );
 
     builder.finish(account_info, None);
 
-    build_output(BYTE_PREFIX_ACCOUNT, builder.finished_data().to_vec())
+    build_output(BYTE_PREFIX_ACCOUNT_V2, builder.finished_data())
 }
 
 pub fn serialize_slot(slot: u64, parent: Option<u64>, status: SlotStatus) -> Vec<u8> {
-    let mut builder = FlatBufferBuilder::new();
+    let mut builder = PooledBuilder::take();
 
     let s = Slot::create(
         &mut builder,
@@ -84,20 +126,27 @@ pub fn serialize_slot(slot: u64, parent: Option<u64>, status: SlotStatus) -> Vec
 
     builder.finish(s, None);
 
-    build_output(BYTE_PREFIX_SLOT, builder.finished_data().to_vec())
+    build_output(BYTE_PREFIX_SLOT, builder.finished_data())
+}
+
+pub fn serialize_slot_rooted(slot: u64) -> Vec<u8> {
+    let mut builder = PooledBuilder::take();
+
+    let s = SlotRooted::create(&mut builder, &SlotRootedArgs { slot });
+    builder.finish(s, None);
+
+    build_output(BYTE_PREFIX_SLOT_ROOTED, builder.finished_data())
 }
 
 pub fn serialize_block(block: &BlockUpdate) -> Vec<u8> {
-    let mut builder = FlatBufferBuilder::new();
+    let mut builder = PooledBuilder::take();
 
     let rewards = extract_rewards(&block.rewards.to_vec().into(), &mut builder);
 
     let blockhash = builder.create_string(block.blockhash);
-    let parent_blockhash = if block.parent_blockhash.is_some() {
-        Some(builder.create_string(block.parent_blockhash.unwrap()))
-    } else {
-        None
-    };
+    let parent_blockhash = block
+        .parent_blockhash
+        .map(|parent_blockhash| builder.create_string(parent_blockhash));
 
     let b = BlockInfo::create(
         &mut builder,
@@ -115,11 +164,50 @@ pub fn serialize_block(block: &BlockUpdate) -> Vec<u8> {
 
     builder.finish(b, None);
 
-    build_output(BYTE_PREFIX_BLOCK, builder.finished_data().to_vec())
+    build_output(BYTE_PREFIX_BLOCK, builder.finished_data())
+}
+
+/// Serializes only the staking and voting rewards of a block.
+/// Returns `None` for blocks without such rewards, which is all of them except the
+/// ones paying out epoch rewards.
+pub fn serialize_rewards(block: &BlockUpdate) -> Option<Vec<u8>> {
+    let rewards = block
+        .rewards
+        .iter()
+        .filter(|reward| {
+            matches!(
+                reward.reward_type,
+                Some(RewardType::Staking) | Some(RewardType::Voting)
+            )
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+
+    if rewards.is_empty() {
+        return None;
+    }
+
+    let mut builder = PooledBuilder::take();
+
+    let rewards = extract_rewards(&Some(rewards), &mut builder);
+
+    let r = Rewards::create(
+        &mut builder,
+        &RewardsArgs {
+            slot: block.slot,
+            block_height: block.block_height,
+            block_time: block.block_time,
+            rewards,
+        },
+    );
+
+    builder.finish(r, None);
+
+    Some(build_output(BYTE_PREFIX_REWARDS, builder.finished_data()))
 }
 
 pub fn serialize_transaction(transaction: &TransactionUpdate) -> Result<Vec<u8>, GeyserError> {
-    let mut builder = FlatBufferBuilder::new();
+    let mut builder = PooledBuilder::take();
 
     let signature_string = Some(builder.create_string(transaction.signature.to_string().as_str()));
 
@@ -150,24 +238,76 @@ pub fn serialize_transaction(transaction: &TransactionUpdate) -> Result<Vec<u8>,
     );
     builder.finish(transaction_info, None);
 
-    Ok(build_output(
-        BYTE_PREFIX_TX,
-        builder.finished_data().to_vec(),
-    ))
+    Ok(build_output(BYTE_PREFIX_TX, builder.finished_data()))
 }
 
 pub fn serialize_metadata(send_errors: u64) -> Vec<u8> {
-    let mut builder = FlatBufferBuilder::new();
+    let mut builder = PooledBuilder::take();
+
+    let version = Some(builder.create_string(PLUGIN_VERSION));
+    let git_commit = Some(builder.create_string(GIT_COMMIT));
+    let schema_hash = Some(builder.create_string(SCHEMA_HASH));
+
+    let obj = Metadata::create(
+        &mut builder,
+        &MetadataArgs {
+            send_errors,
+            version,
+            git_commit,
+            schema_hash,
+        },
+    );
+    builder.finish(obj, None);
+
+    build_output(BYTE_PREFIX_METADATA, builder.finished_data())
+}
+
+pub fn serialize_order_violation(violation: &OrderViolation) -> Vec<u8> {
+    let mut builder = PooledBuilder::take();
+
+    let obj = Diagnostic::create(
+        &mut builder,
+        &DiagnosticArgs {
+            kind: violation.kind,
+            slot: violation.slot,
+            previous: violation.previous,
+            current: violation.current,
+            previous_blockhash: None,
+            blockhash: None,
+        },
+    );
+
+    builder.finish(obj, None);
+
+    build_output(BYTE_PREFIX_DIAGNOSTIC, builder.finished_data())
+}
+
+pub fn serialize_duplicate_block(duplicate: &DuplicateBlock) -> Vec<u8> {
+    let mut builder = PooledBuilder::take();
+
+    let previous_blockhash = Some(builder.create_string(&duplicate.previous_blockhash));
+    let blockhash = Some(builder.create_string(&duplicate.blockhash));
+    let obj = Diagnostic::create(
+        &mut builder,
+        &DiagnosticArgs {
+            kind: Kind::DuplicateBlock,
+            slot: duplicate.slot,
+            previous: 0,
+            current: 0,
+            previous_blockhash,
+            blockhash,
+        },
+    );
 
-    let obj = Metadata::create(&mut builder, &MetadataArgs { send_errors });
     builder.finish(obj, None);
 
-    build_output(BYTE_PREFIX_METADATA, builder.finished_data().to_vec())
+    build_output(BYTE_PREFIX_DIAGNOSTIC, builder.finished_data())
 }
 
-fn build_output(prefix: u8, data: Vec<u8>) -> Vec<u8> {
-    let mut output = vec![prefix];
-    output.extend(data);
+fn build_output(prefix: u8, data: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(1 + data.len());
+    output.push(prefix);
+    output.extend_from_slice(data);
 
     output
 }
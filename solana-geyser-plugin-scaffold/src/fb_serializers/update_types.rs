@@ -7,7 +7,8 @@ use solana_sdk::signature::Signature;
 const BPF_LOADER_WRITE_INSTRUCTION_FIRST_BYTE: u8 = 0;
 const BPF_UPGRADEABLE_LOADER_WRITE_INSTRUCTION_FIRST_BYTE: u8 = 1;
 
-pub struct AccountUpdate {
+/// Borrows the account data from the replica info, which outlives the serialization.
+pub struct AccountUpdate<'a> {
     /// The account's public key
     pub key: Pubkey,
     /// The lamport balance of the account
@@ -19,18 +20,21 @@ pub struct AccountUpdate {
     /// The next epoch for which this account will owe rent
     pub rent_epoch: u64,
     /// The binary data stored on this account
-    pub data: Vec<u8>,
+    pub data: &'a [u8],
     /// Monotonic-increasing counter for sequencing on-chain writes
     pub write_version: u64,
     /// The slot in which this account was updated
     pub slot: u64,
     /// True if this update was triggered by a validator startup
+    #[allow(dead_code)]
     pub is_startup: bool,
+    /// Signature of the transaction that caused this write, available since V0_0_2
+    pub txn_signature: Option<Signature>,
 }
 
-impl AccountUpdate {
+impl<'a> AccountUpdate<'a> {
     pub fn from_account(
-        account: ReplicaAccountInfoVersions,
+        account: ReplicaAccountInfoVersions<'a>,
         slot: u64,
         is_startup: bool,
     ) -> anyhow::Result<Self> {
@@ -45,10 +49,11 @@ impl AccountUpdate {
                     owner,
                     executable: acc.executable,
                     rent_epoch: acc.rent_epoch,
-                    data: acc.data.to_vec(),
+                    data: acc.data,
                     write_version: acc.write_version,
                     slot,
                     is_startup,
+                    txn_signature: None,
                 })
             }
             ReplicaAccountInfoVersions::V0_0_2(acc) => {
@@ -61,10 +66,11 @@ impl AccountUpdate {
                     owner,
                     executable: acc.executable,
                     rent_epoch: acc.rent_epoch,
-                    data: acc.data.to_vec(),
+                    data: acc.data,
                     write_version: acc.write_version,
                     slot,
                     is_startup,
+                    txn_signature: acc.txn_signature.copied(),
                 })
             }
             ReplicaAccountInfoVersions::V0_0_3(acc) => {
@@ -77,10 +83,11 @@ impl AccountUpdate {
                     owner,
                     executable: acc.executable,
                     rent_epoch: acc.rent_epoch,
-                    data: acc.data.to_vec(),
+                    data: acc.data,
                     write_version: acc.write_version,
                     slot,
                     is_startup,
+                    txn_signature: acc.txn.map(|txn| *txn.signature()),
                 })
             }
         }
@@ -118,6 +125,18 @@ impl TransactionUpdate {
         }
     }
 
+    /// Programs invoked by the top level instructions, each listed once.
+    pub fn program_ids(&self) -> Vec<Pubkey> {
+        let mut program_ids = Vec::new();
+        for (program_id, _) in self.transaction.message().program_instructions_iter() {
+            if !program_ids.contains(program_id) {
+                program_ids.push(*program_id);
+            }
+        }
+
+        program_ids
+    }
+
     pub fn is_deploy_tx(&self) -> bool {
         if self.transaction.message().instructions().len() != 1 {
             return false;
@@ -156,6 +175,7 @@ pub struct BlockUpdate<'a> {
     pub block_time: Option<i64>,
     pub block_height: Option<u64>,
     pub executed_transaction_count: Option<u64>,
+    #[allow(dead_code)]
     pub entry_count: Option<u64>,
 }
 
@@ -198,3 +218,64 @@ impl<'a> From<ReplicaBlockInfoVersions<'a>> for BlockUpdate<'a> {
         }
     }
 }
+
+impl<'a> BlockUpdate<'a> {
+    /// Block fetched from an RPC node, requested with signatures and rewards.
+    pub fn from_ui_block(
+        slot: u64,
+        block: &'a solana_transaction_status::UiConfirmedBlock,
+    ) -> Self {
+        BlockUpdate {
+            parent_slot: Some(block.parent_slot),
+            parent_blockhash: Some(&block.previous_blockhash),
+            slot,
+            blockhash: &block.blockhash,
+            rewards: block.rewards.as_deref().unwrap_or_default(),
+            block_time: block.block_time,
+            block_height: block.block_height,
+            executed_transaction_count: block
+                .signatures
+                .as_ref()
+                .map(|signatures| signatures.len() as u64),
+            entry_count: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_geyser_plugin_interface::geyser_plugin_interface::ReplicaAccountInfoV2;
+
+    use super::*;
+
+    #[test]
+    fn test_account_v2_keeps_signature() {
+        let (pubkey, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let signature = Signature::new_unique();
+        let mut info = ReplicaAccountInfoV2 {
+            pubkey: pubkey.as_ref(),
+            lamports: 10,
+            owner: owner.as_ref(),
+            executable: false,
+            rent_epoch: 1,
+            data: b"data",
+            write_version: 7,
+            txn_signature: Some(&signature),
+        };
+
+        let account =
+            AccountUpdate::from_account(ReplicaAccountInfoVersions::V0_0_2(&info), 5, false)
+                .unwrap();
+        assert_eq!(account.key, pubkey);
+        assert_eq!(account.owner, owner);
+        assert_eq!(account.data, b"data");
+        assert_eq!((account.write_version, account.slot), (7, 5));
+        assert_eq!(account.txn_signature, Some(signature));
+
+        info.txn_signature = None;
+        let account =
+            AccountUpdate::from_account(ReplicaAccountInfoVersions::V0_0_2(&info), 5, false)
+                .unwrap();
+        assert_eq!(account.txn_signature, None);
+    }
+}
@@ -1,28 +1,274 @@
-use crate::fb_serializers::update_types::{AccountUpdate, TransactionUpdate};
+use crate::account_dedup::{AccountDedup, DEFAULT_DEDUP_CAPACITY};
+use crate::admin;
+use crate::alerts::Alerter;
+use crate::block_backfill::{BlockBackfill, BlockFetcher};
+use crate::build_info::{GIT_COMMIT, PLUGIN_VERSION};
+use crate::confirmed_slots::ConfirmedSlots;
+use crate::duplicate_blocks::{DuplicateBlock, DuplicateBlockDetector};
+use crate::fb_serializers::update_types::{AccountUpdate, BlockUpdate, TransactionUpdate};
 use crate::fb_serializers::{
-    serialize_account, serialize_block, serialize_metadata, serialize_slot, serialize_transaction,
+    serialize_account, serialize_account_v2, serialize_block, serialize_duplicate_block,
+    serialize_metadata, serialize_order_violation, serialize_rewards, serialize_slot,
+    serialize_slot_rooted, serialize_transaction,
 };
-use crate::{config::Config, metrics::Metrics};
-use log::info;
+use crate::monotonicity::{MonotonicityChecker, OrderViolation};
+use crate::program_stats::ProgramStats;
+use crate::pushgateway::Pushgateway;
+use crate::sinks::{Event, SinkConfig, Sinks};
+use crate::snapshot_export::{SnapshotExporter, DEFAULT_SEGMENT_MAX_BYTES};
+use crate::{
+    config::{Config, Transport},
+    metrics::Metrics,
+};
+use log::{error, info, warn};
+use serde_json::{json, Value};
 use solana_geyser_plugin_interface::geyser_plugin_interface::*;
 use std::{
+    collections::BTreeMap,
     fmt::{Debug, Formatter},
+    io,
+    net::SocketAddr,
+    path::Path,
     sync::atomic::Ordering,
     time::Duration,
 };
-use std::{sync::Arc, thread};
-use utils::{errors::GeyserError, sender::TcpSender};
+use std::{
+    sync::{mpsc::Receiver, Arc},
+    thread,
+};
+use utils::{
+    dead_letter::DeadLetters,
+    encryption::PayloadCipher,
+    errors::GeyserError,
+    flatbuffer::{diagnostic_generated::diagnostic::Kind, SCHEMA_HASH},
+    handshake::{ServerHello, SCHEMA_VERSION_V1, SCHEMA_VERSION_V2},
+    sender::{
+        NatsSender, Publisher, QuicSender, Router, Sharder, TcpSender, WsSender, ZmqSender,
+        DEFAULT_ACCEPTOR_THREADS, DEFAULT_ACK_WINDOW, DEFAULT_HANDSHAKE_TIMEOUT,
+        DEFAULT_WRITER_THREADS, DEFAULT_WRITE_TIMEOUT,
+    },
+    tls,
+    wal::WriteAheadLog,
+};
 
 const UNINIT: &str = "Geyser plugin not initialized yet!";
+const DEFAULT_DRAIN_TIMEOUT_MS: u64 = 5000;
+
+/// The listeners of the plugin, each with a sender of its own.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Listener {
+    Main,
+    Votes,
+    // metadata, when config.control_tcp_port is set
+    Control,
+    // one of config.endpoints
+    Endpoint,
+}
 
 /// This is the main object returned bu our dynamic library in entrypoint.rs
 #[derive(Default)]
 pub struct GeyserPluginHook(Option<Arc<Inner>>);
 
 pub struct Inner {
-    socket: TcpSender,
+    socket: Box<dyn Publisher>,
+    vote_socket: Option<Box<dyn Publisher>>,
+    control_socket: Option<Box<dyn Publisher>>,
     metrics: Arc<Metrics>,
     config: Config,
+    monotonicity: MonotonicityChecker,
+    snapshot_exporter: Option<SnapshotExporter>,
+    program_stats: Option<ProgramStats>,
+    account_dedup: Option<AccountDedup>,
+    confirmed_slots: ConfirmedSlots,
+    duplicate_blocks: DuplicateBlockDetector,
+    block_backfill: Option<BlockBackfill>,
+    sinks: Sinks,
+}
+
+impl Inner {
+    /// Moves the main TCP listener, or the vote one, to `addr`.
+    pub(crate) fn rebind_listener(&self, addr: SocketAddr, votes: bool) -> io::Result<SocketAddr> {
+        let socket = match &self.vote_socket {
+            Some(vote_socket) if votes => vote_socket,
+            None if votes => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "no vote listener, vote_tcp_port is not set",
+                ))
+            }
+            _ => &self.socket,
+        };
+
+        socket.rebind(addr)
+    }
+
+    pub(crate) fn status(&self) -> Value {
+        json!({
+            "plugin_version": PLUGIN_VERSION,
+            "git_commit": GIT_COMMIT,
+            "listener": self.socket.local_addr(),
+            "vote_listener": self.vote_socket.as_ref().and_then(|s| s.local_addr()),
+            "control_listener": self.control_socket.as_ref().and_then(|s| s.local_addr()),
+            "subscribers": self.socket.subscribers().unwrap_or_default(),
+            "metrics": self.metrics.values().into_iter().collect::<BTreeMap<_, _>>(),
+            "sinks": self.sinks.status(),
+        })
+    }
+
+    /// Starts a sink while the plugin runs, returning its name.
+    pub(crate) fn add_sink(&self, config: &SinkConfig) -> anyhow::Result<String> {
+        let sink = config.build()?;
+        let name = sink.name().to_string();
+        self.sinks.add(sink);
+
+        info!("[sinks] - added {}", name);
+        Ok(name)
+    }
+
+    /// Closes a running sink, letting it handle the events it has queued.
+    pub(crate) fn remove_sink(&self, name: &str) -> bool {
+        let removed = self.sinks.remove(name);
+        if removed {
+            info!("[sinks] - removed {}", name);
+        }
+
+        removed
+    }
+
+    /// Whether the messages for `socket` are left unserialized, nobody getting them, see
+    /// skip_without_subscribers. Counts them if so.
+    fn skips_unsubscribed(&self, socket: &dyn Publisher) -> bool {
+        let skipped =
+            self.config.skip_without_subscribers.unwrap_or(false) && !socket.needs_messages();
+        if skipped {
+            self.metrics
+                .skipped_unsubscribed
+                .fetch_add(1, Ordering::Relaxed);
+        }
+
+        skipped
+    }
+
+    /// Counts the violation and, if enabled, publishes it as a diagnostic message.
+    fn report_order_violation(&self, violation: Option<OrderViolation>) -> anyhow::Result<()> {
+        let violation = match violation {
+            Some(violation) => violation,
+            None => return Ok(()),
+        };
+
+        let counter = match violation.kind {
+            Kind::WriteVersionOrder => &self.metrics.write_version_order_warns,
+            Kind::TxIndexOrder => &self.metrics.tx_index_order_warns,
+            _ => &self.metrics.slot_order_warns,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+
+        if self.config.send_order_diagnostics.unwrap_or(false)
+            && self.monotonicity.should_publish(&violation)
+        {
+            self.socket.publish(serialize_order_violation(&violation))?;
+        }
+
+        Ok(())
+    }
+
+    /// Counts the duplicate block and, if enabled, publishes it as a diagnostic message.
+    fn report_duplicate_block(&self, duplicate: Option<DuplicateBlock>) -> anyhow::Result<()> {
+        let duplicate = match duplicate {
+            Some(duplicate) => duplicate,
+            None => return Ok(()),
+        };
+
+        self.metrics
+            .duplicate_block_warns
+            .fetch_add(1, Ordering::Relaxed);
+        warn!(
+            "Duplicate block for slot {}: {} then {}",
+            duplicate.slot, duplicate.previous_blockhash, duplicate.blockhash
+        );
+
+        if self
+            .config
+            .send_duplicate_block_diagnostics
+            .unwrap_or(false)
+        {
+            self.socket.publish(serialize_duplicate_block(&duplicate))?;
+        }
+
+        Ok(())
+    }
+
+    /// Hands the event to every sink, it is only built if there is one.
+    fn publish_event(&self, event: impl FnOnce() -> Event) {
+        self.sinks.publish(event)
+    }
+
+    /// Publishes the block and its rewards, depending on the config.
+    fn publish_block(&self, block: &BlockUpdate) -> anyhow::Result<()> {
+        let send_rewards = self.config.send_rewards.unwrap_or(false);
+        if self.config.send_blocks {
+            self.publish_event(|| Event::from_block(block));
+        } else {
+            self.metrics.skipped_blocks.fetch_add(1, Ordering::Relaxed);
+        }
+
+        // checked once for both, so a skipped block is counted once
+        if !(self.config.send_blocks || send_rewards) || self.skips_unsubscribed(&*self.socket) {
+            return Ok(());
+        }
+
+        if self.config.send_blocks {
+            let data = serialize_block(block);
+            self.socket.publish(data)?;
+        }
+
+        if send_rewards {
+            if let Some(data) = serialize_rewards(block) {
+                self.socket.publish(data)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fetches and publishes the blocks missed by the validator notifications.
+    fn backfill_blocks(&self, fetcher: BlockFetcher, slots: Receiver<u64>) {
+        for slot in slots {
+            let block = match fetcher.fetch(slot) {
+                Some(block) => block,
+                None => {
+                    self.metrics.backfill_errs.fetch_add(1, Ordering::Relaxed);
+                    continue;
+                }
+            };
+
+            self.metrics
+                .backfilled_blocks
+                .fetch_add(1, Ordering::Relaxed);
+            if let Err(e) = self.publish_block(&BlockUpdate::from_ui_block(slot, &block)) {
+                info!("{}", e);
+            }
+        }
+    }
+
+    /// Publishes the active filters every `interval`. The config can't change after load, so
+    /// the state is only repeated for subscribers that connect later.
+    fn broadcast_filter_state(&self, interval: Duration) {
+        let data = self.config.filter_state().to_message();
+
+        loop {
+            if let Some(vote_socket) = &self.vote_socket {
+                if let Err(e) = vote_socket.publish(data.clone()) {
+                    info!("{}", e);
+                }
+            }
+            if let Err(e) = self.socket.publish(data.clone()) {
+                info!("{}", e);
+            }
+
+            thread::sleep(interval);
+        }
+    }
 }
 
 impl GeyserPluginHook {
@@ -63,6 +309,11 @@ impl GeyserPluginHook {
                             GeyserError::ConnLockError => {
                                 inner.metrics.conn_lock_errs.fetch_add(1, Ordering::Relaxed);
                             }
+                            GeyserError::ZmqSend(_) | GeyserError::NatsSend(_) => {
+                                inner.metrics.send_errs.fetch_add(1, Ordering::Relaxed);
+                            }
+                            // messages published while shutting down are dropped on purpose
+                            GeyserError::SenderDraining => {}
                         }
 
                         Ok(())
@@ -98,30 +349,405 @@ impl GeyserPlugin for GeyserPluginHook {
 
         let cfg = Config::read(config_file).unwrap();
 
-        let socket = TcpSender::new(
-            cfg.tcp_batch_max_bytes,
-            cfg.tcp_strict_delivery.unwrap_or(false),
-            cfg.tcp_min_subscribers.unwrap_or(0),
+        let hello = ServerHello::new(PLUGIN_VERSION, GIT_COMMIT);
+        info!(
+            "[on_load] - plugin version {}, commit {}, schema {}",
+            hello.plugin_version, hello.git_commit, hello.schema_hash
         );
-        socket.bind(cfg.tcp_port, cfg.tcp_buffer_size).unwrap();
+
+        let acceptor_threads = cfg.tcp_acceptor_threads.unwrap_or(DEFAULT_ACCEPTOR_THREADS);
+        let writer_threads = cfg.tcp_writer_threads.unwrap_or(DEFAULT_WRITER_THREADS);
+        let handshake_timeout = cfg
+            .tcp_handshake_timeout_ms
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_HANDSHAKE_TIMEOUT);
+        let write_timeout = cfg
+            .tcp_write_timeout_ms
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_WRITE_TIMEOUT);
+        let quotas = cfg.tcp_subscriber_quotas.clone().unwrap_or_default();
+        let tls = cfg.tls.as_ref().map(|tls| {
+            tls::server_config(
+                Path::new(&tls.cert_path),
+                Path::new(&tls.key_path),
+                tls.client_ca_path.as_deref().map(Path::new),
+            )
+            .unwrap()
+        });
+
+        let address_family = cfg.tcp_address_family.unwrap_or_default();
+        let bind_ip = cfg.tcp_bind_addr.unwrap_or(address_family.unspecified());
+
+        let new_tcp_sender = || {
+            let sender = TcpSender::new(
+                cfg.tcp_batch_max_bytes,
+                cfg.tcp_strict_delivery.unwrap_or(false),
+                cfg.tcp_min_subscribers.unwrap_or(0),
+            )
+            .with_handshake(&hello)
+            .with_handshake_timeout(handshake_timeout)
+            .with_write_timeout(write_timeout)
+            .with_quotas(quotas.clone())
+            .with_topics(cfg.tcp_topics.clone().unwrap_or_default())
+            .with_min_subscribers_by_type(
+                cfg.tcp_min_subscribers_by_type.clone().unwrap_or_default(),
+            )
+            .with_address_family(address_family)
+            .with_ack_window(cfg.tcp_ack_window.unwrap_or(DEFAULT_ACK_WINDOW))
+            .with_group_balancing(cfg.tcp_group_balancing.unwrap_or_default())
+            .with_threads(acceptor_threads, writer_threads)
+            .with_fanout_threads(cfg.tcp_fanout_threads.unwrap_or(1));
+
+            let sender = match cfg.tcp_max_connections {
+                Some(max_connections) => sender.with_max_connections(max_connections),
+                None => sender,
+            };
+
+            let sender = match &cfg.tcp_ip_filter {
+                Some(filter) => sender.with_ip_filter(filter.clone()),
+                None => sender,
+            };
+
+            let sender = match cfg.tcp_default_quota {
+                Some(quota) => sender.with_default_quota(quota),
+                None => sender,
+            };
+
+            let sender = match cfg.tcp_stall_timeout_ms {
+                Some(timeout) => sender.with_stall_timeout(Duration::from_millis(timeout)),
+                None => sender,
+            };
+
+            let sender = match cfg.tcp_writer_watchdog_ms {
+                Some(timeout) => sender.with_writer_watchdog(Duration::from_millis(timeout)),
+                None => sender,
+            };
+
+            let sender = match cfg.tcp_strict_delivery_timeout_ms {
+                Some(timeout) => {
+                    sender.with_strict_delivery_timeout(Duration::from_millis(timeout))
+                }
+                None => sender,
+            };
+
+            let sender = match cfg.tcp_message_ttl_ms {
+                Some(ttl) => sender.with_message_ttl(Duration::from_millis(ttl)),
+                None => sender,
+            };
+
+            let sender = match cfg.tcp_max_message_bytes {
+                Some(max_bytes) => sender.with_max_message_bytes(max_bytes),
+                None => sender,
+            };
+
+            let sender = match &tls {
+                Some(tls) => sender.with_tls(tls.clone()),
+                None => sender,
+            };
+
+            // a cipher per sender, each with nonces of its own
+            let sender = match &cfg.tcp_encryption_key {
+                Some(key) => sender.with_encryption(PayloadCipher::from_hex(key).unwrap()),
+                None => sender,
+            };
+
+            match &cfg.compression {
+                Some(policy) => sender.with_compression(policy.clone()),
+                None => sender,
+            }
+        };
+
+        // the unix socket path, if set, replaces the port
+        let new_sender = |transport: Transport,
+                          port: u16,
+                          uds_path: Option<&str>,
+                          listener: Listener|
+         -> Box<dyn Publisher> {
+            let addr = SocketAddr::new(bind_ip, port);
+            match transport {
+                Transport::Tcp => {
+                    let sender = new_tcp_sender();
+                    // only the main stream is logged
+                    let sender = match &cfg.tcp_wal {
+                        Some(wal) if listener == Listener::Main => {
+                            sender.with_wal(WriteAheadLog::open(wal.clone()).unwrap())
+                        }
+                        _ => sender,
+                    };
+                    let sender = match &cfg.tcp_dead_letters {
+                        Some(dead_letters) if listener == Listener::Main => sender
+                            .with_dead_letters(DeadLetters::open(dead_letters.clone()).unwrap()),
+                        _ => sender,
+                    };
+                    match uds_path {
+                        Some(path) => sender
+                            .bind_unix(Path::new(path), cfg.tcp_buffer_size)
+                            .unwrap(),
+                        None => sender.bind_addr(addr, cfg.tcp_buffer_size).unwrap(),
+                    }
+                    Box::new(sender)
+                }
+                Transport::Zmq => {
+                    let endpoint = match uds_path {
+                        Some(path) => format!("ipc://{}", path),
+                        None => format!("tcp://{}", addr),
+                    };
+                    let sender = ZmqSender::bind(&endpoint, cfg.tcp_buffer_size as i32).unwrap();
+                    match &cfg.compression {
+                        Some(policy) => Box::new(sender.with_compression(policy.clone())),
+                        None => Box::new(sender),
+                    }
+                }
+                Transport::Nats => {
+                    let url = cfg
+                        .nats_url
+                        .as_deref()
+                        .expect("nats_url is required by nats");
+                    let prefix = cfg.nats_subject_prefix.as_deref().unwrap_or("geyser");
+                    // the vote subjects are under the main prefix, persisted by its stream
+                    let sender = match listener {
+                        Listener::Votes => {
+                            NatsSender::connect(url, &format!("{}.vote", prefix), None)
+                        }
+                        Listener::Control => {
+                            NatsSender::connect(url, &format!("{}.control", prefix), None)
+                        }
+                        _ => NatsSender::connect(url, prefix, cfg.nats_stream.as_deref()),
+                    }
+                    .unwrap();
+                    match &cfg.compression {
+                        Some(policy) => Box::new(sender.with_compression(policy.clone())),
+                        None => Box::new(sender),
+                    }
+                }
+                Transport::Ws => {
+                    if uds_path.is_some() {
+                        warn!("[on_load] - uds_path is ignored by the ws transport");
+                    }
+                    let sender = WsSender::bind(addr, cfg.tcp_buffer_size).unwrap();
+                    match &cfg.compression {
+                        Some(policy) => Box::new(sender.with_compression(policy.clone())),
+                        None => Box::new(sender),
+                    }
+                }
+                Transport::Quic => {
+                    if uds_path.is_some() {
+                        warn!("[on_load] - uds_path is ignored by the quic transport");
+                    }
+                    let tls = tls.clone().expect("tls is required by quic");
+                    let sender = QuicSender::bind(addr, tls, cfg.tcp_buffer_size).unwrap();
+                    match &cfg.compression {
+                        Some(policy) => Box::new(sender.with_compression(policy.clone())),
+                        None => Box::new(sender),
+                    }
+                }
+            }
+        };
+
+        let transport = cfg.transport.unwrap_or_default();
+        let socket = new_sender(
+            transport,
+            cfg.tcp_port,
+            cfg.uds_path.as_deref(),
+            Listener::Main,
+        );
+        let socket = match &cfg.tcp_shard_ports {
+            Some(ports) if !ports.is_empty() => {
+                let shards = ports
+                    .iter()
+                    .map(|port| new_sender(transport, *port, None, Listener::Endpoint))
+                    .collect();
+
+                info!("[on_load] - {} shards created", ports.len());
+
+                Box::new(Router::new(socket).with_route(None, Box::new(Sharder::new(shards))))
+            }
+            _ => socket,
+        };
+        let socket = match &cfg.endpoints {
+            Some(endpoints) if !endpoints.is_empty() => {
+                let router = endpoints
+                    .iter()
+                    .fold(Router::new(socket), |router, endpoint| {
+                        let sender = new_sender(
+                            endpoint.transport.unwrap_or(transport),
+                            endpoint
+                                .port
+                                .or(endpoint.uds_path.as_ref().map(|_| 0))
+                                .expect("endpoints need a port or a uds_path"),
+                            endpoint.uds_path.as_deref(),
+                            Listener::Endpoint,
+                        );
+                        router.with_route(endpoint.message_types.clone(), sender)
+                    });
+
+                info!("[on_load] - {} more endpoints created", endpoints.len());
+
+                Box::new(router)
+            }
+            _ => socket,
+        };
 
         info!("[on_load] - socket created");
 
+        let vote_socket = cfg.vote_tcp_port.map(|port| {
+            let vote_transport = cfg.vote_transport.unwrap_or(transport);
+            let vote_socket = new_sender(vote_transport, port, None, Listener::Votes);
+
+            info!("[on_load] - vote socket created");
+
+            vote_socket
+        });
+
+        let control_socket = cfg.control_tcp_port.map(|port| {
+            let control_socket = new_sender(transport, port, None, Listener::Control);
+
+            info!("[on_load] - control socket created");
+
+            control_socket
+        });
+
+        let snapshot_exporter = cfg.startup_snapshot_dir.as_ref().map(|dir| {
+            SnapshotExporter::new(
+                dir.into(),
+                cfg.startup_snapshot_segment_bytes
+                    .unwrap_or(DEFAULT_SEGMENT_MAX_BYTES),
+            )
+            .unwrap()
+        });
+
+        let program_stats = cfg.program_stats_top_n.map(ProgramStats::new);
+        let account_dedup = cfg.skip_unchanged_accounts.unwrap_or(false).then(|| {
+            AccountDedup::new(
+                cfg.skip_unchanged_accounts_capacity
+                    .unwrap_or(DEFAULT_DEDUP_CAPACITY),
+            )
+        });
+
+        let (block_backfill, backfill_fetcher) = match &cfg.block_backfill_rpc_url {
+            Some(rpc_url) => {
+                let (block_backfill, slots) = BlockBackfill::new();
+                (
+                    Some(block_backfill),
+                    Some((BlockFetcher::new(rpc_url.clone()), slots)),
+                )
+            }
+            None => (None, None),
+        };
+
+        let sinks = Sinks::default();
+        for sink in cfg.sinks.iter().flatten() {
+            sinks.add(sink.build().unwrap());
+        }
+
         let plugin = Arc::new(Inner {
             socket,
+            vote_socket,
+            control_socket,
             metrics: metrics.clone(),
             config: cfg,
+            monotonicity: MonotonicityChecker::default(),
+            snapshot_exporter,
+            program_stats,
+            account_dedup,
+            confirmed_slots: ConfirmedSlots::default(),
+            duplicate_blocks: DuplicateBlockDetector::default(),
+            block_backfill,
+            sinks,
         });
 
         self.0 = Some(plugin.clone());
 
+        if let Some(alerts) = &plugin.config.alerts {
+            Alerter::new(alerts.clone()).spawn(metrics.clone());
+        }
+
+        if let Some(pushgateway) = &plugin.config.pushgateway {
+            Pushgateway::new(pushgateway).spawn(metrics.clone());
+        }
+
+        if let Some(admin) = &plugin.config.admin {
+            admin::spawn(admin, plugin.clone()).unwrap();
+        }
+
+        if let Some((fetcher, slots)) = backfill_fetcher {
+            let plugin = plugin.clone();
+            thread::spawn(move || plugin.backfill_blocks(fetcher, slots));
+        }
+
+        if let Some(interval) = plugin.config.filter_state_interval_secs {
+            let plugin = plugin.clone();
+            thread::spawn(move || plugin.broadcast_filter_state(Duration::from_secs(interval)));
+        }
+
         thread::spawn(move || loop {
             let data = serialize_metadata(metrics.send_errs.load(Ordering::Relaxed));
-            if let Err(e) = plugin.socket.publish(data) {
+            let socket = plugin.control_socket.as_ref().unwrap_or(&plugin.socket);
+            if let Err(e) = socket.publish(data) {
                 info!("{}", e);
             }
 
             info!("{}", metrics);
+            if plugin.config.tcp_message_ttl_ms.is_some() {
+                info!("expired_messages={}", plugin.socket.expired_messages());
+            }
+            if plugin.config.tcp_max_connections.is_some() || plugin.config.tcp_ip_filter.is_some()
+            {
+                info!(
+                    "rejected_connections={}",
+                    plugin.socket.rejected_connections()
+                );
+            }
+            let stalled_connections = plugin.socket.stalled_connections();
+            if stalled_connections > 0 {
+                info!("stalled_conns={}", stalled_connections);
+            }
+            if plugin.config.tcp_writer_watchdog_ms.is_some() {
+                info!("hung_conns={}", plugin.socket.hung_connections());
+            }
+            if plugin.config.tcp_strict_delivery_timeout_ms.is_some() {
+                info!("abandoned_batches={}", plugin.socket.abandoned_batches());
+            }
+            let listener_recoveries = plugin.socket.listener_recoveries();
+            if listener_recoveries > 0 {
+                info!("listener_recoveries={}", listener_recoveries);
+            }
+            let writer_panics = plugin.socket.writer_panics();
+            if writer_panics > 0 {
+                info!("writer_panics={}", writer_panics);
+            }
+            if let Ok(subscribers) = plugin.socket.subscribers() {
+                for subscriber in subscribers {
+                    info!(
+                        "subscriber {}: sent_batches={} sent_bytes={} dropped_batches={} expired_batches={} pending_batches={} filtered_messages={}",
+                        subscriber.label(),
+                        subscriber.sent_batches,
+                        subscriber.sent_bytes,
+                        subscriber.dropped_batches,
+                        subscriber.expired_batches,
+                        subscriber.pending_batches,
+                        subscriber.filtered_messages
+                    );
+                }
+            }
+            for sink in plugin.sinks.status() {
+                info!(
+                    "sink {} ({:?}): sent_events={} dropped_events={} failed_events={}",
+                    sink.name,
+                    sink.state,
+                    sink.sent_events,
+                    sink.dropped_events,
+                    sink.failed_events
+                );
+            }
+            if let Some(program_stats) = &plugin.program_stats {
+                for (program, traffic) in program_stats.take_top() {
+                    info!(
+                        "program {}: messages={} bytes={}",
+                        program, traffic.messages, traffic.bytes
+                    );
+                }
+            }
             thread::sleep(Duration::from_secs(10));
         });
 
@@ -130,12 +756,39 @@ impl GeyserPlugin for GeyserPluginHook {
 
     /// Lifecycle: the plugin will be unloaded by the plugin manager
     /// Note: Do any cleanup necessary.
-    fn on_unload(&mut self) {}
+    /// Flushes the buffered messages and the subscribers' queues, up to tcp_drain_timeout_ms,
+    /// then closes the listeners and the connections.
+    fn on_unload(&mut self) {
+        let Some(inner) = self.0.take() else {
+            return;
+        };
+
+        let timeout = Duration::from_millis(
+            inner
+                .config
+                .tcp_drain_timeout_ms
+                .unwrap_or(DEFAULT_DRAIN_TIMEOUT_MS),
+        );
+        let sockets = std::iter::once(&inner.socket)
+            .chain(&inner.vote_socket)
+            .chain(&inner.control_socket);
+        for socket in sockets {
+            match socket.drain(timeout) {
+                Ok(0) => {}
+                Ok(abandoned) => warn!("[on_unload] - {} bytes abandoned", abandoned),
+                Err(e) => error!("[on_unload] - error draining: {}", e),
+            }
+            socket.shutdown();
+        }
+
+        info!("[on_unload] - sockets closed");
+    }
 
     /// Event: an account has been updated at slot
     /// - When `is_startup` is true, it indicates the account is loaded from
-    /// snapshots when the validator starts up.
+    ///   snapshots when the validator starts up.
     /// - When `is_startup` is false, the account is updated during transaction processing.
+    ///
     /// Note: The account is versioned, so you can decide how to handle the different
     /// implementations.
     fn update_account(
@@ -144,16 +797,72 @@ impl GeyserPlugin for GeyserPluginHook {
         slot: u64,
         is_startup: bool,
     ) -> Result<()> {
-        if is_startup {
-            return Ok(());
-        }
-
         self.with_inner(
             || GeyserPluginError::AccountsUpdateError { msg: UNINIT.into() },
             |inner| {
-                let data =
-                    serialize_account(&AccountUpdate::from_account(account, slot, is_startup)?);
-                inner.socket.publish(data)?;
+                if is_startup {
+                    if let Some(exporter) = &inner.snapshot_exporter {
+                        let account = AccountUpdate::from_account(account, slot, is_startup)?;
+                        exporter.append(serialize_account(&account))?;
+                    } else {
+                        inner
+                            .metrics
+                            .skipped_startup_accounts
+                            .fetch_add(1, Ordering::Relaxed);
+                    }
+
+                    return Ok(());
+                }
+
+                let account = AccountUpdate::from_account(account, slot, is_startup)?;
+                inner.report_order_violation(inner.monotonicity.check_write_version(
+                    slot,
+                    &account.key,
+                    account.write_version,
+                ))?;
+
+                if let Some(account_dedup) = &inner.account_dedup {
+                    if account_dedup.is_unchanged(
+                        &account.key,
+                        account.lamports,
+                        &account.owner,
+                        account.executable,
+                        account.data,
+                    ) {
+                        inner
+                            .metrics
+                            .skipped_unchanged_accounts
+                            .fetch_add(1, Ordering::Relaxed);
+                        return Ok(());
+                    }
+                }
+
+                inner.publish_event(|| Event::from_account(&account));
+                if inner.skips_unsubscribed(&*inner.socket) {
+                    return Ok(());
+                }
+
+                // v1 is also published while nobody is connected, to keep it the default
+                let mut bytes = 0;
+                let send_v2 = inner.socket.has_subscribers_with_schema(SCHEMA_VERSION_V2);
+                if !send_v2 || inner.socket.has_subscribers_with_schema(SCHEMA_VERSION_V1) {
+                    let data = serialize_account(&account);
+                    bytes += data.len();
+                    inner
+                        .socket
+                        .publish_for_schema(data, Some(SCHEMA_VERSION_V1))?;
+                }
+                if send_v2 {
+                    let data = serialize_account_v2(&account);
+                    bytes += data.len();
+                    inner
+                        .socket
+                        .publish_for_schema(data, Some(SCHEMA_VERSION_V2))?;
+                }
+
+                if let Some(program_stats) = &inner.program_stats {
+                    program_stats.record([&account.owner], bytes);
+                }
 
                 Ok(())
             },
@@ -163,7 +872,16 @@ impl GeyserPlugin for GeyserPluginHook {
     /// Lifecycle: called when all accounts have been notified when the validator
     /// restores the AccountsDb from snapshots at startup.
     fn notify_end_of_startup(&self) -> Result<()> {
-        Ok(())
+        self.with_inner(
+            || GeyserPluginError::AccountsUpdateError { msg: UNINIT.into() },
+            |inner| {
+                if let Some(exporter) = &inner.snapshot_exporter {
+                    exporter.finish(PLUGIN_VERSION, SCHEMA_HASH)?;
+                }
+
+                Ok(())
+            },
+        )
     }
 
     /// Event: a slot status is updated.
@@ -171,9 +889,51 @@ impl GeyserPlugin for GeyserPluginHook {
         self.with_inner(
             || GeyserPluginError::SlotStatusUpdateError { msg: UNINIT.into() },
             |inner| {
+                inner.report_order_violation(inner.monotonicity.check_slot(slot, &status))?;
+
+                match status {
+                    SlotStatus::Processed => {
+                        inner
+                            .metrics
+                            .processed_slot
+                            .fetch_max(slot, Ordering::Relaxed);
+                    }
+                    SlotStatus::Rooted => {
+                        inner.metrics.rooted_slot.fetch_max(slot, Ordering::Relaxed);
+                    }
+                    SlotStatus::Confirmed => {}
+                }
+
+                if let (Some(block_backfill), SlotStatus::Rooted) = (&inner.block_backfill, &status)
+                {
+                    block_backfill.on_rooted(slot);
+                }
+
+                let rooted = if inner.config.send_slot_rooted.unwrap_or(false) {
+                    match status {
+                        SlotStatus::Confirmed => {
+                            inner.confirmed_slots.confirm(slot);
+                            false
+                        }
+                        SlotStatus::Rooted => inner.confirmed_slots.root(slot),
+                        SlotStatus::Processed => false,
+                    }
+                } else {
+                    false
+                };
+
+                inner.publish_event(|| Event::from_slot(slot, parent, &status));
+                if inner.skips_unsubscribed(&*inner.socket) {
+                    return Ok(());
+                }
+
                 let data = serialize_slot(slot, parent, status);
                 inner.socket.publish(data)?;
 
+                if rooted {
+                    inner.socket.publish(serialize_slot_rooted(slot))?;
+                }
+
                 Ok(())
             },
         )
@@ -190,16 +950,50 @@ impl GeyserPlugin for GeyserPluginHook {
             || GeyserPluginError::TransactionUpdateError { msg: UNINIT.into() },
             |inner| {
                 let tx_update = TransactionUpdate::from_transaction(transaction, slot);
+                if let Some(block_backfill) = &inner.block_backfill {
+                    block_backfill.on_transaction(slot);
+                }
+                inner.report_order_violation(
+                    inner.monotonicity.check_tx_index(slot, tx_update.index),
+                )?;
 
-                if inner.config.skip_vote_txs && tx_update.is_vote {
-                    return Ok(());
+                if tx_update.is_vote {
+                    if let Some(vote_socket) = &inner.vote_socket {
+                        if inner.skips_unsubscribed(&**vote_socket) {
+                            return Ok(());
+                        }
+                        let data = serialize_transaction(&tx_update)?;
+                        vote_socket.publish(data)?;
+
+                        return Ok(());
+                    }
+
+                    if inner.config.skip_vote_txs {
+                        inner
+                            .metrics
+                            .skipped_vote_txs
+                            .fetch_add(1, Ordering::Relaxed);
+                        return Ok(());
+                    }
                 }
 
                 if inner.config.skip_deploy_txs && tx_update.is_deploy_tx() {
+                    inner
+                        .metrics
+                        .skipped_deploy_txs
+                        .fetch_add(1, Ordering::Relaxed);
+                    return Ok(());
+                }
+
+                inner.publish_event(|| Event::from_transaction(&tx_update));
+                if inner.skips_unsubscribed(&*inner.socket) {
                     return Ok(());
                 }
 
                 let data = serialize_transaction(&tx_update)?;
+                if let Some(program_stats) = &inner.program_stats {
+                    program_stats.record(&tx_update.program_ids(), data.len());
+                }
                 inner.socket.publish(data)?;
 
                 Ok(())
@@ -211,14 +1005,16 @@ impl GeyserPlugin for GeyserPluginHook {
         self.with_inner(
             || GeyserPluginError::SlotStatusUpdateError { msg: UNINIT.into() },
             |inner| {
-                if !inner.config.send_blocks {
-                    return Ok(());
-                }
+                let block: BlockUpdate = blockinfo.into();
+                inner.report_duplicate_block(
+                    inner.duplicate_blocks.check(block.slot, block.blockhash),
+                )?;
 
-                let data = serialize_block(&blockinfo.into());
-                inner.socket.publish(data)?;
+                if let Some(block_backfill) = &inner.block_backfill {
+                    block_backfill.on_block(block.slot);
+                }
 
-                Ok(())
+                inner.publish_block(&block)
             },
         )
     }
@@ -1,5 +1,17 @@
+mod account_dedup;
+mod admin;
+mod alerts;
+mod block_backfill;
+mod build_info;
 mod config;
+mod confirmed_slots;
+mod duplicate_blocks;
 mod entrypoint;
 mod fb_serializers;
 mod geyser_plugin_hook;
 mod metrics;
+mod monotonicity;
+mod program_stats;
+mod pushgateway;
+mod sinks;
+mod snapshot_export;
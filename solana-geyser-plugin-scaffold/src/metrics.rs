@@ -7,6 +7,23 @@ pub struct Metrics {
     pub sender_lock_errs: std::sync::atomic::AtomicU64,
     pub conn_lock_errs: std::sync::atomic::AtomicU64,
     pub untyped_errs: std::sync::atomic::AtomicU64,
+    pub slot_order_warns: std::sync::atomic::AtomicU64,
+    pub write_version_order_warns: std::sync::atomic::AtomicU64,
+    pub tx_index_order_warns: std::sync::atomic::AtomicU64,
+    pub duplicate_block_warns: std::sync::atomic::AtomicU64,
+    pub backfilled_blocks: std::sync::atomic::AtomicU64,
+    pub backfill_errs: std::sync::atomic::AtomicU64,
+    // messages dropped by the plugin filters, before serialization
+    pub skipped_startup_accounts: std::sync::atomic::AtomicU64,
+    pub skipped_vote_txs: std::sync::atomic::AtomicU64,
+    pub skipped_deploy_txs: std::sync::atomic::AtomicU64,
+    pub skipped_blocks: std::sync::atomic::AtomicU64,
+    pub skipped_unchanged_accounts: std::sync::atomic::AtomicU64,
+    // messages not serialized while nobody is subscribed, see skip_without_subscribers
+    pub skipped_unsubscribed: std::sync::atomic::AtomicU64,
+    // last slots notified with these statuses
+    pub processed_slot: std::sync::atomic::AtomicU64,
+    pub rooted_slot: std::sync::atomic::AtomicU64,
 }
 
 impl Metrics {
@@ -18,19 +35,67 @@ impl Metrics {
             sender_lock_errs: std::sync::atomic::AtomicU64::new(0),
             conn_lock_errs: std::sync::atomic::AtomicU64::new(0),
             untyped_errs: std::sync::atomic::AtomicU64::new(0),
+            slot_order_warns: std::sync::atomic::AtomicU64::new(0),
+            write_version_order_warns: std::sync::atomic::AtomicU64::new(0),
+            tx_index_order_warns: std::sync::atomic::AtomicU64::new(0),
+            duplicate_block_warns: std::sync::atomic::AtomicU64::new(0),
+            backfilled_blocks: std::sync::atomic::AtomicU64::new(0),
+            backfill_errs: std::sync::atomic::AtomicU64::new(0),
+            skipped_startup_accounts: std::sync::atomic::AtomicU64::new(0),
+            skipped_vote_txs: std::sync::atomic::AtomicU64::new(0),
+            skipped_deploy_txs: std::sync::atomic::AtomicU64::new(0),
+            skipped_blocks: std::sync::atomic::AtomicU64::new(0),
+            skipped_unchanged_accounts: std::sync::atomic::AtomicU64::new(0),
+            skipped_unsubscribed: std::sync::atomic::AtomicU64::new(0),
+            processed_slot: std::sync::atomic::AtomicU64::new(0),
+            rooted_slot: std::sync::atomic::AtomicU64::new(0),
         })
     }
+
+    /// Current value of every metric, by name.
+    pub fn values(&self) -> Vec<(&'static str, u64)> {
+        [
+            ("send_errs", &self.send_errs),
+            ("disconnect_errs", &self.disconnect_errs),
+            ("serialize_errs", &self.serialize_errs),
+            ("sender_lock_errs", &self.sender_lock_errs),
+            ("conn_lock_errs", &self.conn_lock_errs),
+            ("untyped_errs", &self.untyped_errs),
+            ("slot_order_warns", &self.slot_order_warns),
+            ("write_version_order_warns", &self.write_version_order_warns),
+            ("tx_index_order_warns", &self.tx_index_order_warns),
+            ("duplicate_block_warns", &self.duplicate_block_warns),
+            ("backfilled_blocks", &self.backfilled_blocks),
+            ("backfill_errs", &self.backfill_errs),
+            ("skipped_startup_accounts", &self.skipped_startup_accounts),
+            ("skipped_vote_txs", &self.skipped_vote_txs),
+            ("skipped_deploy_txs", &self.skipped_deploy_txs),
+            ("skipped_blocks", &self.skipped_blocks),
+            (
+                "skipped_unchanged_accounts",
+                &self.skipped_unchanged_accounts,
+            ),
+            ("skipped_unsubscribed", &self.skipped_unsubscribed),
+            ("processed_slot", &self.processed_slot),
+            ("rooted_slot", &self.rooted_slot),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name, value.load(std::sync::atomic::Ordering::Relaxed)))
+        .collect()
+    }
+
+    /// Whether the metric is a gauge rather than a counter.
+    pub fn is_gauge(name: &str) -> bool {
+        matches!(name, "processed_slot" | "rooted_slot")
+    }
 }
 
 impl std::fmt::Display for Metrics {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        fmt.debug_struct("geyser-metrics")
-            .field("send_errs", &self.send_errs)
-            .field("disconnect_errs", &self.disconnect_errs)
-            .field("serialize_errs", &self.serialize_errs)
-            .field("sender_lock_errs", &self.sender_lock_errs)
-            .field("conn_lock_errs", &self.conn_lock_errs)
-            .field("untyped_errs", &self.untyped_errs)
-            .finish()
+        let mut metrics = fmt.debug_struct("geyser-metrics");
+        for (name, value) in self.values() {
+            metrics.field(name, &value);
+        }
+        metrics.finish()
     }
 }
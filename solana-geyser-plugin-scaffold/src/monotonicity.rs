@@ -0,0 +1,225 @@
+use lru::LruCache;
+use solana_geyser_plugin_interface::geyser_plugin_interface::SlotStatus;
+use solana_sdk::pubkey::Pubkey;
+use std::{
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+use utils::flatbuffer::diagnostic_generated::diagnostic::Kind;
+
+/// Spread the accounts and slots over several locks, the validator notifies from many threads
+const SHARDS: usize = 16;
+/// Accounts whose last write_version is remembered, the least recently written are forgotten
+const WRITE_VERSION_CAPACITY: usize = 100_000;
+/// Diagnostics of a kind published at most once per interval
+const DIAGNOSTIC_INTERVAL_MS: u64 = 1000;
+
+/// An ordering the validator is not expected to produce.
+pub struct OrderViolation {
+    pub kind: Kind,
+    pub slot: u64,
+    pub previous: u64,
+    pub current: u64,
+}
+
+/// Lightweight checks that the validator delivers notifications in the expected order:
+/// - rooted and confirmed slots never go backwards (processed slots can, due to forks)
+/// - the write_versions of an account are non-decreasing
+/// - transaction indices are non-decreasing within a slot
+///
+/// Account and transaction notifications are sent from several replay threads, so a small
+/// amount of reordering is possible, the violations are meant to feed warning metrics and
+/// rate limited diagnostics only.
+pub struct MonotonicityChecker {
+    last_rooted_slot: AtomicU64,
+    last_confirmed_slot: AtomicU64,
+    write_versions: Vec<Mutex<LruCache<Pubkey, u64>>>,
+    // (slot, highest index) of the last transactions of the slots in each shard
+    last_txs: Vec<Mutex<(u64, usize)>>,
+    // when a diagnostic of each kind was last published, in ms since the epoch
+    last_published_ms: [AtomicU64; 3],
+}
+
+impl Default for MonotonicityChecker {
+    fn default() -> Self {
+        let per_shard = NonZeroUsize::new(WRITE_VERSION_CAPACITY / SHARDS).unwrap();
+        MonotonicityChecker {
+            last_rooted_slot: AtomicU64::new(0),
+            last_confirmed_slot: AtomicU64::new(0),
+            write_versions: (0..SHARDS)
+                .map(|_| Mutex::new(LruCache::new(per_shard)))
+                .collect(),
+            last_txs: (0..SHARDS).map(|_| Mutex::new((0, 0))).collect(),
+            last_published_ms: Default::default(),
+        }
+    }
+}
+
+impl MonotonicityChecker {
+    pub fn check_slot(&self, slot: u64, status: &SlotStatus) -> Option<OrderViolation> {
+        let last = match status {
+            SlotStatus::Rooted => &self.last_rooted_slot,
+            SlotStatus::Confirmed => &self.last_confirmed_slot,
+            SlotStatus::Processed => return None,
+        };
+
+        let previous = last.fetch_max(slot, Ordering::Relaxed);
+        (slot < previous).then_some(OrderViolation {
+            kind: Kind::SlotOrder,
+            slot,
+            previous,
+            current: slot,
+        })
+    }
+
+    pub fn check_write_version(
+        &self,
+        slot: u64,
+        pubkey: &Pubkey,
+        write_version: u64,
+    ) -> Option<OrderViolation> {
+        let shard = &self.write_versions[pubkey.as_ref()[0] as usize % SHARDS];
+        let mut write_versions = shard.lock().unwrap_or_else(|e| e.into_inner());
+        let last = write_versions.get_or_insert_mut(*pubkey, || write_version);
+        let previous = *last;
+        *last = previous.max(write_version);
+
+        (write_version < previous).then_some(OrderViolation {
+            kind: Kind::WriteVersionOrder,
+            slot,
+            previous,
+            current: write_version,
+        })
+    }
+
+    pub fn check_tx_index(&self, slot: u64, index: Option<usize>) -> Option<OrderViolation> {
+        let index = index?;
+        // a poisoned lock only means another check panicked, the state is still usable
+        let mut last = self.last_txs[slot as usize % SHARDS]
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let (last_slot, last_index) = *last;
+
+        if slot != last_slot {
+            // a slot that old is done with, its transactions arriving late on another fork
+            if slot > last_slot {
+                *last = (slot, index);
+            }
+
+            return None;
+        }
+
+        if index < last_index {
+            return Some(OrderViolation {
+                kind: Kind::TxIndexOrder,
+                slot,
+                previous: last_index as u64,
+                current: index as u64,
+            });
+        }
+
+        last.1 = index;
+        None
+    }
+
+    /// Whether to publish a diagnostic for `violation`, at most one of each kind per
+    /// `DIAGNOSTIC_INTERVAL_MS`, as bursts of reordering are common under load.
+    pub fn should_publish(&self, violation: &OrderViolation) -> bool {
+        let last_published_ms = match violation.kind {
+            Kind::WriteVersionOrder => &self.last_published_ms[0],
+            Kind::TxIndexOrder => &self.last_published_ms[1],
+            _ => &self.last_published_ms[2],
+        };
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+
+        let previous = last_published_ms.load(Ordering::Relaxed);
+        now_ms >= previous + DIAGNOSTIC_INTERVAL_MS
+            && last_published_ms
+                .compare_exchange(previous, now_ms, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slot_order() {
+        let checker = MonotonicityChecker::default();
+
+        assert!(checker.check_slot(10, &SlotStatus::Rooted).is_none());
+        assert!(checker.check_slot(11, &SlotStatus::Rooted).is_none());
+        let violation = checker.check_slot(9, &SlotStatus::Rooted).unwrap();
+        assert_eq!(violation.kind, Kind::SlotOrder);
+        assert_eq!((violation.previous, violation.current), (11, 9));
+
+        // confirmed slots are tracked apart, processed ones not at all
+        assert!(checker.check_slot(5, &SlotStatus::Confirmed).is_none());
+        assert!(checker.check_slot(1, &SlotStatus::Processed).is_none());
+        assert!(checker.check_slot(4, &SlotStatus::Confirmed).is_some());
+    }
+
+    #[test]
+    fn test_write_version_order() {
+        let checker = MonotonicityChecker::default();
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        assert!(checker.check_write_version(1, &first, 100).is_none());
+        assert!(checker.check_write_version(1, &first, 100).is_none());
+        let violation = checker.check_write_version(2, &first, 99).unwrap();
+        assert_eq!(violation.kind, Kind::WriteVersionOrder);
+        assert_eq!((violation.slot, violation.previous), (2, 100));
+        // the highest version seen is kept
+        assert!(checker.check_write_version(2, &first, 99).is_some());
+
+        // accounts are apart, as they are written from different threads
+        assert!(checker.check_write_version(2, &second, 50).is_none());
+        assert!(checker.check_write_version(2, &first, 101).is_none());
+    }
+
+    #[test]
+    fn test_tx_index_order() {
+        let checker = MonotonicityChecker::default();
+
+        assert!(checker.check_tx_index(10, None).is_none());
+        assert!(checker.check_tx_index(10, Some(3)).is_none());
+        let violation = checker.check_tx_index(10, Some(2)).unwrap();
+        assert_eq!(violation.kind, Kind::TxIndexOrder);
+        assert_eq!((violation.previous, violation.current), (3, 2));
+
+        // slots are apart, a late one which was since forgotten is ignored
+        assert!(checker.check_tx_index(11, Some(0)).is_none());
+        assert!(checker.check_tx_index(10, Some(1)).is_some());
+        assert!(checker.check_tx_index(11, Some(1)).is_none());
+        assert!(checker
+            .check_tx_index(10 + SHARDS as u64, Some(0))
+            .is_none());
+        assert!(checker.check_tx_index(10, Some(0)).is_none());
+    }
+
+    #[test]
+    fn test_diagnostics_rate_limit() {
+        let checker = MonotonicityChecker::default();
+        let violation = |kind| OrderViolation {
+            kind,
+            slot: 1,
+            previous: 2,
+            current: 1,
+        };
+
+        assert!(checker.should_publish(&violation(Kind::TxIndexOrder)));
+        assert!(!checker.should_publish(&violation(Kind::TxIndexOrder)));
+        // kinds are limited apart
+        assert!(checker.should_publish(&violation(Kind::WriteVersionOrder)));
+        assert!(checker.should_publish(&violation(Kind::SlotOrder)));
+        assert!(!checker.should_publish(&violation(Kind::SlotOrder)));
+    }
+}
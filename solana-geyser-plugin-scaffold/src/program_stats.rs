@@ -0,0 +1,78 @@
+use solana_sdk::pubkey::Pubkey;
+use std::{collections::HashMap, sync::Mutex};
+
+#[derive(Default, Debug, Clone, Copy)]
+pub struct ProgramTraffic {
+    pub messages: u64,
+    pub bytes: u64,
+}
+
+/// Messages and bytes published per account owner and invoked program, over a window reset
+/// every time the top programs are taken. Helps deciding what to filter out when bandwidth
+/// is tight.
+pub struct ProgramStats {
+    top_n: usize,
+    window: Mutex<HashMap<Pubkey, ProgramTraffic>>,
+}
+
+impl ProgramStats {
+    pub fn new(top_n: usize) -> Self {
+        ProgramStats {
+            top_n,
+            window: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Counts a published message for every program, duplicates included.
+    pub fn record<'a>(&self, programs: impl IntoIterator<Item = &'a Pubkey>, bytes: usize) {
+        let mut window = self.window.lock().unwrap_or_else(|e| e.into_inner());
+        for program in programs {
+            let traffic = window.entry(*program).or_default();
+            traffic.messages += 1;
+            traffic.bytes += bytes as u64;
+        }
+    }
+
+    /// The top programs by bytes since the last call, starting a new window.
+    pub fn take_top(&self) -> Vec<(Pubkey, ProgramTraffic)> {
+        let window = {
+            let mut window = self.window.lock().unwrap_or_else(|e| e.into_inner());
+            std::mem::take(&mut *window)
+        };
+
+        let mut top = window.into_iter().collect::<Vec<_>>();
+        top.sort_unstable_by_key(|(_, traffic)| std::cmp::Reverse(traffic.bytes));
+        top.truncate(self.top_n);
+
+        top
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_top() {
+        let stats = ProgramStats::new(2);
+        let (a, b, c) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+
+        stats.record([&a, &b], 100);
+        stats.record([&b, &b], 10);
+        stats.record([&c], 50);
+
+        let top = stats
+            .take_top()
+            .into_iter()
+            .map(|(program, traffic)| (program, traffic.messages, traffic.bytes))
+            .collect::<Vec<_>>();
+        assert_eq!(top, [(b, 3, 120), (a, 1, 100)]);
+
+        // a new window
+        assert!(stats.take_top().is_empty());
+    }
+}
@@ -0,0 +1,81 @@
+use crate::metrics::Metrics;
+use log::warn;
+use reqwest::blocking::Client;
+use serde::Deserialize;
+use std::{fmt::Write, sync::Arc, thread, time::Duration};
+
+const DEFAULT_JOB: &str = "solana_geyser_plugin";
+const DEFAULT_INTERVAL_SECS: u64 = 15;
+const METRIC_PREFIX: &str = "solana_geyser_";
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PushgatewayConfig {
+    // e.g. "http://pushgateway:9091"
+    pub url: String,
+    // "solana_geyser_plugin" by default
+    pub job: Option<String>,
+    // added to the grouping key if set, e.g. the validator identity
+    pub instance: Option<String>,
+    // 15 by default
+    pub interval_secs: Option<u64>,
+}
+
+/// Pushes the metrics to a Prometheus Pushgateway, for hosts that cannot be scraped.
+pub struct Pushgateway {
+    url: String,
+    interval: Duration,
+    client: Client,
+}
+
+impl Pushgateway {
+    pub fn new(config: &PushgatewayConfig) -> Self {
+        let mut url = format!(
+            "{}/metrics/job/{}",
+            config.url.trim_end_matches('/'),
+            config.job.as_deref().unwrap_or(DEFAULT_JOB)
+        );
+        if let Some(instance) = &config.instance {
+            url = format!("{}/instance/{}", url, instance);
+        }
+
+        Pushgateway {
+            url,
+            interval: Duration::from_secs(config.interval_secs.unwrap_or(DEFAULT_INTERVAL_SECS)),
+            client: Client::new(),
+        }
+    }
+
+    pub fn spawn(self, metrics: Arc<Metrics>) {
+        thread::spawn(move || loop {
+            // PUT replaces every metric of the group, so none is left over from a previous push
+            let pushed = self
+                .client
+                .put(&self.url)
+                .header("Content-Type", "text/plain; version=0.0.4")
+                .body(render(&metrics))
+                .send()
+                .and_then(|response| response.error_for_status());
+            if let Err(e) = pushed {
+                warn!("[pushgateway] - failed to push the metrics: {}", e);
+            }
+
+            thread::sleep(self.interval);
+        });
+    }
+}
+
+/// The metrics in the Prometheus text format.
+fn render(metrics: &Metrics) -> String {
+    let mut body = String::new();
+    for (name, value) in metrics.values() {
+        let kind = if Metrics::is_gauge(name) {
+            "gauge"
+        } else {
+            "counter"
+        };
+        let _ = writeln!(body, "# TYPE {}{} {}", METRIC_PREFIX, name, kind);
+        let _ = writeln!(body, "{}{} {}", METRIC_PREFIX, name, value);
+    }
+
+    body
+}
@@ -0,0 +1,169 @@
+use super::{ClosedSink, Event, Sink, SinkStats};
+use log::warn;
+use serde::Deserialize;
+use std::{
+    sync::{
+        atomic::Ordering,
+        mpsc::{self, RecvTimeoutError, SyncSender, TrySendError},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+const DEFAULT_BATCH_SIZE: usize = 100;
+const DEFAULT_BATCH_INTERVAL_MS: u64 = 1000;
+const DEFAULT_QUEUE_SIZE: usize = 10_000;
+const DEFAULT_MAX_RETRIES: u32 = 5;
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+const MAX_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Batching options shared by the sinks, flattened into their configs.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct BatchOptions {
+    // events written at once, 100 by default
+    pub batch_size: Option<usize>,
+
+    // max time an event waits for its batch to fill up, 1000ms by default
+    pub batch_interval_ms: Option<u64>,
+
+    // events queued for the sink before new ones are dropped, 10000 by default
+    pub queue_size: Option<usize>,
+
+    // retries of a failed batch, with exponential backoff, before it is dropped, 5 by default
+    pub max_retries: Option<u32>,
+}
+
+/// Writes batches of events to a destination, called from the sink's own thread.
+pub trait BatchWriter: Send + 'static {
+    fn write(&mut self, events: &[Arc<Event>]) -> anyhow::Result<()>;
+}
+
+/// Queues the events for a `BatchWriter`, so the validator is never blocked by a sink.
+pub struct BatchSink {
+    name: String,
+    queue: SyncSender<Arc<Event>>,
+    stats: Arc<SinkStats>,
+    worker: JoinHandle<()>,
+}
+
+impl BatchSink {
+    pub fn spawn(name: String, options: &BatchOptions, mut writer: impl BatchWriter) -> Self {
+        let batch_size = options.batch_size.unwrap_or(DEFAULT_BATCH_SIZE).max(1);
+        let batch_interval = Duration::from_millis(
+            options
+                .batch_interval_ms
+                .unwrap_or(DEFAULT_BATCH_INTERVAL_MS),
+        );
+        let max_retries = options.max_retries.unwrap_or(DEFAULT_MAX_RETRIES);
+
+        let (queue, events) =
+            mpsc::sync_channel::<Arc<Event>>(options.queue_size.unwrap_or(DEFAULT_QUEUE_SIZE));
+        let stats = Arc::new(SinkStats::default());
+
+        let thread_stats = stats.clone();
+        let thread_name = name.clone();
+        let worker = thread::spawn(move || {
+            let mut batch = Vec::with_capacity(batch_size);
+            let mut deadline = Instant::now() + batch_interval;
+
+            let mut closed = false;
+            while !closed {
+                match events.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                    Ok(event) => {
+                        batch.push(event);
+                        if batch.len() < batch_size {
+                            continue;
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    // the sink was closed, the queued events have been received
+                    Err(RecvTimeoutError::Disconnected) => closed = true,
+                }
+
+                if !batch.is_empty() {
+                    write_with_retries(
+                        &thread_name,
+                        &mut writer,
+                        &batch,
+                        max_retries,
+                        &thread_stats,
+                    );
+                    batch.clear();
+                }
+                deadline = Instant::now() + batch_interval;
+            }
+        });
+
+        BatchSink {
+            name,
+            queue,
+            stats,
+            worker,
+        }
+    }
+}
+
+impl Sink for BatchSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn send(&self, event: Arc<Event>) {
+        if let Err(TrySendError::Full(_)) = self.queue.try_send(event) {
+            self.stats.dropped_events.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn stats(&self) -> &SinkStats {
+        &self.stats
+    }
+
+    fn close(self: Box<Self>) -> ClosedSink {
+        drop(self.queue);
+
+        ClosedSink {
+            name: self.name,
+            stats: self.stats,
+            worker: self.worker,
+        }
+    }
+}
+
+fn write_with_retries(
+    name: &str,
+    writer: &mut impl BatchWriter,
+    batch: &[Arc<Event>],
+    max_retries: u32,
+    stats: &SinkStats,
+) {
+    let mut backoff = INITIAL_BACKOFF;
+    for attempt in 0..=max_retries {
+        match writer.write(batch) {
+            Ok(()) => {
+                stats
+                    .sent_events
+                    .fetch_add(batch.len() as u64, Ordering::Relaxed);
+                return;
+            }
+            Err(e) => {
+                warn!(
+                    "[sink {}] - failed to write {} events (attempt {}): {}",
+                    name,
+                    batch.len(),
+                    attempt + 1,
+                    e
+                );
+            }
+        }
+
+        if attempt < max_retries {
+            thread::sleep(backoff);
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+
+    stats
+        .failed_events
+        .fetch_add(batch.len() as u64, Ordering::Relaxed);
+}
@@ -0,0 +1,133 @@
+use super::{
+    batch::{BatchOptions, BatchWriter},
+    Event,
+};
+use reqwest::blocking::Client;
+use serde::Deserialize;
+use std::{sync::Arc, time::Duration};
+use utils::encoding::AccountDataEncoding;
+
+const DEFAULT_TIMEOUT_MS: u64 = 30_000;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ClickHouseTables {
+    pub accounts: String,
+    pub slots: String,
+    pub transactions: String,
+    pub blocks: String,
+}
+
+impl Default for ClickHouseTables {
+    fn default() -> Self {
+        ClickHouseTables {
+            accounts: "accounts".to_string(),
+            slots: "slots".to_string(),
+            transactions: "transactions".to_string(),
+            blocks: "blocks".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ClickHouseConfig {
+    // HTTP interface of the server, e.g. http://localhost:8123
+    pub url: String,
+    pub database: Option<String>,
+    pub user: Option<String>,
+    pub password: Option<String>,
+
+    // table of every event type, named after the event types by default
+    #[serde(default)]
+    pub tables: ClickHouseTables,
+
+    // 30000ms by default
+    pub timeout_ms: Option<u64>,
+
+    // encoding of the account data: "base64", the default, "base64+zstd" or "hex"
+    #[serde(default)]
+    pub encoding: AccountDataEncoding,
+
+    #[serde(flatten)]
+    pub batch: BatchOptions,
+}
+
+/// Inserts the events in the table of their type, as `JSONEachRow`: the columns are named
+/// after the event fields, account data is encoded as configured and fields without a column
+/// are ignored.
+pub struct ClickHouseWriter {
+    client: Client,
+    config: ClickHouseConfig,
+}
+
+impl ClickHouseWriter {
+    pub fn new(config: &ClickHouseConfig) -> anyhow::Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_millis(
+                config.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS),
+            ))
+            .build()?;
+
+        Ok(ClickHouseWriter {
+            client,
+            config: config.clone(),
+        })
+    }
+
+    fn table(&self, event: &Event) -> &str {
+        match event {
+            Event::Account { .. } => &self.config.tables.accounts,
+            Event::Slot { .. } => &self.config.tables.slots,
+            Event::Transaction { .. } => &self.config.tables.transactions,
+            Event::Block { .. } => &self.config.tables.blocks,
+        }
+    }
+
+    fn insert(&self, table: &str, rows: Vec<u8>) -> anyhow::Result<()> {
+        let mut request = self
+            .client
+            .post(&self.config.url)
+            .query(&[
+                ("query", format!("INSERT INTO {} FORMAT JSONEachRow", table)),
+                ("input_format_skip_unknown_fields", "1".to_string()),
+            ])
+            .body(rows);
+
+        if let Some(database) = &self.config.database {
+            request = request.header("X-ClickHouse-Database", database);
+        }
+        if let Some(user) = &self.config.user {
+            request = request.basic_auth(user, self.config.password.as_ref());
+        }
+
+        request.send()?.error_for_status()?;
+
+        Ok(())
+    }
+}
+
+impl BatchWriter for ClickHouseWriter {
+    fn write(&mut self, events: &[Arc<Event>]) -> anyhow::Result<()> {
+        // one insert per table, in the order the tables first appear in the batch
+        let mut inserts: Vec<(&str, Vec<u8>)> = Vec::new();
+        for event in events {
+            let table = self.table(event);
+            let i = match inserts.iter().position(|(t, _)| *t == table) {
+                Some(i) => i,
+                None => {
+                    inserts.push((table, Vec::new()));
+                    inserts.len() - 1
+                }
+            };
+            let rows = &mut inserts[i].1;
+
+            serde_json::to_writer(&mut *rows, &event.encoded(self.config.encoding))?;
+            rows.push(b'\n');
+        }
+
+        for (table, rows) in inserts {
+            self.insert(table, rows)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,139 @@
+use super::{
+    batch::{BatchOptions, BatchWriter},
+    Event,
+};
+use anyhow::anyhow;
+use chrono::Utc;
+use reqwest::blocking::Client;
+use serde::{Deserialize, Serialize};
+use std::{io::Write, sync::Arc, time::Duration};
+
+const DEFAULT_INDEX: &str = "geyser-transactions-%Y.%m.%d";
+const DEFAULT_TIMEOUT_MS: u64 = 30_000;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ElasticsearchConfig {
+    // e.g. http://localhost:9200, OpenSearch works as well
+    pub url: String,
+
+    // strftime pattern of the index name, rotated daily by default:
+    // geyser-transactions-%Y.%m.%d
+    pub index: Option<String>,
+
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub api_key: Option<String>,
+
+    // 30000ms by default
+    pub timeout_ms: Option<u64>,
+
+    #[serde(flatten)]
+    pub batch: BatchOptions,
+}
+
+/// What is indexed of a transaction.
+#[derive(Serialize)]
+struct TransactionSummary<'a> {
+    #[serde(rename = "@timestamp")]
+    timestamp: &'a str,
+    signature: &'a str,
+    slot: u64,
+    index: Option<usize>,
+    is_vote: bool,
+    programs: &'a [String],
+    err: Option<&'a str>,
+    fee: u64,
+    compute_units_consumed: Option<u64>,
+}
+
+/// Indexes a summary of the transactions with the bulk API, using their signature as the
+/// document id so retried batches don't duplicate anything. Other events are ignored.
+pub struct ElasticsearchWriter {
+    client: Client,
+    config: ElasticsearchConfig,
+}
+
+impl ElasticsearchWriter {
+    pub fn new(config: &ElasticsearchConfig) -> anyhow::Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_millis(
+                config.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS),
+            ))
+            .build()?;
+
+        Ok(ElasticsearchWriter {
+            client,
+            config: config.clone(),
+        })
+    }
+}
+
+impl BatchWriter for ElasticsearchWriter {
+    fn write(&mut self, events: &[Arc<Event>]) -> anyhow::Result<()> {
+        let now = Utc::now();
+        let timestamp = now.to_rfc3339();
+        let index = now
+            .format(self.config.index.as_deref().unwrap_or(DEFAULT_INDEX))
+            .to_string();
+
+        let mut body = Vec::new();
+        for event in events {
+            if let Event::Transaction {
+                signature,
+                slot,
+                index: tx_index,
+                is_vote,
+                fee,
+                compute_units_consumed,
+                err,
+                programs,
+                ..
+            } = event.as_ref()
+            {
+                serde_json::to_writer(
+                    &mut body,
+                    &serde_json::json!({"index": {"_index": index, "_id": signature}}),
+                )?;
+                body.write_all(b"\n")?;
+                serde_json::to_writer(
+                    &mut body,
+                    &TransactionSummary {
+                        timestamp: &timestamp,
+                        signature,
+                        slot: *slot,
+                        index: *tx_index,
+                        is_vote: *is_vote,
+                        programs,
+                        err: err.as_deref(),
+                        fee: *fee,
+                        compute_units_consumed: *compute_units_consumed,
+                    },
+                )?;
+                body.write_all(b"\n")?;
+            }
+        }
+
+        if body.is_empty() {
+            return Ok(());
+        }
+
+        let mut request = self
+            .client
+            .post(format!("{}/_bulk", self.config.url.trim_end_matches('/')))
+            .header("Content-Type", "application/x-ndjson")
+            .body(body);
+        if let Some(username) = &self.config.username {
+            request = request.basic_auth(username, self.config.password.as_ref());
+        }
+        if let Some(api_key) = &self.config.api_key {
+            request = request.header("Authorization", format!("ApiKey {}", api_key));
+        }
+
+        let response: serde_json::Value = request.send()?.error_for_status()?.json()?;
+        if response["errors"].as_bool().unwrap_or(false) {
+            return Err(anyhow!("bulk request had failed items"));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,200 @@
+use crate::fb_serializers::update_types::{AccountUpdate, BlockUpdate, TransactionUpdate};
+use serde::{Serialize, Serializer};
+use solana_geyser_plugin_interface::geyser_plugin_interface::SlotStatus;
+use utils::encoding::AccountDataEncoding;
+
+/// Data published to the sinks, self-describing so it can go to text based outputs as is.
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    Account {
+        pubkey: String,
+        owner: String,
+        slot: u64,
+        lamports: u64,
+        rent_epoch: u64,
+        executable: bool,
+        write_version: u64,
+        #[serde(serialize_with = "serialize_base64")]
+        data: Vec<u8>,
+        txn_signature: Option<String>,
+    },
+    Slot {
+        slot: u64,
+        parent: Option<u64>,
+        status: &'static str,
+    },
+    Transaction {
+        signature: String,
+        slot: u64,
+        index: Option<usize>,
+        is_vote: bool,
+        fee: u64,
+        compute_units_consumed: Option<u64>,
+        err: Option<String>,
+        account_keys: Vec<String>,
+        // invoked by the top level instructions
+        programs: Vec<String>,
+        // only streamed by the pubsub sink, kept out of the JSON events
+        #[serde(skip)]
+        log_messages: Option<Vec<String>>,
+    },
+    Block {
+        slot: u64,
+        blockhash: String,
+        parent_slot: Option<u64>,
+        parent_blockhash: Option<String>,
+        block_time: Option<i64>,
+        block_height: Option<u64>,
+        executed_transaction_count: Option<u64>,
+    },
+}
+
+impl Event {
+    pub fn slot(&self) -> u64 {
+        match self {
+            Event::Account { slot, .. }
+            | Event::Slot { slot, .. }
+            | Event::Transaction { slot, .. }
+            | Event::Block { slot, .. } => *slot,
+        }
+    }
+
+    /// The event, serialized with its account data in `encoding` instead of base64.
+    pub fn encoded(&self, encoding: AccountDataEncoding) -> EncodedEvent<'_> {
+        EncodedEvent {
+            event: self,
+            encoding,
+        }
+    }
+
+    pub fn from_account(account: &AccountUpdate) -> Self {
+        Event::Account {
+            pubkey: account.key.to_string(),
+            owner: account.owner.to_string(),
+            slot: account.slot,
+            lamports: account.lamports,
+            rent_epoch: account.rent_epoch,
+            executable: account.executable,
+            write_version: account.write_version,
+            data: account.data.to_vec(),
+            txn_signature: account.txn_signature.map(|s| s.to_string()),
+        }
+    }
+
+    pub fn from_slot(slot: u64, parent: Option<u64>, status: &SlotStatus) -> Self {
+        Event::Slot {
+            slot,
+            parent,
+            status: match status {
+                SlotStatus::Processed => "processed",
+                SlotStatus::Confirmed => "confirmed",
+                SlotStatus::Rooted => "rooted",
+            },
+        }
+    }
+
+    pub fn from_transaction(tx: &TransactionUpdate) -> Self {
+        Event::Transaction {
+            signature: tx.signature.to_string(),
+            slot: tx.slot,
+            index: tx.index,
+            is_vote: tx.is_vote,
+            fee: tx.transaction_meta.fee,
+            compute_units_consumed: tx.transaction_meta.compute_units_consumed,
+            err: tx
+                .transaction_meta
+                .status
+                .as_ref()
+                .err()
+                .map(|e| e.to_string()),
+            account_keys: tx
+                .transaction
+                .message()
+                .account_keys()
+                .iter()
+                .map(|key| key.to_string())
+                .collect(),
+            programs: tx
+                .program_ids()
+                .iter()
+                .map(|program| program.to_string())
+                .collect(),
+            log_messages: tx.transaction_meta.log_messages.clone(),
+        }
+    }
+
+    pub fn from_block(block: &BlockUpdate) -> Self {
+        Event::Block {
+            slot: block.slot,
+            blockhash: block.blockhash.to_string(),
+            parent_slot: block.parent_slot,
+            parent_blockhash: block.parent_blockhash.map(str::to_string),
+            block_time: block.block_time,
+            block_height: block.block_height,
+            executed_transaction_count: block.executed_transaction_count,
+        }
+    }
+}
+
+/// An event serialized with its account data in the encoding a sink is configured with, see
+/// `Event::encoded`.
+pub struct EncodedEvent<'a> {
+    event: &'a Event,
+    encoding: AccountDataEncoding,
+}
+
+impl Serialize for EncodedEvent<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let Event::Account {
+            pubkey,
+            owner,
+            slot,
+            lamports,
+            rent_epoch,
+            executable,
+            write_version,
+            data,
+            txn_signature,
+        } = self.event
+        else {
+            return self.event.serialize(serializer);
+        };
+
+        // the account variant, as tagged in Event
+        #[derive(Serialize)]
+        #[serde(tag = "type", rename = "account")]
+        struct Account<'a> {
+            pubkey: &'a str,
+            owner: &'a str,
+            slot: u64,
+            lamports: u64,
+            rent_epoch: u64,
+            executable: bool,
+            write_version: u64,
+            data: String,
+            txn_signature: &'a Option<String>,
+        }
+
+        Account {
+            pubkey,
+            owner,
+            slot: *slot,
+            lamports: *lamports,
+            rent_epoch: *rent_epoch,
+            executable: *executable,
+            write_version: *write_version,
+            data: encode(data, self.encoding)?,
+            txn_signature,
+        }
+        .serialize(serializer)
+    }
+}
+
+fn encode<E: serde::ser::Error>(data: &[u8], encoding: AccountDataEncoding) -> Result<String, E> {
+    encoding.encode(data).map_err(E::custom)
+}
+
+fn serialize_base64<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&encode(data, AccountDataEncoding::Base64)?)
+}
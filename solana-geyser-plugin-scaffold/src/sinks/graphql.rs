@@ -0,0 +1,328 @@
+//! GraphQL server streaming the account and transaction events to subscriptions, over the
+//! graphql-ws and graphql-transport-ws protocols.
+use super::{server::spawn_server, ClosedSink, Event, Sink, SinkStats};
+use crate::build_info::{GIT_COMMIT, PLUGIN_VERSION};
+use async_graphql::{
+    http::{WebSocket as GraphqlWebSocket, WebSocketProtocols, WsMessage},
+    Context, EmptyMutation, Object, Schema, SimpleObject, Subscription,
+};
+use axum::{
+    extract::{
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    http::{header::SEC_WEBSOCKET_PROTOCOL, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use futures_util::{future, SinkExt, Stream, StreamExt};
+use serde::Deserialize;
+use std::{
+    collections::HashSet,
+    sync::{atomic::Ordering, Arc},
+    thread::JoinHandle,
+};
+use tokio::sync::{broadcast, oneshot};
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
+use utils::encoding::AccountDataEncoding;
+
+const DEFAULT_QUEUE_SIZE: usize = 10000;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GraphqlConfig {
+    // e.g. "0.0.0.0:8900", queries are POSTed to /graphql and subscriptions use a websocket
+    // on the same path
+    pub addr: String,
+    // events kept for the slowest subscription, 10000 by default, it misses the older ones
+    // when falling further behind
+    pub queue_size: Option<usize>,
+}
+
+type GraphqlSchema = Schema<Query, EmptyMutation, Subscription>;
+
+pub struct GraphqlSink {
+    name: String,
+    events: broadcast::Sender<Arc<Event>>,
+    stats: Arc<SinkStats>,
+    server: JoinHandle<()>,
+    // stops the server when dropped
+    shutdown: oneshot::Sender<()>,
+}
+
+impl GraphqlSink {
+    pub fn spawn(config: &GraphqlConfig) -> anyhow::Result<Self> {
+        let (events, _) = broadcast::channel(config.queue_size.unwrap_or(DEFAULT_QUEUE_SIZE));
+        let stats = Arc::new(SinkStats::default());
+        let schema = Schema::build(Query, EmptyMutation, Subscription)
+            .data(events.clone())
+            .data(stats.clone())
+            .finish();
+        let router = Router::new()
+            .route("/graphql", get(subscribe).post(query))
+            .with_state(schema);
+
+        let (shutdown, stopped) = oneshot::channel();
+        let server = spawn_server("graphql", &config.addr, router, async {
+            let _ = stopped.await;
+        })?;
+
+        Ok(GraphqlSink {
+            name: format!("graphql {}", config.addr),
+            events,
+            stats,
+            server,
+            shutdown,
+        })
+    }
+}
+
+impl Sink for GraphqlSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Only counted as sent if there is a subscription.
+    fn send(&self, event: Arc<Event>) {
+        if self.events.send(event).is_ok() {
+            self.stats.sent_events.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn stats(&self) -> &SinkStats {
+        &self.stats
+    }
+
+    fn close(self: Box<Self>) -> ClosedSink {
+        drop(self.shutdown);
+
+        ClosedSink {
+            name: self.name,
+            stats: self.stats,
+            worker: self.server,
+        }
+    }
+}
+
+async fn query(
+    State(schema): State<GraphqlSchema>,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    Json(schema.execute(request).await)
+}
+
+async fn subscribe(
+    State(schema): State<GraphqlSchema>,
+    headers: HeaderMap,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    let protocol = headers
+        .get(SEC_WEBSOCKET_PROTOCOL)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| {
+            value
+                .split(',')
+                .find_map(|protocol| protocol.trim().parse::<WebSocketProtocols>().ok())
+        });
+    let protocol = match protocol {
+        Some(protocol) => protocol,
+        None => return (StatusCode::BAD_REQUEST, "unsupported websocket protocol").into_response(),
+    };
+
+    upgrade
+        .protocols([protocol.sec_websocket_protocol()])
+        .on_upgrade(move |socket| serve_websocket(schema, socket, protocol))
+}
+
+async fn serve_websocket(schema: GraphqlSchema, socket: WebSocket, protocol: WebSocketProtocols) {
+    let (mut sink, stream) = socket.split();
+    let incoming = stream
+        .take_while(|message| future::ready(message.is_ok()))
+        .filter_map(|message| {
+            future::ready(match message {
+                Ok(Message::Text(text)) => Some(text.into_bytes()),
+                Ok(Message::Binary(data)) => Some(data),
+                _ => None,
+            })
+        });
+
+    let mut outgoing = std::pin::pin!(GraphqlWebSocket::new(schema, incoming, protocol));
+    while let Some(message) = outgoing.next().await {
+        let message = match message {
+            WsMessage::Text(text) => Message::Text(text),
+            WsMessage::Close(code, reason) => Message::Close(Some(CloseFrame {
+                code,
+                reason: reason.into(),
+            })),
+        };
+
+        if sink.send(message).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Events published from now on, the ones missed by falling behind are counted as dropped.
+fn events(ctx: &Context<'_>) -> impl Stream<Item = Arc<Event>> {
+    let stats = ctx.data_unchecked::<Arc<SinkStats>>().clone();
+    let events = ctx
+        .data_unchecked::<broadcast::Sender<Arc<Event>>>()
+        .subscribe();
+
+    BroadcastStream::new(events).filter_map(move |event| {
+        future::ready(match event {
+            Ok(event) => Some(event),
+            Err(BroadcastStreamRecvError::Lagged(missed)) => {
+                stats.dropped_events.fetch_add(missed, Ordering::Relaxed);
+                None
+            }
+        })
+    })
+}
+
+fn matches(filter: &Option<HashSet<String>>, values: &[&String]) -> bool {
+    filter.as_ref().map_or(true, |filter| {
+        values.iter().any(|value| filter.contains(*value))
+    })
+}
+
+#[derive(SimpleObject)]
+pub struct Account {
+    pubkey: String,
+    owner: String,
+    slot: u64,
+    lamports: u64,
+    rent_epoch: u64,
+    executable: bool,
+    write_version: u64,
+    /// Base64 encoded
+    data: String,
+    txn_signature: Option<String>,
+}
+
+#[derive(SimpleObject)]
+pub struct Transaction {
+    signature: String,
+    slot: u64,
+    index: Option<u64>,
+    is_vote: bool,
+    fee: u64,
+    compute_units_consumed: Option<u64>,
+    err: Option<String>,
+    account_keys: Vec<String>,
+    /// Invoked by the top level instructions
+    programs: Vec<String>,
+}
+
+pub struct Query;
+
+#[Object]
+impl Query {
+    async fn version(&self) -> &str {
+        PLUGIN_VERSION
+    }
+
+    async fn git_commit(&self) -> &str {
+        GIT_COMMIT
+    }
+}
+
+pub struct Subscription;
+
+#[Subscription]
+impl Subscription {
+    /// Account updates, only of the given pubkeys and owners if set.
+    async fn accounts(
+        &self,
+        ctx: &Context<'_>,
+        pubkeys: Option<Vec<String>>,
+        owners: Option<Vec<String>>,
+    ) -> impl Stream<Item = Account> {
+        let pubkeys = pubkeys.map(HashSet::from_iter);
+        let owners = owners.map(HashSet::from_iter);
+
+        events(ctx).filter_map(move |event| {
+            let account = match &*event {
+                Event::Account {
+                    pubkey,
+                    owner,
+                    slot,
+                    lamports,
+                    rent_epoch,
+                    executable,
+                    write_version,
+                    data,
+                    txn_signature,
+                } if matches(&pubkeys, &[pubkey]) && matches(&owners, &[owner]) => {
+                    AccountDataEncoding::Base64
+                        .encode(data)
+                        .ok()
+                        .map(|data| Account {
+                            pubkey: pubkey.clone(),
+                            owner: owner.clone(),
+                            slot: *slot,
+                            lamports: *lamports,
+                            rent_epoch: *rent_epoch,
+                            executable: *executable,
+                            write_version: *write_version,
+                            data,
+                            txn_signature: txn_signature.clone(),
+                        })
+                }
+                _ => None,
+            };
+
+            future::ready(account)
+        })
+    }
+
+    /// Transactions, only the ones mentioning one of the given accounts and invoking one of
+    /// the given programs if set. Votes are left out unless asked for.
+    async fn transactions(
+        &self,
+        ctx: &Context<'_>,
+        accounts: Option<Vec<String>>,
+        programs: Option<Vec<String>>,
+        #[graphql(default = false)] include_votes: bool,
+        #[graphql(default = true)] include_failed: bool,
+    ) -> impl Stream<Item = Transaction> {
+        let accounts = accounts.map(HashSet::from_iter);
+        let programs = programs.map(HashSet::from_iter);
+
+        events(ctx).filter_map(move |event| {
+            let transaction = match &*event {
+                Event::Transaction {
+                    signature,
+                    slot,
+                    index,
+                    is_vote,
+                    fee,
+                    compute_units_consumed,
+                    err,
+                    account_keys,
+                    programs: invoked,
+                    ..
+                } if (include_votes || !is_vote)
+                    && (include_failed || err.is_none())
+                    && matches(&accounts, &account_keys.iter().collect::<Vec<_>>())
+                    && matches(&programs, &invoked.iter().collect::<Vec<_>>()) =>
+                {
+                    Some(Transaction {
+                        signature: signature.clone(),
+                        slot: *slot,
+                        index: index.map(|index| index as u64),
+                        is_vote: *is_vote,
+                        fee: *fee,
+                        compute_units_consumed: *compute_units_consumed,
+                        err: err.clone(),
+                        account_keys: account_keys.clone(),
+                        programs: invoked.clone(),
+                    })
+                }
+                _ => None,
+            };
+
+            future::ready(transaction)
+        })
+    }
+}
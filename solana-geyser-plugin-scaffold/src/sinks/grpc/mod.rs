@@ -0,0 +1,520 @@
+//! gRPC server streaming the events to subscriptions, speaking the `Subscribe` method of
+//! Yellowstone geyser-grpc so its clients only need the plugin's address.
+//!
+//! The updates are sent as the plugin gets them, the commitment asked for is ignored. See
+//! geyser.proto for the parts of the Yellowstone messages the plugin fills.
+use super::{server::spawn_server, ClosedSink, Event, Sink, SinkStats};
+use crate::build_info::{GIT_COMMIT, PLUGIN_VERSION};
+use futures_util::StreamExt;
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{atomic::Ordering, Arc},
+    thread::JoinHandle,
+    time::Duration,
+};
+use tokio::{
+    sync::{
+        broadcast::{self, error::RecvError},
+        mpsc, oneshot,
+    },
+    time::Instant,
+};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status, Streaming};
+use utils::encoding::AccountDataEncoding;
+
+#[allow(dead_code, clippy::all)]
+mod geyser_generated;
+
+use geyser_generated::{
+    geyser_server::{Geyser, GeyserServer},
+    subscribe_request_filter_accounts_filter::Filter,
+    subscribe_request_filter_accounts_filter_memcmp::Data,
+    subscribe_update::UpdateOneof,
+    BlockHeight, CommitmentLevel, GetVersionRequest, GetVersionResponse, Message, PingRequest,
+    PongResponse, SubscribeRequest, SubscribeRequestFilterAccounts,
+    SubscribeRequestFilterTransactions, SubscribeUpdate, SubscribeUpdateAccount,
+    SubscribeUpdateAccountInfo, SubscribeUpdateBlockMeta, SubscribeUpdatePing, SubscribeUpdatePong,
+    SubscribeUpdateSlot, SubscribeUpdateTransaction, SubscribeUpdateTransactionInfo,
+    SubscribeUpdateTransactionStatus, Transaction, TransactionError, TransactionStatusMeta,
+    UnixTimestamp,
+};
+
+const DEFAULT_QUEUE_SIZE: usize = 10000;
+// updates waiting to be written to a subscription
+const SUBSCRIPTION_BUFFER: usize = 1000;
+// keeps idle subscriptions open through proxies, like Yellowstone does
+const PING_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GrpcConfig {
+    // e.g. "0.0.0.0:10000"
+    pub addr: String,
+    // events kept for the slowest subscription, 10000 by default, it misses the older ones
+    // when falling further behind
+    pub queue_size: Option<usize>,
+}
+
+pub struct GrpcSink {
+    name: String,
+    events: broadcast::Sender<Arc<Event>>,
+    stats: Arc<SinkStats>,
+    server: JoinHandle<()>,
+    // stops the server when dropped
+    shutdown: oneshot::Sender<()>,
+}
+
+impl GrpcSink {
+    pub fn spawn(config: &GrpcConfig) -> anyhow::Result<Self> {
+        let (events, _) = broadcast::channel(config.queue_size.unwrap_or(DEFAULT_QUEUE_SIZE));
+        let stats = Arc::new(SinkStats::default());
+        let service = GeyserService {
+            events: events.clone(),
+            stats: stats.clone(),
+        };
+        let router = tonic::service::Routes::new(GeyserServer::new(service)).into_axum_router();
+
+        let (shutdown, stopped) = oneshot::channel();
+        let server = spawn_server("grpc", &config.addr, router, async {
+            let _ = stopped.await;
+        })?;
+
+        Ok(GrpcSink {
+            name: format!("grpc {}", config.addr),
+            events,
+            stats,
+            server,
+            shutdown,
+        })
+    }
+}
+
+impl Sink for GrpcSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Only counted as sent if there is a subscription.
+    fn send(&self, event: Arc<Event>) {
+        if self.events.send(event).is_ok() {
+            self.stats.sent_events.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn stats(&self) -> &SinkStats {
+        &self.stats
+    }
+
+    fn close(self: Box<Self>) -> ClosedSink {
+        drop(self.shutdown);
+
+        ClosedSink {
+            name: self.name,
+            stats: self.stats,
+            worker: self.server,
+        }
+    }
+}
+
+struct GeyserService {
+    events: broadcast::Sender<Arc<Event>>,
+    stats: Arc<SinkStats>,
+}
+
+#[tonic::async_trait]
+impl Geyser for GeyserService {
+    type SubscribeStream = ReceiverStream<Result<SubscribeUpdate, Status>>;
+
+    async fn subscribe(
+        &self,
+        request: Request<Streaming<SubscribeRequest>>,
+    ) -> Result<Response<Self::SubscribeStream>, Status> {
+        let (updates, stream) = mpsc::channel(SUBSCRIPTION_BUFFER);
+        tokio::spawn(serve_subscription(
+            request.into_inner(),
+            self.events.subscribe(),
+            updates,
+            self.stats.clone(),
+        ));
+
+        Ok(Response::new(ReceiverStream::new(stream)))
+    }
+
+    async fn ping(&self, request: Request<PingRequest>) -> Result<Response<PongResponse>, Status> {
+        Ok(Response::new(PongResponse {
+            count: request.into_inner().count,
+        }))
+    }
+
+    async fn get_version(
+        &self,
+        _request: Request<GetVersionRequest>,
+    ) -> Result<Response<GetVersionResponse>, Status> {
+        let version = json!({
+            "plugin_version": PLUGIN_VERSION,
+            "git_commit": GIT_COMMIT,
+        });
+
+        Ok(Response::new(GetVersionResponse {
+            version: version.to_string(),
+        }))
+    }
+}
+
+/// Streams the events matching the latest filters of the subscription, until either side
+/// closes it.
+async fn serve_subscription(
+    mut requests: Streaming<SubscribeRequest>,
+    mut events: broadcast::Receiver<Arc<Event>>,
+    updates: mpsc::Sender<Result<SubscribeUpdate, Status>>,
+    stats: Arc<SinkStats>,
+) {
+    let mut filters = Filters::default();
+    let mut ping = tokio::time::interval_at(Instant::now() + PING_INTERVAL, PING_INTERVAL);
+
+    loop {
+        let sent = tokio::select! {
+            request = requests.next() => match request {
+                Some(Ok(request)) => {
+                    let pong = request.ping.map(|ping| update(
+                        Vec::new(),
+                        UpdateOneof::Pong(SubscribeUpdatePong { id: ping.id }),
+                    ));
+                    // a ping alone keeps the filters
+                    if pong.is_none() || !Filters::is_empty_request(&request) {
+                        match Filters::parse(request) {
+                            Ok(parsed) => filters = parsed,
+                            Err(status) => {
+                                let _ = updates.send(Err(status)).await;
+                                return;
+                            }
+                        }
+                    }
+
+                    match pong {
+                        Some(pong) => updates.send(Ok(pong)).await.is_ok(),
+                        None => true,
+                    }
+                }
+                _ => return,
+            },
+            event = events.recv() => match event {
+                Ok(event) => {
+                    let mut sent = true;
+                    for update in filters.updates(&event) {
+                        sent &= updates.send(Ok(update)).await.is_ok();
+                    }
+                    sent
+                }
+                Err(RecvError::Lagged(missed)) => {
+                    stats.dropped_events.fetch_add(missed, Ordering::Relaxed);
+                    true
+                }
+                Err(RecvError::Closed) => return,
+            },
+            _ = ping.tick() => {
+                let ping = update(Vec::new(), UpdateOneof::Ping(SubscribeUpdatePing {}));
+                updates.send(Ok(ping)).await.is_ok()
+            }
+        };
+
+        if !sent {
+            return;
+        }
+    }
+}
+
+enum DataFilter {
+    Size(usize),
+    Bytes { offset: usize, bytes: Vec<u8> },
+}
+
+impl DataFilter {
+    fn matches(&self, data: &[u8]) -> bool {
+        match self {
+            DataFilter::Size(size) => data.len() == *size,
+            DataFilter::Bytes { offset, bytes } => data
+                .get(*offset..offset + bytes.len())
+                .is_some_and(|slice| slice == bytes.as_slice()),
+        }
+    }
+}
+
+/// Fields are ANDed, the values of a field ORed, empty fields match everything.
+struct AccountFilter {
+    account: HashSet<String>,
+    owner: HashSet<String>,
+    data: Vec<DataFilter>,
+}
+
+impl AccountFilter {
+    // Status is what the subscription is closed with
+    #[allow(clippy::result_large_err)]
+    fn parse(filter: SubscribeRequestFilterAccounts) -> Result<Self, Status> {
+        let data = filter
+            .filters
+            .into_iter()
+            .filter_map(|filter| filter.filter)
+            .map(|filter| match filter {
+                Filter::Datasize(size) => Ok(DataFilter::Size(size as usize)),
+                Filter::Memcmp(memcmp) => {
+                    let bytes = match memcmp.data {
+                        Some(Data::Bytes(bytes)) => bytes,
+                        Some(Data::Base58(data)) => bs58::decode(data)
+                            .into_vec()
+                            .map_err(|e| Status::invalid_argument(e.to_string()))?,
+                        Some(Data::Base64(data)) => AccountDataEncoding::Base64
+                            .decode(&data)
+                            .map_err(|e| Status::invalid_argument(e.to_string()))?,
+                        None => return Err(Status::invalid_argument("memcmp without data")),
+                    };
+
+                    Ok(DataFilter::Bytes {
+                        offset: memcmp.offset as usize,
+                        bytes,
+                    })
+                }
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(AccountFilter {
+            account: filter.account.into_iter().collect(),
+            owner: filter.owner.into_iter().collect(),
+            data,
+        })
+    }
+
+    fn matches(&self, pubkey: &String, owner: &String, data: &[u8]) -> bool {
+        (self.account.is_empty() || self.account.contains(pubkey))
+            && (self.owner.is_empty() || self.owner.contains(owner))
+            && self.data.iter().all(|filter| filter.matches(data))
+    }
+}
+
+struct TransactionFilter(SubscribeRequestFilterTransactions);
+
+impl TransactionFilter {
+    fn matches(&self, signature: &str, is_vote: bool, failed: bool, keys: &[String]) -> bool {
+        let filter = &self.0;
+
+        filter.vote.map_or(true, |vote| vote == is_vote)
+            && filter.failed.map_or(true, |f| f == failed)
+            && filter.signature.as_ref().map_or(true, |s| s == signature)
+            && (filter.account_include.is_empty()
+                || filter.account_include.iter().any(|key| keys.contains(key)))
+            && !filter.account_exclude.iter().any(|key| keys.contains(key))
+            && filter.account_required.iter().all(|key| keys.contains(key))
+    }
+}
+
+/// The named filters of the last request of a subscription.
+#[derive(Default)]
+struct Filters {
+    accounts: HashMap<String, AccountFilter>,
+    slots: Vec<String>,
+    transactions: HashMap<String, TransactionFilter>,
+    transactions_status: HashMap<String, TransactionFilter>,
+    blocks_meta: Vec<String>,
+}
+
+impl Filters {
+    // Status is what the subscription is closed with
+    #[allow(clippy::result_large_err)]
+    fn parse(request: SubscribeRequest) -> Result<Self, Status> {
+        let transactions = |filters: HashMap<String, SubscribeRequestFilterTransactions>| {
+            filters
+                .into_iter()
+                .map(|(name, filter)| (name, TransactionFilter(filter)))
+                .collect()
+        };
+
+        Ok(Filters {
+            accounts: request
+                .accounts
+                .into_iter()
+                .map(|(name, filter)| Ok((name, AccountFilter::parse(filter)?)))
+                .collect::<Result<_, Status>>()?,
+            slots: request.slots.into_keys().collect(),
+            transactions: transactions(request.transactions),
+            transactions_status: transactions(request.transactions_status),
+            blocks_meta: request.blocks_meta.into_keys().collect(),
+        })
+    }
+
+    fn is_empty_request(request: &SubscribeRequest) -> bool {
+        request.accounts.is_empty()
+            && request.slots.is_empty()
+            && request.transactions.is_empty()
+            && request.transactions_status.is_empty()
+            && request.blocks_meta.is_empty()
+    }
+
+    /// The updates to send for the event, with the names of the filters it matched.
+    fn updates(&self, event: &Event) -> Vec<SubscribeUpdate> {
+        match event {
+            Event::Account {
+                pubkey,
+                owner,
+                slot,
+                lamports,
+                rent_epoch,
+                executable,
+                write_version,
+                data,
+                txn_signature,
+            } => {
+                let names = matching(&self.accounts, |filter| filter.matches(pubkey, owner, data));
+                if names.is_empty() {
+                    return Vec::new();
+                }
+
+                let account = SubscribeUpdateAccountInfo {
+                    pubkey: decode(pubkey),
+                    lamports: *lamports,
+                    owner: decode(owner),
+                    executable: *executable,
+                    rent_epoch: *rent_epoch,
+                    data: data.clone(),
+                    write_version: *write_version,
+                    txn_signature: txn_signature.as_deref().map(decode),
+                };
+                vec![update(
+                    names,
+                    UpdateOneof::Account(SubscribeUpdateAccount {
+                        account: Some(account),
+                        slot: *slot,
+                        is_startup: false,
+                    }),
+                )]
+            }
+            Event::Slot {
+                slot,
+                parent,
+                status,
+            } if !self.slots.is_empty() => {
+                let status = match *status {
+                    "confirmed" => CommitmentLevel::Confirmed,
+                    "rooted" => CommitmentLevel::Finalized,
+                    _ => CommitmentLevel::Processed,
+                };
+                vec![update(
+                    self.slots.clone(),
+                    UpdateOneof::Slot(SubscribeUpdateSlot {
+                        slot: *slot,
+                        parent: *parent,
+                        status: status as i32,
+                    }),
+                )]
+            }
+            Event::Transaction {
+                signature,
+                slot,
+                index,
+                is_vote,
+                fee,
+                compute_units_consumed,
+                err,
+                account_keys,
+                log_messages,
+                ..
+            } => {
+                let matches = |filter: &TransactionFilter| {
+                    filter.matches(signature, *is_vote, err.is_some(), account_keys)
+                };
+                let err = err.as_ref().map(|err| TransactionError {
+                    err: err.as_bytes().to_vec(),
+                });
+                let index = index.unwrap_or_default() as u64;
+
+                let mut updates = Vec::new();
+                let names = matching(&self.transactions, matches);
+                if !names.is_empty() {
+                    let transaction = SubscribeUpdateTransactionInfo {
+                        signature: decode(signature),
+                        is_vote: *is_vote,
+                        transaction: Some(Transaction {
+                            signatures: vec![decode(signature)],
+                            message: Some(Message {
+                                account_keys: account_keys.iter().map(|key| decode(key)).collect(),
+                            }),
+                        }),
+                        meta: Some(TransactionStatusMeta {
+                            err: err.clone(),
+                            fee: *fee,
+                            log_messages: log_messages.clone().unwrap_or_default(),
+                            log_messages_none: log_messages.is_none(),
+                            compute_units_consumed: *compute_units_consumed,
+                        }),
+                        index,
+                    };
+                    updates.push(update(
+                        names,
+                        UpdateOneof::Transaction(SubscribeUpdateTransaction {
+                            transaction: Some(transaction),
+                            slot: *slot,
+                        }),
+                    ));
+                }
+
+                let names = matching(&self.transactions_status, matches);
+                if !names.is_empty() {
+                    updates.push(update(
+                        names,
+                        UpdateOneof::TransactionStatus(SubscribeUpdateTransactionStatus {
+                            slot: *slot,
+                            signature: decode(signature),
+                            is_vote: *is_vote,
+                            index,
+                            err,
+                        }),
+                    ));
+                }
+
+                updates
+            }
+            Event::Block {
+                slot,
+                blockhash,
+                parent_slot,
+                parent_blockhash,
+                block_time,
+                block_height,
+                executed_transaction_count,
+            } if !self.blocks_meta.is_empty() => vec![update(
+                self.blocks_meta.clone(),
+                UpdateOneof::BlockMeta(SubscribeUpdateBlockMeta {
+                    slot: *slot,
+                    blockhash: blockhash.clone(),
+                    block_time: block_time.map(|timestamp| UnixTimestamp { timestamp }),
+                    block_height: block_height.map(|block_height| BlockHeight { block_height }),
+                    parent_slot: parent_slot.unwrap_or_default(),
+                    parent_blockhash: parent_blockhash.clone().unwrap_or_default(),
+                    executed_transaction_count: executed_transaction_count.unwrap_or_default(),
+                }),
+            )],
+            _ => Vec::new(),
+        }
+    }
+}
+
+fn matching<T>(filters: &HashMap<String, T>, matches: impl Fn(&T) -> bool) -> Vec<String> {
+    filters
+        .iter()
+        .filter(|(_, filter)| matches(filter))
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+fn update(filters: Vec<String>, update: UpdateOneof) -> SubscribeUpdate {
+    SubscribeUpdate {
+        filters,
+        update_oneof: Some(update),
+    }
+}
+
+/// Raw bytes of a base58 pubkey or signature, as Yellowstone sends them.
+fn decode(base58: &str) -> Vec<u8> {
+    bs58::decode(base58).into_vec().unwrap_or_default()
+}
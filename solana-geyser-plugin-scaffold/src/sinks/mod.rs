@@ -0,0 +1,258 @@
+//! Sinks publishing the plugin data as JSON events to other systems, besides the TCP stream.
+//! All but the webhook and pubsub ones are built with the cargo feature named after them.
+use serde::{Deserialize, Serialize};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    thread::JoinHandle,
+};
+
+mod batch;
+#[cfg(feature = "clickhouse")]
+mod clickhouse;
+#[cfg(feature = "elasticsearch")]
+mod elasticsearch;
+mod event;
+#[cfg(feature = "graphql")]
+mod graphql;
+#[cfg(feature = "grpc")]
+mod grpc;
+#[cfg(feature = "parquet")]
+mod parquet;
+#[cfg(feature = "postgres")]
+mod postgres;
+mod pubsub;
+#[cfg(feature = "s3")]
+mod s3;
+pub(crate) mod server;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod webhook;
+
+pub use event::Event;
+
+use batch::BatchSink;
+#[cfg(feature = "clickhouse")]
+use clickhouse::{ClickHouseConfig, ClickHouseWriter};
+#[cfg(feature = "elasticsearch")]
+use elasticsearch::{ElasticsearchConfig, ElasticsearchWriter};
+#[cfg(feature = "graphql")]
+use graphql::{GraphqlConfig, GraphqlSink};
+#[cfg(feature = "grpc")]
+use grpc::{GrpcConfig, GrpcSink};
+#[cfg(feature = "parquet")]
+use parquet::{ParquetConfig, ParquetWriter};
+#[cfg(feature = "postgres")]
+use postgres::{PostgresConfig, PostgresWriter};
+use pubsub::{PubsubConfig, PubsubSink};
+#[cfg(feature = "s3")]
+use s3::{S3Config, S3Writer};
+#[cfg(feature = "sqlite")]
+use sqlite::{SqliteConfig, SqliteWriter};
+use webhook::{WebhookConfig, WebhookWriter};
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SinkConfig {
+    Webhook(WebhookConfig),
+    #[cfg(feature = "clickhouse")]
+    #[serde(rename = "clickhouse")]
+    ClickHouse(ClickHouseConfig),
+    #[cfg(feature = "s3")]
+    S3(S3Config),
+    #[cfg(feature = "parquet")]
+    Parquet(ParquetConfig),
+    #[cfg(feature = "sqlite")]
+    Sqlite(SqliteConfig),
+    #[cfg(feature = "elasticsearch")]
+    Elasticsearch(ElasticsearchConfig),
+    #[cfg(feature = "postgres")]
+    Postgres(PostgresConfig),
+    #[cfg(feature = "graphql")]
+    Graphql(GraphqlConfig),
+    Pubsub(PubsubConfig),
+    #[cfg(feature = "grpc")]
+    Grpc(GrpcConfig),
+}
+
+#[derive(Default, Debug)]
+pub struct SinkStats {
+    pub sent_events: AtomicU64,
+    // dropped because the sink's queue was full
+    pub dropped_events: AtomicU64,
+    // dropped after the last retry failed
+    pub failed_events: AtomicU64,
+}
+
+pub trait Sink: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Queues the event, without blocking.
+    fn send(&self, event: Arc<Event>);
+
+    fn stats(&self) -> &SinkStats;
+
+    /// Stops taking events, the returned worker finishing once the queued ones are handled.
+    fn close(self: Box<Self>) -> ClosedSink;
+}
+
+/// A removed sink, handling the events it had queued.
+pub struct ClosedSink {
+    pub name: String,
+    pub stats: Arc<SinkStats>,
+    pub worker: JoinHandle<()>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SinkState {
+    Running,
+    // removed, its queued events are still being handled
+    Draining,
+    Stopped,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SinkStatus {
+    pub name: String,
+    pub state: SinkState,
+    pub sent_events: u64,
+    pub dropped_events: u64,
+    pub failed_events: u64,
+}
+
+impl SinkStatus {
+    fn new(name: &str, state: SinkState, stats: &SinkStats) -> Self {
+        SinkStatus {
+            name: name.to_string(),
+            state,
+            sent_events: stats.sent_events.load(Ordering::Relaxed),
+            dropped_events: stats.dropped_events.load(Ordering::Relaxed),
+            failed_events: stats.failed_events.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// The sinks events are published to, which can be added and removed while the plugin runs.
+#[derive(Default)]
+pub struct Sinks {
+    running: RwLock<Vec<Box<dyn Sink>>>,
+    closed: Mutex<Vec<ClosedSink>>,
+}
+
+impl Sinks {
+    pub fn add(&self, sink: Box<dyn Sink>) {
+        let mut running = self.running.write().unwrap_or_else(|e| e.into_inner());
+        running.push(sink);
+    }
+
+    /// Closes the first running sink named `name`, false if there is none.
+    pub fn remove(&self, name: &str) -> bool {
+        let sink = {
+            let mut running = self.running.write().unwrap_or_else(|e| e.into_inner());
+            match running.iter().position(|sink| sink.name() == name) {
+                Some(i) => running.remove(i),
+                None => return false,
+            }
+        };
+
+        let mut closed = self.closed.lock().unwrap_or_else(|e| e.into_inner());
+        closed.push(sink.close());
+
+        true
+    }
+
+    /// Sends the event to every running sink, only building it if there is one.
+    pub fn publish(&self, event: impl FnOnce() -> Event) {
+        let running = self.running.read().unwrap_or_else(|e| e.into_inner());
+        if running.is_empty() {
+            return;
+        }
+
+        let event = Arc::new(event());
+        for sink in running.iter() {
+            sink.send(event.clone());
+        }
+    }
+
+    /// The running sinks, then the removed ones.
+    pub fn status(&self) -> Vec<SinkStatus> {
+        let mut status = {
+            let running = self.running.read().unwrap_or_else(|e| e.into_inner());
+            running
+                .iter()
+                .map(|sink| SinkStatus::new(sink.name(), SinkState::Running, sink.stats()))
+                .collect::<Vec<_>>()
+        };
+
+        let closed = self.closed.lock().unwrap_or_else(|e| e.into_inner());
+        status.extend(closed.iter().map(|sink| {
+            let state = if sink.worker.is_finished() {
+                SinkState::Stopped
+            } else {
+                SinkState::Draining
+            };
+            SinkStatus::new(&sink.name, state, &sink.stats)
+        }));
+
+        status
+    }
+}
+
+impl SinkConfig {
+    pub fn build(&self) -> anyhow::Result<Box<dyn Sink>> {
+        let sink = match self {
+            SinkConfig::Webhook(config) => BatchSink::spawn(
+                format!("webhook {}", config.url),
+                &config.batch,
+                WebhookWriter::new(config)?,
+            ),
+            #[cfg(feature = "clickhouse")]
+            SinkConfig::ClickHouse(config) => BatchSink::spawn(
+                format!("clickhouse {}", config.url),
+                &config.batch,
+                ClickHouseWriter::new(config)?,
+            ),
+            #[cfg(feature = "s3")]
+            SinkConfig::S3(config) => BatchSink::spawn(
+                format!("s3 {}", config.bucket),
+                &config.batch,
+                S3Writer::new(config)?,
+            ),
+            #[cfg(feature = "parquet")]
+            SinkConfig::Parquet(config) => BatchSink::spawn(
+                format!("parquet {}", config.dir),
+                &config.batch,
+                ParquetWriter::new(config)?,
+            ),
+            #[cfg(feature = "sqlite")]
+            SinkConfig::Sqlite(config) => BatchSink::spawn(
+                format!("sqlite {}", config.path),
+                &config.batch,
+                SqliteWriter::new(config)?,
+            ),
+            #[cfg(feature = "elasticsearch")]
+            SinkConfig::Elasticsearch(config) => BatchSink::spawn(
+                format!("elasticsearch {}", config.url),
+                &config.batch,
+                ElasticsearchWriter::new(config)?,
+            ),
+            #[cfg(feature = "postgres")]
+            SinkConfig::Postgres(config) => BatchSink::spawn(
+                "postgres".to_string(),
+                &config.batch,
+                PostgresWriter::new(config)?,
+            ),
+            // servers streaming to their subscriptions, rather than batch writers
+            #[cfg(feature = "graphql")]
+            SinkConfig::Graphql(config) => return Ok(Box::new(GraphqlSink::spawn(config)?)),
+            SinkConfig::Pubsub(config) => return Ok(Box::new(PubsubSink::spawn(config)?)),
+            #[cfg(feature = "grpc")]
+            SinkConfig::Grpc(config) => return Ok(Box::new(GrpcSink::spawn(config)?)),
+        };
+
+        Ok(Box::new(sink))
+    }
+}
@@ -0,0 +1,305 @@
+use super::{
+    batch::{BatchOptions, BatchWriter},
+    Event,
+};
+use arrow_array::{
+    builder::{ListBuilder, StringBuilder},
+    ArrayRef, BinaryArray, BooleanArray, Int64Array, RecordBatch, StringArray, UInt64Array,
+};
+use parquet::{
+    arrow::ArrowWriter,
+    basic::{Compression, ZstdLevel},
+    file::properties::WriterProperties,
+};
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    path::PathBuf,
+    sync::Arc,
+};
+
+const DEFAULT_SLOT_RANGE: u64 = 10_000;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ParquetConfig {
+    pub dir: String,
+
+    // slots per partition directory, 10000 by default
+    pub slot_range: Option<u64>,
+
+    // every batch is written as one file per event type and partition, larger batches than
+    // the other sinks are recommended, e.g. "batch_size": 100000, "batch_interval_ms": 60000
+    #[serde(flatten)]
+    pub batch: BatchOptions,
+}
+
+/// Writes the events to zstd compressed Parquet files, one table per event type, partitioned
+/// by slot range: `<dir>/<type>/slot_range=<first slot of the range>/<first>-<last>.parquet`.
+/// The columns are named after the event fields.
+pub struct ParquetWriter {
+    dir: PathBuf,
+    slot_range: u64,
+    properties: WriterProperties,
+}
+
+impl ParquetWriter {
+    pub fn new(config: &ParquetConfig) -> anyhow::Result<Self> {
+        let dir = PathBuf::from(&config.dir);
+        fs::create_dir_all(&dir)?;
+
+        Ok(ParquetWriter {
+            dir,
+            slot_range: config.slot_range.unwrap_or(DEFAULT_SLOT_RANGE).max(1),
+            properties: WriterProperties::builder()
+                .set_compression(Compression::ZSTD(ZstdLevel::default()))
+                .build(),
+        })
+    }
+
+    fn write_file(
+        &self,
+        event_type: &str,
+        partition: u64,
+        events: &[&Event],
+    ) -> anyhow::Result<()> {
+        let batch = match event_type {
+            "account" => accounts_batch(events),
+            "slot" => slots_batch(events),
+            "transaction" => transactions_batch(events),
+            _ => blocks_batch(events),
+        }?;
+
+        let dir = self
+            .dir
+            .join(event_type)
+            .join(format!("slot_range={}", partition));
+        fs::create_dir_all(&dir)?;
+
+        let first_slot = events.iter().map(|e| e.slot()).min().unwrap_or_default();
+        let last_slot = events.iter().map(|e| e.slot()).max().unwrap_or_default();
+        let mut name = format!("{:012}-{:012}", first_slot, last_slot);
+        let mut n = 0;
+        while dir.join(format!("{}.parquet", name)).exists() {
+            n += 1;
+            name = format!("{:012}-{:012}-{}", first_slot, last_slot, n);
+        }
+
+        // written under a temporary name, so readers never see a partial file
+        let tmp_path = dir.join(format!(".{}.parquet.tmp", name));
+        let mut writer = ArrowWriter::try_new(
+            File::create(&tmp_path)?,
+            batch.schema(),
+            Some(self.properties.clone()),
+        )?;
+        writer.write(&batch)?;
+        writer.close()?;
+        fs::rename(tmp_path, dir.join(format!("{}.parquet", name)))?;
+
+        Ok(())
+    }
+}
+
+impl BatchWriter for ParquetWriter {
+    fn write(&mut self, events: &[Arc<Event>]) -> anyhow::Result<()> {
+        let mut files: BTreeMap<(&str, u64), Vec<&Event>> = BTreeMap::new();
+        for event in events {
+            let event_type = match event.as_ref() {
+                Event::Account { .. } => "account",
+                Event::Slot { .. } => "slot",
+                Event::Transaction { .. } => "transaction",
+                Event::Block { .. } => "block",
+            };
+            let partition = event.slot() - event.slot() % self.slot_range;
+
+            files
+                .entry((event_type, partition))
+                .or_default()
+                .push(event);
+        }
+
+        for ((event_type, partition), events) in files {
+            self.write_file(event_type, partition, &events)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn column(array: impl arrow_array::Array + 'static) -> ArrayRef {
+    Arc::new(array)
+}
+
+fn accounts_batch(events: &[&Event]) -> anyhow::Result<RecordBatch> {
+    let mut pubkey = Vec::with_capacity(events.len());
+    let mut owner = Vec::with_capacity(events.len());
+    let mut slot = Vec::with_capacity(events.len());
+    let mut lamports = Vec::with_capacity(events.len());
+    let mut rent_epoch = Vec::with_capacity(events.len());
+    let mut executable = Vec::with_capacity(events.len());
+    let mut write_version = Vec::with_capacity(events.len());
+    let mut data = Vec::with_capacity(events.len());
+    let mut txn_signature = Vec::with_capacity(events.len());
+
+    for event in events {
+        if let Event::Account {
+            pubkey: p,
+            owner: o,
+            slot: s,
+            lamports: l,
+            rent_epoch: r,
+            executable: e,
+            write_version: w,
+            data: d,
+            txn_signature: t,
+        } = event
+        {
+            pubkey.push(p.as_str());
+            owner.push(o.as_str());
+            slot.push(*s);
+            lamports.push(*l);
+            rent_epoch.push(*r);
+            executable.push(*e);
+            write_version.push(*w);
+            data.push(d.as_slice());
+            txn_signature.push(t.as_deref());
+        }
+    }
+
+    Ok(RecordBatch::try_from_iter([
+        ("pubkey", column(StringArray::from(pubkey))),
+        ("owner", column(StringArray::from(owner))),
+        ("slot", column(UInt64Array::from(slot))),
+        ("lamports", column(UInt64Array::from(lamports))),
+        ("rent_epoch", column(UInt64Array::from(rent_epoch))),
+        ("executable", column(BooleanArray::from(executable))),
+        ("write_version", column(UInt64Array::from(write_version))),
+        ("data", column(BinaryArray::from_vec(data))),
+        ("txn_signature", column(StringArray::from(txn_signature))),
+    ])?)
+}
+
+fn slots_batch(events: &[&Event]) -> anyhow::Result<RecordBatch> {
+    let mut slot = Vec::with_capacity(events.len());
+    let mut parent = Vec::with_capacity(events.len());
+    let mut status = Vec::with_capacity(events.len());
+
+    for event in events {
+        if let Event::Slot {
+            slot: s,
+            parent: p,
+            status: st,
+        } = event
+        {
+            slot.push(*s);
+            parent.push(*p);
+            status.push(*st);
+        }
+    }
+
+    Ok(RecordBatch::try_from_iter([
+        ("slot", column(UInt64Array::from(slot))),
+        ("parent", column(UInt64Array::from(parent))),
+        ("status", column(StringArray::from(status))),
+    ])?)
+}
+
+fn transactions_batch(events: &[&Event]) -> anyhow::Result<RecordBatch> {
+    let mut signature = Vec::with_capacity(events.len());
+    let mut slot = Vec::with_capacity(events.len());
+    let mut index = Vec::with_capacity(events.len());
+    let mut is_vote = Vec::with_capacity(events.len());
+    let mut fee = Vec::with_capacity(events.len());
+    let mut compute_units_consumed = Vec::with_capacity(events.len());
+    let mut err = Vec::with_capacity(events.len());
+    let mut account_keys = ListBuilder::new(StringBuilder::new());
+    let mut programs = ListBuilder::new(StringBuilder::new());
+
+    for event in events {
+        if let Event::Transaction {
+            signature: sig,
+            slot: s,
+            index: i,
+            is_vote: v,
+            fee: f,
+            compute_units_consumed: c,
+            err: e,
+            account_keys: keys,
+            programs: p,
+            ..
+        } = event
+        {
+            signature.push(sig.as_str());
+            slot.push(*s);
+            index.push(i.map(|i| i as u64));
+            is_vote.push(*v);
+            fee.push(*f);
+            compute_units_consumed.push(*c);
+            err.push(e.as_deref());
+            account_keys.append_value(keys.iter().map(Some));
+            programs.append_value(p.iter().map(Some));
+        }
+    }
+
+    Ok(RecordBatch::try_from_iter([
+        ("signature", column(StringArray::from(signature))),
+        ("slot", column(UInt64Array::from(slot))),
+        ("index", column(UInt64Array::from(index))),
+        ("is_vote", column(BooleanArray::from(is_vote))),
+        ("fee", column(UInt64Array::from(fee))),
+        (
+            "compute_units_consumed",
+            column(UInt64Array::from(compute_units_consumed)),
+        ),
+        ("err", column(StringArray::from(err))),
+        ("account_keys", column(account_keys.finish())),
+        ("programs", column(programs.finish())),
+    ])?)
+}
+
+fn blocks_batch(events: &[&Event]) -> anyhow::Result<RecordBatch> {
+    let mut slot = Vec::with_capacity(events.len());
+    let mut blockhash = Vec::with_capacity(events.len());
+    let mut parent_slot = Vec::with_capacity(events.len());
+    let mut parent_blockhash = Vec::with_capacity(events.len());
+    let mut block_time = Vec::with_capacity(events.len());
+    let mut block_height = Vec::with_capacity(events.len());
+    let mut executed_transaction_count = Vec::with_capacity(events.len());
+
+    for event in events {
+        if let Event::Block {
+            slot: s,
+            blockhash: b,
+            parent_slot: ps,
+            parent_blockhash: pb,
+            block_time: t,
+            block_height: h,
+            executed_transaction_count: c,
+        } = event
+        {
+            slot.push(*s);
+            blockhash.push(b.as_str());
+            parent_slot.push(*ps);
+            parent_blockhash.push(pb.as_deref());
+            block_time.push(*t);
+            block_height.push(*h);
+            executed_transaction_count.push(*c);
+        }
+    }
+
+    Ok(RecordBatch::try_from_iter([
+        ("slot", column(UInt64Array::from(slot))),
+        ("blockhash", column(StringArray::from(blockhash))),
+        ("parent_slot", column(UInt64Array::from(parent_slot))),
+        (
+            "parent_blockhash",
+            column(StringArray::from(parent_blockhash)),
+        ),
+        ("block_time", column(Int64Array::from(block_time))),
+        ("block_height", column(UInt64Array::from(block_height))),
+        (
+            "executed_transaction_count",
+            column(UInt64Array::from(executed_transaction_count)),
+        ),
+    ])?)
+}
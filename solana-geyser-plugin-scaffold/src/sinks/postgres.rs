@@ -0,0 +1,255 @@
+use super::{
+    batch::{BatchOptions, BatchWriter},
+    Event,
+};
+use postgres::{binary_copy::BinaryCopyInWriter, types::Type, Client, NoTls, Transaction};
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PostgresConfig {
+    // e.g. "host=localhost user=geyser dbname=solana"
+    pub connection: String,
+
+    // created if missing, "accounts" and "transactions" by default
+    pub accounts_table: Option<String>,
+    pub transactions_table: Option<String>,
+
+    #[serde(flatten)]
+    pub batch: BatchOptions,
+}
+
+/// Writes the accounts and transactions to Postgres tables, copying every batch to temporary
+/// staging tables first. Accounts are upserted by pubkey, keeping the highest write_version,
+/// transactions are inserted once by signature. Other events are ignored.
+///
+/// The tables can be created beforehand, e.g. as Timescale hypertables, as long as they have
+/// the same columns and a unique pubkey / signature.
+pub struct PostgresWriter {
+    connection: String,
+    accounts_table: String,
+    transactions_table: String,
+    // connected on the first write, from the sink's thread
+    client: Option<Client>,
+}
+
+impl PostgresWriter {
+    pub fn new(config: &PostgresConfig) -> anyhow::Result<Self> {
+        Ok(PostgresWriter {
+            connection: config.connection.clone(),
+            accounts_table: config
+                .accounts_table
+                .clone()
+                .unwrap_or_else(|| "accounts".to_string()),
+            transactions_table: config
+                .transactions_table
+                .clone()
+                .unwrap_or_else(|| "transactions".to_string()),
+            client: None,
+        })
+    }
+
+    fn connect(&self) -> anyhow::Result<Client> {
+        let mut client = Client::connect(&self.connection, NoTls)?;
+
+        // rent_epoch and the other u64 values are stored as BIGINT, u64::MAX wraps to -1
+        client.batch_execute(&format!(
+            "CREATE TABLE IF NOT EXISTS {accounts} (
+                pubkey TEXT PRIMARY KEY,
+                owner TEXT NOT NULL,
+                slot BIGINT NOT NULL,
+                lamports BIGINT NOT NULL,
+                rent_epoch BIGINT NOT NULL,
+                executable BOOL NOT NULL,
+                write_version BIGINT NOT NULL,
+                data BYTEA NOT NULL,
+                txn_signature TEXT
+            );
+            CREATE TABLE IF NOT EXISTS {transactions} (
+                signature TEXT PRIMARY KEY,
+                slot BIGINT NOT NULL,
+                tx_index BIGINT,
+                is_vote BOOL NOT NULL,
+                fee BIGINT NOT NULL,
+                compute_units_consumed BIGINT,
+                err TEXT,
+                account_keys TEXT[] NOT NULL,
+                programs TEXT[] NOT NULL
+            );
+            CREATE TEMP TABLE {accounts}_staging (LIKE {accounts}) ON COMMIT DELETE ROWS;
+            CREATE TEMP TABLE {transactions}_staging (LIKE {transactions}) ON COMMIT DELETE ROWS;",
+            accounts = self.accounts_table,
+            transactions = self.transactions_table,
+        ))?;
+
+        Ok(client)
+    }
+
+    fn write_accounts(&self, tx: &mut Transaction, events: &[Arc<Event>]) -> anyhow::Result<()> {
+        let sink = tx.copy_in(&format!(
+            "COPY {}_staging (pubkey, owner, slot, lamports, rent_epoch, executable, \
+             write_version, data, txn_signature) FROM STDIN BINARY",
+            self.accounts_table
+        ))?;
+        let mut writer = BinaryCopyInWriter::new(
+            sink,
+            &[
+                Type::TEXT,
+                Type::TEXT,
+                Type::INT8,
+                Type::INT8,
+                Type::INT8,
+                Type::BOOL,
+                Type::INT8,
+                Type::BYTEA,
+                Type::TEXT,
+            ],
+        );
+
+        for event in events {
+            if let Event::Account {
+                pubkey,
+                owner,
+                slot,
+                lamports,
+                rent_epoch,
+                executable,
+                write_version,
+                data,
+                txn_signature,
+            } = event.as_ref()
+            {
+                writer.write(&[
+                    pubkey,
+                    owner,
+                    &(*slot as i64),
+                    &(*lamports as i64),
+                    &(*rent_epoch as i64),
+                    executable,
+                    &(*write_version as i64),
+                    data,
+                    txn_signature,
+                ])?;
+            }
+        }
+        writer.finish()?;
+
+        tx.batch_execute(&format!(
+            "INSERT INTO {accounts}
+            SELECT DISTINCT ON (pubkey) * FROM {accounts}_staging
+            ORDER BY pubkey, write_version DESC
+            ON CONFLICT (pubkey) DO UPDATE SET
+                owner = EXCLUDED.owner,
+                slot = EXCLUDED.slot,
+                lamports = EXCLUDED.lamports,
+                rent_epoch = EXCLUDED.rent_epoch,
+                executable = EXCLUDED.executable,
+                write_version = EXCLUDED.write_version,
+                data = EXCLUDED.data,
+                txn_signature = EXCLUDED.txn_signature
+            WHERE {accounts}.write_version < EXCLUDED.write_version",
+            accounts = self.accounts_table,
+        ))?;
+
+        Ok(())
+    }
+
+    fn write_transactions(
+        &self,
+        tx: &mut Transaction,
+        events: &[Arc<Event>],
+    ) -> anyhow::Result<()> {
+        let sink = tx.copy_in(&format!(
+            "COPY {}_staging (signature, slot, tx_index, is_vote, fee, compute_units_consumed, \
+             err, account_keys, programs) FROM STDIN BINARY",
+            self.transactions_table
+        ))?;
+        let mut writer = BinaryCopyInWriter::new(
+            sink,
+            &[
+                Type::TEXT,
+                Type::INT8,
+                Type::INT8,
+                Type::BOOL,
+                Type::INT8,
+                Type::INT8,
+                Type::TEXT,
+                Type::TEXT_ARRAY,
+                Type::TEXT_ARRAY,
+            ],
+        );
+
+        for event in events {
+            if let Event::Transaction {
+                signature,
+                slot,
+                index,
+                is_vote,
+                fee,
+                compute_units_consumed,
+                err,
+                account_keys,
+                programs,
+                ..
+            } = event.as_ref()
+            {
+                writer.write(&[
+                    signature,
+                    &(*slot as i64),
+                    &index.map(|i| i as i64),
+                    is_vote,
+                    &(*fee as i64),
+                    &compute_units_consumed.map(|c| c as i64),
+                    err,
+                    account_keys,
+                    programs,
+                ])?;
+            }
+        }
+        writer.finish()?;
+
+        tx.batch_execute(&format!(
+            "INSERT INTO {transactions} SELECT * FROM {transactions}_staging
+            ON CONFLICT (signature) DO NOTHING",
+            transactions = self.transactions_table,
+        ))?;
+
+        Ok(())
+    }
+}
+
+impl BatchWriter for PostgresWriter {
+    fn write(&mut self, events: &[Arc<Event>]) -> anyhow::Result<()> {
+        let has_accounts = events
+            .iter()
+            .any(|e| matches!(e.as_ref(), Event::Account { .. }));
+        let has_transactions = events
+            .iter()
+            .any(|e| matches!(e.as_ref(), Event::Transaction { .. }));
+        if !has_accounts && !has_transactions {
+            return Ok(());
+        }
+
+        let mut client = match self.client.take() {
+            Some(client) if !client.is_closed() => client,
+            _ => self.connect()?,
+        };
+
+        let result = (|| {
+            let mut tx = client.transaction()?;
+            if has_accounts {
+                self.write_accounts(&mut tx, events)?;
+            }
+            if has_transactions {
+                self.write_transactions(&mut tx, events)?;
+            }
+            tx.commit()?;
+
+            Ok(())
+        })();
+
+        self.client = Some(client);
+
+        result
+    }
+}
@@ -0,0 +1,476 @@
+//! WebSocket endpoint emulating the accountSubscribe, programSubscribe and logsSubscribe
+//! methods of the Solana RPC PubSub API, for clients such as web3.js.
+//!
+//! Notifications are sent as the plugin gets the updates, the commitment asked for is
+//! ignored. Transaction errors are notified as their message rather than as an object.
+use super::{server::spawn_server, ClosedSink, Event, Sink, SinkStats};
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    response::Response,
+    routing::get,
+    Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    sync::{atomic::Ordering, Arc},
+    thread::JoinHandle,
+};
+use tokio::sync::{
+    broadcast::{self, error::RecvError},
+    oneshot,
+};
+use utils::encoding::AccountDataEncoding;
+
+const DEFAULT_QUEUE_SIZE: usize = 10000;
+
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PubsubConfig {
+    // e.g. "0.0.0.0:8900", the websocket is served on any path
+    pub addr: String,
+    // events kept for the slowest connection, 10000 by default, it misses the older ones
+    // when falling further behind
+    pub queue_size: Option<usize>,
+}
+
+#[derive(Clone)]
+struct Channel {
+    events: broadcast::Sender<Arc<Event>>,
+    stats: Arc<SinkStats>,
+}
+
+pub struct PubsubSink {
+    name: String,
+    channel: Channel,
+    server: JoinHandle<()>,
+    // stops the server when dropped
+    shutdown: oneshot::Sender<()>,
+}
+
+impl PubsubSink {
+    pub fn spawn(config: &PubsubConfig) -> anyhow::Result<Self> {
+        let (events, _) = broadcast::channel(config.queue_size.unwrap_or(DEFAULT_QUEUE_SIZE));
+        let channel = Channel {
+            events,
+            stats: Arc::new(SinkStats::default()),
+        };
+        let router = Router::new()
+            .fallback(get(upgrade))
+            .with_state(channel.clone());
+
+        let (shutdown, stopped) = oneshot::channel();
+        let server = spawn_server("pubsub", &config.addr, router, async {
+            let _ = stopped.await;
+        })?;
+
+        Ok(PubsubSink {
+            name: format!("pubsub {}", config.addr),
+            channel,
+            server,
+            shutdown,
+        })
+    }
+}
+
+impl Sink for PubsubSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Only counted as sent if there is a connection.
+    fn send(&self, event: Arc<Event>) {
+        if self.channel.events.send(event).is_ok() {
+            self.channel
+                .stats
+                .sent_events
+                .fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn stats(&self) -> &SinkStats {
+        &self.channel.stats
+    }
+
+    fn close(self: Box<Self>) -> ClosedSink {
+        drop(self.shutdown);
+
+        ClosedSink {
+            name: self.name,
+            stats: self.channel.stats,
+            worker: self.server,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+enum Encoding {
+    #[default]
+    #[serde(rename = "base58")]
+    Base58,
+    #[serde(rename = "base64")]
+    Base64,
+    #[serde(rename = "base64+zstd")]
+    Base64Zstd,
+}
+
+impl Encoding {
+    fn encode(&self, data: &[u8]) -> Option<Value> {
+        let (encoded, name) = match self {
+            Encoding::Base58 => (bs58::encode(data).into_string(), "base58"),
+            Encoding::Base64 => (AccountDataEncoding::Base64.encode(data).ok()?, "base64"),
+            Encoding::Base64Zstd => (
+                AccountDataEncoding::Base64Zstd.encode(data).ok()?,
+                "base64+zstd",
+            ),
+        };
+
+        Some(json!([encoded, name]))
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+enum ProgramFilter {
+    DataSize(usize),
+    Memcmp(Memcmp),
+}
+
+#[derive(Deserialize, Debug)]
+struct Memcmp {
+    offset: usize,
+    bytes: String,
+    // base58 by default
+    encoding: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct AccountOptions {
+    encoding: Encoding,
+    filters: Vec<ProgramFilter>,
+}
+
+enum DataFilter {
+    Size(usize),
+    Bytes { offset: usize, bytes: Vec<u8> },
+}
+
+impl DataFilter {
+    fn parse(filter: ProgramFilter) -> Result<Self, String> {
+        match filter {
+            ProgramFilter::DataSize(size) => Ok(DataFilter::Size(size)),
+            ProgramFilter::Memcmp(memcmp) => {
+                let bytes = match memcmp.encoding.as_deref() {
+                    None | Some("base58") => bs58::decode(&memcmp.bytes)
+                        .into_vec()
+                        .map_err(|e| e.to_string())?,
+                    Some("base64") => AccountDataEncoding::Base64
+                        .decode(&memcmp.bytes)
+                        .map_err(|e| e.to_string())?,
+                    Some(encoding) => return Err(format!("unsupported encoding {}", encoding)),
+                };
+
+                Ok(DataFilter::Bytes {
+                    offset: memcmp.offset,
+                    bytes,
+                })
+            }
+        }
+    }
+
+    fn matches(&self, data: &[u8]) -> bool {
+        match self {
+            DataFilter::Size(size) => data.len() == *size,
+            DataFilter::Bytes { offset, bytes } => data
+                .get(*offset..offset + bytes.len())
+                .is_some_and(|slice| slice == bytes.as_slice()),
+        }
+    }
+}
+
+enum Subscription {
+    Account {
+        pubkey: String,
+        encoding: Encoding,
+    },
+    Program {
+        program: String,
+        encoding: Encoding,
+        filters: Vec<DataFilter>,
+    },
+    Logs {
+        // None for all transactions
+        mentions: Option<String>,
+        include_votes: bool,
+    },
+}
+
+impl Subscription {
+    fn parse(method: &str, params: &[Value]) -> Result<Self, String> {
+        let options = || {
+            params
+                .get(1)
+                .map(|options| AccountOptions::deserialize(options).map_err(|e| e.to_string()))
+                .unwrap_or_else(|| Ok(AccountOptions::default()))
+        };
+        let pubkey = || {
+            params
+                .first()
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .ok_or_else(|| "missing pubkey".to_string())
+        };
+
+        match method {
+            "accountSubscribe" => Ok(Subscription::Account {
+                pubkey: pubkey()?,
+                encoding: options()?.encoding,
+            }),
+            "programSubscribe" => {
+                let options = options()?;
+                Ok(Subscription::Program {
+                    program: pubkey()?,
+                    encoding: options.encoding,
+                    filters: options
+                        .filters
+                        .into_iter()
+                        .map(DataFilter::parse)
+                        .collect::<Result<_, _>>()?,
+                })
+            }
+            "logsSubscribe" => match params.first() {
+                Some(Value::String(filter)) if filter == "all" => Ok(Subscription::Logs {
+                    mentions: None,
+                    include_votes: false,
+                }),
+                Some(Value::String(filter)) if filter == "allWithVotes" => Ok(Subscription::Logs {
+                    mentions: None,
+                    include_votes: true,
+                }),
+                Some(Value::Object(filter)) => match filter.get("mentions") {
+                    Some(Value::Array(mentions)) if mentions.len() == 1 => Ok(Subscription::Logs {
+                        mentions: mentions[0].as_str().map(str::to_string),
+                        include_votes: true,
+                    }),
+                    _ => Err("mentions takes a single pubkey".to_string()),
+                },
+                _ => Err("unsupported logs filter".to_string()),
+            },
+            _ => Err(format!("unsupported method {}", method)),
+        }
+    }
+
+    /// The notification's method and result, if the event is subscribed to.
+    fn notification(&self, event: &Event) -> Option<(&'static str, Value)> {
+        match (self, event) {
+            (
+                Subscription::Account { pubkey, encoding },
+                Event::Account {
+                    pubkey: account, ..
+                },
+            ) if pubkey == account => Some((
+                "accountNotification",
+                with_context(event.slot(), account_value(event, *encoding)?),
+            )),
+            (
+                Subscription::Program {
+                    program,
+                    encoding,
+                    filters,
+                },
+                Event::Account {
+                    pubkey,
+                    owner,
+                    data,
+                    ..
+                },
+            ) if program == owner && filters.iter().all(|filter| filter.matches(data)) => Some((
+                "programNotification",
+                with_context(
+                    event.slot(),
+                    json!({
+                        "pubkey": pubkey,
+                        "account": account_value(event, *encoding)?,
+                    }),
+                ),
+            )),
+            (
+                Subscription::Logs {
+                    mentions,
+                    include_votes,
+                },
+                Event::Transaction {
+                    signature,
+                    is_vote,
+                    err,
+                    account_keys,
+                    log_messages,
+                    ..
+                },
+            ) if (*include_votes || !is_vote)
+                && mentions
+                    .as_ref()
+                    .map_or(true, |mention| account_keys.contains(mention)) =>
+            {
+                Some((
+                    "logsNotification",
+                    with_context(
+                        event.slot(),
+                        json!({
+                            "signature": signature,
+                            "err": err,
+                            "logs": log_messages.as_deref().unwrap_or_default(),
+                        }),
+                    ),
+                ))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn with_context(slot: u64, value: Value) -> Value {
+    json!({
+        "context": {"slot": slot},
+        "value": value,
+    })
+}
+
+fn account_value(event: &Event, encoding: Encoding) -> Option<Value> {
+    match event {
+        Event::Account {
+            owner,
+            lamports,
+            rent_epoch,
+            executable,
+            data,
+            ..
+        } => Some(json!({
+            "lamports": lamports,
+            "owner": owner,
+            "data": encoding.encode(data)?,
+            "executable": executable,
+            "rentEpoch": rent_epoch,
+            "space": data.len(),
+        })),
+        _ => None,
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct Request {
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Vec<Value>,
+}
+
+fn error(id: Value, code: i64, message: String) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "error": {"code": code, "message": message},
+        "id": id,
+    })
+}
+
+async fn upgrade(State(channel): State<Channel>, upgrade: WebSocketUpgrade) -> Response {
+    upgrade.on_upgrade(move |socket| serve_connection(channel, socket))
+}
+
+async fn serve_connection(channel: Channel, mut socket: WebSocket) {
+    let mut events = channel.events.subscribe();
+    let mut subscriptions = HashMap::new();
+    let mut next_id = 0u64;
+
+    loop {
+        let messages = tokio::select! {
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    vec![handle_request(&text, &mut subscriptions, &mut next_id)]
+                }
+                Some(Ok(_)) => continue,
+                _ => break,
+            },
+            event = events.recv() => match event {
+                Ok(event) => subscriptions
+                    .iter()
+                    .filter_map(|(id, subscription): (&u64, &Subscription)| {
+                        let (method, result) = subscription.notification(&event)?;
+                        Some(json!({
+                            "jsonrpc": "2.0",
+                            "method": method,
+                            "params": {"result": result, "subscription": id},
+                        }))
+                    })
+                    .collect(),
+                Err(RecvError::Lagged(missed)) => {
+                    channel.stats.dropped_events.fetch_add(missed, Ordering::Relaxed);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            },
+        };
+
+        for message in messages {
+            if socket
+                .send(Message::Text(message.to_string()))
+                .await
+                .is_err()
+            {
+                return;
+            }
+        }
+    }
+}
+
+fn handle_request(
+    text: &str,
+    subscriptions: &mut HashMap<u64, Subscription>,
+    next_id: &mut u64,
+) -> Value {
+    let request = match serde_json::from_str::<Request>(text) {
+        Ok(request) => request,
+        Err(e) => return error(Value::Null, INVALID_REQUEST, e.to_string()),
+    };
+
+    let result = match request.method.as_str() {
+        "accountUnsubscribe" | "programUnsubscribe" | "logsUnsubscribe" => {
+            match request.params.first().and_then(Value::as_u64) {
+                Some(id) => json!(subscriptions.remove(&id).is_some()),
+                None => return error(request.id, INVALID_PARAMS, "missing subscription id".into()),
+            }
+        }
+        "accountSubscribe" | "programSubscribe" | "logsSubscribe" => {
+            match Subscription::parse(&request.method, &request.params) {
+                Ok(subscription) => {
+                    let id = *next_id;
+                    *next_id += 1;
+                    subscriptions.insert(id, subscription);
+                    json!(id)
+                }
+                Err(e) => return error(request.id, INVALID_PARAMS, e),
+            }
+        }
+        method => {
+            return error(
+                request.id,
+                METHOD_NOT_FOUND,
+                format!("unsupported method {}", method),
+            )
+        }
+    };
+
+    json!({
+        "jsonrpc": "2.0",
+        "result": result,
+        "id": request.id,
+    })
+}
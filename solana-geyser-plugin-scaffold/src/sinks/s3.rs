@@ -0,0 +1,164 @@
+use super::{
+    batch::{BatchOptions, BatchWriter},
+    Event,
+};
+use chrono::Utc;
+use object_store::{
+    aws::{AmazonS3, AmazonS3Builder},
+    path::Path,
+    ObjectStore,
+};
+use serde::{Deserialize, Serialize};
+use std::{io::Write, sync::Arc};
+use tokio::runtime::Runtime;
+use utils::encoding::AccountDataEncoding;
+
+const MANIFEST_FILE: &str = "manifest.json";
+const DEFAULT_ZSTD_LEVEL: i32 = 3;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct S3Config {
+    pub bucket: String,
+
+    // key prefix of the archive, the bucket root by default
+    pub prefix: Option<String>,
+
+    pub region: Option<String>,
+
+    // for S3 compatible storages, e.g. http://localhost:9000 for MinIO
+    pub endpoint: Option<String>,
+
+    // read from the AWS_* environment variables if not set
+    pub access_key_id: Option<String>,
+    pub secret_access_key: Option<String>,
+
+    // 3 by default
+    pub zstd_level: Option<i32>,
+
+    // encoding of the account data: "base64", the default, "base64+zstd" or "hex"
+    #[serde(default)]
+    pub encoding: AccountDataEncoding,
+
+    // every batch is written as one segment, larger batches than the other sinks are
+    // recommended, e.g. "batch_size": 100000, "batch_interval_ms": 60000
+    #[serde(flatten)]
+    pub batch: BatchOptions,
+}
+
+#[derive(Serialize, Debug, Clone)]
+struct SegmentInfo {
+    key: String,
+    first_slot: u64,
+    last_slot: u64,
+    events: usize,
+    created_at: String,
+}
+
+/// Segments and their manifest for one day of archive.
+struct Manifest {
+    date: String,
+    segments: Vec<SegmentInfo>,
+}
+
+/// Archives the events to S3 as zstd compressed JSON lines segments, partitioned by day and
+/// hour: `<prefix>/<YYYY-MM-DD>/<HH>/<first slot>-<last slot>-<n>.jsonl.zst`. Every day has
+/// a `manifest.json` listing its segments, in the order they were written.
+pub struct S3Writer {
+    store: AmazonS3,
+    runtime: Runtime,
+    prefix: String,
+    zstd_level: i32,
+    encoding: AccountDataEncoding,
+    manifest: Manifest,
+}
+
+impl S3Writer {
+    pub fn new(config: &S3Config) -> anyhow::Result<Self> {
+        let mut builder = AmazonS3Builder::from_env().with_bucket_name(&config.bucket);
+        if let Some(region) = &config.region {
+            builder = builder.with_region(region);
+        }
+        if let Some(endpoint) = &config.endpoint {
+            builder = builder
+                .with_endpoint(endpoint)
+                .with_allow_http(endpoint.starts_with("http://"));
+        }
+        if let Some(access_key_id) = &config.access_key_id {
+            builder = builder.with_access_key_id(access_key_id);
+        }
+        if let Some(secret_access_key) = &config.secret_access_key {
+            builder = builder.with_secret_access_key(secret_access_key);
+        }
+
+        Ok(S3Writer {
+            store: builder.build()?,
+            runtime: tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?,
+            prefix: config.prefix.clone().unwrap_or_default(),
+            zstd_level: config.zstd_level.unwrap_or(DEFAULT_ZSTD_LEVEL),
+            encoding: config.encoding,
+            manifest: Manifest {
+                date: String::new(),
+                segments: Vec::new(),
+            },
+        })
+    }
+
+    fn path(&self, key: &str) -> Path {
+        match self.prefix.trim_matches('/') {
+            "" => Path::from(key),
+            prefix => Path::from(format!("{}/{}", prefix, key)),
+        }
+    }
+
+    fn put(&self, key: &str, data: Vec<u8>) -> anyhow::Result<()> {
+        let path = self.path(key);
+        self.runtime.block_on(self.store.put(&path, data.into()))?;
+
+        Ok(())
+    }
+}
+
+impl BatchWriter for S3Writer {
+    fn write(&mut self, events: &[Arc<Event>]) -> anyhow::Result<()> {
+        let now = Utc::now();
+        let date = now.format("%Y-%m-%d").to_string();
+        if self.manifest.date != date {
+            self.manifest = Manifest {
+                date: date.clone(),
+                segments: Vec::new(),
+            };
+        }
+
+        let first_slot = events.iter().map(|e| e.slot()).min().unwrap_or_default();
+        let last_slot = events.iter().map(|e| e.slot()).max().unwrap_or_default();
+        let key = format!(
+            "{}/{}/{:012}-{:012}-{}.jsonl.zst",
+            date,
+            now.format("%H"),
+            first_slot,
+            last_slot,
+            self.manifest.segments.len()
+        );
+
+        let mut encoder = zstd::Encoder::new(Vec::new(), self.zstd_level)?;
+        for event in events {
+            serde_json::to_writer(&mut encoder, &event.encoded(self.encoding))?;
+            encoder.write_all(b"\n")?;
+        }
+        self.put(&key, encoder.finish()?)?;
+
+        self.manifest.segments.push(SegmentInfo {
+            key,
+            first_slot,
+            last_slot,
+            events: events.len(),
+            created_at: now.to_rfc3339(),
+        });
+        let manifest = serde_json::to_vec_pretty(&self.manifest.segments)?;
+        self.put(&format!("{}/{}", date, MANIFEST_FILE), manifest)?;
+
+        Ok(())
+    }
+}
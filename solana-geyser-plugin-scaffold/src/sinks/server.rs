@@ -0,0 +1,42 @@
+use axum::Router;
+use log::{info, warn};
+use std::{
+    future::Future,
+    thread::{self, JoinHandle},
+};
+
+/// Serves the router from a thread of its own, until `shutdown` completes. The address is
+/// bound right away, so that a taken port fails the plugin load.
+pub(crate) fn spawn_server(
+    name: &'static str,
+    addr: &str,
+    router: Router,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> anyhow::Result<JoinHandle<()>> {
+    let listener = std::net::TcpListener::bind(addr)?;
+    listener.set_nonblocking(true)?;
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(2)
+        .enable_all()
+        .build()?;
+
+    info!("[{}] - listening on {}", name, addr);
+    let server = thread::spawn(move || {
+        // the open websockets are dropped with the runtime, once the server returns
+        runtime.block_on(async move {
+            let served = match tokio::net::TcpListener::from_std(listener) {
+                Ok(listener) => {
+                    axum::serve(listener, router)
+                        .with_graceful_shutdown(shutdown)
+                        .await
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = served {
+                warn!("[{}] - server stopped: {}", name, e);
+            }
+        })
+    });
+
+    Ok(server)
+}
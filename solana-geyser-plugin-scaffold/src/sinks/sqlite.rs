@@ -0,0 +1,102 @@
+use super::{
+    batch::{BatchOptions, BatchWriter},
+    Event,
+};
+use rusqlite::{params, Connection};
+use serde::Deserialize;
+use std::sync::Arc;
+use utils::encoding::AccountDataEncoding;
+
+const DEFAULT_RETENTION_SLOTS: u64 = 10_000;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS events (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        slot INTEGER NOT NULL,
+        type TEXT NOT NULL,
+        pubkey TEXT,
+        signature TEXT,
+        event TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS events_slot ON events (slot);
+    CREATE INDEX IF NOT EXISTS events_pubkey ON events (pubkey, slot) WHERE pubkey IS NOT NULL;
+    CREATE INDEX IF NOT EXISTS events_signature ON events (signature) WHERE signature IS NOT NULL;
+";
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SqliteConfig {
+    pub path: String,
+
+    // events older than this many slots behind the newest one are deleted, 10000 by default
+    pub retention_slots: Option<u64>,
+
+    // encoding of the account data: "base64", the default, "base64+zstd" or "hex"
+    #[serde(default)]
+    pub encoding: AccountDataEncoding,
+
+    #[serde(flatten)]
+    pub batch: BatchOptions,
+}
+
+/// Archives the recent events in a SQLite `events` table, as JSON along with the columns
+/// they are looked up by: slot, account pubkey and transaction signature. Gives small
+/// deployments a replay and backfill source without running a database server.
+pub struct SqliteWriter {
+    conn: Connection,
+    retention_slots: u64,
+    encoding: AccountDataEncoding,
+}
+
+impl SqliteWriter {
+    pub fn new(config: &SqliteConfig) -> anyhow::Result<Self> {
+        let conn = Connection::open(&config.path)?;
+        // lets readers query the archive while it is written
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.execute_batch(SCHEMA)?;
+
+        Ok(SqliteWriter {
+            conn,
+            retention_slots: config.retention_slots.unwrap_or(DEFAULT_RETENTION_SLOTS),
+            encoding: config.encoding,
+        })
+    }
+}
+
+impl BatchWriter for SqliteWriter {
+    fn write(&mut self, events: &[Arc<Event>]) -> anyhow::Result<()> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut insert = tx.prepare_cached(
+                "INSERT INTO events (slot, type, pubkey, signature, event) VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+
+            for event in events {
+                let (event_type, pubkey, signature) = match event.as_ref() {
+                    Event::Account { pubkey, .. } => ("account", Some(pubkey), None),
+                    Event::Slot { .. } => ("slot", None, None),
+                    Event::Transaction { signature, .. } => ("transaction", None, Some(signature)),
+                    Event::Block { .. } => ("block", None, None),
+                };
+
+                insert.execute(params![
+                    event.slot(),
+                    event_type,
+                    pubkey,
+                    signature,
+                    serde_json::to_string(&event.encoded(self.encoding))?,
+                ])?;
+            }
+        }
+
+        if let Some(newest) = events.iter().map(|e| e.slot()).max() {
+            tx.execute(
+                "DELETE FROM events WHERE slot < ?1",
+                params![newest.saturating_sub(self.retention_slots)],
+            )?;
+        }
+
+        tx.commit()?;
+
+        Ok(())
+    }
+}
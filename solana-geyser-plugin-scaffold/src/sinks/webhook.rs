@@ -0,0 +1,84 @@
+use super::{
+    batch::{BatchOptions, BatchWriter},
+    Event,
+};
+use anyhow::Context;
+use reqwest::{
+    blocking::Client,
+    header::{HeaderMap, HeaderName, HeaderValue},
+};
+use serde::Deserialize;
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use utils::encoding::AccountDataEncoding;
+
+const DEFAULT_TIMEOUT_MS: u64 = 10_000;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct WebhookConfig {
+    pub url: String,
+
+    // sent with every request, e.g. {"Authorization": "Bearer ..."}
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+
+    // 10000ms by default
+    pub timeout_ms: Option<u64>,
+
+    // encoding of the account data: "base64", the default, "base64+zstd" or "hex"
+    #[serde(default)]
+    pub encoding: AccountDataEncoding,
+
+    #[serde(flatten)]
+    pub batch: BatchOptions,
+}
+
+/// POSTs every batch of events to a URL, as a JSON array.
+pub struct WebhookWriter {
+    client: Client,
+    url: String,
+    encoding: AccountDataEncoding,
+}
+
+impl WebhookWriter {
+    pub fn new(config: &WebhookConfig) -> anyhow::Result<Self> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &config.headers {
+            headers.insert(
+                HeaderName::from_bytes(name.as_bytes())
+                    .with_context(|| format!("invalid header name {}", name))?,
+                HeaderValue::from_str(value)
+                    .with_context(|| format!("invalid value of header {}", name))?,
+            );
+        }
+
+        let client = Client::builder()
+            .default_headers(headers)
+            .timeout(Duration::from_millis(
+                config.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS),
+            ))
+            .build()?;
+
+        Ok(WebhookWriter {
+            client,
+            url: config.url.clone(),
+            encoding: config.encoding,
+        })
+    }
+}
+
+impl BatchWriter for WebhookWriter {
+    fn write(&mut self, events: &[Arc<Event>]) -> anyhow::Result<()> {
+        self.client
+            .post(&self.url)
+            .json(
+                &events
+                    .iter()
+                    .map(|event| event.encoded(self.encoding))
+                    .collect::<Vec<_>>(),
+            )
+            .send()?
+            .error_for_status()?;
+
+        Ok(())
+    }
+}
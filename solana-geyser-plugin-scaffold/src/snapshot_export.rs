@@ -0,0 +1,120 @@
+use log::info;
+use serde::Serialize;
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::PathBuf,
+    sync::Mutex,
+};
+use utils::sender::TcpBuffer;
+
+pub const DEFAULT_SEGMENT_MAX_BYTES: usize = 64 * 1024 * 1024;
+const INDEX_FILE: &str = "index.json";
+const ZSTD_LEVEL: i32 = 3;
+
+#[derive(Serialize)]
+struct SegmentInfo {
+    file: String,
+    accounts: usize,
+    /// Size of the segment before compression
+    bytes: usize,
+}
+
+#[derive(Serialize)]
+struct SnapshotIndex<'a> {
+    plugin_version: &'a str,
+    schema_hash: &'a str,
+    accounts: usize,
+    segments: &'a [SegmentInfo],
+}
+
+struct ExportState {
+    buffer: TcpBuffer,
+    accounts: usize,
+    segments: Vec<SegmentInfo>,
+}
+
+/// Writes the startup accounts to zstd compressed segment files instead of the TCP stream.
+///
+/// Each segment holds a single batch, in the same format as the TCP stream, of serialized
+/// account messages. `index.json` lists the segments once the startup is over.
+pub struct SnapshotExporter {
+    dir: PathBuf,
+    segment_max_bytes: usize,
+    state: Mutex<ExportState>,
+}
+
+impl SnapshotExporter {
+    pub fn new(dir: PathBuf, segment_max_bytes: usize) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+
+        Ok(SnapshotExporter {
+            dir,
+            segment_max_bytes,
+            state: Mutex::new(ExportState {
+                buffer: TcpBuffer::new(1024),
+                accounts: 0,
+                segments: Vec::new(),
+            }),
+        })
+    }
+
+    pub fn append(&self, message: Vec<u8>) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        state.buffer.append(message);
+        state.accounts += 1;
+
+        if state.buffer.total_bytesize() >= self.segment_max_bytes {
+            self.write_segment(&mut state)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the last segment and the index.
+    pub fn finish(&self, plugin_version: &str, schema_hash: &str) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        self.write_segment(&mut state)?;
+
+        let index = SnapshotIndex {
+            plugin_version,
+            schema_hash,
+            accounts: state.segments.iter().map(|s| s.accounts).sum(),
+            segments: &state.segments,
+        };
+        let data = serde_json::to_vec_pretty(&index)?;
+        fs::write(self.dir.join(INDEX_FILE), data)?;
+
+        info!(
+            "[snapshot_export] - {} accounts written to {} segments in {}",
+            index.accounts,
+            state.segments.len(),
+            self.dir.display()
+        );
+
+        Ok(())
+    }
+
+    fn write_segment(&self, state: &mut ExportState) -> io::Result<()> {
+        if state.buffer.total_bytesize() == 0 {
+            return Ok(());
+        }
+
+        let batch = state.buffer.flush_data();
+        let file = format!("accounts-{:05}.bin.zst", state.segments.len());
+
+        let mut encoder = zstd::Encoder::new(File::create(self.dir.join(&file))?, ZSTD_LEVEL)?;
+        encoder.write_all(&batch)?;
+        encoder.finish()?.sync_all()?;
+
+        state.segments.push(SegmentInfo {
+            file,
+            accounts: state.accounts,
+            bytes: batch.len(),
+        });
+        state.accounts = 0;
+
+        Ok(())
+    }
+}
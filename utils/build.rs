@@ -0,0 +1,45 @@
+use std::{fs, path::Path};
+
+// Hashes the flatbuffer schemas so producers and consumers can check they were built
+// against the same message definitions.
+fn main() {
+    let dir = Path::new("src/flatbuffer");
+    println!("cargo:rerun-if-changed={}", dir.display());
+
+    let mut schemas = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "fbs"))
+        .collect::<Vec<_>>();
+    schemas.sort();
+
+    // FNV-1a, stable across toolchains unlike std's DefaultHasher
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for schema in schemas {
+        for byte in fs::read(schema).unwrap() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    println!("cargo:rustc-env=FLATBUFFER_SCHEMA_HASH={:016x}", hash);
+
+    #[cfg(feature = "ffi")]
+    generate_header();
+
+    #[cfg(feature = "node")]
+    napi_build::setup();
+}
+
+/// The C header of the `ffi` module, checked in so consumers don't need cbindgen.
+#[cfg(feature = "ffi")]
+fn generate_header() {
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+
+    cbindgen::Builder::new()
+        .with_src("src/ffi.rs")
+        .with_config(cbindgen::Config::from_file("cbindgen.toml").unwrap())
+        .generate()
+        .unwrap()
+        .write_to_file("include/solana_geyser_zmq.h");
+}
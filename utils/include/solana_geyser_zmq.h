@@ -0,0 +1,185 @@
+#ifndef SOLANA_GEYSER_ZMQ_H
+#define SOLANA_GEYSER_ZMQ_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define GEYSER_OK 0
+
+/**
+ * No event was received before the timeout
+ */
+#define GEYSER_TIMEOUT 1
+
+/**
+ * A null pointer or an invalid message
+ */
+#define GEYSER_ERR_INVALID -1
+
+/**
+ * A message without typed decoding, such as a diagnostic
+ */
+#define GEYSER_ERR_UNSUPPORTED -2
+
+/**
+ * The receiver stopped
+ */
+#define GEYSER_ERR_CLOSED -3
+
+typedef enum GeyserEventKind {
+  GEYSER_EVENT_KIND_NONE,
+  GEYSER_EVENT_KIND_ACCOUNT,
+  GEYSER_EVENT_KIND_SLOT,
+  GEYSER_EVENT_KIND_TRANSACTION,
+  GEYSER_EVENT_KIND_BLOCK,
+  GEYSER_EVENT_KIND_METADATA,
+  GEYSER_EVENT_KIND_REWARDS,
+} GeyserEventKind;
+
+typedef enum GeyserSlotStatus {
+  GEYSER_SLOT_STATUS_PROCESSED,
+  GEYSER_SLOT_STATUS_CONFIRMED,
+  GEYSER_SLOT_STATUS_ROOTED,
+} GeyserSlotStatus;
+
+typedef struct GeyserReceiver GeyserReceiver;
+
+typedef struct GeyserAccount {
+  const char *pubkey;
+  const char *owner;
+  uint64_t lamports;
+  uint64_t rent_epoch;
+  bool executable;
+  uint64_t write_version;
+  const uint8_t *data;
+  uintptr_t data_len;
+  /**
+   * Null if the update wasn't caused by a transaction
+   */
+  const char *txn_signature;
+} GeyserAccount;
+
+typedef struct GeyserSlot {
+  enum GeyserSlotStatus status;
+  bool has_parent;
+  uint64_t parent;
+} GeyserSlot;
+
+typedef struct GeyserTransaction {
+  const char *signature;
+  bool is_vote;
+  uint64_t fee;
+  /**
+   * Name of the error, null if the transaction succeeded
+   */
+  const char *err;
+  const char *const *account_keys;
+  uintptr_t account_keys_len;
+  const char *const *log_messages;
+  uintptr_t log_messages_len;
+} GeyserTransaction;
+
+typedef struct GeyserBlock {
+  const char *blockhash;
+  bool has_parent_slot;
+  uint64_t parent_slot;
+  int64_t block_time;
+  uint64_t block_height;
+} GeyserBlock;
+
+typedef struct GeyserMetadata {
+  uint64_t send_errors;
+  /**
+   * Null if unknown
+   */
+  const char *version;
+} GeyserMetadata;
+
+/**
+ * The rewards themselves are not exposed, as for blocks.
+ */
+typedef struct GeyserRewards {
+  bool has_block_height;
+  uint64_t block_height;
+  bool has_block_time;
+  int64_t block_time;
+  uintptr_t rewards_len;
+} GeyserRewards;
+
+/**
+ * A decoded event, its fields under the member named after its kind, the other members
+ * being zeroed. The pointers stay valid until `geyser_event_free`.
+ */
+typedef struct GeyserEvent {
+  enum GeyserEventKind kind;
+  uint64_t slot;
+  struct GeyserAccount account;
+  struct GeyserSlot slot_update;
+  struct GeyserTransaction transaction;
+  struct GeyserBlock block;
+  struct GeyserMetadata metadata;
+  struct GeyserRewards rewards;
+  void *owned;
+} GeyserEvent;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Decodes the message into `out_event`, to be released with `geyser_event_free` if
+ * `GEYSER_OK` is returned.
+ *
+ * # Safety
+ * `buf` must point to `len` readable bytes and `out_event` to a writable `GeyserEvent`.
+ */
+int geyser_decode(const uint8_t *buf, uintptr_t len, struct GeyserEvent *out_event);
+
+/**
+ * Releases the memory of a decoded event, its pointers becoming dangling.
+ *
+ * # Safety
+ * `event` must be null or have been filled by `geyser_decode` or `geyser_poll`.
+ */
+void geyser_event_free(struct GeyserEvent *event);
+
+/**
+ * Connects to a plugin, e.g. "127.0.0.1:2000", reconnecting every `reconnect_interval_ms`
+ * when the connection drops. Returns null if the address is invalid.
+ *
+ * # Safety
+ * `addr` must be a nul terminated string.
+ */
+struct GeyserReceiver *geyser_connect(const char *addr, uint64_t reconnect_interval_ms);
+
+/**
+ * Waits up to `timeout_ms` for the next event and decodes it into `out_event`, to be
+ * released with `geyser_event_free` if `GEYSER_OK` is returned. Messages without typed
+ * decoding are skipped. Timeouts too long to set a deadline with, e.g. `UINT64_MAX`, wait
+ * until an event comes or the receiver is closed.
+ *
+ * # Safety
+ * `receiver` must come from `geyser_connect` and `out_event` point to a writable
+ * `GeyserEvent`.
+ */
+int geyser_poll(struct GeyserReceiver *receiver,
+                struct GeyserEvent *out_event,
+                uint64_t timeout_ms);
+
+/**
+ * Disconnects and releases the receiver.
+ *
+ * # Safety
+ * `receiver` must be null or come from `geyser_connect`, and not be used afterwards.
+ */
+void geyser_receiver_free(struct GeyserReceiver *receiver);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* SOLANA_GEYSER_ZMQ_H */
@@ -0,0 +1,12 @@
+// Native bindings generated by `npm run build`, see src/node.rs
+const binding = require('./binding.js')
+
+binding.TcpReceiver.prototype[Symbol.asyncIterator] = async function* () {
+  for (;;) {
+    const event = await this.next()
+    if (event === null) return
+    yield event
+  }
+}
+
+module.exports = binding
@@ -0,0 +1,175 @@
+//! Chunking of the messages larger than a sender's `max_message_bytes`, for consumers which
+//! can't take batches of any size.
+//!
+//! A message is split, as published, compressed or not, into chunk messages behind the
+//! `BYTE_PREFIX_CHUNK` byte, each holding, as little endian, the prefix byte of the message,
+//! the id of the message on the sender as 8 bytes, the index of the chunk and the number of
+//! chunks as 4 bytes each, then its part of the rest of the message. `Reassembler` puts the
+//! message back together, as `TcpReceiver` does before handing it to its callback.
+use crate::flatbuffer::consts::BYTE_PREFIX_CHUNK;
+use std::{collections::VecDeque, convert::TryInto};
+
+pub const CHUNK_HEADER_BYTE_SIZE: usize = 2 + 8 + 4 + 4;
+pub const DEFAULT_MAX_PARTIAL_MESSAGES: usize = 16;
+
+/// Splits the message into chunks of at most `max_bytes`, header included.
+pub fn split_message(message: &[u8], max_bytes: usize, id: u64) -> Vec<Vec<u8>> {
+    let Some((&prefix, data)) = message.split_first() else {
+        return Vec::new();
+    };
+
+    let chunk_bytes = max_bytes.saturating_sub(CHUNK_HEADER_BYTE_SIZE).max(1);
+    let count = data.len().div_ceil(chunk_bytes).max(1);
+
+    (0..count)
+        .map(|index| {
+            let part = &data[(index * chunk_bytes).min(data.len())
+                ..((index + 1) * chunk_bytes).min(data.len())];
+
+            let mut chunk = Vec::with_capacity(CHUNK_HEADER_BYTE_SIZE + part.len());
+            chunk.push(BYTE_PREFIX_CHUNK);
+            chunk.push(prefix);
+            chunk.extend_from_slice(&id.to_le_bytes());
+            chunk.extend_from_slice(&(index as u32).to_le_bytes());
+            chunk.extend_from_slice(&(count as u32).to_le_bytes());
+            chunk.extend_from_slice(part);
+            chunk
+        })
+        .collect()
+}
+
+/// Prefix byte of the message a chunk is part of, None if it's not a chunk.
+pub fn chunked_prefix(message: &[u8]) -> Option<u8> {
+    match message {
+        [BYTE_PREFIX_CHUNK, prefix, ..] => Some(*prefix),
+        _ => None,
+    }
+}
+
+struct Chunk<'a> {
+    prefix: u8,
+    id: u64,
+    index: usize,
+    count: usize,
+    data: &'a [u8],
+}
+
+fn parse_chunk(message: &[u8]) -> Option<Chunk<'_>> {
+    let prefix = chunked_prefix(message)?;
+    let header = message.get(..CHUNK_HEADER_BYTE_SIZE)?;
+
+    Some(Chunk {
+        prefix,
+        id: u64::from_le_bytes(header[2..10].try_into().ok()?),
+        index: u32::from_le_bytes(header[10..14].try_into().ok()?) as usize,
+        count: u32::from_le_bytes(header[14..18].try_into().ok()?) as usize,
+        data: &message[CHUNK_HEADER_BYTE_SIZE..],
+    })
+}
+
+struct Partial {
+    id: u64,
+    prefix: u8,
+    parts: Vec<Option<Vec<u8>>>,
+    missing: usize,
+}
+
+/// Puts chunked messages back together, whatever the order their chunks come in. The
+/// messages missing chunks, e.g. dropped by the sender, are given up on once more than
+/// `max_partial` are being reassembled, the oldest first.
+pub struct Reassembler {
+    partial: VecDeque<Partial>,
+    max_partial: usize,
+}
+
+impl Default for Reassembler {
+    fn default() -> Self {
+        Reassembler::new(DEFAULT_MAX_PARTIAL_MESSAGES)
+    }
+}
+
+impl Reassembler {
+    pub fn new(max_partial: usize) -> Self {
+        Reassembler {
+            partial: VecDeque::new(),
+            max_partial: max_partial.max(1),
+        }
+    }
+
+    /// Adds a chunk, returning the message once it's complete. Messages which aren't chunks
+    /// are returned as is.
+    pub fn push(&mut self, message: Vec<u8>) -> Option<Vec<u8>> {
+        let Some(chunk) = parse_chunk(&message) else {
+            return Some(message);
+        };
+        if chunk.index >= chunk.count {
+            return None;
+        }
+
+        let position = match self.partial.iter().position(|p| p.id == chunk.id) {
+            Some(position) => position,
+            None => {
+                if self.partial.len() >= self.max_partial {
+                    self.partial.pop_front();
+                }
+                self.partial.push_back(Partial {
+                    id: chunk.id,
+                    prefix: chunk.prefix,
+                    parts: vec![None; chunk.count],
+                    missing: chunk.count,
+                });
+                self.partial.len() - 1
+            }
+        };
+
+        let partial = &mut self.partial[position];
+        if let Some(part @ None) = partial.parts.get_mut(chunk.index) {
+            *part = Some(chunk.data.to_vec());
+            partial.missing -= 1;
+        }
+        if partial.missing > 0 {
+            return None;
+        }
+
+        let partial = self.partial.remove(position)?;
+        let mut message = vec![partial.prefix];
+        for part in partial.parts.into_iter().flatten() {
+            message.extend(part);
+        }
+
+        Some(message)
+    }
+
+    /// Forgets the partial messages, e.g. when reconnecting to the sender.
+    pub fn clear(&mut self) {
+        self.partial.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_and_reassemble() {
+        let message = (0..100u8).collect::<Vec<_>>();
+        let chunks = split_message(&message, CHUNK_HEADER_BYTE_SIZE + 10, 7);
+        assert_eq!(chunks.len(), 10);
+        assert!(chunks
+            .iter()
+            .all(|chunk| chunk.len() <= CHUNK_HEADER_BYTE_SIZE + 10));
+        assert_eq!(chunked_prefix(&chunks[0]), Some(0));
+
+        let mut reassembler = Reassembler::new(2);
+        let other = split_message(&[1, 2, 3], CHUNK_HEADER_BYTE_SIZE + 1, 8);
+
+        // interleaved with another message, out of order
+        for chunk in chunks.iter().rev().skip(1) {
+            assert_eq!(reassembler.push(chunk.clone()), None);
+        }
+        assert_eq!(reassembler.push(other[1].clone()), None);
+        assert_eq!(reassembler.push(other[0].clone()), Some(vec![1, 2, 3]));
+        assert_eq!(reassembler.push(vec![5, 6]), Some(vec![5, 6]));
+        assert_eq!(reassembler.push(chunks[9].clone()), Some(message));
+    }
+}
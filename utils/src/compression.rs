@@ -0,0 +1,143 @@
+//! Message-level zstd or lz4 compression.
+//!
+//! A compressed message keeps its prefix byte, with `COMPRESSED_FLAG` set, followed by the
+//! zstd or lz4 frame of the flatbuffer, told apart by the magic number the frame starts with.
+use crate::{flatbuffer::consts::COMPRESSED_FLAG, message_type::MessageType};
+use serde::{Deserialize, Serialize};
+use std::{
+    borrow::Cow,
+    io::{self, Read, Write},
+};
+
+pub const DEFAULT_COMPRESSION_LEVEL: i32 = 3;
+pub const DEFAULT_COMPRESSION_MIN_BYTES: usize = 512;
+
+const LZ4_FRAME_MAGIC: [u8; 4] = [0x04, 0x22, 0x4d, 0x18];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum CompressionCodec {
+    #[default]
+    Zstd,
+    /// Faster than zstd for a lower ratio, for latency-sensitive consumers
+    Lz4,
+}
+
+pub const SUPPORTED_COMPRESSION_CODECS: [CompressionCodec; 2] =
+    [CompressionCodec::Zstd, CompressionCodec::Lz4];
+
+impl CompressionCodec {
+    fn compress(&self, data: &[u8], level: i32) -> io::Result<Vec<u8>> {
+        match self {
+            CompressionCodec::Zstd => zstd::bulk::compress(data, level),
+            CompressionCodec::Lz4 => {
+                let mut encoder = lz4_flex::frame::FrameEncoder::new(Vec::new());
+                encoder.write_all(data)?;
+                encoder.finish().map_err(io::Error::other)
+            }
+        }
+    }
+}
+
+/// Which messages get compressed and how hard.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CompressionPolicy {
+    /// zstd by default, subscribers can ask for another codec in their client hello
+    pub codec: Option<CompressionCodec>,
+    /// zstd level, 3 by default, lz4 has no levels
+    pub level: Option<i32>,
+    /// Types of the messages to compress, e.g. `["account", "block"]`
+    pub message_types: Vec<MessageType>,
+    /// Smaller messages are sent as is, since compressing them costs CPU for no gain,
+    /// 512 by default
+    pub min_bytes: Option<usize>,
+}
+
+impl CompressionPolicy {
+    /// Compresses the message with the policy's codec if the policy covers it, returns it
+    /// untouched otherwise or if compression doesn't make it smaller.
+    pub fn apply(&self, message: Vec<u8>) -> Vec<u8> {
+        self.apply_with(message, self.codec.unwrap_or_default())
+    }
+
+    /// Like `apply`, with the given codec instead of the policy's one.
+    pub fn apply_with(&self, message: Vec<u8>, codec: CompressionCodec) -> Vec<u8> {
+        let covered = message
+            .first()
+            .and_then(|prefix| MessageType::from_prefix(*prefix))
+            .is_some_and(|message_type| self.message_types.contains(&message_type));
+
+        if !covered || message.len() < self.min_bytes.unwrap_or(DEFAULT_COMPRESSION_MIN_BYTES) {
+            return message;
+        }
+
+        let level = self.level.unwrap_or(DEFAULT_COMPRESSION_LEVEL);
+        match codec.compress(&message[1..], level) {
+            Ok(compressed) if compressed.len() + 1 < message.len() => {
+                let mut output = Vec::with_capacity(compressed.len() + 1);
+                output.push(message[0] | COMPRESSED_FLAG);
+                output.extend(compressed);
+                output
+            }
+            _ => message,
+        }
+    }
+}
+
+pub fn is_compressed(message: &[u8]) -> bool {
+    message
+        .first()
+        .is_some_and(|prefix| prefix & COMPRESSED_FLAG != 0)
+}
+
+/// Returns the message as published before compression, borrowed if it wasn't compressed.
+pub fn decompress(message: &[u8]) -> io::Result<Cow<'_, [u8]>> {
+    if !is_compressed(message) {
+        return Ok(Cow::Borrowed(message));
+    }
+
+    let mut output = vec![message[0] & !COMPRESSED_FLAG];
+    let frame = &message[1..];
+    if frame.starts_with(&LZ4_FRAME_MAGIC) {
+        lz4_flex::frame::FrameDecoder::new(frame).read_to_end(&mut output)?;
+    } else {
+        output.extend(zstd::stream::decode_all(frame)?);
+    }
+
+    Ok(Cow::Owned(output))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flatbuffer::consts::{BYTE_PREFIX_ACCOUNT, BYTE_PREFIX_SLOT};
+
+    #[test]
+    fn test_compression_policy() {
+        let policy = CompressionPolicy {
+            codec: None,
+            level: None,
+            message_types: vec![MessageType::Account],
+            min_bytes: Some(16),
+        };
+
+        let mut account = vec![BYTE_PREFIX_ACCOUNT];
+        account.extend(vec![7; 4096]);
+        let compressed = policy.apply(account.clone());
+        assert!(is_compressed(&compressed));
+        assert!(compressed.len() < account.len());
+        assert_eq!(
+            decompress(&compressed).unwrap().as_ref(),
+            account.as_slice()
+        );
+
+        let lz4 = policy.apply_with(account.clone(), CompressionCodec::Lz4);
+        assert!(is_compressed(&lz4));
+        assert_ne!(lz4, compressed);
+        assert_eq!(decompress(&lz4).unwrap().as_ref(), account.as_slice());
+
+        let mut slot = vec![BYTE_PREFIX_SLOT];
+        slot.extend(vec![7; 4096]);
+        assert_eq!(policy.apply(slot.clone()), slot);
+    }
+}
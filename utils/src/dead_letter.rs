@@ -0,0 +1,220 @@
+//! Dead letters of `TcpSender`: batches no subscriber could be sent, kept on disk so operators
+//! can reprocess the data instead of losing it.
+//!
+//! Every batch is written to a file of its own, framed as `PROTOCOL_VERSION_V1` like the
+//! write-ahead log's segments, so it's read with `wal::read_segment`. The file name holds the
+//! range of slots of the batch's messages, see `dead_letters`.
+use crate::event::message_slot;
+use serde::Deserialize;
+use std::{
+    collections::VecDeque,
+    convert::TryInto,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+pub const DEFAULT_MAX_BYTES: u64 = 1024 * 1024 * 1024;
+const HEADER_BYTE_SIZE: usize = 4;
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DeadLetterConfig {
+    pub dir: PathBuf,
+    /// Oldest dead letters are deleted once the directory is larger than this,
+    /// `DEFAULT_MAX_BYTES` if not set
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+}
+
+/// A batch in the dead-letter directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeadLetter {
+    pub path: PathBuf,
+    /// Lowest and highest slots of the messages, None if none has one, e.g. metadata
+    pub slots: Option<(u64, u64)>,
+    pub bytes: u64,
+}
+
+pub struct DeadLetters {
+    config: DeadLetterConfig,
+    // oldest first
+    letters: VecDeque<(u64, DeadLetter)>,
+}
+
+impl DeadLetters {
+    /// Opens the dead-letter directory, the batches left by a previous run are kept.
+    pub fn open(config: DeadLetterConfig) -> io::Result<Self> {
+        fs::create_dir_all(&config.dir)?;
+
+        let letters = scan(&config.dir)?.into();
+
+        Ok(DeadLetters { config, letters })
+    }
+
+    /// Writes an encoded batch, then deletes the oldest ones past `max_bytes`.
+    pub fn append(&mut self, batch: &[u8]) -> io::Result<()> {
+        let id = self.letters.back().map_or(0, |(id, _)| id + 1);
+        let slots = batch_slots(batch);
+        let path = letter_path(&self.config.dir, id, slots);
+
+        fs::write(&path, batch)?;
+        self.letters.push_back((
+            id,
+            DeadLetter {
+                path,
+                slots,
+                bytes: batch.len() as u64,
+            },
+        ));
+
+        self.enforce_retention()
+    }
+
+    /// Deletes the oldest batches past `max_bytes`, never the newest one.
+    fn enforce_retention(&mut self) -> io::Result<()> {
+        let max_bytes = self.config.max_bytes.unwrap_or(DEFAULT_MAX_BYTES);
+        let mut total_bytes = self.letters.iter().map(|(_, l)| l.bytes).sum::<u64>();
+
+        while self.letters.len() > 1 && total_bytes > max_bytes {
+            if let Some((_, oldest)) = self.letters.pop_front() {
+                total_bytes -= oldest.bytes;
+                fs::remove_file(oldest.path)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Batches in the dead-letter directory `dir`, oldest first.
+pub fn dead_letters(dir: &Path) -> io::Result<Vec<DeadLetter>> {
+    Ok(scan(dir)?.into_iter().map(|(_, letter)| letter).collect())
+}
+
+fn scan(dir: &Path) -> io::Result<Vec<(u64, DeadLetter)>> {
+    let mut letters = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let Some((id, slots)) = entry.file_name().to_str().and_then(parse_letter_name) else {
+            continue;
+        };
+        letters.push((
+            id,
+            DeadLetter {
+                path: entry.path(),
+                slots,
+                bytes: entry.metadata()?.len(),
+            },
+        ));
+    }
+    letters.sort_unstable_by_key(|(id, _)| *id);
+
+    Ok(letters)
+}
+
+/// Lowest and highest slots of the messages of an encoded batch.
+fn batch_slots(batch: &[u8]) -> Option<(u64, u64)> {
+    let mut slots: Option<(u64, u64)> = None;
+
+    let mut i = HEADER_BYTE_SIZE;
+    while let Some(size) = read_size(batch, i) {
+        i += HEADER_BYTE_SIZE;
+        let end = (i + size).min(batch.len());
+        if let Some(slot) = message_slot(&batch[i..end]) {
+            slots = Some(slots.map_or((slot, slot), |(min, max)| (min.min(slot), max.max(slot))));
+        }
+        i = end;
+    }
+
+    slots
+}
+
+fn read_size(data: &[u8], i: usize) -> Option<usize> {
+    let bytes = data.get(i..i + HEADER_BYTE_SIZE)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?) as usize)
+}
+
+fn letter_path(dir: &Path, id: u64, slots: Option<(u64, u64)>) -> PathBuf {
+    match slots {
+        Some((first, last)) => dir.join(format!("dead-{:010}-{}-{}.bin", id, first, last)),
+        None => dir.join(format!("dead-{:010}.bin", id)),
+    }
+}
+
+fn parse_letter_name(name: &str) -> Option<(u64, Option<(u64, u64)>)> {
+    let mut parts = name.strip_prefix("dead-")?.strip_suffix(".bin")?.split('-');
+    let id = parts.next()?.parse().ok()?;
+
+    match (parts.next(), parts.next()) {
+        (None, _) => Some((id, None)),
+        (Some(first), Some(last)) => Some((id, Some((first.parse().ok()?, last.parse().ok()?)))),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flatbuffer::{
+        consts::BYTE_PREFIX_SLOT,
+        slot_generated::slot::{Slot, SlotArgs, Status},
+    };
+    use crate::sender::TcpBuffer;
+    use crate::wal::read_segment;
+
+    fn slot_message(slot: u64) -> Vec<u8> {
+        let mut builder = flatbuffers::FlatBufferBuilder::new();
+        let root = Slot::create(
+            &mut builder,
+            &SlotArgs {
+                slot,
+                status: Status::Processed,
+                parent: None,
+            },
+        );
+        builder.finish(root, None);
+
+        let mut message = vec![BYTE_PREFIX_SLOT];
+        message.extend_from_slice(builder.finished_data());
+        message
+    }
+
+    #[test]
+    fn test_dead_letters_keep_slots_and_enforce_retention() {
+        let dir = std::env::temp_dir().join(format!("dead-{}", uuid::Uuid::new_v4()));
+        let config = DeadLetterConfig {
+            dir: dir.clone(),
+            max_bytes: None,
+        };
+
+        let mut letters = DeadLetters::open(config.clone()).unwrap();
+        let mut buffer = TcpBuffer::new(2);
+        buffer.append(slot_message(12));
+        buffer.append(slot_message(10));
+        let batch = buffer.flush_data();
+        letters.append(&batch).unwrap();
+        letters.append(&TcpBuffer::new(1).flush_data()).unwrap();
+        drop(letters);
+
+        let found = dead_letters(&dir).unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].slots, Some((10, 12)));
+        assert_eq!(found[1].slots, None);
+        assert_eq!(
+            read_segment(&found[0].path).unwrap(),
+            vec![slot_message(12), slot_message(10)]
+        );
+
+        // a new run keeps the batches and numbers the next ones after them
+        let mut letters = DeadLetters::open(DeadLetterConfig {
+            max_bytes: Some(batch.len() as u64),
+            ..config
+        })
+        .unwrap();
+        letters.append(&batch).unwrap();
+        let found = dead_letters(&dir).unwrap();
+        assert_eq!(found.len(), 1);
+        assert!(found[0].path.ends_with(format!("dead-{:010}-10-12.bin", 2)));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,157 @@
+//! Helpers for consumers decoding the flatbuffer messages produced by the plugin.
+use crate::flatbuffer::transaction_info_generated::transaction_info::{
+    TransactionInfo, TransactionTokenBalance, UiTokenAmount, UiTokenAmountPtr,
+};
+use flatbuffers::{ForwardsUOffset, Vector};
+
+/// Token amount in base units together with the decimals of its mint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenAmount {
+    pub amount: u64,
+    pub decimals: u8,
+}
+
+impl TokenAmount {
+    pub fn new(amount: u64, decimals: u8) -> Self {
+        TokenAmount { amount, decimals }
+    }
+
+    /// Parses the raw `amount` string of a serialized `UiTokenAmount`.
+    pub fn from_ui_token_amount(ui_token_amount: &UiTokenAmount) -> Option<Self> {
+        let amount = ui_token_amount.amount()?.parse().ok()?;
+
+        Some(Self::new(amount, ui_token_amount.decimals()))
+    }
+
+    /// Amount in whole tokens, computed the same way the validator computes `ui_amount`.
+    pub fn ui_amount(&self) -> f64 {
+        self.amount as f64 / 10f64.powi(self.decimals as i32)
+    }
+
+    /// Exact decimal representation with trailing zeros trimmed, e.g. `"1.5"` for
+    /// 1_500_000 base units of a 6 decimals mint. Matches `ui_amount_string`.
+    pub fn ui_amount_string(&self) -> String {
+        let decimals = self.decimals as usize;
+        if decimals == 0 {
+            return self.amount.to_string();
+        }
+
+        let mut s = format!("{:0>width$}", self.amount, width = decimals + 1);
+        s.insert(s.len() - decimals, '.');
+
+        s.trim_end_matches('0').trim_end_matches('.').to_string()
+    }
+}
+
+/// Reconstructs the ui amount of a serialized `TransactionTokenBalance`.
+///
+/// `UiTokenAmount::ui_amount` is always serialized as `0.0`, the real value travels in the
+/// parallel `*_token_balances_ptr` vector of `TransactionInfo`. The raw `amount` is preferred
+/// since it's exact, then the ptr value, then `ui_amount_string`.
+pub fn token_balance_ui_amount(
+    balance: &TransactionTokenBalance,
+    ptr: Option<&UiTokenAmountPtr>,
+) -> Option<f64> {
+    let ui_token_amount = balance.ui_token_amount()?;
+
+    if let Some(amount) = TokenAmount::from_ui_token_amount(&ui_token_amount) {
+        return Some(amount.ui_amount());
+    }
+
+    if let Some(amount) = ptr.and_then(|ptr| ptr.amount()) {
+        return Some(amount);
+    }
+
+    ui_token_amount.ui_amount_string()?.parse().ok()
+}
+
+/// Ui amounts of `pre_token_balances`, in the same order as the balances.
+pub fn pre_token_ui_amounts(tx: &TransactionInfo) -> Vec<Option<f64>> {
+    token_ui_amounts(
+        tx.transaction_meta()
+            .and_then(|meta| meta.pre_token_balances()),
+        tx.pre_token_balances_ptr(),
+    )
+}
+
+/// Ui amounts of `post_token_balances`, in the same order as the balances.
+pub fn post_token_ui_amounts(tx: &TransactionInfo) -> Vec<Option<f64>> {
+    token_ui_amounts(
+        tx.transaction_meta()
+            .and_then(|meta| meta.post_token_balances()),
+        tx.post_token_balances_ptr(),
+    )
+}
+
+fn token_ui_amounts<'a>(
+    balances: Option<Vector<'a, ForwardsUOffset<TransactionTokenBalance<'a>>>>,
+    ptrs: Option<Vector<'a, ForwardsUOffset<UiTokenAmountPtr<'a>>>>,
+) -> Vec<Option<f64>> {
+    let balances = match balances {
+        Some(balances) => balances,
+        None => return Vec::new(),
+    };
+
+    balances
+        .iter()
+        .enumerate()
+        .map(|(i, balance)| {
+            let ptr = ptrs.filter(|ptrs| i < ptrs.len()).map(|ptrs| ptrs.get(i));
+            token_balance_ui_amount(&balance, ptr.as_ref())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flatbuffer::transaction_info_generated::transaction_info::{
+        TransactionTokenBalanceArgs, UiTokenAmountArgs, UiTokenAmountPtrArgs,
+    };
+    use flatbuffers::FlatBufferBuilder;
+
+    #[test]
+    fn test_ui_amount_string() {
+        assert_eq!(TokenAmount::new(1_500_000, 6).ui_amount_string(), "1.5");
+        assert_eq!(TokenAmount::new(42, 0).ui_amount_string(), "42");
+        assert_eq!(TokenAmount::new(1, 9).ui_amount_string(), "0.000000001");
+        assert_eq!(TokenAmount::new(0, 6).ui_amount_string(), "0");
+        assert_eq!(TokenAmount::new(1_500_000, 6).ui_amount(), 1.5);
+    }
+
+    #[test]
+    fn test_token_balance_ui_amount() {
+        let mut builder = FlatBufferBuilder::new();
+        let amount = Some(builder.create_string("not a number"));
+        let ui_token_amount = Some(UiTokenAmount::create(
+            &mut builder,
+            &UiTokenAmountArgs {
+                ui_amount: 0.0,
+                decimals: 6,
+                amount,
+                ui_amount_string: None,
+            },
+        ));
+        let balance = TransactionTokenBalance::create(
+            &mut builder,
+            &TransactionTokenBalanceArgs {
+                ui_token_amount,
+                ..Default::default()
+            },
+        );
+        builder.finish(balance, None);
+        let balance =
+            flatbuffers::root::<TransactionTokenBalance>(builder.finished_data()).unwrap();
+
+        let mut ptr_builder = FlatBufferBuilder::new();
+        let ptr = UiTokenAmountPtr::create(
+            &mut ptr_builder,
+            &UiTokenAmountPtrArgs { amount: Some(2.5) },
+        );
+        ptr_builder.finish(ptr, None);
+        let ptr = flatbuffers::root::<UiTokenAmountPtr>(ptr_builder.finished_data()).unwrap();
+
+        assert_eq!(token_balance_ui_amount(&balance, Some(&ptr)), Some(2.5));
+        assert_eq!(token_balance_ui_amount(&balance, None), None);
+    }
+}
@@ -0,0 +1,73 @@
+//! Text encodings of binary account data, for consumers emitting JSON and other text formats.
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::Deserialize;
+use std::io;
+
+/// Encoding of account data in text outputs, named after the matching RPC encoding options.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AccountDataEncoding {
+    #[default]
+    #[serde(rename = "base64")]
+    Base64,
+    #[serde(rename = "base64+zstd")]
+    Base64Zstd,
+    #[serde(rename = "hex")]
+    Hex,
+}
+
+impl AccountDataEncoding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountDataEncoding::Base64 => "base64",
+            AccountDataEncoding::Base64Zstd => "base64+zstd",
+            AccountDataEncoding::Hex => "hex",
+        }
+    }
+
+    pub fn encode(&self, data: &[u8]) -> io::Result<String> {
+        match self {
+            AccountDataEncoding::Base64 => Ok(STANDARD.encode(data)),
+            AccountDataEncoding::Base64Zstd => Ok(STANDARD.encode(zstd::encode_all(data, 0)?)),
+            AccountDataEncoding::Hex => Ok(hex::encode(data)),
+        }
+    }
+
+    pub fn decode(&self, data: &str) -> io::Result<Vec<u8>> {
+        let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidData, e);
+
+        match self {
+            AccountDataEncoding::Base64 => {
+                STANDARD.decode(data).map_err(|e| invalid(e.to_string()))
+            }
+            AccountDataEncoding::Base64Zstd => {
+                let compressed = STANDARD.decode(data).map_err(|e| invalid(e.to_string()))?;
+                zstd::decode_all(compressed.as_slice())
+            }
+            AccountDataEncoding::Hex => hex::decode(data).map_err(|e| invalid(e.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AccountDataEncoding;
+
+    #[test]
+    fn test_encode_decode() {
+        let data = vec![7u8; 256];
+
+        for encoding in [
+            AccountDataEncoding::Base64,
+            AccountDataEncoding::Base64Zstd,
+            AccountDataEncoding::Hex,
+        ] {
+            let encoded = encoding.encode(&data).unwrap();
+            assert_eq!(encoding.decode(&encoded).unwrap(), data);
+        }
+
+        assert_eq!(AccountDataEncoding::Hex.encode(&[1, 255]).unwrap(), "01ff");
+
+        let encoding: AccountDataEncoding = serde_json::from_str("\"base64+zstd\"").unwrap();
+        assert_eq!(encoding, AccountDataEncoding::Base64Zstd);
+    }
+}
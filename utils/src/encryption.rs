@@ -0,0 +1,115 @@
+//! Encryption of the batches' messages with ChaCha20-Poly1305 and a pre-shared key, for
+//! deployments which can't terminate TLS in front of the sender.
+//!
+//! The messages of a batch are sealed together, the batch carrying the nonce followed by the
+//! ciphertext and its tag instead of them; its size and checksum are those of the sealed
+//! messages. Nonces are a random prefix, drawn once per cipher, followed by a counter, so
+//! none is used twice with a key. The server and client hellos are not encrypted.
+use chacha20poly1305::aead::{Aead, NewAead};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use uuid::Uuid;
+
+pub const KEY_BYTE_SIZE: usize = 32;
+pub const NONCE_BYTE_SIZE: usize = 12;
+pub const TAG_BYTE_SIZE: usize = 16;
+const NONCE_PREFIX_BYTE_SIZE: usize = 4;
+
+pub struct PayloadCipher {
+    cipher: ChaCha20Poly1305,
+    nonce_prefix: [u8; NONCE_PREFIX_BYTE_SIZE],
+    nonce_counter: AtomicU64,
+}
+
+impl PayloadCipher {
+    pub fn new(key: &[u8]) -> io::Result<Self> {
+        if key.len() != KEY_BYTE_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "encryption keys are {} bytes, not {}",
+                    KEY_BYTE_SIZE,
+                    key.len()
+                ),
+            ));
+        }
+
+        let mut nonce_prefix = [0; NONCE_PREFIX_BYTE_SIZE];
+        nonce_prefix.copy_from_slice(&Uuid::new_v4().as_bytes()[..NONCE_PREFIX_BYTE_SIZE]);
+
+        Ok(PayloadCipher {
+            cipher: ChaCha20Poly1305::new(Key::from_slice(key)),
+            nonce_prefix,
+            nonce_counter: AtomicU64::new(0),
+        })
+    }
+
+    /// Cipher of a key written as hex, as in the plugin's config.
+    pub fn from_hex(key: &str) -> io::Result<Self> {
+        let key =
+            hex::decode(key.trim()).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+        Self::new(&key)
+    }
+
+    /// The nonce followed by the encrypted payload and its tag.
+    pub fn seal(&self, payload: &[u8]) -> Vec<u8> {
+        let mut nonce = [0; NONCE_BYTE_SIZE];
+        nonce[..NONCE_PREFIX_BYTE_SIZE].copy_from_slice(&self.nonce_prefix);
+        nonce[NONCE_PREFIX_BYTE_SIZE..].copy_from_slice(
+            &self
+                .nonce_counter
+                .fetch_add(1, Ordering::Relaxed)
+                .to_le_bytes(),
+        );
+
+        let mut sealed = Vec::with_capacity(NONCE_BYTE_SIZE + payload.len() + TAG_BYTE_SIZE);
+        sealed.extend_from_slice(&nonce);
+        // only fails on payloads larger than the cipher can take, far past a batch's u32 size
+        sealed.extend(
+            self.cipher
+                .encrypt(Nonce::from_slice(&nonce), payload)
+                .expect("payload too large to encrypt"),
+        );
+
+        sealed
+    }
+
+    /// Decrypts what `seal` returned, failing if it was not sealed with the same key or was
+    /// tampered with.
+    pub fn open(&self, sealed: &[u8]) -> io::Result<Vec<u8>> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "undecryptable batch");
+        if sealed.len() < NONCE_BYTE_SIZE + TAG_BYTE_SIZE {
+            return Err(invalid());
+        }
+
+        let (nonce, ciphertext) = sealed.split_at(NONCE_BYTE_SIZE);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| invalid())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_open() {
+        let cipher = PayloadCipher::from_hex(&"ab".repeat(KEY_BYTE_SIZE)).unwrap();
+        let other = PayloadCipher::new(&[1; KEY_BYTE_SIZE]).unwrap();
+
+        let sealed = cipher.seal(b"hello world");
+        assert_eq!(sealed.len(), NONCE_BYTE_SIZE + 11 + TAG_BYTE_SIZE);
+        // a new nonce every time
+        assert_ne!(cipher.seal(b"hello world"), sealed);
+        assert_eq!(cipher.open(&sealed).unwrap(), b"hello world");
+
+        assert!(other.open(&sealed).is_err());
+        let mut tampered = sealed;
+        tampered[NONCE_BYTE_SIZE] ^= 1;
+        assert!(cipher.open(&tampered).is_err());
+        assert!(PayloadCipher::new(&[1; 16]).is_err());
+    }
+}
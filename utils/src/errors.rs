@@ -16,4 +16,15 @@ pub enum GeyserError {
 
     #[error("tx serialization error")]
     TxSerializeError,
+
+    #[error("sender is draining and no longer accepts messages")]
+    SenderDraining,
+
+    #[error("zmq send error: {0}")]
+    #[cfg(feature = "zmq")]
+    ZmqSend(zmq::Error),
+
+    #[error("nats send error: {0}")]
+    #[cfg(feature = "nats")]
+    NatsSend(std::io::Error),
 }
@@ -0,0 +1,413 @@
+//! Typed decoding of the messages published by the plugin, so consumers don't have to deal
+//! with the byte prefixes and the flatbuffer schemas.
+use crate::{
+    compression::decompress,
+    flatbuffer::{
+        account_data_generated::account_data::root_as_account_data,
+        account_info_generated::account_info::root_as_account_info,
+        account_info_v2_generated::account_info_v2::root_as_account_info as root_as_account_info_v2,
+        block_info_generated::block_info::root_as_block_info,
+        common_generated::common::Reward as RewardInfo,
+        consts::{
+            BYTE_PREFIX_ACCOUNT, BYTE_PREFIX_ACCOUNT_V2, BYTE_PREFIX_BLOCK, BYTE_PREFIX_METADATA,
+            BYTE_PREFIX_REWARDS, BYTE_PREFIX_SLOT, BYTE_PREFIX_SLOT_ROOTED, BYTE_PREFIX_TX,
+        },
+        metadata_generated::metadata::root_as_metadata,
+        rewards_generated::rewards::root_as_rewards,
+        slot_generated::slot::{root_as_slot, Status},
+        slot_rooted_generated::slot_rooted::root_as_slot_rooted,
+        transaction_info_generated::transaction_info::root_as_transaction_info,
+    },
+};
+use flatbuffers::{ForwardsUOffset, InvalidFlatbuffer, Vector};
+use std::io;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum DecodeError {
+    #[error("empty message")]
+    Empty,
+
+    #[error("no typed decoding for messages with prefix {0}")]
+    UnsupportedPrefix(u8),
+
+    #[error("cannot decompress message: {0}")]
+    Decompress(#[from] io::Error),
+
+    #[error("invalid flatbuffer: {0}")]
+    InvalidFlatbuffer(#[from] InvalidFlatbuffer),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountEvent {
+    pub pubkey: String,
+    pub owner: String,
+    pub slot: u64,
+    pub lamports: u64,
+    pub rent_epoch: u64,
+    pub executable: bool,
+    pub write_version: u64,
+    pub data: Vec<u8>,
+    pub txn_signature: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotStatus {
+    Processed,
+    Confirmed,
+    Rooted,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlotEvent {
+    pub slot: u64,
+    pub status: SlotStatus,
+    pub parent: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxEvent {
+    pub signature: String,
+    pub slot: u64,
+    pub index: Option<u64>,
+    pub is_vote: bool,
+    pub fee: u64,
+    pub compute_units_consumed: Option<u64>,
+    // name of the transaction error, if it failed
+    pub err: Option<String>,
+    pub account_keys: Vec<String>,
+    pub log_messages: Vec<String>,
+    pub memo: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reward {
+    pub pubkey: String,
+    pub lamports: i64,
+    pub post_balance: u64,
+    pub reward_type: Option<String>,
+    pub commission: Option<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockEvent {
+    pub slot: u64,
+    pub blockhash: String,
+    pub parent_slot: Option<u64>,
+    pub parent_blockhash: Option<String>,
+    pub block_time: i64,
+    pub block_height: u64,
+    pub executed_transaction_count: Option<u64>,
+    pub rewards: Vec<Reward>,
+}
+
+/// Rewards paid out in a block, published on their own at epoch boundaries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RewardsEvent {
+    pub slot: u64,
+    pub block_height: Option<u64>,
+    pub block_time: Option<i64>,
+    pub rewards: Vec<Reward>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metadata {
+    pub send_errors: u64,
+    pub version: Option<String>,
+    pub git_commit: Option<String>,
+    pub schema_hash: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    Account(AccountEvent),
+    Slot(SlotEvent),
+    Transaction(TxEvent),
+    Block(BlockEvent),
+    Rewards(RewardsEvent),
+    Metadata(Metadata),
+}
+
+/// Decodes a message as handed to the `TcpReceiver` callback, compressed or not. Both
+/// account schema versions decode to `AccountEvent`, and rooted slot notifications to a
+/// `SlotEvent` without parent.
+pub fn decode_event(message: &[u8]) -> Result<Event, DecodeError> {
+    let message = decompress(message)?;
+    let (prefix, data) = message.split_first().ok_or(DecodeError::Empty)?;
+
+    let event = match *prefix {
+        BYTE_PREFIX_ACCOUNT => {
+            let info = root_as_account_info(data)?;
+            let account = root_as_account_data(
+                info.account_data()
+                    .map(|data| data.bytes())
+                    .unwrap_or_default(),
+            )?;
+
+            Event::Account(AccountEvent {
+                pubkey: info.pubkey().unwrap_or_default().to_string(),
+                owner: info.owner().unwrap_or_default().to_string(),
+                slot: info.slot(),
+                lamports: account.lamports(),
+                rent_epoch: account.rent_epoch(),
+                executable: account.executable(),
+                write_version: account.version(),
+                data: account
+                    .data()
+                    .map(|data| data.bytes().to_vec())
+                    .unwrap_or_default(),
+                txn_signature: info.txn_signature().map(str::to_string),
+            })
+        }
+        BYTE_PREFIX_ACCOUNT_V2 => {
+            let info = root_as_account_info_v2(data)?;
+
+            Event::Account(AccountEvent {
+                pubkey: info.pubkey().unwrap_or_default().to_string(),
+                owner: info.owner().unwrap_or_default().to_string(),
+                slot: info.slot(),
+                lamports: info.lamports(),
+                rent_epoch: info.rent_epoch(),
+                executable: info.executable(),
+                write_version: info.write_version(),
+                data: info
+                    .data()
+                    .map(|data| data.bytes().to_vec())
+                    .unwrap_or_default(),
+                txn_signature: info.txn_signature().map(str::to_string),
+            })
+        }
+        BYTE_PREFIX_SLOT => {
+            let slot = root_as_slot(data)?;
+
+            Event::Slot(SlotEvent {
+                slot: slot.slot(),
+                status: match slot.status() {
+                    Status::Rooted => SlotStatus::Rooted,
+                    Status::Confirmed => SlotStatus::Confirmed,
+                    _ => SlotStatus::Processed,
+                },
+                parent: slot.parent(),
+            })
+        }
+        BYTE_PREFIX_SLOT_ROOTED => Event::Slot(SlotEvent {
+            slot: root_as_slot_rooted(data)?.slot(),
+            status: SlotStatus::Rooted,
+            parent: None,
+        }),
+        BYTE_PREFIX_TX => {
+            let tx = root_as_transaction_info(data)?;
+            let meta = tx.transaction_meta();
+
+            Event::Transaction(TxEvent {
+                signature: tx.signature_string().unwrap_or_default().to_string(),
+                slot: tx.slot(),
+                index: tx.index(),
+                is_vote: tx.is_vote(),
+                fee: meta.map(|meta| meta.fee()).unwrap_or_default(),
+                compute_units_consumed: tx.compute_units_consumed(),
+                err: meta.and_then(|meta| meta.status()).map(|status| {
+                    status
+                        .err_type()
+                        .variant_name()
+                        .unwrap_or("Unknown")
+                        .to_string()
+                }),
+                account_keys: strings(tx.account_keys_string()),
+                log_messages: strings(meta.and_then(|meta| meta.log_messages())),
+                memo: tx.memo().map(str::to_string),
+            })
+        }
+        BYTE_PREFIX_BLOCK => {
+            let block = root_as_block_info(data)?;
+
+            Event::Block(BlockEvent {
+                slot: block.slot(),
+                blockhash: block.blockhash().unwrap_or_default().to_string(),
+                parent_slot: block.parent_slot(),
+                parent_blockhash: block.parent_blockhash().map(str::to_string),
+                block_time: block.block_time(),
+                block_height: block.block_height(),
+                executed_transaction_count: block.executed_transaction_count(),
+                rewards: block
+                    .rewards()
+                    .map(|rewards| {
+                        rewards
+                            .iter()
+                            .map(|reward| decode_reward(&reward))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            })
+        }
+        BYTE_PREFIX_REWARDS => {
+            let rewards = root_as_rewards(data)?;
+
+            Event::Rewards(RewardsEvent {
+                slot: rewards.slot(),
+                block_height: rewards.block_height(),
+                block_time: rewards.block_time(),
+                rewards: rewards
+                    .rewards()
+                    .map(|rewards| {
+                        rewards
+                            .iter()
+                            .map(|reward| decode_reward(&reward))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            })
+        }
+        BYTE_PREFIX_METADATA => {
+            let metadata = root_as_metadata(data)?;
+
+            Event::Metadata(Metadata {
+                send_errors: metadata.send_errors(),
+                version: metadata.version().map(str::to_string),
+                git_commit: metadata.git_commit().map(str::to_string),
+                schema_hash: metadata.schema_hash().map(str::to_string),
+            })
+        }
+        prefix => return Err(DecodeError::UnsupportedPrefix(prefix)),
+    };
+
+    Ok(event)
+}
+
+/// Slot of a message, compressed or not, without decoding the rest of it. None for messages
+/// without one, e.g. metadata.
+pub fn message_slot(message: &[u8]) -> Option<u64> {
+    let message = decompress(message).ok()?;
+    let (prefix, data) = message.split_first()?;
+
+    match *prefix {
+        BYTE_PREFIX_ACCOUNT => root_as_account_info(data).ok().map(|info| info.slot()),
+        BYTE_PREFIX_ACCOUNT_V2 => root_as_account_info_v2(data).ok().map(|info| info.slot()),
+        BYTE_PREFIX_SLOT => root_as_slot(data).ok().map(|slot| slot.slot()),
+        BYTE_PREFIX_SLOT_ROOTED => root_as_slot_rooted(data).ok().map(|slot| slot.slot()),
+        BYTE_PREFIX_TX => root_as_transaction_info(data).ok().map(|tx| tx.slot()),
+        BYTE_PREFIX_BLOCK => root_as_block_info(data).ok().map(|block| block.slot()),
+        BYTE_PREFIX_REWARDS => root_as_rewards(data).ok().map(|rewards| rewards.slot()),
+        _ => None,
+    }
+}
+
+/// What a message is partitioned by across shards: the pubkey of an account, the signature
+/// of a transaction. None for the other messages.
+pub fn message_shard_key(message: &[u8]) -> Option<String> {
+    let message = decompress(message).ok()?;
+    let (prefix, data) = message.split_first()?;
+
+    let key = match *prefix {
+        BYTE_PREFIX_ACCOUNT => root_as_account_info(data).ok()?.pubkey(),
+        BYTE_PREFIX_ACCOUNT_V2 => root_as_account_info_v2(data).ok()?.pubkey(),
+        BYTE_PREFIX_TX => root_as_transaction_info(data).ok()?.signature_string(),
+        _ => None,
+    };
+
+    key.map(str::to_string)
+}
+
+fn decode_reward(reward: &RewardInfo) -> Reward {
+    Reward {
+        pubkey: reward.pubkey().unwrap_or_default().to_string(),
+        lamports: reward.lamports(),
+        post_balance: reward.post_balance(),
+        reward_type: reward.reward_type().variant_name().map(str::to_string),
+        commission: reward.commission(),
+    }
+}
+
+fn strings(vector: Option<Vector<ForwardsUOffset<&str>>>) -> Vec<String> {
+    vector
+        .map(|vector| vector.iter().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flatbuffer::{
+        common_generated::common::{Reward as RewardInfo, RewardArgs, RewardType},
+        consts::COMPRESSED_FLAG,
+        rewards_generated::rewards::{Rewards, RewardsArgs},
+        slot_generated::slot::{Slot, SlotArgs},
+    };
+
+    #[test]
+    fn test_decode_compressed_slot() {
+        let mut builder = flatbuffers::FlatBufferBuilder::new();
+        let slot = Slot::create(
+            &mut builder,
+            &SlotArgs {
+                slot: 42,
+                status: Status::Confirmed,
+                parent: Some(41),
+            },
+        );
+        builder.finish(slot, None);
+        let mut message = vec![BYTE_PREFIX_SLOT | COMPRESSED_FLAG];
+        message.extend(zstd::encode_all(builder.finished_data(), 0).unwrap());
+
+        let event = decode_event(&message).unwrap();
+
+        assert_eq!(
+            event,
+            Event::Slot(SlotEvent {
+                slot: 42,
+                status: SlotStatus::Confirmed,
+                parent: Some(41),
+            })
+        );
+        assert!(matches!(
+            decode_event(&[6, 0]),
+            Err(DecodeError::UnsupportedPrefix(6))
+        ));
+    }
+
+    #[test]
+    fn test_decode_rewards() {
+        let mut builder = flatbuffers::FlatBufferBuilder::new();
+        let pubkey = builder.create_string("validator");
+        let reward = RewardInfo::create(
+            &mut builder,
+            &RewardArgs {
+                pubkey: Some(pubkey),
+                lamports: 500,
+                post_balance: 1500,
+                reward_type: RewardType::Staking,
+                commission: Some(5),
+            },
+        );
+        let rewards = builder.create_vector(&[reward]);
+        let rewards = Rewards::create(
+            &mut builder,
+            &RewardsArgs {
+                slot: 432000,
+                block_height: Some(400000),
+                block_time: None,
+                rewards: Some(rewards),
+            },
+        );
+        builder.finish(rewards, None);
+        let mut message = vec![BYTE_PREFIX_REWARDS];
+        message.extend_from_slice(builder.finished_data());
+
+        assert_eq!(
+            decode_event(&message).unwrap(),
+            Event::Rewards(RewardsEvent {
+                slot: 432000,
+                block_height: Some(400000),
+                block_time: None,
+                rewards: vec![Reward {
+                    pubkey: "validator".to_string(),
+                    lamports: 500,
+                    post_balance: 1500,
+                    reward_type: Some("Staking".to_string()),
+                    commission: Some(5),
+                }],
+            })
+        );
+        assert_eq!(message_slot(&message), Some(432000));
+    }
+}
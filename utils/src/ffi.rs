@@ -0,0 +1,540 @@
+//! C ABI for the typed decoding and the receiver, built with `--features ffi`, which also
+//! generates `include/solana_geyser_zmq.h`.
+//!
+//! ```c
+//! GeyserReceiver *receiver = geyser_connect("127.0.0.1:2000", 1000);
+//! GeyserEvent event = {0};
+//! int status;
+//! while ((status = geyser_poll(receiver, &event, 1000)) != GEYSER_ERR_CLOSED) {
+//!     if (status != GEYSER_OK)
+//!         continue;
+//!     if (event.kind == GEYSER_EVENT_KIND_ACCOUNT)
+//!         printf("%s %llu\n", event.account.pubkey, event.account.lamports);
+//!     geyser_event_free(&event);
+//! }
+//! geyser_receiver_free(receiver);
+//! ```
+use crate::event::{decode_event, DecodeError, Event, SlotStatus};
+use crate::threaded::ThreadedReceiver;
+use std::{
+    ffi::{c_char, c_int, c_void, CStr, CString},
+    net::SocketAddr,
+    ptr, slice,
+    sync::mpsc::RecvTimeoutError,
+    time::{Duration, Instant},
+};
+
+pub const GEYSER_OK: c_int = 0;
+/// No event was received before the timeout
+pub const GEYSER_TIMEOUT: c_int = 1;
+/// A null pointer or an invalid message
+pub const GEYSER_ERR_INVALID: c_int = -1;
+/// A message without typed decoding, such as a diagnostic
+pub const GEYSER_ERR_UNSUPPORTED: c_int = -2;
+/// The receiver stopped
+pub const GEYSER_ERR_CLOSED: c_int = -3;
+
+const QUEUE_SIZE: usize = 10000;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeyserEventKind {
+    None,
+    Account,
+    Slot,
+    Transaction,
+    Block,
+    Metadata,
+    Rewards,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeyserSlotStatus {
+    Processed,
+    Confirmed,
+    Rooted,
+}
+
+#[repr(C)]
+pub struct GeyserAccount {
+    pub pubkey: *const c_char,
+    pub owner: *const c_char,
+    pub lamports: u64,
+    pub rent_epoch: u64,
+    pub executable: bool,
+    pub write_version: u64,
+    pub data: *const u8,
+    pub data_len: usize,
+    /// Null if the update wasn't caused by a transaction
+    pub txn_signature: *const c_char,
+}
+
+#[repr(C)]
+pub struct GeyserSlot {
+    pub status: GeyserSlotStatus,
+    pub has_parent: bool,
+    pub parent: u64,
+}
+
+#[repr(C)]
+pub struct GeyserTransaction {
+    pub signature: *const c_char,
+    pub is_vote: bool,
+    pub fee: u64,
+    /// Name of the error, null if the transaction succeeded
+    pub err: *const c_char,
+    pub account_keys: *const *const c_char,
+    pub account_keys_len: usize,
+    pub log_messages: *const *const c_char,
+    pub log_messages_len: usize,
+}
+
+#[repr(C)]
+pub struct GeyserBlock {
+    pub blockhash: *const c_char,
+    pub has_parent_slot: bool,
+    pub parent_slot: u64,
+    pub block_time: i64,
+    pub block_height: u64,
+}
+
+/// The rewards themselves are not exposed, as for blocks.
+#[repr(C)]
+pub struct GeyserRewards {
+    pub has_block_height: bool,
+    pub block_height: u64,
+    pub has_block_time: bool,
+    pub block_time: i64,
+    pub rewards_len: usize,
+}
+
+#[repr(C)]
+pub struct GeyserMetadata {
+    pub send_errors: u64,
+    /// Null if unknown
+    pub version: *const c_char,
+}
+
+/// A decoded event, its fields under the member named after its kind, the other members
+/// being zeroed. The pointers stay valid until `geyser_event_free`.
+#[repr(C)]
+pub struct GeyserEvent {
+    pub kind: GeyserEventKind,
+    pub slot: u64,
+    pub account: GeyserAccount,
+    pub slot_update: GeyserSlot,
+    pub transaction: GeyserTransaction,
+    pub block: GeyserBlock,
+    pub metadata: GeyserMetadata,
+    pub rewards: GeyserRewards,
+    owned: *mut c_void,
+}
+
+/// Memory the pointers of an event point to.
+#[derive(Default)]
+struct Owned {
+    strings: Vec<CString>,
+    string_arrays: Vec<Vec<*const c_char>>,
+    data: Vec<u8>,
+}
+
+impl Owned {
+    fn string(&mut self, value: String) -> *const c_char {
+        // the flatbuffer strings can't hold a nul byte
+        let value = CString::new(value).unwrap_or_default();
+        let ptr = value.as_ptr();
+        self.strings.push(value);
+
+        ptr
+    }
+
+    fn optional_string(&mut self, value: Option<String>) -> *const c_char {
+        value.map_or(ptr::null(), |value| self.string(value))
+    }
+
+    fn string_array(&mut self, values: Vec<String>) -> (*const *const c_char, usize) {
+        let array = values
+            .into_iter()
+            .map(|value| self.string(value))
+            .collect::<Vec<_>>();
+        let (ptr, len) = (array.as_ptr(), array.len());
+        self.string_arrays.push(array);
+
+        (ptr, len)
+    }
+}
+
+impl GeyserEvent {
+    fn empty() -> Self {
+        GeyserEvent {
+            kind: GeyserEventKind::None,
+            slot: 0,
+            account: GeyserAccount {
+                pubkey: ptr::null(),
+                owner: ptr::null(),
+                lamports: 0,
+                rent_epoch: 0,
+                executable: false,
+                write_version: 0,
+                data: ptr::null(),
+                data_len: 0,
+                txn_signature: ptr::null(),
+            },
+            slot_update: GeyserSlot {
+                status: GeyserSlotStatus::Processed,
+                has_parent: false,
+                parent: 0,
+            },
+            transaction: GeyserTransaction {
+                signature: ptr::null(),
+                is_vote: false,
+                fee: 0,
+                err: ptr::null(),
+                account_keys: ptr::null(),
+                account_keys_len: 0,
+                log_messages: ptr::null(),
+                log_messages_len: 0,
+            },
+            block: GeyserBlock {
+                blockhash: ptr::null(),
+                has_parent_slot: false,
+                parent_slot: 0,
+                block_time: 0,
+                block_height: 0,
+            },
+            metadata: GeyserMetadata {
+                send_errors: 0,
+                version: ptr::null(),
+            },
+            rewards: GeyserRewards {
+                has_block_height: false,
+                block_height: 0,
+                has_block_time: false,
+                block_time: 0,
+                rewards_len: 0,
+            },
+            owned: ptr::null_mut(),
+        }
+    }
+
+    fn new(event: Event) -> Self {
+        let mut owned = Box::<Owned>::default();
+        let mut output = GeyserEvent::empty();
+
+        match event {
+            Event::Account(account) => {
+                output.kind = GeyserEventKind::Account;
+                output.slot = account.slot;
+                owned.data = account.data;
+                output.account = GeyserAccount {
+                    pubkey: owned.string(account.pubkey),
+                    owner: owned.string(account.owner),
+                    lamports: account.lamports,
+                    rent_epoch: account.rent_epoch,
+                    executable: account.executable,
+                    write_version: account.write_version,
+                    data: owned.data.as_ptr(),
+                    data_len: owned.data.len(),
+                    txn_signature: owned.optional_string(account.txn_signature),
+                };
+            }
+            Event::Slot(slot) => {
+                output.kind = GeyserEventKind::Slot;
+                output.slot = slot.slot;
+                output.slot_update = GeyserSlot {
+                    status: match slot.status {
+                        SlotStatus::Processed => GeyserSlotStatus::Processed,
+                        SlotStatus::Confirmed => GeyserSlotStatus::Confirmed,
+                        SlotStatus::Rooted => GeyserSlotStatus::Rooted,
+                    },
+                    has_parent: slot.parent.is_some(),
+                    parent: slot.parent.unwrap_or_default(),
+                };
+            }
+            Event::Transaction(tx) => {
+                output.kind = GeyserEventKind::Transaction;
+                output.slot = tx.slot;
+                let (account_keys, account_keys_len) = owned.string_array(tx.account_keys);
+                let (log_messages, log_messages_len) = owned.string_array(tx.log_messages);
+                output.transaction = GeyserTransaction {
+                    signature: owned.string(tx.signature),
+                    is_vote: tx.is_vote,
+                    fee: tx.fee,
+                    err: owned.optional_string(tx.err),
+                    account_keys,
+                    account_keys_len,
+                    log_messages,
+                    log_messages_len,
+                };
+            }
+            Event::Block(block) => {
+                output.kind = GeyserEventKind::Block;
+                output.slot = block.slot;
+                output.block = GeyserBlock {
+                    blockhash: owned.string(block.blockhash),
+                    has_parent_slot: block.parent_slot.is_some(),
+                    parent_slot: block.parent_slot.unwrap_or_default(),
+                    block_time: block.block_time,
+                    block_height: block.block_height,
+                };
+            }
+            Event::Rewards(rewards) => {
+                output.kind = GeyserEventKind::Rewards;
+                output.slot = rewards.slot;
+                output.rewards = GeyserRewards {
+                    has_block_height: rewards.block_height.is_some(),
+                    block_height: rewards.block_height.unwrap_or_default(),
+                    has_block_time: rewards.block_time.is_some(),
+                    block_time: rewards.block_time.unwrap_or_default(),
+                    rewards_len: rewards.rewards.len(),
+                };
+            }
+            Event::Metadata(metadata) => {
+                output.kind = GeyserEventKind::Metadata;
+                output.metadata = GeyserMetadata {
+                    send_errors: metadata.send_errors,
+                    version: owned.optional_string(metadata.version),
+                };
+            }
+        }
+
+        output.owned = Box::into_raw(owned) as *mut c_void;
+        output
+    }
+}
+
+/// Decodes the message into `out_event`, to be released with `geyser_event_free` if
+/// `GEYSER_OK` is returned.
+///
+/// # Safety
+/// `buf` must point to `len` readable bytes and `out_event` to a writable `GeyserEvent`.
+#[no_mangle]
+pub unsafe extern "C" fn geyser_decode(
+    buf: *const u8,
+    len: usize,
+    out_event: *mut GeyserEvent,
+) -> c_int {
+    if buf.is_null() || out_event.is_null() {
+        return GEYSER_ERR_INVALID;
+    }
+
+    match decode_event(slice::from_raw_parts(buf, len)) {
+        Ok(event) => {
+            out_event.write(GeyserEvent::new(event));
+            GEYSER_OK
+        }
+        Err(DecodeError::UnsupportedPrefix(_)) => GEYSER_ERR_UNSUPPORTED,
+        Err(_) => GEYSER_ERR_INVALID,
+    }
+}
+
+/// Releases the memory of a decoded event, its pointers becoming dangling.
+///
+/// # Safety
+/// `event` must be null or have been filled by `geyser_decode` or `geyser_poll`.
+#[no_mangle]
+pub unsafe extern "C" fn geyser_event_free(event: *mut GeyserEvent) {
+    if let Some(event) = event.as_mut() {
+        if !event.owned.is_null() {
+            drop(Box::from_raw(event.owned as *mut Owned));
+        }
+        *event = GeyserEvent::empty();
+    }
+}
+
+pub struct GeyserReceiver(ThreadedReceiver);
+
+/// Connects to a plugin, e.g. "127.0.0.1:2000", reconnecting every `reconnect_interval_ms`
+/// when the connection drops. Returns null if the address is invalid.
+///
+/// # Safety
+/// `addr` must be a nul terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn geyser_connect(
+    addr: *const c_char,
+    reconnect_interval_ms: u64,
+) -> *mut GeyserReceiver {
+    if addr.is_null() {
+        return ptr::null_mut();
+    }
+
+    let receiver = CStr::from_ptr(addr)
+        .to_str()
+        .ok()
+        .and_then(|addr| addr.parse::<SocketAddr>().ok())
+        .and_then(|addr| {
+            ThreadedReceiver::spawn(
+                addr,
+                Duration::from_millis(reconnect_interval_ms),
+                QUEUE_SIZE,
+            )
+            .ok()
+        });
+
+    match receiver {
+        Some(receiver) => Box::into_raw(Box::new(GeyserReceiver(receiver))),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Waits up to `timeout_ms` for the next event and decodes it into `out_event`, to be
+/// released with `geyser_event_free` if `GEYSER_OK` is returned. Messages without typed
+/// decoding are skipped. Timeouts too long to set a deadline with, e.g. `UINT64_MAX`, wait
+/// until an event comes or the receiver is closed.
+///
+/// # Safety
+/// `receiver` must come from `geyser_connect` and `out_event` point to a writable
+/// `GeyserEvent`.
+#[no_mangle]
+pub unsafe extern "C" fn geyser_poll(
+    receiver: *mut GeyserReceiver,
+    out_event: *mut GeyserEvent,
+    timeout_ms: u64,
+) -> c_int {
+    let receiver = match receiver.as_mut() {
+        Some(receiver) if !out_event.is_null() => receiver,
+        _ => return GEYSER_ERR_INVALID,
+    };
+
+    let deadline = Instant::now().checked_add(Duration::from_millis(timeout_ms));
+    loop {
+        let message = match deadline {
+            Some(deadline) => receiver
+                .0
+                .recv_timeout(deadline.saturating_duration_since(Instant::now())),
+            None => receiver
+                .0
+                .recv()
+                .map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        let message = match message {
+            Ok(message) => message,
+            Err(RecvTimeoutError::Timeout) => return GEYSER_TIMEOUT,
+            Err(RecvTimeoutError::Disconnected) => return GEYSER_ERR_CLOSED,
+        };
+
+        match geyser_decode(message.as_ptr(), message.len(), out_event) {
+            GEYSER_ERR_UNSUPPORTED => continue,
+            result => return result,
+        }
+    }
+}
+
+/// Disconnects and releases the receiver.
+///
+/// # Safety
+/// `receiver` must be null or come from `geyser_connect`, and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn geyser_receiver_free(receiver: *mut GeyserReceiver) {
+    if !receiver.is_null() {
+        drop(Box::from_raw(receiver));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flatbuffer::{
+        account_info_v2_generated::account_info_v2::{AccountInfo, AccountInfoArgs},
+        consts::{BYTE_PREFIX_ACCOUNT_V2, BYTE_PREFIX_SLOT},
+        slot_generated::slot::{Slot, SlotArgs, Status},
+    };
+    use crate::sender::TcpSender;
+
+    #[test]
+    fn test_decode_and_free() {
+        let mut builder = flatbuffers::FlatBufferBuilder::new();
+        let args = AccountInfoArgs {
+            pubkey: Some(builder.create_string("pubkey")),
+            owner: Some(builder.create_string("owner")),
+            slot: 42,
+            lamports: 1000,
+            data: Some(builder.create_vector(&[1u8, 2, 3])),
+            ..Default::default()
+        };
+        let info = AccountInfo::create(&mut builder, &args);
+        builder.finish(info, None);
+        let mut message = vec![BYTE_PREFIX_ACCOUNT_V2];
+        message.extend_from_slice(builder.finished_data());
+
+        let mut event = GeyserEvent::empty();
+        unsafe {
+            assert_eq!(
+                geyser_decode(message.as_ptr(), message.len(), &mut event),
+                GEYSER_OK
+            );
+
+            assert_eq!(event.kind, GeyserEventKind::Account);
+            assert_eq!(event.slot, 42);
+            assert_eq!(event.account.lamports, 1000);
+            assert_eq!(CStr::from_ptr(event.account.pubkey).to_str(), Ok("pubkey"));
+            assert_eq!(CStr::from_ptr(event.account.owner).to_str(), Ok("owner"));
+            assert_eq!(
+                slice::from_raw_parts(event.account.data, event.account.data_len),
+                [1, 2, 3]
+            );
+            assert!(event.account.txn_signature.is_null());
+
+            geyser_event_free(&mut event);
+            assert_eq!(event.kind, GeyserEventKind::None);
+            assert!(event.owned.is_null());
+
+            // freeing again, or a zeroed event, does nothing
+            geyser_event_free(&mut event);
+            geyser_event_free(ptr::null_mut());
+
+            assert_eq!(
+                geyser_decode(message.as_ptr(), 0, &mut event),
+                GEYSER_ERR_INVALID
+            );
+            assert_eq!(
+                geyser_decode([6, 0].as_ptr(), 2, &mut event),
+                GEYSER_ERR_UNSUPPORTED
+            );
+            assert_eq!(event.kind, GeyserEventKind::None);
+        }
+    }
+    #[test]
+    fn test_poll_without_deadline() {
+        let sender = TcpSender::new(10, false, 0).with_handshake_timeout(Duration::from_millis(10));
+        sender.bind(9083, 100).unwrap();
+
+        let addr = CString::new("127.0.0.1:9083").unwrap();
+        let receiver = unsafe { geyser_connect(addr.as_ptr(), 1000) };
+        assert!(!receiver.is_null());
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while sender.subscribers().unwrap().is_empty() {
+            assert!(
+                Instant::now() < deadline,
+                "timed out waiting for the receiver"
+            );
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        let mut builder = flatbuffers::FlatBufferBuilder::new();
+        let slot = Slot::create(
+            &mut builder,
+            &SlotArgs {
+                slot: 42,
+                status: Status::Processed,
+                parent: None,
+            },
+        );
+        builder.finish(slot, None);
+        let mut message = vec![BYTE_PREFIX_SLOT];
+        message.extend_from_slice(builder.finished_data());
+        sender.publish(message).unwrap();
+
+        let mut event = GeyserEvent::empty();
+        unsafe {
+            // too long for a deadline, waits for the event instead of overflowing
+            assert_eq!(geyser_poll(receiver, &mut event, u64::MAX), GEYSER_OK);
+            assert_eq!(event.kind, GeyserEventKind::Slot);
+            assert_eq!(event.slot, 42);
+
+            geyser_event_free(&mut event);
+            geyser_receiver_free(receiver);
+        }
+    }
+}
@@ -0,0 +1,140 @@
+//! Per-subscriber filters, sent by clients in their `ClientHello`, and the filters of the
+//! plugin itself, broadcast to subscribers as `FilterState` control messages.
+use crate::chunk::chunked_prefix;
+use crate::flatbuffer::{
+    consts::{BYTE_PREFIX_FILTER_STATE, BYTE_PREFIX_SLOT},
+    slot_generated::slot::{root_as_slot, Status},
+};
+use crate::handshake::{from_json_message, to_json_message};
+use crate::message_type::MessageType;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum SlotStatusFilter {
+    Processed,
+    Rooted,
+    Confirmed,
+}
+
+impl SlotStatusFilter {
+    fn matches(&self, status: Status) -> bool {
+        matches!(
+            (self, status),
+            (SlotStatusFilter::Processed, Status::Processed)
+                | (SlotStatusFilter::Rooted, Status::Rooted)
+                | (SlotStatusFilter::Confirmed, Status::Confirmed)
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct SubscriptionFilters {
+    /// Types of the data messages to receive, e.g. `["slot", "block"]`. All of them if not
+    /// set. Control messages are always received.
+    #[serde(default)]
+    pub message_types: Option<Vec<MessageType>>,
+    /// Slot statuses to receive, e.g. `["rooted"]`. All of them if not set.
+    #[serde(default)]
+    pub slot_statuses: Option<Vec<SlotStatusFilter>>,
+}
+
+impl SubscriptionFilters {
+    /// True if every message passes, so the connection can share the unfiltered batch.
+    pub fn is_empty(&self) -> bool {
+        self.message_types.is_none() && self.slot_statuses.is_none()
+    }
+
+    /// Checks a message, prefix byte included, against the filters.
+    /// Messages which can't be decoded are let through.
+    pub fn matches(&self, message: &[u8]) -> bool {
+        // chunks are of the type of the message they're part of
+        if let (Some(types), Some(message_type)) = (
+            &self.message_types,
+            chunked_prefix(message)
+                .or(message.first().copied())
+                .and_then(MessageType::from_prefix),
+        ) {
+            if !types.contains(&message_type) {
+                return false;
+            }
+        }
+
+        match message.split_first() {
+            Some((&BYTE_PREFIX_SLOT, data)) => match (&self.slot_statuses, root_as_slot(data)) {
+                (Some(statuses), Ok(slot)) => statuses.iter().any(|s| s.matches(slot.status())),
+                _ => true,
+            },
+            _ => true,
+        }
+    }
+}
+
+/// Filters the plugin applies before publishing anything. A change across restarts means subscribers missed
+/// or will miss data they may have relied on, and might need to re-synchronize.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct FilterState {
+    pub send_accounts: bool,
+    pub send_transactions: bool,
+    pub send_blocks: bool,
+    pub send_rewards: bool,
+    pub skip_vote_txs: bool,
+    pub skip_deploy_txs: bool,
+    /// Vote transactions are published on a separate listener
+    pub vote_txs_rerouted: bool,
+}
+
+impl FilterState {
+    pub fn to_message(&self) -> Vec<u8> {
+        to_json_message(BYTE_PREFIX_FILTER_STATE, self)
+    }
+
+    pub fn from_message(message: &[u8]) -> Option<Self> {
+        from_json_message(BYTE_PREFIX_FILTER_STATE, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flatbuffer::consts::BYTE_PREFIX_ACCOUNT;
+    use crate::flatbuffer::slot_generated::slot::{Slot, SlotArgs};
+    use flatbuffers::FlatBufferBuilder;
+
+    fn slot_message(status: Status) -> Vec<u8> {
+        let mut builder = FlatBufferBuilder::new();
+        let slot = Slot::create(
+            &mut builder,
+            &SlotArgs {
+                slot: 1,
+                status,
+                parent: None,
+            },
+        );
+        builder.finish(slot, None);
+
+        let mut message = vec![BYTE_PREFIX_SLOT];
+        message.extend_from_slice(builder.finished_data());
+        message
+    }
+
+    #[test]
+    fn test_slot_status_filter() {
+        let filters: SubscriptionFilters =
+            serde_json::from_str(r#"{"slot_statuses": ["rooted"]}"#).unwrap();
+
+        assert!(filters.matches(&slot_message(Status::Rooted)));
+        assert!(!filters.matches(&slot_message(Status::Processed)));
+        assert!(SubscriptionFilters::default().matches(&slot_message(Status::Processed)));
+    }
+
+    #[test]
+    fn test_message_type_filter() {
+        let filters: SubscriptionFilters =
+            serde_json::from_str(r#"{"message_types": ["slot"]}"#).unwrap();
+
+        assert!(filters.matches(&slot_message(Status::Processed)));
+        assert!(!filters.matches(&[BYTE_PREFIX_ACCOUNT, 0, 0]));
+        assert!(filters.matches(&FilterState::default().to_message()));
+    }
+}
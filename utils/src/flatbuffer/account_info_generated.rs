@@ -35,6 +35,7 @@ pub mod account_info {
         pub const VT_OWNER: flatbuffers::VOffsetT = 6;
         pub const VT_SLOT: flatbuffers::VOffsetT = 8;
         pub const VT_ACCOUNT_DATA: flatbuffers::VOffsetT = 10;
+        pub const VT_TXN_SIGNATURE: flatbuffers::VOffsetT = 12;
 
         #[inline]
         pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
@@ -47,6 +48,9 @@ pub mod account_info {
         ) -> flatbuffers::WIPOffset<AccountInfo<'bldr>> {
             let mut builder = AccountInfoBuilder::new(_fbb);
             builder.add_slot(args.slot);
+            if let Some(x) = args.txn_signature {
+                builder.add_txn_signature(x);
+            }
             if let Some(x) = args.account_data {
                 builder.add_account_data(x);
             }
@@ -99,6 +103,16 @@ pub mod account_info {
                     )
             }
         }
+        #[inline]
+        pub fn txn_signature(&self) -> Option<&'a str> {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab
+                    .get::<flatbuffers::ForwardsUOffset<&str>>(AccountInfo::VT_TXN_SIGNATURE, None)
+            }
+        }
     }
 
     impl flatbuffers::Verifiable for AccountInfo<'_> {
@@ -121,6 +135,11 @@ pub mod account_info {
                     Self::VT_ACCOUNT_DATA,
                     false,
                 )?
+                .visit_field::<flatbuffers::ForwardsUOffset<&str>>(
+                    "txn_signature",
+                    Self::VT_TXN_SIGNATURE,
+                    false,
+                )?
                 .finish();
             Ok(())
         }
@@ -130,6 +149,7 @@ pub mod account_info {
         pub owner: Option<flatbuffers::WIPOffset<&'a str>>,
         pub slot: u64,
         pub account_data: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a, u8>>>,
+        pub txn_signature: Option<flatbuffers::WIPOffset<&'a str>>,
     }
     impl<'a> Default for AccountInfoArgs<'a> {
         #[inline]
@@ -139,6 +159,7 @@ pub mod account_info {
                 owner: None,
                 slot: 0,
                 account_data: None,
+                txn_signature: None,
             }
         }
     }
@@ -173,6 +194,13 @@ pub mod account_info {
             );
         }
         #[inline]
+        pub fn add_txn_signature(&mut self, txn_signature: flatbuffers::WIPOffset<&'b str>) {
+            self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(
+                AccountInfo::VT_TXN_SIGNATURE,
+                txn_signature,
+            );
+        }
+        #[inline]
         pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> AccountInfoBuilder<'a, 'b> {
             let start = _fbb.start_table();
             AccountInfoBuilder {
@@ -194,6 +222,7 @@ pub mod account_info {
             ds.field("owner", &self.owner());
             ds.field("slot", &self.slot());
             ds.field("account_data", &self.account_data());
+            ds.field("txn_signature", &self.txn_signature());
             ds.finish()
         }
     }
@@ -3,3 +3,14 @@ pub const BYTE_PREFIX_SLOT: u8 = 1;
 pub const BYTE_PREFIX_TX: u8 = 2;
 pub const BYTE_PREFIX_BLOCK: u8 = 3;
 pub const BYTE_PREFIX_METADATA: u8 = 4;
+pub const BYTE_PREFIX_REWARDS: u8 = 5;
+pub const BYTE_PREFIX_HANDSHAKE: u8 = 6;
+pub const BYTE_PREFIX_DIAGNOSTIC: u8 = 7;
+pub const BYTE_PREFIX_FILTER_STATE: u8 = 8;
+pub const BYTE_PREFIX_ACCOUNT_V2: u8 = 9;
+pub const BYTE_PREFIX_SLOT_ROOTED: u8 = 10;
+pub const BYTE_PREFIX_ACK: u8 = 11;
+pub const BYTE_PREFIX_CHUNK: u8 = 12;
+
+/// Set on the prefix byte of messages compressed with zstd, see `compression`
+pub const COMPRESSED_FLAG: u8 = 0x80;
@@ -0,0 +1,422 @@
+// automatically generated by the FlatBuffers compiler, do not modify
+
+// @generated
+
+extern crate flatbuffers;
+
+#[allow(unused_imports, dead_code)]
+pub mod diagnostic {
+
+    use core::cmp::Ordering;
+    use core::mem;
+
+    extern crate flatbuffers;
+    use self::flatbuffers::{EndianScalar, Follow};
+
+    #[deprecated(
+        since = "2.0.0",
+        note = "Use associated constants instead. This will no longer be generated in 2021."
+    )]
+    pub const ENUM_MIN_KIND: i8 = 0;
+    #[deprecated(
+        since = "2.0.0",
+        note = "Use associated constants instead. This will no longer be generated in 2021."
+    )]
+    pub const ENUM_MAX_KIND: i8 = 3;
+    #[deprecated(
+        since = "2.0.0",
+        note = "Use associated constants instead. This will no longer be generated in 2021."
+    )]
+    #[allow(non_camel_case_types)]
+    pub const ENUM_VALUES_KIND: [Kind; 4] = [
+        Kind::SlotOrder,
+        Kind::WriteVersionOrder,
+        Kind::TxIndexOrder,
+        Kind::DuplicateBlock,
+    ];
+
+    #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+    #[repr(transparent)]
+    pub struct Kind(pub i8);
+    #[allow(non_upper_case_globals)]
+    impl Kind {
+        pub const SlotOrder: Self = Self(0);
+        pub const WriteVersionOrder: Self = Self(1);
+        pub const TxIndexOrder: Self = Self(2);
+        pub const DuplicateBlock: Self = Self(3);
+
+        pub const ENUM_MIN: i8 = 0;
+        pub const ENUM_MAX: i8 = 3;
+        pub const ENUM_VALUES: &'static [Self] = &[
+            Self::SlotOrder,
+            Self::WriteVersionOrder,
+            Self::TxIndexOrder,
+            Self::DuplicateBlock,
+        ];
+        /// Returns the variant's name or "" if unknown.
+        pub fn variant_name(self) -> Option<&'static str> {
+            match self {
+                Self::SlotOrder => Some("SlotOrder"),
+                Self::WriteVersionOrder => Some("WriteVersionOrder"),
+                Self::TxIndexOrder => Some("TxIndexOrder"),
+                Self::DuplicateBlock => Some("DuplicateBlock"),
+                _ => None,
+            }
+        }
+    }
+    impl core::fmt::Debug for Kind {
+        fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+            if let Some(name) = self.variant_name() {
+                f.write_str(name)
+            } else {
+                f.write_fmt(format_args!("<UNKNOWN {:?}>", self.0))
+            }
+        }
+    }
+    impl<'a> flatbuffers::Follow<'a> for Kind {
+        type Inner = Self;
+        #[inline]
+        unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+            let b = flatbuffers::read_scalar_at::<i8>(buf, loc);
+            Self(b)
+        }
+    }
+
+    impl flatbuffers::Push for Kind {
+        type Output = Kind;
+        #[inline]
+        unsafe fn push(&self, dst: &mut [u8], _written_len: usize) {
+            flatbuffers::emplace_scalar::<i8>(dst, self.0);
+        }
+    }
+
+    impl flatbuffers::EndianScalar for Kind {
+        type Scalar = i8;
+        #[inline]
+        fn to_little_endian(self) -> i8 {
+            self.0.to_le()
+        }
+        #[inline]
+        #[allow(clippy::wrong_self_convention)]
+        fn from_little_endian(v: i8) -> Self {
+            let b = i8::from_le(v);
+            Self(b)
+        }
+    }
+
+    impl<'a> flatbuffers::Verifiable for Kind {
+        #[inline]
+        fn run_verifier(
+            v: &mut flatbuffers::Verifier,
+            pos: usize,
+        ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+            use self::flatbuffers::Verifiable;
+            i8::run_verifier(v, pos)
+        }
+    }
+
+    impl flatbuffers::SimpleToVerifyInSlice for Kind {}
+    pub enum DiagnosticOffset {}
+    #[derive(Copy, Clone, PartialEq)]
+
+    pub struct Diagnostic<'a> {
+        pub _tab: flatbuffers::Table<'a>,
+    }
+
+    impl<'a> flatbuffers::Follow<'a> for Diagnostic<'a> {
+        type Inner = Diagnostic<'a>;
+        #[inline]
+        unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+            Self {
+                _tab: flatbuffers::Table::new(buf, loc),
+            }
+        }
+    }
+
+    impl<'a> Diagnostic<'a> {
+        pub const VT_KIND: flatbuffers::VOffsetT = 4;
+        pub const VT_SLOT: flatbuffers::VOffsetT = 6;
+        pub const VT_PREVIOUS: flatbuffers::VOffsetT = 8;
+        pub const VT_CURRENT: flatbuffers::VOffsetT = 10;
+        pub const VT_PREVIOUS_BLOCKHASH: flatbuffers::VOffsetT = 12;
+        pub const VT_BLOCKHASH: flatbuffers::VOffsetT = 14;
+
+        #[inline]
+        pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+            Diagnostic { _tab: table }
+        }
+        #[allow(unused_mut)]
+        pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+            _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+            args: &'args DiagnosticArgs<'args>,
+        ) -> flatbuffers::WIPOffset<Diagnostic<'bldr>> {
+            let mut builder = DiagnosticBuilder::new(_fbb);
+            builder.add_current(args.current);
+            builder.add_previous(args.previous);
+            builder.add_slot(args.slot);
+            if let Some(x) = args.blockhash {
+                builder.add_blockhash(x);
+            }
+            if let Some(x) = args.previous_blockhash {
+                builder.add_previous_blockhash(x);
+            }
+            builder.add_kind(args.kind);
+            builder.finish()
+        }
+
+        #[inline]
+        pub fn kind(&self) -> Kind {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab
+                    .get::<Kind>(Diagnostic::VT_KIND, Some(Kind::SlotOrder))
+                    .unwrap()
+            }
+        }
+        #[inline]
+        pub fn slot(&self) -> u64 {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe { self._tab.get::<u64>(Diagnostic::VT_SLOT, Some(0)).unwrap() }
+        }
+        #[inline]
+        pub fn previous(&self) -> u64 {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab
+                    .get::<u64>(Diagnostic::VT_PREVIOUS, Some(0))
+                    .unwrap()
+            }
+        }
+        #[inline]
+        pub fn current(&self) -> u64 {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab
+                    .get::<u64>(Diagnostic::VT_CURRENT, Some(0))
+                    .unwrap()
+            }
+        }
+        #[inline]
+        pub fn previous_blockhash(&self) -> Option<&'a str> {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(
+                    Diagnostic::VT_PREVIOUS_BLOCKHASH,
+                    None,
+                )
+            }
+        }
+        #[inline]
+        pub fn blockhash(&self) -> Option<&'a str> {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab
+                    .get::<flatbuffers::ForwardsUOffset<&str>>(Diagnostic::VT_BLOCKHASH, None)
+            }
+        }
+    }
+
+    impl flatbuffers::Verifiable for Diagnostic<'_> {
+        #[inline]
+        fn run_verifier(
+            v: &mut flatbuffers::Verifier,
+            pos: usize,
+        ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+            use self::flatbuffers::Verifiable;
+            v.visit_table(pos)?
+                .visit_field::<Kind>("kind", Self::VT_KIND, false)?
+                .visit_field::<u64>("slot", Self::VT_SLOT, false)?
+                .visit_field::<u64>("previous", Self::VT_PREVIOUS, false)?
+                .visit_field::<u64>("current", Self::VT_CURRENT, false)?
+                .visit_field::<flatbuffers::ForwardsUOffset<&str>>(
+                    "previous_blockhash",
+                    Self::VT_PREVIOUS_BLOCKHASH,
+                    false,
+                )?
+                .visit_field::<flatbuffers::ForwardsUOffset<&str>>(
+                    "blockhash",
+                    Self::VT_BLOCKHASH,
+                    false,
+                )?
+                .finish();
+            Ok(())
+        }
+    }
+    pub struct DiagnosticArgs<'a> {
+        pub kind: Kind,
+        pub slot: u64,
+        pub previous: u64,
+        pub current: u64,
+        pub previous_blockhash: Option<flatbuffers::WIPOffset<&'a str>>,
+        pub blockhash: Option<flatbuffers::WIPOffset<&'a str>>,
+    }
+    impl<'a> Default for DiagnosticArgs<'a> {
+        #[inline]
+        fn default() -> Self {
+            DiagnosticArgs {
+                kind: Kind::SlotOrder,
+                slot: 0,
+                previous: 0,
+                current: 0,
+                previous_blockhash: None,
+                blockhash: None,
+            }
+        }
+    }
+
+    pub struct DiagnosticBuilder<'a: 'b, 'b> {
+        fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+        start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+    }
+    impl<'a: 'b, 'b> DiagnosticBuilder<'a, 'b> {
+        #[inline]
+        pub fn add_kind(&mut self, kind: Kind) {
+            self.fbb_
+                .push_slot::<Kind>(Diagnostic::VT_KIND, kind, Kind::SlotOrder);
+        }
+        #[inline]
+        pub fn add_slot(&mut self, slot: u64) {
+            self.fbb_.push_slot::<u64>(Diagnostic::VT_SLOT, slot, 0);
+        }
+        #[inline]
+        pub fn add_previous(&mut self, previous: u64) {
+            self.fbb_
+                .push_slot::<u64>(Diagnostic::VT_PREVIOUS, previous, 0);
+        }
+        #[inline]
+        pub fn add_current(&mut self, current: u64) {
+            self.fbb_
+                .push_slot::<u64>(Diagnostic::VT_CURRENT, current, 0);
+        }
+        #[inline]
+        pub fn add_previous_blockhash(
+            &mut self,
+            previous_blockhash: flatbuffers::WIPOffset<&'b str>,
+        ) {
+            self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(
+                Diagnostic::VT_PREVIOUS_BLOCKHASH,
+                previous_blockhash,
+            );
+        }
+        #[inline]
+        pub fn add_blockhash(&mut self, blockhash: flatbuffers::WIPOffset<&'b str>) {
+            self.fbb_
+                .push_slot_always::<flatbuffers::WIPOffset<_>>(Diagnostic::VT_BLOCKHASH, blockhash);
+        }
+        #[inline]
+        pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> DiagnosticBuilder<'a, 'b> {
+            let start = _fbb.start_table();
+            DiagnosticBuilder {
+                fbb_: _fbb,
+                start_: start,
+            }
+        }
+        #[inline]
+        pub fn finish(self) -> flatbuffers::WIPOffset<Diagnostic<'a>> {
+            let o = self.fbb_.end_table(self.start_);
+            flatbuffers::WIPOffset::new(o.value())
+        }
+    }
+
+    impl core::fmt::Debug for Diagnostic<'_> {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            let mut ds = f.debug_struct("Diagnostic");
+            ds.field("kind", &self.kind());
+            ds.field("slot", &self.slot());
+            ds.field("previous", &self.previous());
+            ds.field("current", &self.current());
+            ds.field("previous_blockhash", &self.previous_blockhash());
+            ds.field("blockhash", &self.blockhash());
+            ds.finish()
+        }
+    }
+    #[inline]
+    /// Verifies that a buffer of bytes contains a `Diagnostic`
+    /// and returns it.
+    /// Note that verification is still experimental and may not
+    /// catch every error, or be maximally performant. For the
+    /// previous, unchecked, behavior use
+    /// `root_as_diagnostic_unchecked`.
+    pub fn root_as_diagnostic(buf: &[u8]) -> Result<Diagnostic, flatbuffers::InvalidFlatbuffer> {
+        flatbuffers::root::<Diagnostic>(buf)
+    }
+    #[inline]
+    /// Verifies that a buffer of bytes contains a size prefixed
+    /// `Diagnostic` and returns it.
+    /// Note that verification is still experimental and may not
+    /// catch every error, or be maximally performant. For the
+    /// previous, unchecked, behavior use
+    /// `size_prefixed_root_as_diagnostic_unchecked`.
+    pub fn size_prefixed_root_as_diagnostic(
+        buf: &[u8],
+    ) -> Result<Diagnostic, flatbuffers::InvalidFlatbuffer> {
+        flatbuffers::size_prefixed_root::<Diagnostic>(buf)
+    }
+    #[inline]
+    /// Verifies, with the given options, that a buffer of bytes
+    /// contains a `Diagnostic` and returns it.
+    /// Note that verification is still experimental and may not
+    /// catch every error, or be maximally performant. For the
+    /// previous, unchecked, behavior use
+    /// `root_as_diagnostic_unchecked`.
+    pub fn root_as_diagnostic_with_opts<'b, 'o>(
+        opts: &'o flatbuffers::VerifierOptions,
+        buf: &'b [u8],
+    ) -> Result<Diagnostic<'b>, flatbuffers::InvalidFlatbuffer> {
+        flatbuffers::root_with_opts::<Diagnostic<'b>>(opts, buf)
+    }
+    #[inline]
+    /// Verifies, with the given verifier options, that a buffer of
+    /// bytes contains a size prefixed `Diagnostic` and returns
+    /// it. Note that verification is still experimental and may not
+    /// catch every error, or be maximally performant. For the
+    /// previous, unchecked, behavior use
+    /// `root_as_diagnostic_unchecked`.
+    pub fn size_prefixed_root_as_diagnostic_with_opts<'b, 'o>(
+        opts: &'o flatbuffers::VerifierOptions,
+        buf: &'b [u8],
+    ) -> Result<Diagnostic<'b>, flatbuffers::InvalidFlatbuffer> {
+        flatbuffers::size_prefixed_root_with_opts::<Diagnostic<'b>>(opts, buf)
+    }
+    #[inline]
+    /// Assumes, without verification, that a buffer of bytes contains a Diagnostic and returns it.
+    /// # Safety
+    /// Callers must trust the given bytes do indeed contain a valid `Diagnostic`.
+    pub unsafe fn root_as_diagnostic_unchecked(buf: &[u8]) -> Diagnostic {
+        flatbuffers::root_unchecked::<Diagnostic>(buf)
+    }
+    #[inline]
+    /// Assumes, without verification, that a buffer of bytes contains a size prefixed Diagnostic and returns it.
+    /// # Safety
+    /// Callers must trust the given bytes do indeed contain a valid size prefixed `Diagnostic`.
+    pub unsafe fn size_prefixed_root_as_diagnostic_unchecked(buf: &[u8]) -> Diagnostic {
+        flatbuffers::size_prefixed_root_unchecked::<Diagnostic>(buf)
+    }
+    #[inline]
+    pub fn finish_diagnostic_buffer<'a, 'b>(
+        fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+        root: flatbuffers::WIPOffset<Diagnostic<'a>>,
+    ) {
+        fbb.finish(root, None);
+    }
+
+    #[inline]
+    pub fn finish_size_prefixed_diagnostic_buffer<'a, 'b>(
+        fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+        root: flatbuffers::WIPOffset<Diagnostic<'a>>,
+    ) {
+        fbb.finish_size_prefixed(root, None);
+    }
+} // pub mod Diagnostic
@@ -32,6 +32,9 @@ pub mod metadata {
 
     impl<'a> Metadata<'a> {
         pub const VT_SEND_ERRORS: flatbuffers::VOffsetT = 4;
+        pub const VT_VERSION: flatbuffers::VOffsetT = 6;
+        pub const VT_GIT_COMMIT: flatbuffers::VOffsetT = 8;
+        pub const VT_SCHEMA_HASH: flatbuffers::VOffsetT = 10;
 
         #[inline]
         pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
@@ -40,10 +43,19 @@ pub mod metadata {
         #[allow(unused_mut)]
         pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
             _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
-            args: &'args MetadataArgs,
+            args: &'args MetadataArgs<'args>,
         ) -> flatbuffers::WIPOffset<Metadata<'bldr>> {
             let mut builder = MetadataBuilder::new(_fbb);
             builder.add_send_errors(args.send_errors);
+            if let Some(x) = args.schema_hash {
+                builder.add_schema_hash(x);
+            }
+            if let Some(x) = args.git_commit {
+                builder.add_git_commit(x);
+            }
+            if let Some(x) = args.version {
+                builder.add_version(x);
+            }
             builder.finish()
         }
 
@@ -58,6 +70,36 @@ pub mod metadata {
                     .unwrap()
             }
         }
+        #[inline]
+        pub fn version(&self) -> Option<&'a str> {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab
+                    .get::<flatbuffers::ForwardsUOffset<&str>>(Metadata::VT_VERSION, None)
+            }
+        }
+        #[inline]
+        pub fn git_commit(&self) -> Option<&'a str> {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab
+                    .get::<flatbuffers::ForwardsUOffset<&str>>(Metadata::VT_GIT_COMMIT, None)
+            }
+        }
+        #[inline]
+        pub fn schema_hash(&self) -> Option<&'a str> {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab
+                    .get::<flatbuffers::ForwardsUOffset<&str>>(Metadata::VT_SCHEMA_HASH, None)
+            }
+        }
     }
 
     impl flatbuffers::Verifiable for Metadata<'_> {
@@ -69,17 +111,40 @@ pub mod metadata {
             use self::flatbuffers::Verifiable;
             v.visit_table(pos)?
                 .visit_field::<u64>("send_errors", Self::VT_SEND_ERRORS, false)?
+                .visit_field::<flatbuffers::ForwardsUOffset<&str>>(
+                    "version",
+                    Self::VT_VERSION,
+                    false,
+                )?
+                .visit_field::<flatbuffers::ForwardsUOffset<&str>>(
+                    "git_commit",
+                    Self::VT_GIT_COMMIT,
+                    false,
+                )?
+                .visit_field::<flatbuffers::ForwardsUOffset<&str>>(
+                    "schema_hash",
+                    Self::VT_SCHEMA_HASH,
+                    false,
+                )?
                 .finish();
             Ok(())
         }
     }
-    pub struct MetadataArgs {
+    pub struct MetadataArgs<'a> {
         pub send_errors: u64,
+        pub version: Option<flatbuffers::WIPOffset<&'a str>>,
+        pub git_commit: Option<flatbuffers::WIPOffset<&'a str>>,
+        pub schema_hash: Option<flatbuffers::WIPOffset<&'a str>>,
     }
-    impl<'a> Default for MetadataArgs {
+    impl<'a> Default for MetadataArgs<'a> {
         #[inline]
         fn default() -> Self {
-            MetadataArgs { send_errors: 0 }
+            MetadataArgs {
+                send_errors: 0,
+                version: None,
+                git_commit: None,
+                schema_hash: None,
+            }
         }
     }
 
@@ -94,6 +159,23 @@ pub mod metadata {
                 .push_slot::<u64>(Metadata::VT_SEND_ERRORS, send_errors, 0);
         }
         #[inline]
+        pub fn add_version(&mut self, version: flatbuffers::WIPOffset<&'b str>) {
+            self.fbb_
+                .push_slot_always::<flatbuffers::WIPOffset<_>>(Metadata::VT_VERSION, version);
+        }
+        #[inline]
+        pub fn add_git_commit(&mut self, git_commit: flatbuffers::WIPOffset<&'b str>) {
+            self.fbb_
+                .push_slot_always::<flatbuffers::WIPOffset<_>>(Metadata::VT_GIT_COMMIT, git_commit);
+        }
+        #[inline]
+        pub fn add_schema_hash(&mut self, schema_hash: flatbuffers::WIPOffset<&'b str>) {
+            self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(
+                Metadata::VT_SCHEMA_HASH,
+                schema_hash,
+            );
+        }
+        #[inline]
         pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> MetadataBuilder<'a, 'b> {
             let start = _fbb.start_table();
             MetadataBuilder {
@@ -112,6 +194,9 @@ pub mod metadata {
         fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
             let mut ds = f.debug_struct("Metadata");
             ds.field("send_errors", &self.send_errors());
+            ds.field("version", &self.version());
+            ds.field("git_commit", &self.git_commit());
+            ds.field("schema_hash", &self.schema_hash());
             ds.finish()
         }
     }
@@ -3,13 +3,24 @@ pub mod account_data_generated;
 #[allow(dead_code, clippy::all)]
 pub mod account_info_generated;
 #[allow(dead_code, clippy::all)]
+pub mod account_info_v2_generated;
+#[allow(dead_code, clippy::all)]
 pub mod block_info_generated;
 #[allow(dead_code, clippy::all)]
 pub mod common_generated;
 pub mod consts;
 #[allow(dead_code, clippy::all)]
+pub mod diagnostic_generated;
+#[allow(dead_code, clippy::all)]
 pub mod metadata_generated;
 #[allow(dead_code, clippy::all)]
+pub mod rewards_generated;
+#[allow(dead_code, clippy::all)]
 pub mod slot_generated;
 #[allow(dead_code, clippy::all)]
+pub mod slot_rooted_generated;
+#[allow(dead_code, clippy::all)]
 pub mod transaction_info_generated;
+
+/// Hash of the `.fbs` schemas the generated code was built from.
+pub const SCHEMA_HASH: &str = env!("FLATBUFFER_SCHEMA_HASH");
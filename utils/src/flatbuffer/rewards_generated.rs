@@ -0,0 +1,267 @@
+// automatically generated by the FlatBuffers compiler, do not modify
+
+// @generated
+
+extern crate flatbuffers;
+
+#[allow(unused_imports, dead_code)]
+pub mod rewards {
+    use crate::flatbuffer::common_generated::common::Reward;
+
+    extern crate flatbuffers;
+    use self::flatbuffers::{EndianScalar, Follow};
+
+    pub enum RewardsOffset {}
+    #[derive(Copy, Clone, PartialEq)]
+
+    pub struct Rewards<'a> {
+        pub _tab: flatbuffers::Table<'a>,
+    }
+
+    impl<'a> flatbuffers::Follow<'a> for Rewards<'a> {
+        type Inner = Rewards<'a>;
+        #[inline]
+        unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+            Self {
+                _tab: flatbuffers::Table::new(buf, loc),
+            }
+        }
+    }
+
+    impl<'a> Rewards<'a> {
+        pub const VT_SLOT: flatbuffers::VOffsetT = 4;
+        pub const VT_BLOCK_HEIGHT: flatbuffers::VOffsetT = 6;
+        pub const VT_BLOCK_TIME: flatbuffers::VOffsetT = 8;
+        pub const VT_REWARDS: flatbuffers::VOffsetT = 10;
+
+        #[inline]
+        pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+            Rewards { _tab: table }
+        }
+        #[allow(unused_mut)]
+        pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+            _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+            args: &'args RewardsArgs<'args>,
+        ) -> flatbuffers::WIPOffset<Rewards<'bldr>> {
+            let mut builder = RewardsBuilder::new(_fbb);
+            if let Some(x) = args.block_time {
+                builder.add_block_time(x);
+            }
+            if let Some(x) = args.block_height {
+                builder.add_block_height(x);
+            }
+            builder.add_slot(args.slot);
+            if let Some(x) = args.rewards {
+                builder.add_rewards(x);
+            }
+            builder.finish()
+        }
+
+        #[inline]
+        pub fn slot(&self) -> u64 {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe { self._tab.get::<u64>(Rewards::VT_SLOT, Some(0)).unwrap() }
+        }
+        #[inline]
+        pub fn block_height(&self) -> Option<u64> {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe { self._tab.get::<u64>(Rewards::VT_BLOCK_HEIGHT, None) }
+        }
+        #[inline]
+        pub fn block_time(&self) -> Option<i64> {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe { self._tab.get::<i64>(Rewards::VT_BLOCK_TIME, None) }
+        }
+        #[inline]
+        pub fn rewards(
+            &self,
+        ) -> Option<flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<Reward<'a>>>> {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe {
+                self._tab.get::<flatbuffers::ForwardsUOffset<
+                    flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<Reward>>,
+                >>(Rewards::VT_REWARDS, None)
+            }
+        }
+    }
+
+    impl flatbuffers::Verifiable for Rewards<'_> {
+        #[inline]
+        fn run_verifier(
+            v: &mut flatbuffers::Verifier,
+            pos: usize,
+        ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+            use self::flatbuffers::Verifiable;
+            v.visit_table(pos)?
+                .visit_field::<u64>("slot", Self::VT_SLOT, false)?
+                .visit_field::<u64>("block_height", Self::VT_BLOCK_HEIGHT, false)?
+                .visit_field::<i64>("block_time", Self::VT_BLOCK_TIME, false)?
+                .visit_field::<flatbuffers::ForwardsUOffset<
+                    flatbuffers::Vector<'_, flatbuffers::ForwardsUOffset<Reward>>,
+                >>("rewards", Self::VT_REWARDS, false)?
+                .finish();
+            Ok(())
+        }
+    }
+    pub struct RewardsArgs<'a> {
+        pub slot: u64,
+        pub block_height: Option<u64>,
+        pub block_time: Option<i64>,
+        pub rewards: Option<
+            flatbuffers::WIPOffset<
+                flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<Reward<'a>>>,
+            >,
+        >,
+    }
+    impl<'a> Default for RewardsArgs<'a> {
+        #[inline]
+        fn default() -> Self {
+            RewardsArgs {
+                slot: 0,
+                block_height: None,
+                block_time: None,
+                rewards: None,
+            }
+        }
+    }
+
+    pub struct RewardsBuilder<'a: 'b, 'b> {
+        fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+        start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+    }
+    impl<'a: 'b, 'b> RewardsBuilder<'a, 'b> {
+        #[inline]
+        pub fn add_slot(&mut self, slot: u64) {
+            self.fbb_.push_slot::<u64>(Rewards::VT_SLOT, slot, 0);
+        }
+        #[inline]
+        pub fn add_block_height(&mut self, block_height: u64) {
+            self.fbb_
+                .push_slot_always::<u64>(Rewards::VT_BLOCK_HEIGHT, block_height);
+        }
+        #[inline]
+        pub fn add_block_time(&mut self, block_time: i64) {
+            self.fbb_
+                .push_slot_always::<i64>(Rewards::VT_BLOCK_TIME, block_time);
+        }
+        #[inline]
+        pub fn add_rewards(
+            &mut self,
+            rewards: flatbuffers::WIPOffset<
+                flatbuffers::Vector<'b, flatbuffers::ForwardsUOffset<Reward<'b>>>,
+            >,
+        ) {
+            self.fbb_
+                .push_slot_always::<flatbuffers::WIPOffset<_>>(Rewards::VT_REWARDS, rewards);
+        }
+        #[inline]
+        pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> RewardsBuilder<'a, 'b> {
+            let start = _fbb.start_table();
+            RewardsBuilder {
+                fbb_: _fbb,
+                start_: start,
+            }
+        }
+        #[inline]
+        pub fn finish(self) -> flatbuffers::WIPOffset<Rewards<'a>> {
+            let o = self.fbb_.end_table(self.start_);
+            flatbuffers::WIPOffset::new(o.value())
+        }
+    }
+
+    impl core::fmt::Debug for Rewards<'_> {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            let mut ds = f.debug_struct("Rewards");
+            ds.field("slot", &self.slot());
+            ds.field("block_height", &self.block_height());
+            ds.field("block_time", &self.block_time());
+            ds.field("rewards", &self.rewards());
+            ds.finish()
+        }
+    }
+    #[inline]
+    /// Verifies that a buffer of bytes contains a `Rewards`
+    /// and returns it.
+    /// Note that verification is still experimental and may not
+    /// catch every error, or be maximally performant. For the
+    /// previous, unchecked, behavior use
+    /// `root_as_rewards_unchecked`.
+    pub fn root_as_rewards(buf: &[u8]) -> Result<Rewards, flatbuffers::InvalidFlatbuffer> {
+        flatbuffers::root::<Rewards>(buf)
+    }
+    #[inline]
+    /// Verifies that a buffer of bytes contains a size prefixed
+    /// `Rewards` and returns it.
+    /// Note that verification is still experimental and may not
+    /// catch every error, or be maximally performant. For the
+    /// previous, unchecked, behavior use
+    /// `size_prefixed_root_as_rewards_unchecked`.
+    pub fn size_prefixed_root_as_rewards(
+        buf: &[u8],
+    ) -> Result<Rewards, flatbuffers::InvalidFlatbuffer> {
+        flatbuffers::size_prefixed_root::<Rewards>(buf)
+    }
+    #[inline]
+    /// Verifies, with the given options, that a buffer of bytes
+    /// contains a `Rewards` and returns it.
+    /// Note that verification is still experimental and may not
+    /// catch every error, or be maximally performant. For the
+    /// previous, unchecked, behavior use
+    /// `root_as_rewards_unchecked`.
+    pub fn root_as_rewards_with_opts<'b, 'o>(
+        opts: &'o flatbuffers::VerifierOptions,
+        buf: &'b [u8],
+    ) -> Result<Rewards<'b>, flatbuffers::InvalidFlatbuffer> {
+        flatbuffers::root_with_opts::<Rewards<'b>>(opts, buf)
+    }
+    #[inline]
+    /// Verifies, with the given verifier options, that a buffer of
+    /// bytes contains a size prefixed `Rewards` and returns
+    /// it. Note that verification is still experimental and may not
+    /// catch every error, or be maximally performant. For the
+    /// previous, unchecked, behavior use
+    /// `root_as_rewards_unchecked`.
+    pub fn size_prefixed_root_as_rewards_with_opts<'b, 'o>(
+        opts: &'o flatbuffers::VerifierOptions,
+        buf: &'b [u8],
+    ) -> Result<Rewards<'b>, flatbuffers::InvalidFlatbuffer> {
+        flatbuffers::size_prefixed_root_with_opts::<Rewards<'b>>(opts, buf)
+    }
+    #[inline]
+    /// Assumes, without verification, that a buffer of bytes contains a Rewards and returns it.
+    /// # Safety
+    /// Callers must trust the given bytes do indeed contain a valid `Rewards`.
+    pub unsafe fn root_as_rewards_unchecked(buf: &[u8]) -> Rewards {
+        flatbuffers::root_unchecked::<Rewards>(buf)
+    }
+    #[inline]
+    /// Assumes, without verification, that a buffer of bytes contains a size prefixed Rewards and returns it.
+    /// # Safety
+    /// Callers must trust the given bytes do indeed contain a valid size prefixed `Rewards`.
+    pub unsafe fn size_prefixed_root_as_rewards_unchecked(buf: &[u8]) -> Rewards {
+        flatbuffers::size_prefixed_root_unchecked::<Rewards>(buf)
+    }
+    #[inline]
+    pub fn finish_rewards_buffer<'a, 'b>(
+        fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+        root: flatbuffers::WIPOffset<Rewards<'a>>,
+    ) {
+        fbb.finish(root, None);
+    }
+
+    #[inline]
+    pub fn finish_size_prefixed_rewards_buffer<'a, 'b>(
+        fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+        root: flatbuffers::WIPOffset<Rewards<'a>>,
+    ) {
+        fbb.finish_size_prefixed(root, None);
+    }
+} // pub mod Rewards
@@ -0,0 +1,190 @@
+// automatically generated by the FlatBuffers compiler, do not modify
+
+// @generated
+
+extern crate flatbuffers;
+
+#[allow(unused_imports, dead_code)]
+pub mod slot_rooted {
+
+    use core::cmp::Ordering;
+    use core::mem;
+
+    extern crate flatbuffers;
+    use self::flatbuffers::{EndianScalar, Follow};
+
+    pub enum SlotRootedOffset {}
+    #[derive(Copy, Clone, PartialEq)]
+
+    pub struct SlotRooted<'a> {
+        pub _tab: flatbuffers::Table<'a>,
+    }
+
+    impl<'a> flatbuffers::Follow<'a> for SlotRooted<'a> {
+        type Inner = SlotRooted<'a>;
+        #[inline]
+        unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+            Self {
+                _tab: flatbuffers::Table::new(buf, loc),
+            }
+        }
+    }
+
+    impl<'a> SlotRooted<'a> {
+        pub const VT_SLOT: flatbuffers::VOffsetT = 4;
+
+        #[inline]
+        pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+            SlotRooted { _tab: table }
+        }
+        #[allow(unused_mut)]
+        pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+            _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+            args: &'args SlotRootedArgs,
+        ) -> flatbuffers::WIPOffset<SlotRooted<'bldr>> {
+            let mut builder = SlotRootedBuilder::new(_fbb);
+            builder.add_slot(args.slot);
+            builder.finish()
+        }
+
+        #[inline]
+        pub fn slot(&self) -> u64 {
+            // Safety:
+            // Created from valid Table for this object
+            // which contains a valid value in this slot
+            unsafe { self._tab.get::<u64>(SlotRooted::VT_SLOT, Some(0)).unwrap() }
+        }
+    }
+
+    impl flatbuffers::Verifiable for SlotRooted<'_> {
+        #[inline]
+        fn run_verifier(
+            v: &mut flatbuffers::Verifier,
+            pos: usize,
+        ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+            use self::flatbuffers::Verifiable;
+            v.visit_table(pos)?
+                .visit_field::<u64>("slot", Self::VT_SLOT, false)?
+                .finish();
+            Ok(())
+        }
+    }
+    pub struct SlotRootedArgs {
+        pub slot: u64,
+    }
+    impl<'a> Default for SlotRootedArgs {
+        #[inline]
+        fn default() -> Self {
+            SlotRootedArgs { slot: 0 }
+        }
+    }
+
+    pub struct SlotRootedBuilder<'a: 'b, 'b> {
+        fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+        start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+    }
+    impl<'a: 'b, 'b> SlotRootedBuilder<'a, 'b> {
+        #[inline]
+        pub fn add_slot(&mut self, slot: u64) {
+            self.fbb_.push_slot::<u64>(SlotRooted::VT_SLOT, slot, 0);
+        }
+        #[inline]
+        pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> SlotRootedBuilder<'a, 'b> {
+            let start = _fbb.start_table();
+            SlotRootedBuilder {
+                fbb_: _fbb,
+                start_: start,
+            }
+        }
+        #[inline]
+        pub fn finish(self) -> flatbuffers::WIPOffset<SlotRooted<'a>> {
+            let o = self.fbb_.end_table(self.start_);
+            flatbuffers::WIPOffset::new(o.value())
+        }
+    }
+
+    impl core::fmt::Debug for SlotRooted<'_> {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            let mut ds = f.debug_struct("SlotRooted");
+            ds.field("slot", &self.slot());
+            ds.finish()
+        }
+    }
+    #[inline]
+    /// Verifies that a buffer of bytes contains a `SlotRooted`
+    /// and returns it.
+    /// Note that verification is still experimental and may not
+    /// catch every error, or be maximally performant. For the
+    /// previous, unchecked, behavior use
+    /// `root_as_slot_rooted_unchecked`.
+    pub fn root_as_slot_rooted(buf: &[u8]) -> Result<SlotRooted, flatbuffers::InvalidFlatbuffer> {
+        flatbuffers::root::<SlotRooted>(buf)
+    }
+    #[inline]
+    /// Verifies that a buffer of bytes contains a size prefixed
+    /// `SlotRooted` and returns it.
+    /// Note that verification is still experimental and may not
+    /// catch every error, or be maximally performant. For the
+    /// previous, unchecked, behavior use
+    /// `size_prefixed_root_as_slot_rooted_unchecked`.
+    pub fn size_prefixed_root_as_slot_rooted(
+        buf: &[u8],
+    ) -> Result<SlotRooted, flatbuffers::InvalidFlatbuffer> {
+        flatbuffers::size_prefixed_root::<SlotRooted>(buf)
+    }
+    #[inline]
+    /// Verifies, with the given options, that a buffer of bytes
+    /// contains a `SlotRooted` and returns it.
+    /// Note that verification is still experimental and may not
+    /// catch every error, or be maximally performant. For the
+    /// previous, unchecked, behavior use
+    /// `root_as_slot_rooted_unchecked`.
+    pub fn root_as_slot_rooted_with_opts<'b, 'o>(
+        opts: &'o flatbuffers::VerifierOptions,
+        buf: &'b [u8],
+    ) -> Result<SlotRooted<'b>, flatbuffers::InvalidFlatbuffer> {
+        flatbuffers::root_with_opts::<SlotRooted<'b>>(opts, buf)
+    }
+    #[inline]
+    /// Verifies, with the given verifier options, that a buffer of
+    /// bytes contains a size prefixed `SlotRooted` and returns
+    /// it. Note that verification is still experimental and may not
+    /// catch every error, or be maximally performant. For the
+    /// previous, unchecked, behavior use
+    /// `root_as_slot_rooted_unchecked`.
+    pub fn size_prefixed_root_as_slot_rooted_with_opts<'b, 'o>(
+        opts: &'o flatbuffers::VerifierOptions,
+        buf: &'b [u8],
+    ) -> Result<SlotRooted<'b>, flatbuffers::InvalidFlatbuffer> {
+        flatbuffers::size_prefixed_root_with_opts::<SlotRooted<'b>>(opts, buf)
+    }
+    #[inline]
+    /// Assumes, without verification, that a buffer of bytes contains a SlotRooted and returns it.
+    /// # Safety
+    /// Callers must trust the given bytes do indeed contain a valid `SlotRooted`.
+    pub unsafe fn root_as_slot_rooted_unchecked(buf: &[u8]) -> SlotRooted {
+        flatbuffers::root_unchecked::<SlotRooted>(buf)
+    }
+    #[inline]
+    /// Assumes, without verification, that a buffer of bytes contains a size prefixed SlotRooted and returns it.
+    /// # Safety
+    /// Callers must trust the given bytes do indeed contain a valid size prefixed `SlotRooted`.
+    pub unsafe fn size_prefixed_root_as_slot_rooted_unchecked(buf: &[u8]) -> SlotRooted {
+        flatbuffers::size_prefixed_root_unchecked::<SlotRooted>(buf)
+    }
+    #[inline]
+    pub fn finish_slot_rooted_buffer<'a, 'b>(
+        fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+        root: flatbuffers::WIPOffset<SlotRooted<'a>>,
+    ) {
+        fbb.finish(root, None);
+    }
+
+    #[inline]
+    pub fn finish_size_prefixed_slot_rooted_buffer<'a, 'b>(
+        fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+        root: flatbuffers::WIPOffset<SlotRooted<'a>>,
+    ) {
+        fbb.finish_size_prefixed(root, None);
+    }
+} // pub mod SlotRooted
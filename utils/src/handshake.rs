@@ -0,0 +1,195 @@
+//! Connection handshake messages.
+//!
+//! Right after a subscriber connects, the sender publishes a `ServerHello` to it as the first
+//! message, encoded as JSON behind the `BYTE_PREFIX_HANDSHAKE` byte. The subscriber may answer
+//! with a `ClientHello`, framed the same way, to select what it receives.
+//!
+//! Both hellos carry the protocol version, so a consumer can tell a framing it doesn't
+//! understand instead of misreading the stream, and a sender can refuse a client asking for
+//! one it doesn't speak. The server hello is framed as `PROTOCOL_VERSION_V1`, everything
+//! after the client hello as the version it selected.
+//!
+//! Clients which set `ClientHello::acks` then acknowledge the batches they read, with
+//! `ack_message`s framed the same way, and the sender holds back their batches past the
+//! acknowledgement window instead of overwhelming them.
+use crate::compression::{CompressionCodec, SUPPORTED_COMPRESSION_CODECS};
+use crate::filters::SubscriptionFilters;
+use crate::flatbuffer::{
+    consts::{BYTE_PREFIX_ACK, BYTE_PREFIX_HANDSHAKE},
+    SCHEMA_HASH,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Batches prefixed with their size, of messages prefixed with theirs, both as 4 bytes
+/// little endian, behind a `ServerHello` sent first
+pub const PROTOCOL_VERSION_V1: u32 = 1;
+/// As `PROTOCOL_VERSION_V1`, with the CRC32 of the batch's payload after its size, as 4 bytes
+/// little endian, so a corrupted batch is detected rather than misread
+pub const PROTOCOL_VERSION_V2: u32 = 2;
+/// As `PROTOCOL_VERSION_V2`, with the sequence number of the batch on the connection after
+/// the checksum, as 8 bytes little endian starting at 0, so that the client can tell how many
+/// batches it missed, e.g. when its buffer on the sender was full
+pub const PROTOCOL_VERSION_V3: u32 = 3;
+pub const SUPPORTED_PROTOCOL_VERSIONS: [u32; 3] = [
+    PROTOCOL_VERSION_V1,
+    PROTOCOL_VERSION_V2,
+    PROTOCOL_VERSION_V3,
+];
+
+/// Original schemas, the default
+pub const SCHEMA_VERSION_V1: u32 = 1;
+/// Accounts published as `account_info_v2`, with the `AccountData` fields inlined
+pub const SCHEMA_VERSION_V2: u32 = 2;
+pub const SUPPORTED_SCHEMA_VERSIONS: [u32; 2] = [SCHEMA_VERSION_V1, SCHEMA_VERSION_V2];
+
+/// How the messages behind their prefix byte are serialized.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SerializationFormat {
+    /// The schemas of `flatbuffer`, selected with the schema version
+    #[default]
+    Flatbuffers,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ServerHello {
+    /// Version of the plugin crate
+    pub plugin_version: String,
+    /// Git commit the plugin was built from
+    pub git_commit: String,
+    /// Hash of the flatbuffer schemas, see `flatbuffer::SCHEMA_HASH`
+    pub schema_hash: String,
+    /// Protocol versions a client can select in its `ClientHello`, only
+    /// `PROTOCOL_VERSION_V1` for senders which predate them
+    #[serde(default = "default_protocol_versions")]
+    pub protocol_versions: Vec<u32>,
+    #[serde(default = "default_serialization_formats")]
+    pub serialization_formats: Vec<SerializationFormat>,
+    /// Schema versions a client can select in its `ClientHello`
+    #[serde(default)]
+    pub schema_versions: Vec<u32>,
+    /// Codecs a client can select in its `ClientHello`, if the sender compresses messages
+    #[serde(default)]
+    pub compression_codecs: Vec<CompressionCodec>,
+}
+
+impl ServerHello {
+    pub fn new(plugin_version: &str, git_commit: &str) -> Self {
+        ServerHello {
+            plugin_version: plugin_version.to_string(),
+            git_commit: git_commit.to_string(),
+            schema_hash: SCHEMA_HASH.to_string(),
+            protocol_versions: SUPPORTED_PROTOCOL_VERSIONS.to_vec(),
+            serialization_formats: vec![SerializationFormat::Flatbuffers],
+            schema_versions: SUPPORTED_SCHEMA_VERSIONS.to_vec(),
+            compression_codecs: SUPPORTED_COMPRESSION_CODECS.to_vec(),
+        }
+    }
+
+    pub fn to_message(&self) -> Vec<u8> {
+        to_json_message(BYTE_PREFIX_HANDSHAKE, self)
+    }
+
+    pub fn from_message(message: &[u8]) -> Option<Self> {
+        from_json_message(BYTE_PREFIX_HANDSHAKE, message)
+    }
+
+    /// True if the peer was built against the same flatbuffer schemas as this crate.
+    pub fn is_schema_compatible(&self) -> bool {
+        self.schema_hash == SCHEMA_HASH
+    }
+
+    /// The newest protocol version both the peer and this crate speak.
+    pub fn negotiate_protocol_version(&self) -> Option<u32> {
+        self.protocol_versions
+            .iter()
+            .filter(|version| SUPPORTED_PROTOCOL_VERSIONS.contains(version))
+            .max()
+            .copied()
+    }
+}
+
+fn default_protocol_versions() -> Vec<u32> {
+    vec![PROTOCOL_VERSION_V1]
+}
+
+fn default_serialization_formats() -> Vec<SerializationFormat> {
+    vec![SerializationFormat::Flatbuffers]
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct ClientHello {
+    /// Human-readable name of the subscriber, used in the sender's logs and metrics
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Free-form labels, e.g. `{"team": "indexer"}`
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+    /// One of `ServerHello::protocol_versions`, `PROTOCOL_VERSION_V1` if not set, the
+    /// sender closes the connection if it doesn't speak it
+    #[serde(default)]
+    pub protocol_version: Option<u32>,
+    /// One of `ServerHello::serialization_formats`, flatbuffers if not set
+    #[serde(default)]
+    pub serialization_format: Option<SerializationFormat>,
+    /// One of `ServerHello::schema_versions`, `SCHEMA_VERSION_V1` if not set
+    #[serde(default)]
+    pub schema_version: Option<u32>,
+    /// Codec of the messages the sender compresses, the sender's one if not set, e.g. lz4
+    /// to trade ratio for speed
+    #[serde(default)]
+    pub compression: Option<CompressionCodec>,
+    #[serde(default)]
+    pub filters: SubscriptionFilters,
+    /// Set if the client acknowledges the batches it reads, from `PROTOCOL_VERSION_V3` on
+    #[serde(default)]
+    pub acks: bool,
+    /// Consumer group of the client: each batch goes to a single client of the group instead
+    /// of every one, so a pool of workers shares the stream. Its clients are expected to use
+    /// the same filters
+    #[serde(default)]
+    pub group: Option<String>,
+}
+
+impl ClientHello {
+    pub fn to_message(&self) -> Vec<u8> {
+        to_json_message(BYTE_PREFIX_HANDSHAKE, self)
+    }
+
+    pub fn from_message(message: &[u8]) -> Option<Self> {
+        from_json_message(BYTE_PREFIX_HANDSHAKE, message)
+    }
+}
+
+/// Acknowledges the batches of the connection up to `sequence` included.
+pub fn ack_message(sequence: u64) -> Vec<u8> {
+    let mut message = vec![BYTE_PREFIX_ACK];
+    message.extend_from_slice(&sequence.to_le_bytes());
+
+    message
+}
+
+/// The sequence number acknowledged by an `ack_message`.
+pub fn parse_ack(message: &[u8]) -> Option<u64> {
+    match message.split_first() {
+        Some((&BYTE_PREFIX_ACK, sequence)) => Some(u64::from_le_bytes(sequence.try_into().ok()?)),
+        _ => None,
+    }
+}
+
+/// Encodes a control message as JSON behind its prefix byte.
+pub(crate) fn to_json_message<T: Serialize>(prefix: u8, value: &T) -> Vec<u8> {
+    let mut message = vec![prefix];
+    // serializing plain structs of strings and enums can't fail
+    message.extend(serde_json::to_vec(value).unwrap());
+
+    message
+}
+
+pub(crate) fn from_json_message<T: DeserializeOwned>(prefix: u8, message: &[u8]) -> Option<T> {
+    match message.split_first() {
+        Some((&p, data)) if p == prefix => serde_json::from_slice(data).ok(),
+        _ => None,
+    }
+}
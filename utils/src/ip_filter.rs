@@ -0,0 +1,52 @@
+//! Client IP ranges a sender accepts connections from, for hosts which can't put a firewall
+//! in front of the listener.
+use ipnet::IpNet;
+use serde::Deserialize;
+use std::net::IpAddr;
+
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct IpFilter {
+    /// Ranges clients must connect from, any address if empty
+    #[serde(default)]
+    pub allow: Vec<IpNet>,
+    /// Ranges clients are rejected from, even those in `allow`
+    #[serde(default)]
+    pub deny: Vec<IpNet>,
+}
+
+impl IpFilter {
+    /// Whether a client connecting from `ip` is accepted. IPv4 clients of a dual-stack
+    /// listener are matched by their IPv4 address.
+    pub fn permits(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+
+        !self.deny.iter().any(|net| net.contains(&ip))
+            && (self.allow.is_empty() || self.allow.iter().any(|net| net.contains(&ip)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_permits() {
+        let filter: IpFilter = serde_json::from_str(
+            r#"{"allow": ["10.0.0.0/8", "::1/128"], "deny": ["10.1.0.0/16"]}"#,
+        )
+        .unwrap();
+
+        assert!(filter.permits("10.2.3.4".parse().unwrap()));
+        assert!(filter.permits("::ffff:10.2.3.4".parse().unwrap()));
+        assert!(filter.permits("::1".parse().unwrap()));
+        assert!(!filter.permits("10.1.2.3".parse().unwrap()));
+        assert!(!filter.permits("192.168.0.1".parse().unwrap()));
+
+        let deny_only = IpFilter {
+            deny: filter.deny,
+            ..Default::default()
+        };
+        assert!(deny_only.permits("192.168.0.1".parse().unwrap()));
+        assert!(!deny_only.permits("10.1.2.3".parse().unwrap()));
+    }
+}
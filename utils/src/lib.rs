@@ -1,4 +1,28 @@
+pub mod chunk;
+pub mod compression;
+pub mod dead_letter;
+pub mod decoder;
+pub mod encoding;
+pub mod encryption;
 pub mod errors;
+pub mod event;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod filters;
 pub mod flatbuffer;
+pub mod handshake;
+pub mod ip_filter;
+pub mod message_type;
+#[cfg(feature = "node")]
+mod node;
+#[cfg(feature = "python")]
+mod python;
+pub mod quota;
 pub mod receiver;
 pub mod sender;
+pub mod spool;
+pub mod sync_receiver;
+#[cfg(any(feature = "python", feature = "ffi"))]
+mod threaded;
+pub mod tls;
+pub mod wal;
@@ -0,0 +1,56 @@
+use crate::flatbuffer::consts::{
+    BYTE_PREFIX_ACCOUNT, BYTE_PREFIX_ACCOUNT_V2, BYTE_PREFIX_BLOCK, BYTE_PREFIX_METADATA,
+    BYTE_PREFIX_REWARDS, BYTE_PREFIX_SLOT, BYTE_PREFIX_SLOT_ROOTED, BYTE_PREFIX_TX,
+    COMPRESSED_FLAG,
+};
+use serde::{Deserialize, Serialize};
+
+/// Types of the data messages, as named in configs and client hellos.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageType {
+    Account,
+    Slot,
+    Transaction,
+    Block,
+    Metadata,
+    Rewards,
+}
+
+impl MessageType {
+    pub fn prefix(&self) -> u8 {
+        match self {
+            MessageType::Account => BYTE_PREFIX_ACCOUNT,
+            MessageType::Slot => BYTE_PREFIX_SLOT,
+            MessageType::Transaction => BYTE_PREFIX_TX,
+            MessageType::Block => BYTE_PREFIX_BLOCK,
+            MessageType::Metadata => BYTE_PREFIX_METADATA,
+            MessageType::Rewards => BYTE_PREFIX_REWARDS,
+        }
+    }
+
+    /// Name of the type, as in configs.
+    pub fn name(&self) -> &'static str {
+        match self {
+            MessageType::Account => "account",
+            MessageType::Slot => "slot",
+            MessageType::Transaction => "transaction",
+            MessageType::Block => "block",
+            MessageType::Metadata => "metadata",
+            MessageType::Rewards => "rewards",
+        }
+    }
+
+    /// Type of a message from its prefix byte, compressed or not.
+    pub fn from_prefix(prefix: u8) -> Option<Self> {
+        match prefix & !COMPRESSED_FLAG {
+            BYTE_PREFIX_ACCOUNT | BYTE_PREFIX_ACCOUNT_V2 => Some(MessageType::Account),
+            BYTE_PREFIX_SLOT | BYTE_PREFIX_SLOT_ROOTED => Some(MessageType::Slot),
+            BYTE_PREFIX_TX => Some(MessageType::Transaction),
+            BYTE_PREFIX_BLOCK => Some(MessageType::Block),
+            BYTE_PREFIX_METADATA => Some(MessageType::Metadata),
+            BYTE_PREFIX_REWARDS => Some(MessageType::Rewards),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,291 @@
+//! Node.js bindings, built with `napi build --features node` from `node/`:
+//!
+//! ```js
+//! const { TcpReceiver } = require('solana-geyser-zmq')
+//!
+//! for await (const event of new TcpReceiver('127.0.0.1:2000')) {
+//!   if (event.type === 'account') console.log(event.account.pubkey, event.account.lamports)
+//! }
+//! ```
+//!
+//! u64 values are JS numbers, as in web3.js, so they are exact up to 2^53.
+use crate::event::{self, decode_event, DecodeError, SlotStatus};
+use crate::receiver::{Callback, TcpReceiver as Receiver};
+use napi::{bindgen_prelude::Buffer, Error, Result, Status};
+use napi_derive::napi;
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+use tokio::sync::{mpsc, oneshot, Mutex};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_RECONNECT_INTERVAL_MS: u32 = 1000;
+const DEFAULT_QUEUE_SIZE: u32 = 10000;
+
+#[napi(object)]
+pub struct Account {
+    pub pubkey: String,
+    pub owner: String,
+    pub lamports: f64,
+    pub rent_epoch: f64,
+    pub executable: bool,
+    pub write_version: f64,
+    pub data: Buffer,
+    pub txn_signature: Option<String>,
+}
+
+#[napi(object)]
+pub struct SlotUpdate {
+    /// "processed", "confirmed" or "rooted"
+    pub status: String,
+    pub parent: Option<f64>,
+}
+
+#[napi(object)]
+pub struct Transaction {
+    pub signature: String,
+    pub index: Option<f64>,
+    pub is_vote: bool,
+    pub fee: f64,
+    pub compute_units_consumed: Option<f64>,
+    /// Name of the error, if the transaction failed
+    pub err: Option<String>,
+    pub account_keys: Vec<String>,
+    pub log_messages: Vec<String>,
+    pub memo: Option<String>,
+}
+
+#[napi(object)]
+pub struct Block {
+    pub blockhash: String,
+    pub parent_slot: Option<f64>,
+    pub parent_blockhash: Option<String>,
+    pub block_time: f64,
+    pub block_height: f64,
+    pub executed_transaction_count: Option<f64>,
+}
+
+#[napi(object)]
+pub struct Reward {
+    pub pubkey: String,
+    pub lamports: f64,
+    pub post_balance: f64,
+    pub reward_type: Option<String>,
+    pub commission: Option<u32>,
+}
+
+#[napi(object)]
+pub struct Rewards {
+    pub block_height: Option<f64>,
+    pub block_time: Option<f64>,
+    pub rewards: Vec<Reward>,
+}
+
+#[napi(object)]
+pub struct Metadata {
+    pub send_errors: f64,
+    pub version: Option<String>,
+    pub git_commit: Option<String>,
+    pub schema_hash: Option<String>,
+}
+
+/// A decoded event, its fields under the property named after its type.
+#[napi(object)]
+pub struct Event {
+    /// "account", "slot", "transaction", "block", "rewards" or "metadata"
+    #[napi(js_name = "type")]
+    pub kind: String,
+    pub slot: f64,
+    pub account: Option<Account>,
+    pub slot_update: Option<SlotUpdate>,
+    pub transaction: Option<Transaction>,
+    pub block: Option<Block>,
+    pub rewards: Option<Rewards>,
+    pub metadata: Option<Metadata>,
+}
+
+impl Event {
+    fn new(kind: &str, slot: u64) -> Self {
+        Event {
+            kind: kind.to_string(),
+            slot: slot as f64,
+            account: None,
+            slot_update: None,
+            transaction: None,
+            block: None,
+            rewards: None,
+            metadata: None,
+        }
+    }
+}
+
+impl From<event::Event> for Event {
+    fn from(event: event::Event) -> Self {
+        match event {
+            event::Event::Account(account) => Event {
+                account: Some(Account {
+                    pubkey: account.pubkey,
+                    owner: account.owner,
+                    lamports: account.lamports as f64,
+                    rent_epoch: account.rent_epoch as f64,
+                    executable: account.executable,
+                    write_version: account.write_version as f64,
+                    data: account.data.into(),
+                    txn_signature: account.txn_signature,
+                }),
+                ..Event::new("account", account.slot)
+            },
+            event::Event::Slot(slot) => Event {
+                slot_update: Some(SlotUpdate {
+                    status: match slot.status {
+                        SlotStatus::Processed => "processed",
+                        SlotStatus::Confirmed => "confirmed",
+                        SlotStatus::Rooted => "rooted",
+                    }
+                    .to_string(),
+                    parent: slot.parent.map(|parent| parent as f64),
+                }),
+                ..Event::new("slot", slot.slot)
+            },
+            event::Event::Transaction(tx) => Event {
+                transaction: Some(Transaction {
+                    signature: tx.signature,
+                    index: tx.index.map(|index| index as f64),
+                    is_vote: tx.is_vote,
+                    fee: tx.fee as f64,
+                    compute_units_consumed: tx.compute_units_consumed.map(|cu| cu as f64),
+                    err: tx.err,
+                    account_keys: tx.account_keys,
+                    log_messages: tx.log_messages,
+                    memo: tx.memo,
+                }),
+                ..Event::new("transaction", tx.slot)
+            },
+            event::Event::Block(block) => Event {
+                block: Some(Block {
+                    blockhash: block.blockhash,
+                    parent_slot: block.parent_slot.map(|slot| slot as f64),
+                    parent_blockhash: block.parent_blockhash,
+                    block_time: block.block_time as f64,
+                    block_height: block.block_height as f64,
+                    executed_transaction_count: block
+                        .executed_transaction_count
+                        .map(|count| count as f64),
+                }),
+                ..Event::new("block", block.slot)
+            },
+            event::Event::Rewards(rewards) => Event {
+                rewards: Some(Rewards {
+                    block_height: rewards.block_height.map(|height| height as f64),
+                    block_time: rewards.block_time.map(|time| time as f64),
+                    rewards: rewards
+                        .rewards
+                        .into_iter()
+                        .map(|reward| Reward {
+                            pubkey: reward.pubkey,
+                            lamports: reward.lamports as f64,
+                            post_balance: reward.post_balance as f64,
+                            reward_type: reward.reward_type,
+                            commission: reward.commission.map(u32::from),
+                        })
+                        .collect(),
+                }),
+                ..Event::new("rewards", rewards.slot)
+            },
+            event::Event::Metadata(metadata) => Event {
+                metadata: Some(Metadata {
+                    send_errors: metadata.send_errors as f64,
+                    version: metadata.version,
+                    git_commit: metadata.git_commit,
+                    schema_hash: metadata.schema_hash,
+                }),
+                ..Event::new("metadata", 0)
+            },
+        }
+    }
+}
+
+fn decode_error(e: DecodeError) -> Error {
+    Error::new(Status::InvalidArg, e.to_string())
+}
+
+/// Decodes a message as received from the plugin, throwing if it can't be.
+#[napi]
+// only exported to JS, which test builds leave out
+#[cfg_attr(test, allow(dead_code))]
+pub fn decode(message: Buffer) -> Result<Event> {
+    decode_event(&message)
+        .map(Event::from)
+        .map_err(decode_error)
+}
+
+/// Async iterator over the events of a plugin, reconnecting when the connection drops.
+/// Messages without typed decoding, such as diagnostics, are skipped.
+#[napi]
+pub struct TcpReceiver {
+    messages: Arc<Mutex<mpsc::Receiver<Vec<u8>>>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+#[napi]
+impl TcpReceiver {
+    #[napi(constructor)]
+    pub fn new(
+        addr: String,
+        reconnect_interval_ms: Option<u32>,
+        queue_size: Option<u32>,
+    ) -> Result<Self> {
+        let addr = addr
+            .parse::<SocketAddr>()
+            .map_err(|e| Error::new(Status::InvalidArg, e.to_string()))?;
+
+        // bounded, so reading from the socket waits for JS to catch up
+        let (sender, messages) =
+            mpsc::channel(queue_size.unwrap_or(DEFAULT_QUEUE_SIZE).max(1) as usize);
+        let callback: Callback = Box::new(move |message| {
+            let sender = sender.clone();
+            Box::pin(async move {
+                let _ = sender.send(message).await;
+            })
+        });
+        let receiver = Receiver::new(
+            callback,
+            CONNECT_TIMEOUT,
+            Duration::from_millis(
+                reconnect_interval_ms.unwrap_or(DEFAULT_RECONNECT_INTERVAL_MS) as u64,
+            ),
+        );
+
+        let (shutdown, stopped) = oneshot::channel::<()>();
+        napi::bindgen_prelude::spawn(async move {
+            tokio::select! {
+                _ = receiver.connect(addr) => {}
+                _ = stopped => {}
+            }
+        });
+
+        Ok(TcpReceiver {
+            messages: Arc::new(Mutex::new(messages)),
+            shutdown: Some(shutdown),
+        })
+    }
+
+    /// The next event, null once closed.
+    #[napi]
+    pub async fn next(&self) -> Result<Option<Event>> {
+        let mut messages = self.messages.lock().await;
+        while let Some(message) = messages.recv().await {
+            match decode_event(&message) {
+                Ok(event) => return Ok(Some(event.into())),
+                Err(DecodeError::UnsupportedPrefix(_)) => continue,
+                Err(e) => return Err(decode_error(e)),
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Disconnects, `next` returning null once the queued events are consumed.
+    #[napi]
+    pub fn close(&mut self) {
+        self.shutdown.take();
+    }
+}
@@ -0,0 +1,188 @@
+//! Python bindings for the receiver and the typed decoding, built as the
+//! `solana_geyser_zmq` module with `maturin build --features python`:
+//!
+//! ```python
+//! import solana_geyser_zmq
+//!
+//! for event in solana_geyser_zmq.TcpReceiver("127.0.0.1:2000"):
+//!     if event["type"] == "account":
+//!         print(event["pubkey"], event["lamports"])
+//! ```
+// raised on the code generated by the pyo3 macros
+#![allow(clippy::useless_conversion)]
+
+use crate::event::{decode_event, DecodeError, Event, Reward, SlotStatus};
+use crate::threaded::ThreadedReceiver;
+use pyo3::{
+    exceptions::PyValueError,
+    prelude::*,
+    types::{PyBytes, PyDict},
+};
+use std::{net::SocketAddr, sync::mpsc::RecvTimeoutError, time::Duration};
+
+// how often a blocked iteration checks for KeyboardInterrupt
+const SIGNALS_INTERVAL: Duration = Duration::from_millis(100);
+
+fn decode_error(e: DecodeError) -> PyErr {
+    PyValueError::new_err(e.to_string())
+}
+
+fn reward_to_dict<'py>(py: Python<'py>, reward: Reward) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new_bound(py);
+    dict.set_item("pubkey", reward.pubkey)?;
+    dict.set_item("lamports", reward.lamports)?;
+    dict.set_item("post_balance", reward.post_balance)?;
+    dict.set_item("reward_type", reward.reward_type)?;
+    dict.set_item("commission", reward.commission)?;
+
+    Ok(dict)
+}
+
+/// The event as a dict, its kind under "type" and its fields named as in the Rust structs.
+fn event_to_dict(py: Python<'_>, event: Event) -> PyResult<Bound<'_, PyDict>> {
+    let dict = PyDict::new_bound(py);
+    match event {
+        Event::Account(account) => {
+            dict.set_item("type", "account")?;
+            dict.set_item("pubkey", account.pubkey)?;
+            dict.set_item("owner", account.owner)?;
+            dict.set_item("slot", account.slot)?;
+            dict.set_item("lamports", account.lamports)?;
+            dict.set_item("rent_epoch", account.rent_epoch)?;
+            dict.set_item("executable", account.executable)?;
+            dict.set_item("write_version", account.write_version)?;
+            dict.set_item("data", PyBytes::new_bound(py, &account.data))?;
+            dict.set_item("txn_signature", account.txn_signature)?;
+        }
+        Event::Slot(slot) => {
+            dict.set_item("type", "slot")?;
+            dict.set_item("slot", slot.slot)?;
+            dict.set_item(
+                "status",
+                match slot.status {
+                    SlotStatus::Processed => "processed",
+                    SlotStatus::Confirmed => "confirmed",
+                    SlotStatus::Rooted => "rooted",
+                },
+            )?;
+            dict.set_item("parent", slot.parent)?;
+        }
+        Event::Transaction(tx) => {
+            dict.set_item("type", "transaction")?;
+            dict.set_item("signature", tx.signature)?;
+            dict.set_item("slot", tx.slot)?;
+            dict.set_item("index", tx.index)?;
+            dict.set_item("is_vote", tx.is_vote)?;
+            dict.set_item("fee", tx.fee)?;
+            dict.set_item("compute_units_consumed", tx.compute_units_consumed)?;
+            dict.set_item("err", tx.err)?;
+            dict.set_item("account_keys", tx.account_keys)?;
+            dict.set_item("log_messages", tx.log_messages)?;
+            dict.set_item("memo", tx.memo)?;
+        }
+        Event::Block(block) => {
+            dict.set_item("type", "block")?;
+            dict.set_item("slot", block.slot)?;
+            dict.set_item("blockhash", block.blockhash)?;
+            dict.set_item("parent_slot", block.parent_slot)?;
+            dict.set_item("parent_blockhash", block.parent_blockhash)?;
+            dict.set_item("block_time", block.block_time)?;
+            dict.set_item("block_height", block.block_height)?;
+            dict.set_item(
+                "executed_transaction_count",
+                block.executed_transaction_count,
+            )?;
+            dict.set_item(
+                "rewards",
+                block
+                    .rewards
+                    .into_iter()
+                    .map(|reward| reward_to_dict(py, reward))
+                    .collect::<PyResult<Vec<_>>>()?,
+            )?;
+        }
+        Event::Rewards(rewards) => {
+            dict.set_item("type", "rewards")?;
+            dict.set_item("slot", rewards.slot)?;
+            dict.set_item("block_height", rewards.block_height)?;
+            dict.set_item("block_time", rewards.block_time)?;
+            dict.set_item(
+                "rewards",
+                rewards
+                    .rewards
+                    .into_iter()
+                    .map(|reward| reward_to_dict(py, reward))
+                    .collect::<PyResult<Vec<_>>>()?,
+            )?;
+        }
+        Event::Metadata(metadata) => {
+            dict.set_item("type", "metadata")?;
+            dict.set_item("send_errors", metadata.send_errors)?;
+            dict.set_item("version", metadata.version)?;
+            dict.set_item("git_commit", metadata.git_commit)?;
+            dict.set_item("schema_hash", metadata.schema_hash)?;
+        }
+    }
+
+    Ok(dict)
+}
+
+/// Decodes a message as received from the plugin, raising ValueError if it can't be.
+#[pyfunction]
+fn decode<'py>(py: Python<'py>, message: &[u8]) -> PyResult<Bound<'py, PyDict>> {
+    event_to_dict(py, decode_event(message).map_err(decode_error)?)
+}
+
+/// Iterator over the events of a plugin, reconnecting when the connection drops. Messages
+/// without typed decoding, such as diagnostics, are skipped.
+#[pyclass(name = "TcpReceiver")]
+struct PyTcpReceiver(ThreadedReceiver);
+
+#[pymethods]
+impl PyTcpReceiver {
+    #[new]
+    #[pyo3(signature = (addr, reconnect_interval_secs = 1.0, queue_size = 10000))]
+    fn new(addr: &str, reconnect_interval_secs: f64, queue_size: usize) -> PyResult<Self> {
+        let addr = addr
+            .parse::<SocketAddr>()
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+
+        Ok(PyTcpReceiver(ThreadedReceiver::spawn(
+            addr,
+            Duration::from_secs_f64(reconnect_interval_secs),
+            queue_size,
+        )?))
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__<'py>(&mut self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyDict>>> {
+        loop {
+            let receiver = &mut self.0;
+            let message = match py.allow_threads(move || receiver.recv_timeout(SIGNALS_INTERVAL)) {
+                Ok(message) => message,
+                Err(RecvTimeoutError::Timeout) => {
+                    py.check_signals()?;
+                    continue;
+                }
+                Err(RecvTimeoutError::Disconnected) => return Ok(None),
+            };
+
+            match decode_event(&message) {
+                Ok(event) => return event_to_dict(py, event).map(Some),
+                Err(DecodeError::UnsupportedPrefix(_)) => continue,
+                Err(e) => return Err(decode_error(e)),
+            }
+        }
+    }
+}
+
+#[pymodule]
+fn solana_geyser_zmq(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(decode, module)?)?;
+    module.add_class::<PyTcpReceiver>()?;
+
+    Ok(())
+}
@@ -0,0 +1,86 @@
+//! Bandwidth and message rate quotas of subscribers, by name or by default.
+use serde::Deserialize;
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+const QUOTA_WINDOW: Duration = Duration::from_secs(1);
+
+/// What happens to a subscriber exceeding its quota, until the next one second window.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaAction {
+    /// Batches are dropped for the subscriber
+    Throttle,
+    /// Account updates are removed from the subscriber's batches, other messages still pass
+    DropAccounts,
+    /// The subscriber is disconnected
+    Disconnect,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubscriberQuota {
+    /// Bytes queued per second, unlimited if not set
+    #[serde(default)]
+    pub bytes_per_sec: Option<u64>,
+    /// Messages queued per second, unlimited if not set
+    #[serde(default)]
+    pub messages_per_sec: Option<u64>,
+    pub action: QuotaAction,
+}
+
+/// Bytes and messages queued to a subscriber in the current window.
+pub(crate) struct QuotaUsage {
+    quota: SubscriberQuota,
+    window: Mutex<(Instant, u64, u64)>,
+}
+
+impl QuotaUsage {
+    pub(crate) fn new(quota: SubscriberQuota) -> Self {
+        QuotaUsage {
+            quota,
+            window: Mutex::new((Instant::now(), 0, 0)),
+        }
+    }
+
+    /// The action to take if the quota of the current window is used up.
+    pub(crate) fn exceeded(&self) -> Option<QuotaAction> {
+        let mut window = self.window.lock().unwrap_or_else(|e| e.into_inner());
+        if window.0.elapsed() >= QUOTA_WINDOW {
+            *window = (Instant::now(), 0, 0);
+        }
+
+        let exceeded = self.quota.bytes_per_sec.is_some_and(|max| window.1 >= max)
+            || self
+                .quota
+                .messages_per_sec
+                .is_some_and(|max| window.2 >= max);
+        exceeded.then_some(self.quota.action)
+    }
+
+    pub(crate) fn add(&self, bytes: usize, messages: usize) {
+        let mut window = self.window.lock().unwrap_or_else(|e| e.into_inner());
+        window.1 += bytes as u64;
+        window.2 += messages as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_rate_quota() {
+        let usage = QuotaUsage::new(SubscriberQuota {
+            bytes_per_sec: None,
+            messages_per_sec: Some(10),
+            action: QuotaAction::Throttle,
+        });
+
+        usage.add(1024 * 1024, 9);
+        assert_eq!(usage.exceeded(), None);
+        usage.add(1, 1);
+        assert_eq!(usage.exceeded(), Some(QuotaAction::Throttle));
+    }
+}
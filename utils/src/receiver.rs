@@ -1,26 +1,280 @@
-use log::{debug, error, info};
+use futures_util::stream::{self, Stream};
+use log::{debug, error, info, warn};
+use std::collections::{HashMap, VecDeque};
 use std::convert::TryInto;
+use std::fmt;
 use std::future::Future;
 use std::io;
 use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
-use std::time::{Duration, Instant};
-use tokio::io::AsyncReadExt;
-use tokio::net::TcpStream;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpStream, UnixStream};
+use tokio::sync::{mpsc, Notify};
 use tokio::time::sleep;
+use tokio_rustls::rustls::{ClientConfig, ServerName};
+use tokio_rustls::TlsConnector;
+use uuid::Uuid;
+
+use crate::chunk::{chunked_prefix, Reassembler};
+use crate::compression::{decompress, is_compressed};
+use crate::encryption::PayloadCipher;
+use crate::event::{decode_event, message_slot, DecodeError, Event};
+use crate::flatbuffer::block_info_generated::block_info::root_as_block_info;
+use crate::flatbuffer::consts::{BYTE_PREFIX_BLOCK, COMPRESSED_FLAG};
+use crate::handshake::{
+    ack_message, ClientHello, ServerHello, PROTOCOL_VERSION_V1, PROTOCOL_VERSION_V2,
+    PROTOCOL_VERSION_V3,
+};
+use crate::message_type::MessageType;
+use crate::sender::TcpBuffer;
+#[cfg(feature = "quic")]
+use crate::sender::{QUIC_ALPN, QUIC_KEEP_ALIVE};
+use crate::spool::{DiskSpool, DEFAULT_SEGMENT_MAX_BYTES};
 
 const HEADER_BYTE_SIZE: usize = 4;
+const CHECKSUM_BYTE_SIZE: usize = 4;
+const SEQUENCE_BYTE_SIZE: usize = 8;
+/// Larger batches, or QUIC messages, are taken for corrupted headers rather than allocated.
+pub const DEFAULT_MAX_BATCH_BYTES: usize = 256 * 1024 * 1024;
+
+const STREAM_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const STREAM_RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+// events decoded ahead of the consumer of a `TcpReceiver::stream`
+const STREAM_QUEUE_SIZE: usize = 10000;
 
 pub type Callback = Box<dyn Fn(Vec<u8>) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>;
+/// Called with the error of the last attempt once the receiver gives up reconnecting.
+pub type GiveUpHook = Box<dyn Fn(&io::Error) + Send + Sync>;
+/// Called with the first and last sequence numbers of the batches the sender dropped for the
+/// receiver, and the slot of the first message received after them, if it has one.
+pub type GapHook = Box<dyn Fn(u64, u64, Option<u64>) + Send + Sync>;
+
+struct SpoolState {
+    memory: VecDeque<Vec<u8>>,
+    disk: DiskSpool,
+}
+
+/// Messages received and not handed to the callback yet. They are kept in memory up to
+/// `memory_messages`, and go to disk past that until the callback catches up.
+struct Spool {
+    state: Mutex<SpoolState>,
+    memory_messages: usize,
+    notify: Notify,
+}
+
+impl Spool {
+    fn push(&self, message: Vec<u8>) -> io::Result<()> {
+        {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            // once on disk, messages keep going there so they are replayed in order
+            if state.disk.is_empty() && state.memory.len() < self.memory_messages {
+                state.memory.push_back(message);
+            } else {
+                state.disk.push(&message)?;
+            }
+        }
+
+        self.notify.notify_one();
+
+        Ok(())
+    }
+
+    fn pop(&self) -> io::Result<Option<Vec<u8>>> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        match state.memory.pop_front() {
+            Some(message) => Ok(Some(message)),
+            None => state.disk.pop(),
+        }
+    }
+}
+
+/// Keeps 1 in N messages of the sampled types.
+struct Sampler {
+    // N and the messages seen so far, by type
+    rates: HashMap<MessageType, (u64, AtomicU64)>,
+}
+
+impl Sampler {
+    /// Messages of other types, control messages included, are always kept.
+    fn keep(&self, message: &[u8]) -> bool {
+        let rate = message
+            .first()
+            .and_then(|prefix| MessageType::from_prefix(*prefix))
+            .and_then(|message_type| self.rates.get(&message_type));
+
+        match rate {
+            Some((n, seen)) => seen.fetch_add(1, Ordering::Relaxed) % n == 0,
+            None => true,
+        }
+    }
+}
+
+enum Connector {
+    // TCP, or Unix socket if connecting to a path
+    Plain,
+    Tls(TlsConnector, ServerName),
+    // the endpoint and the name the sender's certificate is checked for
+    #[cfg(feature = "quic")]
+    Quic(quinn::Endpoint, String),
+}
+
+/// Where a sender listens, see `TcpSender::bind` and `TcpSender::bind_unix`.
+enum Upstream {
+    Addr(SocketAddr),
+    Path(PathBuf),
+}
+
+impl fmt::Display for Upstream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Upstream::Addr(addr) => addr.fmt(f),
+            Upstream::Path(path) => path.display().fmt(f),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Counters {
+    bytes_read: AtomicU64,
+    messages: AtomicU64,
+    decode_errors: AtomicU64,
+    reconnects: AtomicU64,
+    missed_batches: AtomicU64,
+    gaps: AtomicU64,
+    corrupted_batches: AtomicU64,
+    // i64::MIN until a block with a block time is received
+    block_lag_ms: AtomicI64,
+}
+
+/// Handle on the counters of a `TcpReceiver`, which keeps updating them once the receiver
+/// is moved, e.g. to the task running it, for consumers to export their ingestion health.
+#[derive(Debug, Clone)]
+pub struct ReceiverMetrics(Arc<Counters>);
+
+impl Default for ReceiverMetrics {
+    fn default() -> Self {
+        ReceiverMetrics(Arc::new(Counters {
+            bytes_read: AtomicU64::new(0),
+            messages: AtomicU64::new(0),
+            decode_errors: AtomicU64::new(0),
+            reconnects: AtomicU64::new(0),
+            missed_batches: AtomicU64::new(0),
+            gaps: AtomicU64::new(0),
+            corrupted_batches: AtomicU64::new(0),
+            block_lag_ms: AtomicI64::new(i64::MIN),
+        }))
+    }
+}
+
+impl ReceiverMetrics {
+    /// Bytes of the batches, or QUIC messages, read from the sender, headers aside.
+    pub fn bytes_read(&self) -> u64 {
+        self.0.bytes_read.load(Ordering::Relaxed)
+    }
+
+    /// Messages read, chunked ones once put back together, sampled out ones included.
+    pub fn messages(&self) -> u64 {
+        self.0.messages.load(Ordering::Relaxed)
+    }
+
+    /// Batches which couldn't be read, e.g. failing their checksum or to decrypt, each
+    /// closing its connection.
+    pub fn decode_errors(&self) -> u64 {
+        self.0.decode_errors.load(Ordering::Relaxed)
+    }
+
+    /// Connection attempts after the first one.
+    pub fn reconnects(&self) -> u64 {
+        self.0.reconnects.load(Ordering::Relaxed)
+    }
+
+    /// See `TcpReceiver::missed_batches`.
+    pub fn missed_batches(&self) -> u64 {
+        self.0.missed_batches.load(Ordering::Relaxed)
+    }
+
+    /// See `TcpReceiver::gaps`.
+    pub fn gaps(&self) -> u64 {
+        self.0.gaps.load(Ordering::Relaxed)
+    }
+
+    /// Batches whose messages' sizes ran past their end, the rest of which was dropped, see
+    /// `TcpReceiver::with_resync`.
+    pub fn corrupted_batches(&self) -> u64 {
+        self.0.corrupted_batches.load(Ordering::Relaxed)
+    }
+
+    /// How long after its block time the last block was received, None until one is. Block
+    /// times are in seconds, so is the precision.
+    pub fn block_lag(&self) -> Option<Duration> {
+        match self.0.block_lag_ms.load(Ordering::Relaxed) {
+            i64::MIN => None,
+            lag_ms => Some(Duration::from_millis(lag_ms.max(0) as u64)),
+        }
+    }
+
+    fn record_block_lag(&self, message: &[u8]) {
+        if message.first().map(|prefix| prefix & !COMPRESSED_FLAG) != Some(BYTE_PREFIX_BLOCK) {
+            return;
+        }
+
+        let Some(block_time) = decompress(message).ok().and_then(|message| {
+            let block = root_as_block_info(message.get(1..)?).ok()?;
+            Some(block.block_time()).filter(|block_time| *block_time > 0)
+        }) else {
+            return;
+        };
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as i64;
+
+        self.0
+            .block_lag_ms
+            .store(now_ms - block_time * 1000, Ordering::Relaxed);
+    }
+}
 
 pub struct TcpReceiver {
     callback: Callback,
-    #[allow(unused)]
+    // covers the TLS handshake too, but not the server hello
     connect_timeout: Duration,
     reconnect_interval: Duration,
+    // the reconnect interval doubles after each failed attempt up to it if set
+    max_backoff: Option<Duration>,
+    // failed attempts in a row after which `connect` returns, retrying forever if not set
+    max_retries: Option<u32>,
+    on_give_up: Option<GiveUpHook>,
+    // how often the first address is checked for while failed over, see `connect_any`
+    failback_interval: Option<Duration>,
+    // set once the current connection read its first batch, see `connect_loop`
+    connected: AtomicBool,
+    // sent in answer to the server hello, to select what the sender publishes to this receiver
+    client_hello: Option<ClientHello>,
+    // decouples reading from the network from the callback if set
+    spool: Option<Spool>,
+    sampler: Option<Sampler>,
+    metrics: ReceiverMetrics,
+    on_gap: Option<GapHook>,
+    // puts back together the messages the sender split into chunks
+    reassembler: Mutex<Reassembler>,
+    // decrypts the batches if set, see `encryption`
+    cipher: Option<PayloadCipher>,
+    // hands the compressed messages decompressed if set
+    decompress: bool,
+    max_batch_bytes: usize,
+    // drops the rest of a batch whose messages' framing is corrupted, instead of closing the
+    // connection, if set
+    resync: bool,
 }
 
 impl TcpReceiver {
+    /// Hands `callback` the data messages of the sender, chunked ones put back together. Its
+    /// `ServerHello` is answered and consumed by the receiver, and never handed on.
     pub fn new(
         callback: Callback,
         connect_timeout: Duration,
@@ -30,68 +284,1245 @@ impl TcpReceiver {
             callback,
             connect_timeout,
             reconnect_interval,
+            max_backoff: None,
+            max_retries: None,
+            on_give_up: None,
+            failback_interval: None,
+            connected: AtomicBool::new(false),
+            client_hello: None,
+            spool: None,
+            sampler: None,
+            metrics: ReceiverMetrics::default(),
+            on_gap: None,
+            reassembler: Mutex::new(Reassembler::default()),
+            cipher: None,
+            decompress: false,
+            max_batch_bytes: DEFAULT_MAX_BATCH_BYTES,
+            resync: false,
         }
     }
 
+    /// Answers the sender's `ServerHello` with `client_hello`, instead of a default one
+    /// selecting the newest protocol version both sides speak. Senders which don't send a
+    /// server hello get no client hello.
+    pub fn with_client_hello(mut self, client_hello: ClientHello) -> Self {
+        self.client_hello = Some(client_hello);
+
+        self
+    }
+
+    /// Doubles the wait before reconnecting after each failed attempt, from the reconnect
+    /// interval up to `max_interval`, each wait being drawn between half and all of it, so
+    /// the receivers disconnected together, e.g. by a validator restart, don't all come back
+    /// at once. The wait starts over once a connection goes through.
+    pub fn with_backoff(mut self, max_interval: Duration) -> Self {
+        self.max_backoff = Some(max_interval);
+
+        self
+    }
+
+    /// Gives up after `max_retries` reconnection attempts failing in a row, `connect`
+    /// returning the error of the last one after calling `on_give_up` with it.
+    pub fn with_max_retries(
+        mut self,
+        max_retries: u32,
+        on_give_up: impl Fn(&io::Error) + Send + Sync + 'static,
+    ) -> Self {
+        self.max_retries = Some(max_retries);
+        self.on_give_up = Some(Box::new(on_give_up));
+
+        self
+    }
+
+    /// While failed over to another address than the first one of `connect_any`, checks
+    /// every `interval` whether the first one accepts connections again, and moves back to
+    /// it if so. Messages published meanwhile by either sender may be missed or received
+    /// twice, as when failing over.
+    pub fn with_failback(mut self, interval: Duration) -> Self {
+        self.failback_interval = Some(interval);
+
+        self
+    }
+
+    /// Calls `on_gap` whenever the sequence numbers of the batches skip some, i.e. the sender
+    /// dropped them for this receiver. Only detected with senders speaking
+    /// `PROTOCOL_VERSION_V3`, within a connection, as sequence numbers start over on every
+    /// one.
+    pub fn with_on_gap(
+        mut self,
+        on_gap: impl Fn(u64, u64, Option<u64>) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_gap = Some(Box::new(on_gap));
+
+        self
+    }
+
+    /// Hands the messages the sender compressed to the callback decompressed, as published,
+    /// so it doesn't have to handle the codecs. A message which can't be decompressed closes
+    /// the connection.
+    pub fn with_decompression(mut self) -> Self {
+        self.decompress = true;
+
+        self
+    }
+
+    /// Closes the connection on a batch, or QUIC message, said by its header to be larger
+    /// than `max_bytes`, `DEFAULT_MAX_BATCH_BYTES` by default, rather than allocating it.
+    pub fn with_max_batch_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_batch_bytes = max_bytes;
+
+        self
+    }
+
+    /// On a batch whose messages' sizes run past its end, hands the messages before the
+    /// corrupted one to the callback and drops the rest, counting it in
+    /// `ReceiverMetrics::corrupted_batches`, then goes on with the next batch, instead of
+    /// closing the connection. Batches are read whole first, so the next one still starts
+    /// where its header says.
+    pub fn with_resync(mut self) -> Self {
+        self.resync = true;
+
+        self
+    }
+
+    /// Decrypts the batches of a sender encrypting them with the same key, see
+    /// `TcpSender::with_encryption`. Batches which can't be decrypted close the connection.
+    pub fn with_encryption(mut self, cipher: PayloadCipher) -> Self {
+        self.cipher = Some(cipher);
+
+        self
+    }
+
+    /// Only hands 1 in N messages of the given types to the callback, e.g. `{Account: 100}`
+    /// for a statistical view of the account updates. A rate of 0 or 1 keeps every message.
+    pub fn with_sampling(mut self, rates: HashMap<MessageType, u64>) -> Self {
+        self.sampler = Some(Sampler {
+            rates: rates
+                .into_iter()
+                .filter(|(_, n)| *n > 1)
+                .map(|(message_type, n)| (message_type, (n, AtomicU64::new(0))))
+                .collect(),
+        });
+
+        self
+    }
+
+    /// Hands the messages to the callback from a queue instead of as they are read, so
+    /// a slow callback doesn't hold back reading from the sender. Up to `memory_messages`
+    /// are queued in memory, the rest is spooled to `dir` and replayed in order. Messages
+    /// left in `dir` by a previous run are handed to the callback first.
+    pub fn with_spool(mut self, dir: PathBuf, memory_messages: usize) -> io::Result<Self> {
+        self.spool = Some(Spool {
+            state: Mutex::new(SpoolState {
+                memory: VecDeque::with_capacity(memory_messages),
+                disk: DiskSpool::open(dir, DEFAULT_SEGMENT_MAX_BYTES)?,
+            }),
+            memory_messages,
+            notify: Notify::new(),
+        });
+
+        Ok(self)
+    }
+
+    /// Batches the sender dropped instead of sending them to this receiver, e.g. because it
+    /// didn't keep up. Only counted with senders speaking `PROTOCOL_VERSION_V3`.
+    pub fn missed_batches(&self) -> u64 {
+        self.metrics.missed_batches()
+    }
+
+    /// Gaps in the sequence numbers the `missed_batches` were told by, see `with_on_gap`.
+    pub fn gaps(&self) -> u64 {
+        self.metrics.gaps()
+    }
+
+    /// Handle on the receiver's counters, to be taken before running it.
+    pub fn metrics(&self) -> ReceiverMetrics {
+        self.metrics.clone()
+    }
+
     pub async fn connect(&self, addr: SocketAddr) -> io::Result<()> {
+        self.run(&[Upstream::Addr(addr)], Connector::Plain).await
+    }
+
+    /// Connects to a sender listening on the Unix socket at `path`, e.g. on the same host.
+    pub async fn connect_uds(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.run(&[Upstream::Path(path.as_ref().into())], Connector::Plain)
+            .await
+    }
+
+    /// Connects to the first of `addrs`, e.g. senders on redundant validators, failing over
+    /// to the next one right away when the connection drops or can't be made. The reconnect
+    /// interval, or backoff, is only waited once every address failed in a row.
+    pub async fn connect_any(&self, addrs: &[SocketAddr]) -> io::Result<()> {
+        let upstreams = addrs
+            .iter()
+            .copied()
+            .map(Upstream::Addr)
+            .collect::<Vec<_>>();
+        self.run(&upstreams, Connector::Plain).await
+    }
+
+    /// The events of the sender at `addr`, decoded by `decode_event`, for tokio applications
+    /// to poll rather than handing a callback. Reconnects when the connection drops, and
+    /// disconnects once the stream is dropped. Messages without typed decoding, such as
+    /// diagnostics, are skipped. Must be called within a tokio runtime.
+    pub fn stream(addr: SocketAddr) -> impl Stream<Item = Result<Event, DecodeError>> {
+        // bounded, so reading from the socket waits for the consumer to catch up
+        let (sender, messages) = mpsc::channel(STREAM_QUEUE_SIZE);
+        let consumer = sender.clone();
+        let callback: Callback = Box::new(move |message| {
+            let sender = sender.clone();
+            Box::pin(async move {
+                let _ = sender.send(message).await;
+            })
+        });
+        let receiver =
+            TcpReceiver::new(callback, STREAM_CONNECT_TIMEOUT, STREAM_RECONNECT_INTERVAL);
+
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = receiver.connect(addr) => {}
+                _ = consumer.closed() => {}
+            }
+        });
+
+        stream::unfold(messages, |mut messages| async move {
+            loop {
+                let message = messages.recv().await?;
+                match decode_event(&message) {
+                    Err(DecodeError::UnsupportedPrefix(_)) => continue,
+                    event => return Some((event, messages)),
+                }
+            }
+        })
+    }
+
+    /// Connects over TLS, checking that the sender's certificate is valid for `server_name`,
+    /// see `tls::client_config`.
+    pub async fn connect_tls(
+        &self,
+        addr: SocketAddr,
+        server_name: &str,
+        config: Arc<ClientConfig>,
+    ) -> io::Result<()> {
+        let server_name = ServerName::try_from(server_name)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+        self.run(
+            &[Upstream::Addr(addr)],
+            Connector::Tls(TlsConnector::from(config), server_name),
+        )
+        .await
+    }
+
+    /// Connects to a `QuicSender`, checking that its certificate is valid for `server_name`.
+    /// The messages of a type are read in order, the types are interleaved as they arrive.
+    #[cfg(feature = "quic")]
+    pub async fn connect_quic(
+        &self,
+        addr: SocketAddr,
+        server_name: &str,
+        config: Arc<ClientConfig>,
+    ) -> io::Result<()> {
+        let mut crypto = (*config).clone();
+        crypto.alpn_protocols = vec![QUIC_ALPN.to_vec()];
+        let mut client_config = quinn::ClientConfig::new(Arc::new(crypto));
+        let mut transport = quinn::TransportConfig::default();
+        transport.keep_alive_interval(Some(QUIC_KEEP_ALIVE));
+        client_config.transport_config(Arc::new(transport));
+
+        let local_addr = match addr {
+            SocketAddr::V4(_) => SocketAddr::from(([0, 0, 0, 0], 0)),
+            SocketAddr::V6(_) => SocketAddr::from(([0; 16], 0)),
+        };
+        let mut endpoint = quinn::Endpoint::client(local_addr)?;
+        endpoint.set_default_client_config(client_config);
+
+        self.run(
+            &[Upstream::Addr(addr)],
+            Connector::Quic(endpoint, server_name.to_string()),
+        )
+        .await
+    }
+
+    async fn run(&self, upstreams: &[Upstream], connector: Connector) -> io::Result<()> {
+        if upstreams.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "no address to connect to",
+            ));
+        }
+
+        match &self.spool {
+            Some(spool) => {
+                tokio::select! {
+                    res = self.connect_loop(upstreams, &connector) => res,
+                    res = self.replay_spool(spool) => res,
+                }
+            }
+            None => self.connect_loop(upstreams, &connector).await,
+        }
+    }
+
+    async fn replay_spool(&self, spool: &Spool) -> io::Result<()> {
+        loop {
+            while let Some(message) = spool.pop()? {
+                (self.callback)(message).await;
+            }
+
+            spool.notify.notified().await;
+        }
+    }
+
+    async fn connect_loop(&self, upstreams: &[Upstream], connector: &Connector) -> io::Result<()> {
+        let mut failures = 0;
+        let mut active = 0;
+        let mut attempted = false;
         loop {
-            info!("Receiver Connect {:?}", addr);
+            let upstream = &upstreams[active];
+            info!("Receiver Connect {}", upstream);
+            if attempted {
+                self.metrics.0.reconnects.fetch_add(1, Ordering::Relaxed);
+            }
+            attempted = true;
+
+            self.connected.store(false, Ordering::Relaxed);
+            let res = match self.failback_interval.filter(|_| active > 0) {
+                Some(interval) => {
+                    tokio::select! {
+                        res = self.connect_and_read(upstream, connector) => Some(res),
+                        _ = self.wait_for_listener(&upstreams[0], interval) => None,
+                    }
+                }
+                None => Some(self.connect_and_read(upstream, connector).await),
+            };
+            let Some(res) = res else {
+                info!("receiver: {} is back, failing back to it", upstreams[0]);
+                active = 0;
+                failures = 0;
+                continue;
+            };
+
+            let e = match res {
+                Ok(()) => io::Error::new(io::ErrorKind::ConnectionAborted, "connection closed"),
+                Err(e) => {
+                    error!("receiver: read error: {:?}", e);
+                    if e.kind() == io::ErrorKind::InvalidData {
+                        self.metrics.0.decode_errors.fetch_add(1, Ordering::Relaxed);
+                    }
+                    e
+                }
+            };
 
-            if let Err(e) = self.connect_and_read(addr).await {
-                error!("receiver: read error: {:?}", e);
+            // connections closed before their first batch, e.g. rejected by the sender,
+            // count as failed attempts
+            if self.connected.load(Ordering::Relaxed) {
+                failures = 0;
+            }
+            failures += 1;
+            if self
+                .max_retries
+                .is_some_and(|max_retries| failures > max_retries)
+            {
+                warn!(
+                    "receiver: giving up on {} after {} failed attempts",
+                    upstream, failures
+                );
+                if let Some(on_give_up) = &self.on_give_up {
+                    on_give_up(&e);
+                }
+                return Err(e);
             }
 
-            sleep(self.reconnect_interval).await;
+            active = (active + 1) % upstreams.len();
+            if upstreams.len() > 1 {
+                info!(
+                    "receiver: failing over from {} to {}",
+                    upstream, upstreams[active]
+                );
+            }
+            if failures as usize % upstreams.len() == 0 {
+                sleep(self.reconnect_delay(failures / upstreams.len() as u32)).await;
+            }
         }
     }
 
-    async fn connect_and_read(&self, addr: SocketAddr) -> io::Result<()> {
-        let stream = TcpStream::connect(&addr).await?;
+    /// Returns once `upstream` accepts connections, checked every `interval`.
+    async fn wait_for_listener(&self, upstream: &Upstream, interval: Duration) {
+        loop {
+            sleep(interval).await;
+            let accepted = match upstream {
+                Upstream::Addr(addr) => self
+                    .timed_connect(upstream, TcpStream::connect(addr))
+                    .await
+                    .is_ok(),
+                Upstream::Path(path) => self
+                    .timed_connect(upstream, UnixStream::connect(path))
+                    .await
+                    .is_ok(),
+            };
+            if accepted {
+                return;
+            }
+        }
+    }
+
+    /// Wait before the next attempts after `rounds` of them failed in a row, a round trying
+    /// every address once, see `with_backoff`.
+    fn reconnect_delay(&self, rounds: u32) -> Duration {
+        let Some(max_backoff) = self.max_backoff else {
+            return self.reconnect_interval;
+        };
+
+        let interval = self
+            .reconnect_interval
+            .saturating_mul(2u32.saturating_pow(rounds.saturating_sub(1)))
+            .min(max_backoff);
+        let jitter = (Uuid::new_v4().as_u128() % 1000) as f64 / 1000.0;
+
+        interval.mul_f64(0.5 + jitter / 2.0)
+    }
+
+    async fn connect_and_read(&self, upstream: &Upstream, connector: &Connector) -> io::Result<()> {
+        let addr = match upstream {
+            Upstream::Addr(addr) => *addr,
+            // only ever with the plain connector, TLS and QUIC take addresses
+            Upstream::Path(path) => {
+                let stream = self
+                    .timed_connect(upstream, UnixStream::connect(path))
+                    .await?;
+                return self.read_stream(stream).await;
+            }
+        };
+
+        match connector {
+            Connector::Plain => {
+                let stream = self
+                    .timed_connect(upstream, TcpStream::connect(&addr))
+                    .await?;
+                self.read_stream(stream).await
+            }
+            Connector::Tls(connector, server_name) => {
+                let stream = self
+                    .timed_connect(upstream, async {
+                        let stream = TcpStream::connect(&addr).await?;
+                        connector.connect(server_name.clone(), stream).await
+                    })
+                    .await?;
+                self.read_stream(stream).await
+            }
+            #[cfg(feature = "quic")]
+            Connector::Quic(endpoint, server_name) => {
+                let connecting = endpoint
+                    .connect(addr, server_name)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+                let connection = self
+                    .timed_connect(upstream, async { Ok(connecting.await?) })
+                    .await?;
+                self.read_quic(connection).await
+            }
+        }
+    }
+
+    /// Fails with `TimedOut` if connecting takes longer than the connect timeout, e.g. to an
+    /// unresponsive host.
+    async fn timed_connect<T>(
+        &self,
+        upstream: &Upstream,
+        connecting: impl Future<Output = io::Result<T>>,
+    ) -> io::Result<T> {
+        tokio::time::timeout(self.connect_timeout, connecting)
+            .await
+            .map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("connecting to {} timed out", upstream),
+                )
+            })?
+    }
+
+    /// Reads the streams the sender opens, one per message type, until the connection is
+    /// lost.
+    #[cfg(feature = "quic")]
+    async fn read_quic(&self, connection: quinn::Connection) -> io::Result<()> {
+        use futures_util::stream::{FuturesUnordered, StreamExt};
+
+        let mut streams = FuturesUnordered::new();
+
+        loop {
+            tokio::select! {
+                stream = connection.accept_uni() => {
+                    self.connected.store(true, Ordering::Relaxed);
+                    streams.push(self.read_quic_stream(stream?));
+                }
+                Some(res) = streams.next(), if !streams.is_empty() => res?,
+            }
+        }
+    }
+
+    #[cfg(feature = "quic")]
+    async fn read_quic_stream(&self, stream: quinn::RecvStream) -> io::Result<()> {
         let mut stream = tokio::io::BufReader::new(stream);
 
         loop {
-            let (bytes_read, duration, num_elements) = self.read_response(&mut stream).await?;
-            debug!(
-                "TCP Socket: Received {} elements, {} in {:?}",
-                num_elements, bytes_read, duration
-            );
+            let mut header = [0; HEADER_BYTE_SIZE];
+            stream.read_exact(&mut header).await?;
+
+            let size = check_batch_size(u32::from_le_bytes(header), self.max_batch_bytes)?;
+            let mut message = vec![0; size];
+            stream.read_exact(&mut message).await?;
+            self.metrics
+                .0
+                .bytes_read
+                .fetch_add(message.len() as u64, Ordering::Relaxed);
+            self.handle_message(&message).await?;
         }
     }
 
-    async fn read_response(
-        &self,
-        stream: &mut tokio::io::BufReader<TcpStream>,
-    ) -> io::Result<(usize, Duration, u32)> {
-        let mut header = [0; HEADER_BYTE_SIZE];
-        stream.read_exact(&mut header).await?;
+    async fn read_stream(&self, stream: impl AsyncRead + AsyncWrite + Unpin) -> io::Result<()> {
+        let mut stream = tokio::io::BufReader::new(stream);
+        // chunks of a previous connection are never completed
+        self.reassembler
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
 
-        let mut body = vec![0; u32::from_le_bytes(header) as usize];
+        // the server hello, if the sender sends one, comes first and is framed as V1
         let now = Instant::now();
+        let (body, _) =
+            Self::read_batch(&mut stream, PROTOCOL_VERSION_V1, self.max_batch_bytes).await?;
+        self.connected.store(true, Ordering::Relaxed);
+        self.metrics
+            .0
+            .bytes_read
+            .fetch_add(body.len() as u64, Ordering::Relaxed);
+        let duration = now.elapsed();
+        // a sender without handshake starts with data, encrypted if it encrypts batches
+        let server_hello = Self::split_batch(&body)
+            .ok()
+            .and_then(|messages| ServerHello::from_message(messages.first()?));
+        let (protocol_version, acks) = match server_hello {
+            Some(hello) => {
+                let mut client_hello = self.client_hello.clone().unwrap_or_default();
+                let protocol_version = *client_hello
+                    .protocol_version
+                    .get_or_insert(Self::check_server_hello(&hello)?);
+
+                let mut buffer = TcpBuffer::new(1);
+                buffer.append(client_hello.to_message());
+                stream.write_all(&buffer.flush_data()).await?;
+
+                // the batch carries the hello only, which is not handed to the callback
+                (
+                    protocol_version,
+                    client_hello.acks && protocol_version >= PROTOCOL_VERSION_V3,
+                )
+            }
+            None => {
+                self.handle_batch(&self.open_batch(body)?, duration).await?;
+                (PROTOCOL_VERSION_V1, false)
+            }
+        };
+
+        // sequence numbers start over on every connection
+        let mut next_sequence = 0;
+
+        loop {
+            let now = Instant::now();
+            let (body, sequence) =
+                Self::read_batch(&mut stream, protocol_version, self.max_batch_bytes).await?;
+            self.metrics
+                .0
+                .bytes_read
+                .fetch_add(body.len() as u64, Ordering::Relaxed);
+            let body = self.open_batch(body)?;
+
+            if let Some(sequence) = sequence {
+                if sequence > next_sequence {
+                    let missed = sequence - next_sequence;
+                    warn!(
+                        "receiver: the sender dropped {} batches, {} to {}",
+                        missed,
+                        next_sequence,
+                        sequence - 1
+                    );
+                    self.metrics
+                        .0
+                        .missed_batches
+                        .fetch_add(missed, Ordering::Relaxed);
+                    self.metrics.0.gaps.fetch_add(1, Ordering::Relaxed);
+                    if let Some(on_gap) = &self.on_gap {
+                        let slot_hint = first_message(&body).and_then(message_slot);
+                        on_gap(next_sequence, sequence - 1, slot_hint);
+                    }
+                }
+                next_sequence = sequence + 1;
+            }
+
+            self.handle_batch(&body, now.elapsed()).await?;
+
+            // acknowledged once handled, so a slow handler holds the sender back
+            if let Some(sequence) = sequence.filter(|_| acks) {
+                let mut buffer = TcpBuffer::new(1);
+                buffer.append(ack_message(sequence));
+                stream.write_all(&buffer.flush_data()).await?;
+                stream.flush().await?;
+            }
+        }
+    }
+
+    /// Reads the messages of a batch, checking their checksum from `PROTOCOL_VERSION_V2` on,
+    /// along with its sequence number from `PROTOCOL_VERSION_V3` on.
+    async fn read_batch(
+        stream: &mut (impl AsyncRead + Unpin),
+        protocol_version: u32,
+        max_batch_bytes: usize,
+    ) -> io::Result<(Vec<u8>, Option<u64>)> {
+        let mut bytes = [0; FrameHeader::MAX_LEN];
+        let bytes = &mut bytes[..FrameHeader::len(protocol_version)];
+        stream.read_exact(bytes).await?;
+        let header = FrameHeader::parse(bytes, max_batch_bytes)?;
+
+        let mut body = vec![0; header.body_len];
         stream.read_exact(&mut body).await?;
+        header.check(&body)?;
 
-        let duration = now.elapsed();
-        let bytes_read = header.len() + body.len();
-        let mut num_elements = 0;
+        Ok((body, header.sequence))
+    }
+
+    /// Decrypts the messages of a batch if the receiver has a key.
+    fn open_batch(&self, body: Vec<u8>) -> io::Result<Vec<u8>> {
+        match &self.cipher {
+            Some(cipher) => cipher.open(&body),
+            None => Ok(body),
+        }
+    }
+
+    /// Splits a batch into its size prefixed messages.
+    pub(crate) fn split_batch(body: &[u8]) -> io::Result<Vec<&[u8]>> {
+        let (messages, result) = Self::split_batch_until_corrupted(body);
+
+        result.map(|_| messages)
+    }
+
+    /// Splits a batch into its size prefixed messages, up to the first one whose size runs
+    /// past the end of the batch, if any, for which the error is returned too.
+    fn split_batch_until_corrupted(body: &[u8]) -> (Vec<&[u8]>, io::Result<()>) {
+        let mut messages = Vec::new();
 
         let mut i = 0;
         while i < body.len() {
             let mut end = i + HEADER_BYTE_SIZE;
-            let size_bytes = body[i..end].try_into().map_err(|e| {
-                io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    format!("Failed to convert size: {}", e),
-                )
-            })?;
-            let size = u32::from_le_bytes(size_bytes) as usize;
+            let size_bytes = body.get(i..end).and_then(|size| size.try_into().ok());
+            let Some(size) = size_bytes.map(u32::from_le_bytes) else {
+                let e = io::Error::new(io::ErrorKind::InvalidData, "truncated message size");
+                return (messages, Err(e));
+            };
             i = end;
 
-            end = i + size;
-            (self.callback)(body[i..end].to_vec()).await;
+            end = i + size as usize;
+            let Some(message) = body.get(i..end) else {
+                let e = io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("message of {} bytes past the end of the batch", size),
+                );
+                return (messages, Err(e));
+            };
+            messages.push(message);
             i = end;
+        }
+
+        (messages, Ok(()))
+    }
+
+    async fn handle_batch(&self, body: &[u8], duration: Duration) -> io::Result<()> {
+        let (messages, result) = Self::split_batch_until_corrupted(body);
+        if let Err(e) = result {
+            if !self.resync {
+                return Err(e);
+            }
+
+            warn!(
+                "receiver: dropping a corrupted batch of {} bytes after its {} first messages: {}",
+                body.len(),
+                messages.len(),
+                e
+            );
+            self.metrics
+                .0
+                .corrupted_batches
+                .fetch_add(1, Ordering::Relaxed);
+        }
+        for message in &messages {
+            self.handle_message(message).await?;
+        }
+
+        debug!(
+            "TCP Socket: Received {} elements, {} in {:?}",
+            messages.len(),
+            body.len(),
+            duration
+        );
+
+        Ok(())
+    }
+
+    async fn handle_message(&self, message: &[u8]) -> io::Result<()> {
+        let message = match chunked_prefix(message) {
+            Some(_) => {
+                let mut reassembler = self.reassembler.lock().unwrap_or_else(|e| e.into_inner());
+                match reassembler.push(message.to_vec()) {
+                    Some(message) => message,
+                    None => return Ok(()),
+                }
+            }
+            None => message.to_vec(),
+        };
+        self.metrics.0.messages.fetch_add(1, Ordering::Relaxed);
+        self.metrics.record_block_lag(&message);
+
+        if self
+            .sampler
+            .as_ref()
+            .map_or(true, |sampler| sampler.keep(&message))
+        {
+            // chunks are split from the compressed message, so only once put back together
+            let message = match self.decompress && is_compressed(&message) {
+                true => decompress(&message)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+                    .into_owned(),
+                false => message,
+            };
+
+            match &self.spool {
+                Some(spool) => spool.push(message)?,
+                None => (self.callback)(message).await,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The newest protocol version the sender speaks too. Fails on a sender whose framing
+    /// can't be read, rather than misreading the stream.
+    pub(crate) fn check_server_hello(hello: &ServerHello) -> io::Result<u32> {
+        let Some(protocol_version) = hello.negotiate_protocol_version() else {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "sender {} speaks protocol versions {:?}, none of which is supported",
+                    hello.plugin_version, hello.protocol_versions
+                ),
+            ));
+        };
+
+        if !hello.is_schema_compatible() {
+            warn!(
+                "sender {} was built with schema hash {}, messages may not decode",
+                hello.plugin_version, hello.schema_hash
+            );
+        }
+
+        Ok(protocol_version)
+    }
+}
+
+/// What precedes the body of a batch: its size, then its checksum from `PROTOCOL_VERSION_V2`
+/// on, then its sequence number from `PROTOCOL_VERSION_V3` on.
+pub(crate) struct FrameHeader {
+    pub body_len: usize,
+    checksum: Option<u32>,
+    pub sequence: Option<u64>,
+}
+
+impl FrameHeader {
+    pub const MAX_LEN: usize = HEADER_BYTE_SIZE + CHECKSUM_BYTE_SIZE + SEQUENCE_BYTE_SIZE;
+
+    /// The size of the header framed as `protocol_version`.
+    pub fn len(protocol_version: u32) -> usize {
+        match protocol_version {
+            v if v >= PROTOCOL_VERSION_V3 => Self::MAX_LEN,
+            v if v >= PROTOCOL_VERSION_V2 => HEADER_BYTE_SIZE + CHECKSUM_BYTE_SIZE,
+            _ => HEADER_BYTE_SIZE,
+        }
+    }
+
+    /// Parses the `len` bytes of a header, failing if the batch is over `max_batch_bytes`.
+    pub fn parse(bytes: &[u8], max_batch_bytes: usize) -> io::Result<Self> {
+        let word = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+        let body_len = check_batch_size(word(0), max_batch_bytes)?;
+        let checksum = (bytes.len() > HEADER_BYTE_SIZE).then(|| word(HEADER_BYTE_SIZE));
+        let sequence = (bytes.len() == Self::MAX_LEN).then(|| {
+            let at = HEADER_BYTE_SIZE + CHECKSUM_BYTE_SIZE;
+            u64::from_le_bytes(bytes[at..].try_into().unwrap())
+        });
+
+        Ok(FrameHeader {
+            body_len,
+            checksum,
+            sequence,
+        })
+    }
 
-            num_elements += 1;
+    /// Fails if the body doesn't match the checksum of the header, if it has one.
+    pub fn check(&self, body: &[u8]) -> io::Result<()> {
+        match self.checksum {
+            Some(checksum) if crc32fast::hash(body) != checksum => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("checksum mismatch on a batch of {} bytes", body.len()),
+            )),
+            _ => Ok(()),
         }
+    }
+}
+
+/// The size of a batch read from its header, failing if it's over `max_bytes`.
+pub(crate) fn check_batch_size(size: u32, max_bytes: usize) -> io::Result<usize> {
+    let size = size as usize;
+    if size > max_bytes {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "batch of {} bytes over the {} bytes limit, corrupted header?",
+                size, max_bytes
+            ),
+        ));
+    }
+
+    Ok(size)
+}
+
+/// First of the size prefixed messages of a batch.
+fn first_message(body: &[u8]) -> Option<&[u8]> {
+    let size = u32::from_le_bytes(body.get(..HEADER_BYTE_SIZE)?.try_into().ok()?) as usize;
+    body.get(HEADER_BYTE_SIZE..HEADER_BYTE_SIZE + size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compression::{CompressionCodec, CompressionPolicy};
+    use crate::flatbuffer::{
+        consts::{BYTE_PREFIX_ACCOUNT, BYTE_PREFIX_SLOT},
+        slot_generated::slot::{Slot, SlotArgs, Status},
+    };
+    use crate::sender::TcpSender;
+    use std::sync::atomic::AtomicUsize;
+    use tokio::net::TcpListener;
+
+    /// Prefixes a message with its size.
+    fn frame(msg: &[u8]) -> Vec<u8> {
+        let mut framed = (msg.len() as u32).to_le_bytes().to_vec();
+        framed.extend_from_slice(msg);
+
+        framed
+    }
+
+    /// A batch framed as `PROTOCOL_VERSION_V3`, with the checksum of `checksummed` rather
+    /// than of `body` to corrupt it.
+    fn batch(body: &[u8], checksummed: &[u8], sequence: u64) -> Vec<u8> {
+        let mut batch = (body.len() as u32).to_le_bytes().to_vec();
+        batch.extend_from_slice(&crc32fast::hash(checksummed).to_le_bytes());
+        batch.extend_from_slice(&sequence.to_le_bytes());
+        batch.extend_from_slice(body);
+
+        batch
+    }
+
+    /// Listens on `port` like a sender, writing its server hello then `data` as is to the
+    /// first receiver, whose connection is kept open.
+    async fn fake_sender(port: u16, data: Vec<u8>) {
+        let listener = TcpListener::bind(("127.0.0.1", port)).await.unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut hello = TcpBuffer::new(1);
+            hello.append(ServerHello::new("test", "test").to_message());
+            stream.write_all(&hello.flush_data()).await.unwrap();
+            stream.write_all(&data).await.unwrap();
+            std::future::pending::<()>().await;
+        });
+    }
+
+    /// Spawns a receiver connected to `addrs`, returning the messages it gets.
+    fn collecting_receiver(
+        addrs: Vec<SocketAddr>,
+        configure: impl FnOnce(TcpReceiver) -> TcpReceiver,
+    ) -> Arc<Mutex<Vec<Vec<u8>>>> {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let received_clone = received.clone();
+        let receiver = configure(TcpReceiver::new(
+            Box::new(move |data| {
+                received_clone.lock().unwrap().push(data);
+                Box::pin(async {})
+            }),
+            Duration::from_secs(1),
+            Duration::from_secs(1),
+        ));
+        tokio::spawn(async move { receiver.connect_any(&addrs).await });
+
+        received
+    }
+
+    /// Polls `condition` until it holds, failing the test if it takes over 5 seconds.
+    async fn wait_for(condition: impl Fn() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !condition() {
+            assert!(
+                Instant::now() < deadline,
+                "timed out waiting for a condition"
+            );
+            sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    fn slot_message(slot: u64) -> Vec<u8> {
+        let mut builder = flatbuffers::FlatBufferBuilder::new();
+        let slot = Slot::create(
+            &mut builder,
+            &SlotArgs {
+                slot,
+                status: Status::Processed,
+                parent: None,
+            },
+        );
+        builder.finish(slot, None);
+        let mut msg = vec![BYTE_PREFIX_SLOT];
+        msg.extend_from_slice(builder.finished_data());
+
+        msg
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_backoff() {
+        let gave_up = Arc::new(AtomicUsize::new(0));
+        let gave_up_clone = gave_up.clone();
+        let receiver = TcpReceiver::new(
+            Box::new(|_| Box::pin(async {})),
+            Duration::from_secs(1),
+            Duration::from_millis(10),
+        )
+        .with_backoff(Duration::from_millis(40))
+        .with_max_retries(3, move |e| {
+            assert_eq!(e.kind(), io::ErrorKind::ConnectionRefused);
+            gave_up_clone.fetch_add(1, Ordering::Relaxed);
+        });
+
+        // nothing listens on it, waits of 5-10, 10-20 and 20-40ms between the attempts
+        let started = Instant::now();
+        let res = receiver.connect("127.0.0.1:9072".parse().unwrap()).await;
+        assert_eq!(res.unwrap_err().kind(), io::ErrorKind::ConnectionRefused);
+        assert_eq!(gave_up.load(Ordering::Relaxed), 1);
+        assert!(started.elapsed() >= Duration::from_millis(35));
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_connect_timeout() {
+        use tokio_rustls::rustls::RootCertStore;
+
+        // accepted by the kernel but never answered, the TLS handshake hangs
+        let _listener = std::net::TcpListener::bind("127.0.0.1:9073").unwrap();
+        let config = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(RootCertStore::empty())
+            .with_no_client_auth();
+        let receiver = TcpReceiver::new(
+            Box::new(|_| Box::pin(async {})),
+            Duration::from_millis(200),
+            Duration::from_millis(10),
+        )
+        .with_max_retries(0, |_| {});
+
+        let started = Instant::now();
+        let res = receiver
+            .connect_tls(
+                "127.0.0.1:9073".parse().unwrap(),
+                "localhost",
+                Arc::new(config),
+            )
+            .await;
+        assert_eq!(res.unwrap_err().kind(), io::ErrorKind::TimedOut);
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_failover() {
+        let secondary =
+            TcpSender::new(10, false, 0).with_handshake_timeout(Duration::from_millis(10));
+        secondary.bind(9075, 100).unwrap();
+
+        // the first address refuses the connection
+        let received = collecting_receiver(
+            vec![
+                "127.0.0.1:9074".parse().unwrap(),
+                "127.0.0.1:9075".parse().unwrap(),
+            ],
+            |receiver| receiver.with_failback(Duration::from_millis(200)),
+        );
+        wait_for(|| !secondary.subscribers().unwrap().is_empty()).await;
+        secondary.publish(b"secondary".to_vec()).unwrap();
+        wait_for(|| received.lock().unwrap().len() == 1).await;
+
+        let primary =
+            TcpSender::new(10, false, 0).with_handshake_timeout(Duration::from_millis(10));
+        primary.bind(9074, 100).unwrap();
+        wait_for(|| !primary.subscribers().unwrap().is_empty()).await;
+        primary.publish(b"primary".to_vec()).unwrap();
+        wait_for(|| received.lock().unwrap().len() == 2).await;
+
+        assert_eq!(
+            *received.lock().unwrap(),
+            [b"secondary".to_vec(), b"primary".to_vec()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_gap_detection() {
+        // a sender which dropped the batches 2 and 3 for the receiver, then writes a batch
+        // failing its checksum
+        let mut data = Vec::new();
+        for (sequence, slot) in [(0, 10), (1, 11), (4, 14)] {
+            let body = frame(&slot_message(slot));
+            data.extend(batch(&body, &body, sequence));
+        }
+        data.extend(batch(&[1, 2, 3, 4], &[], 5));
+        fake_sender(9076, data).await;
+
+        let gaps = Arc::new(Mutex::new(Vec::new()));
+        let gaps_clone = gaps.clone();
+        let receiver = Arc::new(
+            TcpReceiver::new(
+                Box::new(|_| Box::pin(async {})),
+                Duration::from_secs(1),
+                Duration::from_secs(1),
+            )
+            .with_on_gap(move |from, to, slot_hint| {
+                gaps_clone.lock().unwrap().push((from, to, slot_hint));
+            }),
+        );
+        let metrics = receiver.metrics();
+        let receiver_clone = receiver.clone();
+        tokio::spawn(async move {
+            let _ = receiver_clone
+                .connect("127.0.0.1:9076".parse().unwrap())
+                .await;
+        });
+
+        wait_for(|| metrics.decode_errors() == 1).await;
+
+        assert_eq!(*gaps.lock().unwrap(), [(2, 3, Some(14))]);
+        assert_eq!(receiver.gaps(), 1);
+        assert_eq!(receiver.missed_batches(), 2);
+        // the slots, the server hello is not counted
+        assert_eq!(metrics.messages(), 3);
+        assert!(metrics.bytes_read() > 0);
+        assert_eq!(metrics.reconnects(), 0);
+        assert_eq!(metrics.block_lag(), None);
+    }
+
+    #[tokio::test]
+    async fn test_max_batch_bytes() {
+        // a sender whose next header claims a batch of 1 GiB
+        let mut header = (1u32 << 30).to_le_bytes().to_vec();
+        header.extend_from_slice(&[0; CHECKSUM_BYTE_SIZE + SEQUENCE_BYTE_SIZE]);
+        fake_sender(9079, header).await;
+
+        let receiver = Arc::new(
+            TcpReceiver::new(
+                Box::new(|_| Box::pin(async {})),
+                Duration::from_secs(1),
+                Duration::from_secs(1),
+            )
+            .with_max_batch_bytes(1024 * 1024),
+        );
+        let metrics = receiver.metrics();
+        let receiver_clone = receiver.clone();
+        tokio::spawn(async move {
+            let _ = receiver_clone
+                .connect("127.0.0.1:9079".parse().unwrap())
+                .await;
+        });
+
+        wait_for(|| metrics.decode_errors() == 1).await;
+
+        assert_eq!(metrics.messages(), 0);
+        assert!(check_batch_size(1024, 1024).is_ok());
+        assert!(check_batch_size(1025, 1024).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resync() {
+        // a sender writing a batch whose second message's size runs past its end
+        let mut body = frame(b"abc");
+        body.extend_from_slice(&100u32.to_le_bytes());
+        body.extend_from_slice(b"def");
+        let mut data = batch(&body, &body, 0);
+        data.extend(batch(&frame(b"ghi"), &frame(b"ghi"), 1));
+        fake_sender(9080, data).await;
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let received_clone = received.clone();
+        let receiver = Arc::new(
+            TcpReceiver::new(
+                Box::new(move |msg| {
+                    received_clone.lock().unwrap().push(msg);
+                    Box::pin(async {})
+                }),
+                Duration::from_secs(1),
+                Duration::from_secs(1),
+            )
+            .with_resync(),
+        );
+        let metrics = receiver.metrics();
+        let receiver_clone = receiver.clone();
+        tokio::spawn(async move {
+            let _ = receiver_clone
+                .connect("127.0.0.1:9080".parse().unwrap())
+                .await;
+        });
+
+        wait_for(|| received.lock().unwrap().len() == 2).await;
+
+        // the message before the corrupted one and the next batch's
+        assert_eq!(
+            *received.lock().unwrap(),
+            [b"abc".to_vec(), b"ghi".to_vec()]
+        );
+        assert_eq!(metrics.corrupted_batches(), 1);
+        assert_eq!(metrics.decode_errors(), 0);
+        assert_eq!(metrics.reconnects(), 0);
+        assert!(TcpReceiver::split_batch(&frame(b"abc")[..5]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_decompression() {
+        let sender = TcpSender::new(10, false, 0)
+            .with_handshake_timeout(Duration::from_millis(10))
+            .with_compression(CompressionPolicy {
+                codec: Some(CompressionCodec::Lz4),
+                level: None,
+                message_types: vec![MessageType::Account],
+                min_bytes: Some(0),
+            });
+        sender.bind(9078, 100).unwrap();
+
+        let received = collecting_receiver(
+            vec!["127.0.0.1:9078".parse().unwrap()],
+            TcpReceiver::with_decompression,
+        );
+        wait_for(|| !sender.subscribers().unwrap().is_empty()).await;
+
+        let mut msg = vec![BYTE_PREFIX_ACCOUNT];
+        msg.extend_from_slice(&[7; 4096]);
+        sender.publish(msg.clone()).unwrap();
+        wait_for(|| !received.lock().unwrap().is_empty()).await;
+
+        assert_eq!(*received.lock().unwrap(), [msg]);
+    }
+
+    #[tokio::test]
+    async fn test_chunked_messages() {
+        let sender = TcpSender::new(10, false, 0)
+            .with_handshake_timeout(Duration::from_millis(10))
+            .with_max_message_bytes(64);
+        sender.bind(9065, 100).unwrap();
+
+        let received =
+            collecting_receiver(vec!["127.0.0.1:9065".parse().unwrap()], |receiver| receiver);
+        wait_for(|| !sender.subscribers().unwrap().is_empty()).await;
+
+        let large = (0..1000).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let small = b"hello world".to_vec();
+        sender.publish(large.clone()).unwrap();
+        sender.publish(small.clone()).unwrap();
+        wait_for(|| received.lock().unwrap().len() == 2).await;
+
+        assert_eq!(*received.lock().unwrap(), [large, small]);
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_batches() {
+        let key = [7; crate::encryption::KEY_BYTE_SIZE];
+        let sender = TcpSender::new(10, false, 0)
+            .with_handshake(&ServerHello::new("test", "test"))
+            .with_encryption(PayloadCipher::new(&key).unwrap());
+        sender.bind(9068, 100).unwrap();
+
+        let received = collecting_receiver(vec!["127.0.0.1:9068".parse().unwrap()], |receiver| {
+            receiver.with_encryption(PayloadCipher::new(&key).unwrap())
+        });
+        wait_for(|| !sender.subscribers().unwrap().is_empty()).await;
+
+        let msg = b"hello world".to_vec();
+        sender.publish(msg.clone()).unwrap();
+        sender.publish(msg.clone()).unwrap();
+        wait_for(|| received.lock().unwrap().len() == 2).await;
+
+        assert_eq!(*received.lock().unwrap(), [msg.clone(), msg]);
+    }
+
+    #[tokio::test]
+    async fn test_event_stream() {
+        use crate::event::{SlotEvent, SlotStatus};
+        use futures_util::StreamExt;
+
+        let sender = TcpSender::new(10, false, 0)
+            .with_handshake(&ServerHello::new("test", "test"))
+            .with_handshake_timeout(Duration::from_secs(1));
+        sender.bind(9071, 100).unwrap();
+
+        let mut events = Box::pin(TcpReceiver::stream("127.0.0.1:9071".parse().unwrap()));
+        wait_for(|| !sender.subscribers().unwrap().is_empty()).await;
+        sender.publish(slot_message(42)).unwrap();
+
+        let event = tokio::time::timeout(Duration::from_secs(5), events.next())
+            .await
+            .unwrap();
+        assert_eq!(
+            event.unwrap().unwrap(),
+            Event::Slot(SlotEvent {
+                slot: 42,
+                status: SlotStatus::Processed,
+                parent: None,
+            })
+        );
+
+        // disconnects once dropped, as the sender finds out on its next writes
+        drop(events);
+        wait_for(|| {
+            let _ = sender.publish(slot_message(43));
+            sender.subscribers().unwrap().is_empty()
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_unix_socket() {
+        let path = std::env::temp_dir().join(format!("sender-{}.sock", Uuid::new_v4()));
+        let sender = TcpSender::new(10, false, 0)
+            .with_handshake(&ServerHello::new("test", "test"))
+            .with_handshake_timeout(Duration::from_secs(1));
+        sender.bind_unix(&path, 100).unwrap();
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let received_clone = received.clone();
+        let receiver = TcpReceiver::new(
+            Box::new(move |data| {
+                received_clone.lock().unwrap().push(data);
+                Box::pin(async {})
+            }),
+            Duration::from_secs(1),
+            Duration::from_secs(1),
+        );
+        let path_clone = path.clone();
+        tokio::spawn(async move { receiver.connect_uds(path_clone).await });
+        wait_for(|| !sender.subscribers().unwrap().is_empty()).await;
+
+        sender.publish(b"hello world".to_vec()).unwrap();
+        wait_for(|| !received.lock().unwrap().is_empty()).await;
 
-        Ok((bytes_read, duration, num_elements as u32))
+        assert_eq!(*received.lock().unwrap(), [b"hello world".to_vec()]);
+        sender.shutdown();
+        let _ = std::fs::remove_file(path);
     }
 }
@@ -0,0 +1,433 @@
+//! Accepting subscribers, and their handshake, on threads of the listener.
+use crossbeam_channel::{bounded, Receiver, Sender};
+use log::{error, info, warn};
+use rustls::ServerConfig;
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+use super::connection::{add_conn, Acks, Connection, ConnectionMap};
+use super::stream::{Endpoint, Listener, Stream};
+use super::writers::Writers;
+use super::HEADER_BYTE_SIZE;
+use crate::handshake::{
+    ClientHello, PROTOCOL_VERSION_V1, PROTOCOL_VERSION_V3, SCHEMA_VERSION_V1,
+    SUPPORTED_PROTOCOL_VERSIONS, SUPPORTED_SCHEMA_VERSIONS,
+};
+use crate::ip_filter::IpFilter;
+use crate::quota::{QuotaUsage, SubscriberQuota};
+
+const MAX_CLIENT_HELLO_BYTES: usize = 64 * 1024;
+// how long waking up the acceptors of a replaced listener may take
+pub(super) const WAKE_TIMEOUT: Duration = Duration::from_secs(1);
+// accept errors in a row past which an acceptor gives up, its listener being rebound
+pub(super) const MAX_ACCEPT_ERRORS: usize = 100;
+// how long an acceptor waits after an accept error, e.g. out of file descriptors
+pub(super) const ACCEPT_ERROR_BACKOFF: Duration = Duration::from_millis(10);
+// how often a listener which lost its acceptors is tried to be bound again
+const RECOVERY_INTERVAL: Duration = Duration::from_secs(1);
+// how long a new connection is given to complete the TLS handshake
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Counts a connection as handshaking until dropped, when the handshake thread returns.
+struct Handshaking(Arc<AtomicUsize>);
+
+impl Drop for Handshaking {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// The listener currently accepting subscribers, replaced by `TcpSender::rebind`.
+pub(super) struct Listening {
+    pub(super) endpoint: Endpoint,
+    // set to make the acceptors of the listener return, once woken up by a connection
+    pub(super) stopped: Arc<AtomicBool>,
+    // kept by the listeners replacing it, along with the connections they write to
+    pub(super) writers: Arc<Writers>,
+    pub(super) buffer_size: usize,
+}
+
+/// Sends on the channel of the listener's supervisor when its acceptor returns, e.g.
+/// when it panics.
+struct AcceptorExit(Sender<()>);
+
+impl Drop for AcceptorExit {
+    fn drop(&mut self) {
+        let _ = self.0.send(());
+    }
+}
+
+/// What the acceptors of a listener take from the sender, so that its supervisor can spawn
+/// new ones when the listener is recovered.
+#[derive(Clone)]
+pub(super) struct Acceptors {
+    pub(super) threads: usize,
+    pub(super) conns: Arc<RwLock<ConnectionMap>>,
+    pub(super) handshake: Option<Vec<u8>>,
+    pub(super) handshake_timeout: Duration,
+    pub(super) quotas: Arc<HashMap<String, SubscriberQuota>>,
+    pub(super) default_quota: Option<SubscriberQuota>,
+    pub(super) tls: Option<Arc<ServerConfig>>,
+    pub(super) ack_window: u64,
+    pub(super) max_connections: Option<usize>,
+    pub(super) ip_filter: Option<Arc<IpFilter>>,
+    pub(super) stall_timeout: Option<Duration>,
+    pub(super) handshaking: Arc<AtomicUsize>,
+    pub(super) rejected_connections: Arc<AtomicU64>,
+    pub(super) only_v6: bool,
+    pub(super) listening: Arc<Mutex<Option<Listening>>>,
+    pub(super) listener_recoveries: Arc<AtomicU64>,
+}
+
+impl Acceptors {
+    /// Spawns the acceptors of the listener, and the thread supervising them, which binds
+    /// the listener again if one of them returns while it's not stopped, e.g. on a panic or
+    /// after `MAX_ACCEPT_ERRORS` accept errors in a row.
+    pub(super) fn spawn(
+        &self,
+        listener: &Listener,
+        endpoint: &Endpoint,
+        writers: &Arc<Writers>,
+        buffer_size: usize,
+        stopped: &Arc<AtomicBool>,
+    ) -> io::Result<()> {
+        // with room for every acceptor, none blocks on its way out
+        let (exits, exited) = bounded(self.threads);
+
+        for _ in 0..self.threads {
+            let listener = listener.try_clone()?;
+            let conns = self.conns.clone();
+            let handshake = self.handshake.clone();
+            let handshake_timeout = self.handshake_timeout;
+            let quotas = self.quotas.clone();
+            let default_quota = self.default_quota;
+            let tls = self.tls.clone();
+            let ack_window = self.ack_window;
+            let max_connections = self.max_connections;
+            let ip_filter = self.ip_filter.clone();
+            let stall_timeout = self.stall_timeout;
+            let handshaking = self.handshaking.clone();
+            let rejected_connections = self.rejected_connections.clone();
+            let writers = writers.clone();
+            let stopped = stopped.clone();
+            let exit = AcceptorExit(exits.clone());
+            let mut errors = 0;
+
+            thread::spawn(move || loop {
+                let _exit = &exit;
+                let stream = listener.accept();
+
+                if stopped.load(Ordering::Relaxed) {
+                    break;
+                }
+
+                match stream {
+                    Ok(stream) => {
+                        errors = 0;
+                        // unix socket peers have no address to filter
+                        let peer = stream.peer_addr();
+                        if let (Some(ip_filter), Some(peer)) = (&ip_filter, peer) {
+                            if !ip_filter.permits(peer.ip()) {
+                                rejected_connections.fetch_add(1, Ordering::Relaxed);
+                                warn!("Connection from {} rejected by the IP filter", peer);
+                                continue;
+                            }
+                        }
+                        if let Some(max_connections) = max_connections {
+                            let open = conns.read().map_or(0, |conns| conns.len())
+                                + handshaking.load(Ordering::Relaxed);
+                            if open >= max_connections {
+                                rejected_connections.fetch_add(1, Ordering::Relaxed);
+                                warn!(
+                                    "Connection from {:?} rejected, {} connections are open",
+                                    peer, open
+                                );
+                                continue;
+                            }
+                        }
+                        handshaking.fetch_add(1, Ordering::Relaxed);
+
+                        let conns = conns.clone();
+                        let handshaking = handshaking.clone();
+                        let handshake = handshake.clone();
+                        let quotas = quotas.clone();
+                        let tls = tls.clone();
+                        let writers = writers.clone();
+
+                        // the handshake waits on the client, keep accepting meanwhile
+                        thread::spawn(move || {
+                            let _handshaking = Handshaking(handshaking);
+                            let peer = stream.peer_addr();
+                            let mut stream = match tls {
+                                Some(tls) => match stream.into_tls(tls, TLS_HANDSHAKE_TIMEOUT) {
+                                    Ok(stream) => stream,
+                                    // includes the clients failing certificate verification
+                                    Err(e) => {
+                                        error!("TLS handshake error from {:?}: {}", peer, e);
+                                        return;
+                                    }
+                                },
+                                None => stream,
+                            };
+                            let common_name = stream.peer_common_name();
+
+                            let hello = match exchange_hellos(
+                                &mut stream,
+                                handshake.as_deref(),
+                                handshake_timeout,
+                            ) {
+                                Ok(hello) => hello,
+                                Err(e) => {
+                                    error!("Handshake error: {}", e);
+                                    return;
+                                }
+                            };
+
+                            let schema_version = match hello.schema_version {
+                                Some(version) if SUPPORTED_SCHEMA_VERSIONS.contains(&version) => {
+                                    version
+                                }
+                                Some(version) => {
+                                    warn!(
+                                        "Unsupported schema version {}, using {}",
+                                        version, SCHEMA_VERSION_V1
+                                    );
+                                    SCHEMA_VERSION_V1
+                                }
+                                None => SCHEMA_VERSION_V1,
+                            };
+
+                            let quota = hello
+                                .name
+                                .as_ref()
+                                .and_then(|name| quotas.get(name))
+                                .or(default_quota.as_ref())
+                                .map(|quota| QuotaUsage::new(*quota));
+
+                            let protocol_version =
+                                hello.protocol_version.unwrap_or(PROTOCOL_VERSION_V1);
+                            let closer = stream.closer().ok();
+                            let (stream, ack_stream) = match stream.into_async(&writers.handle) {
+                                Ok(halves) => halves,
+                                Err(e) => {
+                                    error!("Error handing {:?} to the writers: {}", peer, e);
+                                    return;
+                                }
+                            };
+                            // the acks are read from their own half of the stream
+                            let ack_stream = match (hello.acks, ack_stream) {
+                                (true, _) if protocol_version < PROTOCOL_VERSION_V3 => {
+                                    warn!(
+                                        "Acks need protocol version {}, ignored",
+                                        PROTOCOL_VERSION_V3
+                                    );
+                                    None
+                                }
+                                (true, None) => {
+                                    warn!("Acks ignored: TLS streams cannot be split");
+                                    None
+                                }
+                                (acks, ack_stream) => ack_stream.filter(|_| acks),
+                            };
+                            let acks = ack_stream.as_ref().map(|_| Acks {
+                                window: ack_window,
+                                acked: AtomicU64::new(0),
+                                released: Arc::new(Notify::new()),
+                            });
+                            let (queue, queued) = tokio::sync::mpsc::channel(buffer_size.max(1));
+
+                            let name = hello.name.clone().or(common_name);
+                            let conn = add_conn(&conns, name.as_deref(), |id| Connection {
+                                id,
+                                name: hello.name,
+                                labels: hello.labels,
+                                group: hello.group,
+                                peer,
+                                closer,
+                                queue,
+                                pending: AtomicUsize::new(0),
+                                pending_bytes: AtomicUsize::new(0),
+                                buffer_size,
+                                stalled_since: Mutex::new(None),
+                                waiting_since: Mutex::new(None),
+                                stall_timeout,
+                                closed: AtomicBool::new(false),
+                                filters: Some(hello.filters).filter(|filters| !filters.is_empty()),
+                                protocol_version,
+                                next_sequence: AtomicU64::new(0),
+                                acks,
+                                schema_version,
+                                compression: hello.compression,
+                                sent_batches: AtomicU64::new(0),
+                                sent_bytes: AtomicU64::new(0),
+                                dropped_batches: AtomicU64::new(0),
+                                expired_batches: AtomicU64::new(0),
+                                filtered_messages: AtomicU64::new(0),
+                                quota,
+                                quota_exceeded: AtomicU64::new(0),
+                            });
+
+                            info!("Subscriber {} connected from {:?}", conn.label(), conn.peer);
+                            writers.spawn(&conn, queued, stream, ack_stream);
+                        });
+                    }
+                    Err(e) => {
+                        error!("Error accepting connection: {}", e);
+                        errors += 1;
+                        if errors >= MAX_ACCEPT_ERRORS {
+                            break;
+                        }
+                        thread::sleep(ACCEPT_ERROR_BACKOFF);
+                    }
+                }
+            });
+        }
+        drop(exits);
+
+        let acceptors = self.clone();
+        let endpoint = endpoint.clone();
+        let writers = writers.clone();
+        let stopped = stopped.clone();
+        thread::spawn(move || acceptors.supervise(exited, endpoint, writers, buffer_size, stopped));
+
+        Ok(())
+    }
+
+    /// Waits for an acceptor of the listener to return. Unless the listener was stopped
+    /// meanwhile, the other acceptors are stopped and the listener is bound again, until it
+    /// succeeds or the listener is replaced.
+    fn supervise(
+        self,
+        exited: Receiver<()>,
+        endpoint: Endpoint,
+        writers: Arc<Writers>,
+        buffer_size: usize,
+        stopped: Arc<AtomicBool>,
+    ) {
+        if exited.recv().is_err() || stopped.load(Ordering::Relaxed) {
+            return;
+        }
+
+        self.listener_recoveries.fetch_add(1, Ordering::Relaxed);
+        error!("Listener on {:?} lost an acceptor, recovering", endpoint);
+
+        stop_listener(&endpoint, &stopped, self.threads - 1);
+        // the socket is free to bind once every acceptor dropped its handle to it
+        for _ in 1..self.threads {
+            if exited.recv_timeout(WAKE_TIMEOUT).is_err() {
+                break;
+            }
+        }
+
+        loop {
+            let mut current = self.listening.lock().unwrap_or_else(|e| e.into_inner());
+            let Some(listening) = current
+                .as_mut()
+                .filter(|listening| Arc::ptr_eq(&listening.stopped, &stopped))
+            else {
+                // replaced by rebind or shutdown meanwhile
+                return;
+            };
+
+            let recovered = endpoint.bind(self.only_v6).and_then(|listener| {
+                let stopped = Arc::new(AtomicBool::new(false));
+                self.spawn(&listener, &endpoint, &writers, buffer_size, &stopped)?;
+                Ok(stopped)
+            });
+            match recovered {
+                Ok(stopped) => {
+                    listening.stopped = stopped;
+                    info!("Listener on {:?} recovered", endpoint);
+                    return;
+                }
+                Err(e) => warn!("Error binding the listener on {:?}: {}", endpoint, e),
+            }
+            drop(current);
+
+            thread::sleep(RECOVERY_INTERVAL);
+        }
+    }
+}
+
+/// Makes `acceptors` acceptors of a listener return, once the handshakes they started are
+/// done.
+pub(super) fn stop_listener(endpoint: &Endpoint, stopped: &AtomicBool, acceptors: usize) {
+    stopped.store(true, Ordering::Relaxed);
+
+    // every acceptor is blocked accepting, a connection each makes them return
+    for _ in 0..acceptors {
+        if let Err(e) = endpoint.touch(WAKE_TIMEOUT) {
+            warn!("Error closing listener on {:?}: {}", endpoint, e);
+        }
+    }
+
+    #[cfg(unix)]
+    if let Endpoint::Unix(path) = endpoint {
+        // no new connection reaches the listener once its socket is gone
+        if let Err(e) = std::fs::remove_file(path) {
+            warn!("Error removing {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Sends the server hello, if any, then waits for the optional client hello.
+/// Clients which don't send one within `timeout` get the default `ClientHello`.
+fn exchange_hellos(
+    stream: &mut Stream,
+    server_hello: Option<&[u8]>,
+    timeout: Duration,
+) -> io::Result<ClientHello> {
+    if let Some(server_hello) = server_hello {
+        stream.write_all(server_hello)?;
+    }
+
+    stream.set_read_timeout(Some(timeout))?;
+
+    let mut header = [0; HEADER_BYTE_SIZE];
+    let hello = match stream.read_exact(&mut header) {
+        Ok(()) => read_client_hello(stream, u32::from_le_bytes(header) as usize)?,
+        Err(e)
+            if matches!(
+                e.kind(),
+                io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+            ) =>
+        {
+            ClientHello::default()
+        }
+        Err(e) => return Err(e),
+    };
+
+    stream.set_read_timeout(None)?;
+
+    // unlike an unknown schema version, a framing the client can't read can't be served
+    if let Some(version) = hello.protocol_version {
+        if !SUPPORTED_PROTOCOL_VERSIONS.contains(&version) {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("unsupported protocol version {}", version),
+            ));
+        }
+    }
+
+    Ok(hello)
+}
+
+fn read_client_hello(stream: &mut Stream, size: usize) -> io::Result<ClientHello> {
+    if size <= HEADER_BYTE_SIZE || size > MAX_CLIENT_HELLO_BYTES {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid client hello size {}", size),
+        ));
+    }
+
+    let mut body = vec![0; size];
+    stream.read_exact(&mut body)?;
+
+    ClientHello::from_message(&body[HEADER_BYTE_SIZE..])
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid client hello"))
+}
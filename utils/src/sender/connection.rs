@@ -0,0 +1,285 @@
+//! Connected subscribers, with the queue of batches to write to each and its counters.
+use log::warn;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc::error::TrySendError, Notify};
+use uuid::Uuid;
+
+use super::stream::Closer;
+use super::Batch;
+use crate::compression::CompressionCodec;
+use crate::filters::SubscriptionFilters;
+use crate::handshake::PROTOCOL_VERSION_V3;
+use crate::quota::QuotaUsage;
+
+pub(super) type ConnectionMap = HashMap<String, Arc<Connection>>;
+// a batch shared by the connections it's sent to, with when its oldest message was published
+// and its sequence number on the connection, written in its header by the writer
+pub(super) type QueuedBatch = (Arc<Batch>, Instant, Option<u64>);
+
+/// Acknowledgements of a subscriber which asked for them in its client hello.
+pub(super) struct Acks {
+    // batches written and not acknowledged past which the next ones are held
+    pub(super) window: u64,
+    // sequence number of the first batch not acknowledged
+    pub(super) acked: AtomicU64,
+    // wakes the writer up to deliver the batches acknowledgements made room for
+    pub(super) released: Arc<Notify>,
+}
+
+impl Acks {
+    /// Acknowledges the batch if it's past `message_ttl`: it won't be written, hence never
+    /// acknowledged by the client, and would otherwise hold back the next ones forever.
+    pub(super) fn ack_expired(
+        &self,
+        sequence: u64,
+        published_at: Instant,
+        message_ttl: Option<Duration>,
+    ) {
+        if message_ttl.is_some_and(|ttl| published_at.elapsed() > ttl) {
+            self.acked.fetch_max(sequence + 1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// A subscriber, written to by a task of its own on the writers' runtime, see `Writers`.
+pub(super) struct Connection {
+    // key of the connection map, see `add_conn`
+    pub(super) id: String,
+    // name and labels the client identified itself with
+    pub(super) name: Option<String>,
+    pub(super) labels: BTreeMap<String, String>,
+    // consumer group the connection shares the batches with
+    pub(super) group: Option<String>,
+    pub(super) peer: Option<SocketAddr>,
+    // closes the stream while the writer waits on it
+    pub(super) closer: Option<Closer>,
+    pub(super) queue: tokio::sync::mpsc::Sender<QueuedBatch>,
+    // batches queued to the writer and not written yet, bounded by buffer_size
+    pub(super) pending: AtomicUsize,
+    pub(super) pending_bytes: AtomicUsize,
+    pub(super) buffer_size: usize,
+    // since when the buffer has been full with nothing written, None while writes go through
+    pub(super) stalled_since: Mutex<Option<Instant>>,
+    // how long the connection may stay stalled before being evicted, forever if not set
+    pub(super) stall_timeout: Option<Duration>,
+    // since when batches have been queued with none written, None while the queue is empty,
+    // see `TcpSender::with_writer_watchdog`
+    pub(super) waiting_since: Mutex<Option<Instant>>,
+    pub(super) closed: AtomicBool,
+    // set from the client hello, the connection gets every message if not set
+    pub(super) filters: Option<SubscriptionFilters>,
+    // framing of the batches, selected in the client hello
+    pub(super) protocol_version: u32,
+    // sequence number of the next batch, batches dropped for the connection take one too
+    pub(super) next_sequence: AtomicU64,
+    pub(super) acks: Option<Acks>,
+    pub(super) schema_version: u32,
+    // codec the client asked for, the sender's one if not set
+    pub(super) compression: Option<CompressionCodec>,
+    pub(super) sent_batches: AtomicU64,
+    pub(super) sent_bytes: AtomicU64,
+    pub(super) dropped_batches: AtomicU64,
+    // batches dropped for waiting in the queue past the message TTL
+    pub(super) expired_batches: AtomicU64,
+    // messages left out of the connection's batches by its filters
+    pub(super) filtered_messages: AtomicU64,
+    // quota configured for the connection's name
+    pub(super) quota: Option<QuotaUsage>,
+    pub(super) quota_exceeded: AtomicU64,
+}
+
+/// A connected subscriber and its counters, as listed by `TcpSender::subscribers`.
+#[derive(Serialize, Debug, Clone)]
+pub struct SubscriberInfo {
+    /// Name the subscriber identified itself with, or the common name of its certificate,
+    /// suffixed with `#n` when shared with other subscribers, a random id otherwise
+    pub id: String,
+    pub name: Option<String>,
+    pub labels: BTreeMap<String, String>,
+    /// Consumer group the subscriber joined, see `ClientHello::group`
+    pub group: Option<String>,
+    pub peer: Option<SocketAddr>,
+    pub sent_batches: u64,
+    pub sent_bytes: u64,
+    /// Batches not queued because the subscriber's buffer was full
+    pub dropped_batches: u64,
+    /// Batches dropped for being older than the message TTL when their turn came
+    pub expired_batches: u64,
+    pub pending_batches: usize,
+    /// Messages left out by the subscriber's filters
+    pub filtered_messages: u64,
+    /// Batches the quota action was applied to
+    pub quota_exceeded: u64,
+}
+
+impl SubscriberInfo {
+    /// Name the subscriber is logged with, its id.
+    pub fn label(&self) -> &str {
+        &self.id
+    }
+}
+
+impl Connection {
+    pub(super) fn try_send(
+        self: &Arc<Self>,
+        batch: Arc<Batch>,
+        published_at: Instant,
+    ) -> Result<(), TrySendError<Arc<Batch>>> {
+        if self.closed.load(Ordering::Relaxed) {
+            return Err(TrySendError::Closed(batch));
+        }
+
+        if self.pending.load(Ordering::Relaxed) >= self.buffer_size {
+            if self.stalled() {
+                self.evict();
+                return Err(TrySendError::Closed(batch));
+            }
+
+            self.dropped_batches.fetch_add(1, Ordering::Relaxed);
+            return Err(TrySendError::Full(batch));
+        }
+
+        let sequence = (self.protocol_version >= PROTOCOL_VERSION_V3)
+            .then(|| self.next_sequence.fetch_add(1, Ordering::Relaxed));
+
+        {
+            let mut waiting_since = self.waiting_since.lock().unwrap_or_else(|e| e.into_inner());
+            self.pending.fetch_add(1, Ordering::Relaxed);
+            waiting_since.get_or_insert_with(Instant::now);
+        }
+        let len = batch.len();
+        self.pending_bytes.fetch_add(len, Ordering::Relaxed);
+        // the queue has room for buffer_size batches, only ever full once the writer is gone
+        self.queue
+            .try_send((batch, published_at, sequence))
+            .map_err(|e| {
+                self.pending.fetch_sub(1, Ordering::Relaxed);
+                self.pending_bytes.fetch_sub(len, Ordering::Relaxed);
+                match e {
+                    TrySendError::Full((batch, ..)) | TrySendError::Closed((batch, ..)) => {
+                        TrySendError::Closed(batch)
+                    }
+                }
+            })
+    }
+
+    /// Restarts the watchdog's clock once the writer is done with one of the connection's
+    /// batches, or stops it if none is left.
+    pub(super) fn progressed(&self) {
+        let mut waiting_since = self.waiting_since.lock().unwrap_or_else(|e| e.into_inner());
+        *waiting_since = (self.pending.load(Ordering::Relaxed) > 0).then(Instant::now);
+    }
+
+    /// Set once batches have been queued to the connection with none written for longer than
+    /// `timeout`.
+    pub(super) fn hung(&self, timeout: Duration) -> bool {
+        self.waiting_since
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_some_and(|since| since.elapsed() > timeout)
+    }
+
+    /// Set once the buffer has been full with nothing written for longer than the stall
+    /// timeout, starting the clock if it just filled up.
+    fn stalled(&self) -> bool {
+        let Some(stall_timeout) = self.stall_timeout else {
+            return false;
+        };
+
+        let mut stalled_since = self.stalled_since.lock().unwrap_or_else(|e| e.into_inner());
+        stalled_since.get_or_insert_with(Instant::now).elapsed() > stall_timeout
+    }
+
+    /// Closes a stalled connection, its writer gives up on the write it's blocked on and the
+    /// queued batches are released.
+    fn evict(&self) {
+        warn!(
+            "Subscriber {} stalled for more than {:?}, disconnecting",
+            self.label(),
+            self.stall_timeout.unwrap_or_default()
+        );
+
+        self.closed.store(true, Ordering::Relaxed);
+        if let Some(Err(e)) = self.closer.as_ref().map(Closer::close) {
+            warn!("Error closing {}: {}", self.label(), e);
+        }
+    }
+
+    /// Records the acknowledgement of the batches up to `sequence`, and wakes the writer up to
+    /// deliver the held batches as far as the window allows.
+    pub(super) fn ack(&self, sequence: u64) {
+        if let Some(acks) = &self.acks {
+            acks.acked.fetch_max(sequence + 1, Ordering::Relaxed);
+            acks.released.notify_one();
+        }
+    }
+
+    /// Skips the sequence number of a batch the connection won't get, so that the client
+    /// sees the gap.
+    pub(super) fn skip_batch(&self) {
+        self.next_sequence.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Name the connection is logged with, its id.
+    pub(super) fn label(&self) -> &str {
+        &self.id
+    }
+
+    pub(super) fn info(&self) -> SubscriberInfo {
+        SubscriberInfo {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            labels: self.labels.clone(),
+            group: self.group.clone(),
+            peer: self.peer,
+            sent_batches: self.sent_batches.load(Ordering::Relaxed),
+            sent_bytes: self.sent_bytes.load(Ordering::Relaxed),
+            dropped_batches: self.dropped_batches.load(Ordering::Relaxed),
+            expired_batches: self.expired_batches.load(Ordering::Relaxed),
+            pending_batches: self.pending.load(Ordering::Relaxed),
+            filtered_messages: self.filtered_messages.load(Ordering::Relaxed),
+            quota_exceeded: self.quota_exceeded.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Adds the connection under the id it's logged and reported with: `name`, the name
+/// the client identified itself with or the common name of its certificate, suffixed
+/// with `#2`, `#3`... when other subscribers have it, a random id if there is none.
+pub(super) fn add_conn(
+    conns: &Arc<RwLock<ConnectionMap>>,
+    name: Option<&str>,
+    conn: impl FnOnce(String) -> Connection,
+) -> Arc<Connection> {
+    let mut conns = conns.write().unwrap_or_else(|e| e.into_inner());
+    let id = match name {
+        Some(name) => (1..)
+            .map(|n| match n {
+                1 => name.to_string(),
+                n => format!("{}#{}", name, n),
+            })
+            .find(|id| !conns.contains_key(id))
+            .unwrap_or_default(),
+        None => Uuid::new_v4().to_string(),
+    };
+
+    let conn = Arc::new(conn(id.clone()));
+    conns.insert(id, conn.clone());
+    conn
+}
+
+/// Removes the connection, unless its id was already given to a new one.
+pub(super) fn remove_conn(conns: &Arc<RwLock<ConnectionMap>>, conn: &Arc<Connection>) {
+    let mut conns = conns.write().unwrap_or_else(|e| e.into_inner());
+    if conns
+        .get(&conn.id)
+        .is_some_and(|current| Arc::ptr_eq(current, conn))
+    {
+        conns.remove(&conn.id);
+    }
+}
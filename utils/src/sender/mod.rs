@@ -0,0 +1,2517 @@
+use core::time;
+use log::{error, info, warn};
+use rayon::prelude::*;
+use rayon::ThreadPool;
+use rustls::ServerConfig;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{self, IoSlice};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+#[cfg(unix)]
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc::error::TrySendError;
+
+use crate::chunk::{split_message, CHUNK_HEADER_BYTE_SIZE};
+use crate::compression::{CompressionCodec, CompressionPolicy};
+use crate::dead_letter::DeadLetters;
+use crate::encryption::PayloadCipher;
+use crate::errors::GeyserError;
+use crate::filters::SubscriptionFilters;
+use crate::handshake::{ServerHello, PROTOCOL_VERSION_V1, PROTOCOL_VERSION_V2, SCHEMA_VERSION_V1};
+use crate::ip_filter::IpFilter;
+use crate::message_type::MessageType;
+use crate::quota::{QuotaAction, SubscriberQuota};
+use crate::wal::WriteAheadLog;
+
+mod acceptors;
+mod connection;
+#[cfg(feature = "nats")]
+mod nats_sender;
+#[cfg(feature = "quic")]
+mod quic_sender;
+mod router;
+mod shard;
+mod stream;
+mod writers;
+#[cfg(feature = "ws")]
+mod ws_sender;
+#[cfg(feature = "zmq")]
+mod zmq_sender;
+
+use acceptors::{stop_listener, Acceptors, Listening};
+use connection::{remove_conn, Connection, ConnectionMap};
+use stream::{bind_tcp, Closer, Endpoint, Listener};
+use writers::{spawn_watchdog, Writers};
+
+pub use connection::SubscriberInfo;
+#[cfg(feature = "nats")]
+pub use nats_sender::NatsSender;
+#[cfg(feature = "quic")]
+pub use quic_sender::{QuicSender, QUIC_ALPN, QUIC_KEEP_ALIVE};
+pub use router::Router;
+pub use shard::{shard_of, Sharder};
+#[cfg(feature = "ws")]
+pub use ws_sender::WsSender;
+#[cfg(feature = "zmq")]
+pub use zmq_sender::ZmqSender;
+
+const DEFAULT_VECTOR_PREALLOC: usize = 1024 * 1024;
+pub const HEADER_BYTE_SIZE: usize = 4;
+pub const CHECKSUM_BYTE_SIZE: usize = 4;
+pub const SEQUENCE_BYTE_SIZE: usize = 8;
+pub const DEFAULT_ACCEPTOR_THREADS: usize = 1;
+pub const DEFAULT_WRITER_THREADS: usize = 4;
+pub const DEFAULT_HANDSHAKE_TIMEOUT: Duration = Duration::from_millis(100);
+/// Without a timeout, a peer which stopped reading would keep its queue full, and its batches
+/// dropped, for as long as it keeps the connection open.
+pub const DEFAULT_WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+pub const DEFAULT_ACK_WINDOW: u64 = 16;
+
+/// The publishing side of a transport, what the plugin streams its messages through.
+/// Only `TcpSender` knows its subscribers and can be moved to another address.
+pub trait Publisher: Send + Sync {
+    fn publish(&self, message: Vec<u8>) -> Result<(), GeyserError> {
+        self.publish_for_schema(message, None)
+    }
+
+    /// Publishes a message only to the subscribers using `schema_version`, if set.
+    fn publish_for_schema(
+        &self,
+        message: Vec<u8>,
+        schema_version: Option<u32>,
+    ) -> Result<(), GeyserError>;
+
+    fn has_subscribers_with_schema(&self, schema_version: u32) -> bool;
+
+    /// Whether published messages reach a subscriber or are kept for one, so that building
+    /// them can be skipped otherwise. Transports which can't tell always need them.
+    fn needs_messages(&self) -> bool {
+        true
+    }
+
+    /// Stops accepting new messages and flushes the published ones, waiting up to `timeout`.
+    /// Returns the number of bytes abandoned.
+    fn drain(&self, timeout: Duration) -> Result<usize, GeyserError>;
+
+    /// Closes the listener and the connections, see `drain` to flush them first.
+    fn shutdown(&self) {}
+
+    fn subscribers(&self) -> Result<Vec<SubscriberInfo>, GeyserError> {
+        Ok(Vec::new())
+    }
+
+    fn rebind(&self, _addr: SocketAddr) -> io::Result<SocketAddr> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the transport cannot be moved",
+        ))
+    }
+
+    fn local_addr(&self) -> Option<SocketAddr> {
+        None
+    }
+
+    fn expired_messages(&self) -> u64 {
+        0
+    }
+
+    fn rejected_connections(&self) -> u64 {
+        0
+    }
+
+    fn stalled_connections(&self) -> u64 {
+        0
+    }
+
+    fn abandoned_batches(&self) -> u64 {
+        0
+    }
+
+    fn listener_recoveries(&self) -> u64 {
+        0
+    }
+
+    fn hung_connections(&self) -> u64 {
+        0
+    }
+
+    fn writer_panics(&self) -> u64 {
+        0
+    }
+}
+
+struct BufferedMessage {
+    // prefixed with its size, shared by the batches it's in
+    framed: Arc<[u8]>,
+    // framed, compressed with the codecs subscribers asked for instead of the sender's one
+    alternates: Vec<(CompressionCodec, Arc<[u8]>)>,
+    // only sent to the connections using this schema version if set
+    schema_version: Option<u32>,
+    published_at: Instant,
+}
+
+pub struct TcpBuffer {
+    data: Vec<BufferedMessage>,
+    total_bytesize: usize,
+    versioned: usize,
+}
+
+impl TcpBuffer {
+    pub fn new(prealloc: usize) -> Self {
+        TcpBuffer {
+            data: Vec::with_capacity(prealloc),
+            total_bytesize: 0,
+            versioned: 0,
+        }
+    }
+
+    pub fn append(&mut self, msg: Vec<u8>) {
+        self.append_for_schema(msg, None);
+    }
+
+    /// Appends a message meant only for the connections using `schema_version`, if set.
+    pub fn append_for_schema(&mut self, msg: Vec<u8>, schema_version: Option<u32>) {
+        self.append_with_alternates(msg, Vec::new(), schema_version);
+    }
+
+    /// Appends a message along with its versions compressed with other codecs.
+    fn append_with_alternates(
+        &mut self,
+        msg: Vec<u8>,
+        alternates: Vec<(CompressionCodec, Vec<u8>)>,
+        schema_version: Option<u32>,
+    ) {
+        let framed = frame(&msg);
+
+        self.total_bytesize += framed.len();
+        if schema_version.is_some() {
+            self.versioned += 1;
+        }
+        self.data.push(BufferedMessage {
+            framed: framed.into(),
+            alternates: alternates
+                .into_iter()
+                .map(|(codec, msg)| (codec, frame(&msg).into()))
+                .collect(),
+            schema_version,
+            published_at: Instant::now(),
+        });
+    }
+
+    /// Removes the messages published more than `ttl` ago, returns how many were removed.
+    fn expire(&mut self, ttl: Duration) -> usize {
+        let before = self.data.len();
+        self.data.retain(|msg| msg.published_at.elapsed() <= ttl);
+
+        self.total_bytesize = self.data.iter().map(|msg| msg.framed.len()).sum();
+        self.versioned = self
+            .data
+            .iter()
+            .filter(|msg| msg.schema_version.is_some())
+            .count();
+
+        before - self.data.len()
+    }
+
+    /// When the oldest buffered message was published, now if there is none.
+    fn published_at(&self) -> Instant {
+        self.data
+            .first()
+            .map_or_else(Instant::now, |msg| msg.published_at)
+    }
+
+    pub fn total_bytesize(&self) -> usize {
+        self.total_bytesize
+    }
+
+    pub fn clear(&mut self) {
+        self.data.clear();
+        self.total_bytesize = 0;
+        self.versioned = 0;
+    }
+
+    pub fn flush_data(&mut self) -> Vec<u8> {
+        let batch = encode_batch(
+            self.data.iter().map(|msg| &*msg.framed),
+            self.total_bytesize,
+            PROTOCOL_VERSION_V1,
+        );
+
+        // Clear buffers
+        self.clear();
+
+        batch
+    }
+}
+
+/// The messages buffered until they fill a batch, and the last batch, locked apart so that
+/// publishers fill the next batch while the last one is sent, strict delivery retries included.
+struct BatchBuffer {
+    filling: Mutex<TcpBuffer>,
+    // emptied once sent, then swapped with the next batch
+    flushing: Mutex<TcpBuffer>,
+}
+
+impl BatchBuffer {
+    fn new(prealloc: usize) -> Self {
+        BatchBuffer {
+            filling: Mutex::new(TcpBuffer::new(prealloc)),
+            flushing: Mutex::new(TcpBuffer::new(prealloc)),
+        }
+    }
+
+    /// Swaps the filled buffer with the emptied one once the last batch is sent, so that the
+    /// batches are sent in order, and returns the batch, locked until it's sent.
+    fn take_batch(
+        &self,
+        filling: &mut TcpBuffer,
+    ) -> Result<MutexGuard<'_, TcpBuffer>, GeyserError> {
+        let mut flushing = self
+            .flushing
+            .lock()
+            .map_err(|_| GeyserError::SenderLockError)?;
+        std::mem::swap(filling, &mut *flushing);
+
+        Ok(flushing)
+    }
+}
+
+impl BufferedMessage {
+    /// The framed message compressed with `codec`, with the sender's codec if not set.
+    fn framed_with(&self, codec: Option<CompressionCodec>) -> &Arc<[u8]> {
+        codec
+            .and_then(|codec| self.alternates.iter().find(|(c, _)| *c == codec))
+            .map_or(&self.framed, |(_, framed)| framed)
+    }
+}
+
+/// Prefixes a message with its size.
+fn frame(msg: &[u8]) -> Vec<u8> {
+    let mut framed = Vec::with_capacity(HEADER_BYTE_SIZE + msg.len());
+    framed.extend_from_slice(&(msg.len() as u32).to_le_bytes());
+    framed.extend_from_slice(msg);
+
+    framed
+}
+
+/// What a connection gets out of the buffered messages, connections with the same selection
+/// share their batch.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct BatchSelection<'a> {
+    filters: Option<&'a SubscriptionFilters>,
+    skip_accounts: bool,
+    protocol_version: u32,
+    schema_version: u32,
+    // set if the connection asked for another codec than the sender's one
+    compression: Option<CompressionCodec>,
+}
+
+// where the sequence number goes in a batch, set per connection when written
+const SEQUENCE_OFFSET: usize = HEADER_BYTE_SIZE + CHECKSUM_BYTE_SIZE;
+
+/// Size of the header of a batch, before its messages.
+fn batch_header_size(protocol_version: u32) -> usize {
+    match protocol_version {
+        PROTOCOL_VERSION_V1 => HEADER_BYTE_SIZE,
+        PROTOCOL_VERSION_V2 => HEADER_BYTE_SIZE + CHECKSUM_BYTE_SIZE,
+        _ => HEADER_BYTE_SIZE + CHECKSUM_BYTE_SIZE + SEQUENCE_BYTE_SIZE,
+    }
+}
+
+/// Concatenates size prefixed messages into a batch, prefixed with their total size, their
+/// checksum from `PROTOCOL_VERSION_V2` on and room for a sequence number from
+/// `PROTOCOL_VERSION_V3` on.
+fn encode_batch<'a>(
+    messages: impl Iterator<Item = &'a [u8]>,
+    total_bytesize: usize,
+    protocol_version: u32,
+) -> Vec<u8> {
+    let header_size = batch_header_size(protocol_version);
+    let mut batch = Vec::with_capacity(header_size + total_bytesize);
+    batch.extend_from_slice(&(total_bytesize as u32).to_le_bytes());
+    batch.resize(header_size, 0);
+    messages.for_each(|msg| {
+        batch.extend_from_slice(msg);
+    });
+
+    if protocol_version >= PROTOCOL_VERSION_V2 {
+        let checksum = crc32fast::hash(&batch[header_size..]);
+        batch[HEADER_BYTE_SIZE..HEADER_BYTE_SIZE + CHECKSUM_BYTE_SIZE]
+            .copy_from_slice(&checksum.to_le_bytes());
+    }
+
+    batch
+}
+
+/// The messages of the buffer meant for every connection, i.e. of the default schema, as a
+/// `PROTOCOL_VERSION_V1` batch. None if there are none.
+fn replay_batch(buffer: &TcpBuffer) -> Option<Vec<u8>> {
+    let messages = buffer
+        .data
+        .iter()
+        .filter(|msg| msg.schema_version.map_or(true, |v| v == SCHEMA_VERSION_V1))
+        .map(|msg| &*msg.framed)
+        .collect::<Vec<_>>();
+    if messages.is_empty() {
+        return None;
+    }
+
+    let total_bytesize = messages.iter().map(|msg| msg.len()).sum();
+    Some(encode_batch(
+        messages.into_iter(),
+        total_bytesize,
+        PROTOCOL_VERSION_V1,
+    ))
+}
+
+/// Re-encodes a `PROTOCOL_VERSION_V1` batch for `protocol_version`, with only the messages
+/// matching `filters` if set. None if no message is left, along with the number of messages
+/// left out.
+fn reframe_batch(
+    batch: &[u8],
+    filters: Option<&SubscriptionFilters>,
+    protocol_version: u32,
+) -> (Option<Vec<u8>>, u64) {
+    let messages = &batch[HEADER_BYTE_SIZE.min(batch.len())..];
+    let Some(filters) = filters else {
+        return (
+            Some(encode_batch(
+                std::iter::once(messages),
+                messages.len(),
+                protocol_version,
+            )),
+            0,
+        );
+    };
+
+    let mut selected = Vec::new();
+    let mut filtered = 0;
+    let mut i = 0;
+    while let Some(size) = messages
+        .get(i..i + HEADER_BYTE_SIZE)
+        .and_then(|size| size.try_into().ok())
+        .map(|size| u32::from_le_bytes(size) as usize)
+    {
+        let end = (i + HEADER_BYTE_SIZE + size).min(messages.len());
+        let framed = &messages[i..end];
+        if filters.matches(&framed[HEADER_BYTE_SIZE..]) {
+            selected.push(framed);
+        } else {
+            filtered += 1;
+        }
+        i = end;
+    }
+
+    if selected.is_empty() {
+        return (None, filtered);
+    }
+
+    let total_bytesize = selected.iter().map(|msg| msg.len()).sum();
+    (
+        Some(encode_batch(
+            selected.into_iter(),
+            total_bytesize,
+            protocol_version,
+        )),
+        filtered,
+    )
+}
+
+/// A batch queued to connections, its header and its size prefixed messages kept apart so
+/// that they are written with a single vectored write instead of being copied together first.
+struct Batch {
+    // size, checksum and room for the sequence number, as per the protocol version
+    header: Vec<u8>,
+    messages: Vec<Arc<[u8]>>,
+}
+
+impl Batch {
+    /// Batch of size prefixed messages, framed as `protocol_version`.
+    fn new(messages: Vec<Arc<[u8]>>, protocol_version: u32) -> Self {
+        let total_bytesize = messages.iter().map(|msg| msg.len()).sum::<usize>();
+        let mut header = vec![0; batch_header_size(protocol_version)];
+        header[..HEADER_BYTE_SIZE].copy_from_slice(&(total_bytesize as u32).to_le_bytes());
+
+        if protocol_version >= PROTOCOL_VERSION_V2 {
+            let mut hasher = crc32fast::Hasher::new();
+            messages.iter().for_each(|msg| hasher.update(msg));
+            header[HEADER_BYTE_SIZE..SEQUENCE_OFFSET]
+                .copy_from_slice(&hasher.finalize().to_le_bytes());
+        }
+
+        Batch { header, messages }
+    }
+
+    /// Batch already encoded by `encode_batch` as `protocol_version`.
+    fn from_encoded(mut batch: Vec<u8>, protocol_version: u32) -> Self {
+        let messages = batch.split_off(batch_header_size(protocol_version).min(batch.len()));
+
+        Batch {
+            header: batch,
+            messages: vec![messages.into()],
+        }
+    }
+
+    /// The batch with its messages sealed by `cipher`, see `encryption`.
+    fn seal(self, cipher: &PayloadCipher) -> Self {
+        let sealed: Arc<[u8]> = cipher.seal(&self.messages.concat()).into();
+
+        let mut header = self.header;
+        header[..HEADER_BYTE_SIZE].copy_from_slice(&(sealed.len() as u32).to_le_bytes());
+        if header.len() > HEADER_BYTE_SIZE {
+            header[HEADER_BYTE_SIZE..SEQUENCE_OFFSET]
+                .copy_from_slice(&crc32fast::hash(&sealed).to_le_bytes());
+        }
+
+        Batch {
+            header,
+            messages: vec![sealed],
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.header.len() + self.messages.iter().map(|msg| msg.len()).sum::<usize>()
+    }
+
+    /// Writes the batch, with `sequence` in its header if set.
+    async fn write_to(
+        &self,
+        stream: &mut (impl AsyncWrite + Unpin),
+        sequence: Option<u64>,
+    ) -> io::Result<()> {
+        let mut header = [0; SEQUENCE_OFFSET + SEQUENCE_BYTE_SIZE];
+        let header = &mut header[..self.header.len()];
+        header.copy_from_slice(&self.header);
+        if let Some(sequence) = sequence {
+            header[SEQUENCE_OFFSET..].copy_from_slice(&sequence.to_le_bytes());
+        }
+
+        let parts = std::iter::once(&*header)
+            .chain(self.messages.iter().map(|msg| &msg[..]))
+            .collect::<Vec<_>>();
+        // the first part not written entirely, and how much of it was
+        let (mut part, mut offset) = (0, 0);
+        while part < parts.len() {
+            let slices = std::iter::once(IoSlice::new(&parts[part][offset..]))
+                .chain(parts[part + 1..].iter().map(|part| IoSlice::new(part)))
+                .collect::<Vec<_>>();
+            match stream.write_vectored(&slices).await {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(mut written) => {
+                    while part < parts.len() && written >= parts[part].len() - offset {
+                        written -= parts[part].len() - offset;
+                        (part, offset) = (part + 1, 0);
+                    }
+                    offset += written;
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Batch of the messages matching the selection, None if no message is left, along with the
+/// number of messages left out by the subscription filters.
+fn encode_selected_batch(
+    messages: &[BufferedMessage],
+    selection: &BatchSelection,
+) -> (Option<Batch>, u64) {
+    let mut filtered = 0;
+    let messages = messages
+        .iter()
+        .filter(|msg| {
+            msg.schema_version
+                .map_or(true, |version| version == selection.schema_version)
+        })
+        .map(|msg| msg.framed_with(selection.compression).clone())
+        .filter(|framed| {
+            let msg = &framed[HEADER_BYTE_SIZE..];
+            let is_account = msg.first().is_some_and(|prefix| {
+                MessageType::from_prefix(*prefix) == Some(MessageType::Account)
+            });
+
+            if selection.skip_accounts && is_account {
+                return false;
+            }
+
+            let matches = selection
+                .filters
+                .map_or(true, |filters| filters.matches(msg));
+            if !matches {
+                filtered += 1;
+            }
+            matches
+        })
+        .collect::<Vec<_>>();
+
+    if messages.is_empty() {
+        return (None, filtered);
+    }
+
+    (
+        Some(Batch::new(messages, selection.protocol_version)),
+        filtered,
+    )
+}
+
+/// Address family of the listeners bound on every interface.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AddressFamily {
+    #[default]
+    Ipv4,
+    Ipv6,
+    // IPv6 listeners accepting IPv4 connections too, as IPv4-mapped addresses
+    DualStack,
+}
+
+impl AddressFamily {
+    /// The address of every interface of the family.
+    pub fn unspecified(&self) -> IpAddr {
+        match self {
+            AddressFamily::Ipv4 => Ipv4Addr::UNSPECIFIED.into(),
+            AddressFamily::Ipv6 | AddressFamily::DualStack => Ipv6Addr::UNSPECIFIED.into(),
+        }
+    }
+
+    /// Set if IPv6 listeners refuse IPv4 connections.
+    pub fn only_v6(&self) -> bool {
+        *self == AddressFamily::Ipv6
+    }
+}
+
+/// How the member of a consumer group each batch goes to is chosen.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupBalancing {
+    // the one with the fewest batches queued
+    #[default]
+    LeastLoaded,
+    // each in turn
+    RoundRobin,
+}
+
+/// Publishes batches of messages to subscribers over the framed TCP protocol, see `handshake`.
+///
+/// Connections don't get a thread each: a fixed number of acceptors takes new connections,
+/// whose handshake runs on a short-lived thread, then a task of the writers' runtime writes
+/// the batches queued to each connection, the queue bounded by `buffer_size` and the writes by
+/// the write timeout, and another reads its acks if it asked for them. Publishing is
+/// synchronous, as the geyser callbacks calling it are.
+pub struct TcpSender {
+    batch_max_bytes: usize,
+    strict_delivery: bool,
+    // strict delivery gives up on a batch past it if set
+    strict_delivery_timeout: Option<Duration>,
+    // batches strict delivery gave up on
+    abandoned_batches: AtomicU64,
+    min_subscribers: usize,
+    // subscribers the messages of these types wait for, on top of min_subscribers
+    type_min_subscribers: HashMap<MessageType, usize>,
+    acceptor_threads: usize,
+    writer_threads: usize,
+    // threads encoding the batches of the distinct subscriber selections, the calling one's
+    // if not set
+    fanout_pool: Option<ThreadPool>,
+    conns: Arc<RwLock<ConnectionMap>>,
+    buffer: BatchBuffer,
+    // message types batched apart from the others, each with its buffer and batch_max_bytes
+    topics: HashMap<MessageType, (BatchBuffer, usize)>,
+    // batch sent to every new connection before any data
+    handshake: Option<Vec<u8>>,
+    // how long a new connection is given to send its client hello
+    handshake_timeout: Duration,
+    // bandwidth quotas by subscriber name
+    quotas: Arc<HashMap<String, SubscriberQuota>>,
+    // quota of the subscribers without one of their own
+    default_quota: Option<SubscriberQuota>,
+    compression: Option<CompressionPolicy>,
+    // set by drain, no message is accepted afterwards
+    draining: AtomicBool,
+    // messages waiting longer than this are dropped instead of sent
+    message_ttl: Option<Duration>,
+    // messages dropped from the buffer for being older than the TTL
+    expired_messages: AtomicU64,
+    listening: Arc<Mutex<Option<Listening>>>,
+    // listeners bound again after losing their acceptors
+    listener_recoveries: Arc<AtomicU64>,
+    // TCP connections are served over TLS if set
+    tls: Option<Arc<ServerConfig>>,
+    // the messages of the batches are encrypted with it if set
+    cipher: Option<PayloadCipher>,
+    // every batch sent is appended to it first if set
+    wal: Option<Mutex<WriteAheadLog>>,
+    // batches no subscriber could be sent are written to it if set
+    dead_letters: Option<Mutex<DeadLetters>>,
+    // batches subscribers asking for acks can have unacknowledged
+    ack_window: u64,
+    address_family: AddressFamily,
+    // connections, handshaking ones included, past which new ones are closed right away
+    max_connections: Option<usize>,
+    // TCP connections from the addresses it doesn't permit are closed right away
+    ip_filter: Option<Arc<IpFilter>>,
+    // connections with a full buffer and nothing written for this long are closed
+    stall_timeout: Option<Duration>,
+    // writes to a connection not completing within it close the connection
+    write_timeout: Duration,
+    // connections with batches queued and none written for this long are closed
+    writer_watchdog: Option<Duration>,
+    handshaking: Arc<AtomicUsize>,
+    rejected_connections: Arc<AtomicU64>,
+    stalled_connections: Arc<AtomicU64>,
+    // only held weakly by the watchdog, which stops once the sender is dropped
+    hung_connections: Arc<AtomicU64>,
+    writer_panics: Arc<AtomicU64>,
+    group_balancing: GroupBalancing,
+    // messages larger than this are split into chunks, see `chunk`
+    max_message_bytes: Option<usize>,
+    // messages split so far, the id of the next one
+    chunked_messages: AtomicU64,
+    // turn of the round-robin balancing, taken once per batch
+    group_turn: AtomicUsize,
+}
+
+impl TcpSender {
+    pub fn new(batch_max_bytes: usize, strict_delivery: bool, min_subscribers: usize) -> Self {
+        TcpSender {
+            batch_max_bytes,
+            strict_delivery,
+            strict_delivery_timeout: None,
+            abandoned_batches: AtomicU64::new(0),
+            min_subscribers,
+            type_min_subscribers: HashMap::new(),
+            acceptor_threads: DEFAULT_ACCEPTOR_THREADS,
+            writer_threads: DEFAULT_WRITER_THREADS,
+            fanout_pool: None,
+            conns: Arc::new(RwLock::new(HashMap::new())),
+            buffer: BatchBuffer::new(DEFAULT_VECTOR_PREALLOC),
+            topics: HashMap::new(),
+            handshake: None,
+            handshake_timeout: DEFAULT_HANDSHAKE_TIMEOUT,
+            quotas: Arc::new(HashMap::new()),
+            default_quota: None,
+            compression: None,
+            draining: AtomicBool::new(false),
+            message_ttl: None,
+            expired_messages: AtomicU64::new(0),
+            listening: Arc::new(Mutex::new(None)),
+            listener_recoveries: Arc::new(AtomicU64::new(0)),
+            tls: None,
+            cipher: None,
+            wal: None,
+            dead_letters: None,
+            ack_window: DEFAULT_ACK_WINDOW,
+            address_family: AddressFamily::default(),
+            max_connections: None,
+            ip_filter: None,
+            stall_timeout: None,
+            write_timeout: DEFAULT_WRITE_TIMEOUT,
+            writer_watchdog: None,
+            handshaking: Arc::new(AtomicUsize::new(0)),
+            rejected_connections: Arc::new(AtomicU64::new(0)),
+            stalled_connections: Arc::new(AtomicU64::new(0)),
+            hung_connections: Arc::new(AtomicU64::new(0)),
+            writer_panics: Arc::new(AtomicU64::new(0)),
+            group_balancing: GroupBalancing::default(),
+            max_message_bytes: None,
+            chunked_messages: AtomicU64::new(0),
+            group_turn: AtomicUsize::new(0),
+        }
+    }
+
+    /// Greets every new connection with `hello` before streaming any data to it.
+    pub fn with_handshake(mut self, hello: &ServerHello) -> Self {
+        let mut buffer = TcpBuffer::new(1);
+        buffer.append(hello.to_message());
+        self.handshake = Some(buffer.flush_data());
+
+        self
+    }
+
+    /// Sets how long new connections are given to send a `ClientHello` before being
+    /// subscribed to everything. Data published meanwhile is not sent to them.
+    pub fn with_handshake_timeout(mut self, handshake_timeout: Duration) -> Self {
+        self.handshake_timeout = handshake_timeout;
+
+        self
+    }
+
+    /// Serves the TCP connections over TLS, see `tls::server_config`. Unix socket
+    /// connections stay in plain text. Subscribers authenticated by a client certificate
+    /// are identified by its common name instead of a random id.
+    pub fn with_tls(mut self, config: Arc<ServerConfig>) -> Self {
+        self.tls = Some(config);
+
+        self
+    }
+
+    /// Encrypts the messages of every batch with `cipher`, a key shared with the subscribers,
+    /// see `encryption`. Subscribers without the key can still connect, but can't read them.
+    pub fn with_encryption(mut self, cipher: PayloadCipher) -> Self {
+        self.cipher = Some(cipher);
+
+        self
+    }
+
+    /// Sets the family of the listener `bind` binds on every interface, and whether IPv6 ones
+    /// accept IPv4 connections.
+    pub fn with_address_family(mut self, address_family: AddressFamily) -> Self {
+        self.address_family = address_family;
+
+        self
+    }
+
+    /// Sets how many batches subscribers which acknowledge them, see `ClientHello::acks`, can
+    /// have unacknowledged. Their next batches are held until acknowledgements come, and
+    /// dropped only once their buffer is full.
+    pub fn with_ack_window(mut self, ack_window: u64) -> Self {
+        self.ack_window = ack_window.max(1);
+
+        self
+    }
+
+    /// Sets the bandwidth quotas of subscribers, by the name they send in their client hello.
+    pub fn with_quotas(mut self, quotas: HashMap<String, SubscriberQuota>) -> Self {
+        self.quotas = Arc::new(quotas);
+
+        self
+    }
+
+    /// Sets the quota of the subscribers, named or not, without one in `with_quotas`, so no
+    /// single one can take up the bandwidth of the others.
+    pub fn with_default_quota(mut self, quota: SubscriberQuota) -> Self {
+        self.default_quota = Some(quota);
+
+        self
+    }
+
+    /// Compresses the published messages covered by `policy`.
+    pub fn with_compression(mut self, policy: CompressionPolicy) -> Self {
+        self.compression = Some(policy);
+
+        self
+    }
+
+    /// Sets the number of threads accepting connections and of the runtime writing to them.
+    /// Every connection is written to by a task of its own, a slow subscriber doesn't delay
+    /// the others.
+    pub fn with_threads(mut self, acceptor_threads: usize, writer_threads: usize) -> Self {
+        self.acceptor_threads = acceptor_threads.max(1);
+        self.writer_threads = writer_threads.max(1);
+
+        self
+    }
+
+    /// Encodes the batches of subscribers with different filters, framing or compression on a
+    /// pool of `fanout_threads` threads, so that many distinct subscriptions don't hold up the
+    /// geyser callbacks publishing them. 1, the default, encodes them on the calling thread.
+    pub fn with_fanout_threads(mut self, fanout_threads: usize) -> Self {
+        self.fanout_pool = match fanout_threads {
+            0 | 1 => None,
+            threads => rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .thread_name(|i| format!("geyser-fanout-{}", i))
+                .build()
+                .map_err(|e| warn!("Error starting the fan-out threads, not used: {}", e))
+                .ok(),
+        };
+
+        self
+    }
+
+    /// Closes new connections right away while `max_connections` are open or handshaking, to
+    /// keep a connection flood from exhausting the validator's threads and memory.
+    pub fn with_max_connections(mut self, max_connections: usize) -> Self {
+        self.max_connections = Some(max_connections);
+
+        self
+    }
+
+    /// Closes the TCP connections from the addresses `filter` doesn't permit right away,
+    /// before any handshake. Unix socket connections are not filtered.
+    pub fn with_ip_filter(mut self, filter: IpFilter) -> Self {
+        self.ip_filter = Some(Arc::new(filter));
+
+        self
+    }
+
+    /// Disconnects the subscribers whose buffer stays full with nothing written to them for
+    /// longer than `timeout`, e.g. clients gone without closing their connection, freeing the
+    /// batches they hold instead of dropping every new one.
+    pub fn with_stall_timeout(mut self, timeout: Duration) -> Self {
+        self.stall_timeout = Some(timeout);
+
+        self
+    }
+
+    /// Splits the messages larger than `max_message_bytes` into chunks, so batches stay within
+    /// what consumers expect, see `chunk`. The chunks of a message may be sent in separate
+    /// batches, `TcpReceiver` puts them back together.
+    pub fn with_max_message_bytes(mut self, max_message_bytes: usize) -> Self {
+        self.max_message_bytes = Some(max_message_bytes.max(CHUNK_HEADER_BYTE_SIZE + 1));
+
+        self
+    }
+
+    /// Sets how the subscriber of a consumer group each batch goes to is chosen, the least
+    /// loaded by default.
+    pub fn with_group_balancing(mut self, group_balancing: GroupBalancing) -> Self {
+        self.group_balancing = group_balancing;
+
+        self
+    }
+
+    /// Bounds the retries of strict delivery: a batch some subscribers still couldn't be sent
+    /// after `timeout` is given up on, written to the dead letters if set, and counted by
+    /// `abandoned_batches`, rather than stalling the geyser callbacks indefinitely.
+    pub fn with_strict_delivery_timeout(mut self, timeout: Duration) -> Self {
+        self.strict_delivery_timeout = Some(timeout);
+
+        self
+    }
+
+    /// Disconnects the subscribers a write to doesn't complete within `timeout`,
+    /// `DEFAULT_WRITE_TIMEOUT` by default, e.g. peers which stopped reading but keep the
+    /// connection open, which would otherwise get none of the batches published meanwhile.
+    pub fn with_write_timeout(mut self, timeout: Duration) -> Self {
+        self.write_timeout = timeout;
+
+        self
+    }
+
+    /// Checks on the connections from a watchdog thread, disconnecting those with batches
+    /// queued and none written for longer than `timeout`, rather than letting their queues
+    /// grow. Unlike `with_write_timeout`, it also covers the batches which never get their
+    /// turn, e.g. held back by a client which stopped acking them.
+    pub fn with_writer_watchdog(mut self, timeout: Duration) -> Self {
+        self.writer_watchdog = Some(timeout);
+
+        self
+    }
+
+    /// Drops the messages waiting for longer than `ttl`, either in the buffer, e.g. while
+    /// strict delivery retries, or in a connection's queue, rather than delivering stale data.
+    pub fn with_message_ttl(mut self, ttl: Duration) -> Self {
+        self.message_ttl = Some(ttl);
+
+        self
+    }
+
+    /// Appends every batch to `wal` before sending it, see `wal::WriteAheadLog`.
+    pub fn with_wal(mut self, wal: WriteAheadLog) -> Self {
+        self.wal = Some(Mutex::new(wal));
+
+        self
+    }
+
+    /// Writes the batches no subscriber could be sent, for want of subscribers or with all of
+    /// them failing, to `dead_letters`, see `dead_letter::DeadLetters`. Batches retried for
+    /// strict delivery aren't, nor those every subscriber filtered out.
+    pub fn with_dead_letters(mut self, dead_letters: DeadLetters) -> Self {
+        self.dead_letters = Some(Mutex::new(dead_letters));
+
+        self
+    }
+
+    /// Batches the messages of each of these types apart from the others, flushing them once
+    /// their size reaches the given batch_max_bytes, e.g. `{Slot: 0}` to send slot statuses
+    /// right away instead of waiting for account updates to fill a batch. Messages of
+    /// different topics may be sent out of order.
+    pub fn with_topics(mut self, topics: HashMap<MessageType, usize>) -> Self {
+        self.topics = topics
+            .into_iter()
+            .map(|(message_type, batch_max_bytes)| {
+                (message_type, (BatchBuffer::new(1024), batch_max_bytes))
+            })
+            .collect();
+
+        self
+    }
+
+    /// Holds the messages of each of these types until that many subscribers are connected,
+    /// e.g. `{Account: 1}` to publish a snapshot's accounts only once its consumer is there,
+    /// while the other types keep flowing with none. Applies on top of `min_subscribers`.
+    pub fn with_min_subscribers_by_type(
+        mut self,
+        type_min_subscribers: HashMap<MessageType, usize>,
+    ) -> Self {
+        self.type_min_subscribers = type_min_subscribers;
+
+        self
+    }
+
+    /// Messages dropped from the buffer for being older than the TTL. Batches dropped from
+    /// the connection queues are counted by `SubscriberInfo::expired_batches`.
+    pub fn expired_messages(&self) -> u64 {
+        self.expired_messages.load(Ordering::Relaxed)
+    }
+
+    /// Connections closed for going past `with_max_connections` or by `with_ip_filter`.
+    pub fn rejected_connections(&self) -> u64 {
+        self.rejected_connections.load(Ordering::Relaxed)
+    }
+
+    /// Connections closed for a write not completing within `with_write_timeout`.
+    pub fn stalled_connections(&self) -> u64 {
+        self.stalled_connections.load(Ordering::Relaxed)
+    }
+
+    /// Connections closed by the `with_writer_watchdog` watchdog.
+    pub fn hung_connections(&self) -> u64 {
+        self.hung_connections.load(Ordering::Relaxed)
+    }
+
+    /// Deliveries a writer panicked during, the connection being dropped and the writer
+    /// carrying on with the others.
+    pub fn writer_panics(&self) -> u64 {
+        self.writer_panics.load(Ordering::Relaxed)
+    }
+
+    /// Batches strict delivery gave up on after `with_strict_delivery_timeout`.
+    pub fn abandoned_batches(&self) -> u64 {
+        self.abandoned_batches.load(Ordering::Relaxed)
+    }
+
+    /// Listeners bound again after losing their acceptors, e.g. after an acceptor panicked.
+    pub fn listener_recoveries(&self) -> u64 {
+        self.listener_recoveries.load(Ordering::Relaxed)
+    }
+
+    pub fn publish(&self, message: Vec<u8>) -> Result<(), GeyserError> {
+        self.publish_for_schema(message, None)
+    }
+
+    /// Publishes a message only to the connections using `schema_version`, if set.
+    pub fn publish_for_schema(
+        &self,
+        message: Vec<u8>,
+        schema_version: Option<u32>,
+    ) -> Result<(), GeyserError> {
+        if self.draining.load(Ordering::Relaxed) {
+            return Err(GeyserError::SenderDraining);
+        }
+
+        let message_type = message
+            .first()
+            .and_then(|prefix| MessageType::from_prefix(*prefix));
+        if let Some(min_subscribers) =
+            message_type.and_then(|message_type| self.type_min_subscribers.get(&message_type))
+        {
+            // before taking the buffer lock, the other types keep flowing meanwhile
+            self.wait_subscribers(*min_subscribers)?;
+            if self.draining.load(Ordering::Relaxed) {
+                return Err(GeyserError::SenderDraining);
+            }
+        }
+
+        let topic = message_type.and_then(|message_type| self.topics.get(&message_type));
+        let (buffer, batch_max_bytes) = match topic {
+            Some((buffer, batch_max_bytes)) => (buffer, *batch_max_bytes),
+            None => (&self.buffer, self.batch_max_bytes),
+        };
+
+        // compressed before taking the lock, publishers don't wait on each other
+        let (message, alternates) = match &self.compression {
+            Some(policy) => {
+                let alternates = self
+                    .alternate_codecs(policy)
+                    .into_iter()
+                    .map(|codec| (codec, policy.apply_with(message.clone(), codec)))
+                    .collect();
+                (policy.apply(message), alternates)
+            }
+            None => (message, Vec::new()),
+        };
+
+        let mut filling = buffer
+            .filling
+            .lock()
+            .map_err(|_| GeyserError::SenderLockError)?;
+
+        let Some(max_message_bytes) = self.max_message_bytes.filter(|max| message.len() > *max)
+        else {
+            filling.append_with_alternates(message, alternates, schema_version);
+            if filling.total_bytesize < batch_max_bytes {
+                return Ok(());
+            }
+            let mut batch = buffer.take_batch(&mut filling)?;
+            drop(filling);
+            return self.flush_buffer(&mut batch);
+        };
+
+        // the chunks go with the policy's codec, which every subscriber can decompress
+        let id = self.chunked_messages.fetch_add(1, Ordering::Relaxed);
+        let mut res = Ok(());
+        for chunk in split_message(&message, max_message_bytes, id) {
+            filling.append_with_alternates(chunk, Vec::new(), schema_version);
+            if filling.total_bytesize >= batch_max_bytes {
+                // kept locked, the chunks of a message aren't interleaved with other messages
+                let mut batch = buffer.take_batch(&mut filling)?;
+                // the next chunks are still worth sending to the subscribers it didn't fail for
+                res = res.and(self.flush_buffer(&mut batch));
+            }
+        }
+
+        res
+    }
+
+    /// Sends the buffered messages, then clears the buffer.
+    fn flush_buffer(&self, buffer: &mut TcpBuffer) -> Result<(), GeyserError> {
+        // logged even if expired or not delivered, for replay
+        self.log_batch(buffer);
+
+        let mut targets = None;
+        let started = Instant::now();
+
+        loop {
+            if let Some(ttl) = self.message_ttl {
+                let expired = buffer.expire(ttl);
+                self.expired_messages
+                    .fetch_add(expired as u64, Ordering::Relaxed);
+                if buffer.data.is_empty() {
+                    break;
+                }
+            }
+
+            if let Err((e, failed)) = self.send_batch(buffer, targets.as_ref()) {
+                let timed_out = self
+                    .strict_delivery_timeout
+                    .is_some_and(|timeout| started.elapsed() >= timeout);
+                if self.strict_delivery && !failed.is_empty() && timed_out {
+                    // given up on, kept for reprocessing if possible
+                    self.abandoned_batches.fetch_add(1, Ordering::Relaxed);
+                    warn!(
+                        "Batch not delivered to {} subscribers within {:?}, abandoned",
+                        failed.len(),
+                        started.elapsed()
+                    );
+                    if let Some(batch) = replay_batch(buffer) {
+                        self.spill_batch(&batch);
+                    }
+                    self.skip_batches(&failed);
+                    buffer.clear();
+                    return Err(e);
+                } else if self.strict_delivery && !failed.is_empty() {
+                    // for strict delivery, retry the connections with a full buffer until
+                    // there's no error, the ones which got the batch already are skipped
+                    targets = Some(failed);
+                    thread::sleep(time::Duration::from_secs(1));
+                    continue;
+                } else if !self.strict_delivery {
+                    // for regular mode just return error
+                    self.skip_batches(&failed);
+                    buffer.clear();
+                    return Err(e);
+                }
+            }
+
+            break;
+        }
+
+        buffer.clear();
+
+        Ok(())
+    }
+
+    /// Appends the messages of the buffer meant for every connection to the write-ahead log,
+    /// if any. The log failing doesn't keep the batch from being sent.
+    fn log_batch(&self, buffer: &TcpBuffer) {
+        let Some(wal) = &self.wal else {
+            return;
+        };
+        let Some(batch) = replay_batch(buffer) else {
+            return;
+        };
+
+        let mut wal = wal.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = wal.append(&batch) {
+            error!("Error writing to the write-ahead log: {}", e);
+        }
+    }
+
+    /// Writes an encoded batch no subscriber could be sent to the dead letters, if set.
+    fn spill_batch(&self, batch: &[u8]) {
+        let Some(dead_letters) = &self.dead_letters else {
+            return;
+        };
+
+        let mut dead_letters = dead_letters.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = dead_letters.append(batch) {
+            error!("Error writing a dead letter: {}", e);
+        }
+    }
+
+    /// Skips a sequence number on the `ids` connections, which won't get the current batch.
+    fn skip_batches(&self, ids: &HashSet<String>) {
+        if let Ok(conns) = self.conns.read() {
+            ids.iter()
+                .filter_map(|id| conns.get(id))
+                .for_each(|conn| conn.skip_batch());
+        }
+    }
+
+    /// Codecs asked for by connected subscribers, other than the policy's one.
+    fn alternate_codecs(&self, policy: &CompressionPolicy) -> Vec<CompressionCodec> {
+        let mut codecs = Vec::new();
+        if let Ok(conns) = self.conns.read() {
+            for codec in conns.values().filter_map(|conn| conn.compression) {
+                if codec != policy.codec.unwrap_or_default() && !codecs.contains(&codec) {
+                    codecs.push(codec);
+                }
+            }
+        }
+
+        codecs
+    }
+
+    pub fn wait_min_subscribers(&self) -> Result<(), GeyserError> {
+        self.wait_subscribers(self.min_subscribers)
+    }
+
+    /// Waits for `min_subscribers` subscribers, or for the sender to drain.
+    fn wait_subscribers(&self, min_subscribers: usize) -> Result<(), GeyserError> {
+        if min_subscribers > 0 {
+            loop {
+                let conns = {
+                    let conns = self
+                        .conns
+                        .read()
+                        .map_err(|_| GeyserError::SenderLockError)?;
+
+                    conns.len()
+                };
+
+                if conns >= min_subscribers || self.draining.load(Ordering::Relaxed) {
+                    break;
+                }
+
+                warn!("not enough subscribers {}/{}", conns, min_subscribers);
+
+                thread::sleep(time::Duration::from_secs(1));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Stops accepting new messages, flushes the buffered ones and waits up to `timeout` for
+    /// the connection queues to be written out. Returns the number of bytes abandoned, either
+    /// never queued to a connection with a full buffer or still queued at the deadline.
+    pub fn drain(&self, timeout: Duration) -> Result<usize, GeyserError> {
+        let deadline = Instant::now() + timeout;
+        self.draining.store(true, Ordering::Relaxed);
+
+        let mut abandoned = 0;
+        let buffers =
+            std::iter::once(&self.buffer).chain(self.topics.values().map(|(buffer, _)| buffer));
+        for buffer in buffers {
+            let mut filling = buffer
+                .filling
+                .lock()
+                .map_err(|_| GeyserError::SenderLockError)?;
+            let mut buffer = buffer.take_batch(&mut filling)?;
+
+            if buffer.total_bytesize > 0 {
+                self.log_batch(&buffer);
+
+                // retries the connections with a full buffer, until the deadline
+                let mut targets = None;
+                while let Err((_, failed)) = self.send_batch(&buffer, targets.as_ref()) {
+                    if failed.is_empty() {
+                        break;
+                    }
+
+                    if Instant::now() >= deadline {
+                        abandoned += (HEADER_BYTE_SIZE + buffer.total_bytesize) * failed.len();
+                        break;
+                    }
+
+                    targets = Some(failed);
+                    thread::sleep(Duration::from_millis(10));
+                }
+            }
+
+            buffer.clear();
+        }
+
+        loop {
+            let pending = {
+                let conns = self
+                    .conns
+                    .read()
+                    .map_err(|_| GeyserError::SenderLockError)?;
+
+                conns
+                    .values()
+                    .filter(|conn| !conn.closed.load(Ordering::Relaxed))
+                    .map(|conn| conn.pending_bytes.load(Ordering::Relaxed))
+                    .sum::<usize>()
+            };
+
+            if pending == 0 || Instant::now() >= deadline {
+                return Ok(abandoned + pending);
+            }
+
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// True if a connected subscriber selected `schema_version` in its client hello.
+    pub fn has_subscribers_with_schema(&self, schema_version: u32) -> bool {
+        self.conns.read().is_ok_and(|conns| {
+            conns
+                .values()
+                .any(|conn| conn.schema_version == schema_version)
+        })
+    }
+
+    /// False while no subscriber is connected, unless messages are kept for the next ones:
+    /// by strict delivery, the minimum subscribers, the write-ahead log or the dead letters.
+    pub fn needs_messages(&self) -> bool {
+        self.strict_delivery
+            || self.min_subscribers > 0
+            || !self.type_min_subscribers.is_empty()
+            || self.wal.is_some()
+            || self.dead_letters.is_some()
+            || self.conns.read().is_ok_and(|conns| !conns.is_empty())
+    }
+
+    /// The batch as queued to the connections, sealed if `with_encryption` is set.
+    fn queued_batch(&self, batch: Batch) -> Arc<Batch> {
+        match &self.cipher {
+            Some(cipher) => Arc::new(batch.seal(cipher)),
+            None => Arc::new(batch),
+        }
+    }
+
+    /// Connected subscribers, with the name they sent in their client hello.
+    pub fn subscribers(&self) -> Result<Vec<SubscriberInfo>, GeyserError> {
+        let conns = self
+            .conns
+            .read()
+            .map_err(|_| GeyserError::SenderLockError)?;
+
+        Ok(conns.values().map(|conn| conn.info()).collect())
+    }
+
+    /// Sends an already encoded batch to every connection, each connection getting only the
+    /// messages matching its filters, and nothing if none does.
+    pub fn publish_batch(&self, batch: Vec<u8>) -> Result<(), GeyserError> {
+        let mut failed = HashSet::new();
+        let mut closed = Vec::new();
+
+        self.wait_min_subscribers()?;
+
+        let conns = self.connections(None)?;
+
+        // shared by the connections using the same filters and framing
+        let mut encodings = Vec::new();
+        let mut indexes = HashMap::new();
+        let sends = conns
+            .iter()
+            .map(|(id, conn)| {
+                let encoding = (conn.filters.as_ref(), conn.protocol_version);
+                let index = *indexes.entry(encoding).or_insert_with(|| {
+                    encodings.push(encoding);
+                    encodings.len() - 1
+                });
+                (id, conn, index)
+            })
+            .collect::<Vec<_>>();
+
+        let batches = self.fan_out(&encodings, |&(filters, version)| match (filters, version) {
+            (None, PROTOCOL_VERSION_V1) => (
+                Some(self.queued_batch(Batch::from_encoded(batch.clone(), PROTOCOL_VERSION_V1))),
+                0,
+            ),
+            (filters, version) => {
+                let (batch, filtered) = reframe_batch(&batch, filters, version);
+                (
+                    batch.map(|batch| self.queued_batch(Batch::from_encoded(batch, version))),
+                    filtered,
+                )
+            }
+        });
+
+        // sent to, or filtered out by, at least one connection
+        let mut handled = false;
+        for (id, conn, index) in sends {
+            let (batch, filtered) = &batches[index];
+            conn.filtered_messages
+                .fetch_add(*filtered, Ordering::Relaxed);
+
+            let Some(batch) = batch else {
+                handled = true;
+                continue;
+            };
+            if Self::try_send(
+                conn,
+                id,
+                batch.clone(),
+                Instant::now(),
+                &mut failed,
+                &mut closed,
+            ) {
+                handled = true;
+            } else {
+                conn.skip_batch();
+            }
+        }
+
+        if !handled {
+            self.spill_batch(&batch);
+        }
+
+        for conn in &closed {
+            remove_conn(&self.conns, conn);
+        }
+
+        Self::send_result(failed, closed.len() as u64).map_err(|(e, _)| e)
+    }
+
+    /// Sends the buffered messages to the `targets` connections, or to every connection if not
+    /// set, each connection getting only the messages matching its filters.
+    /// On error, also returns the ids of the connections whose buffer was full, so that only
+    /// those get retried. Disconnected ones are gone and never retried.
+    fn send_batch(
+        &self,
+        buffer: &TcpBuffer,
+        targets: Option<&HashSet<String>>,
+    ) -> Result<(), (GeyserError, HashSet<String>)> {
+        let mut failed = HashSet::new();
+        let mut closed = Vec::new();
+        let mut over_quota = Vec::new();
+
+        self.wait_min_subscribers()
+            .map_err(|e| (e, HashSet::new()))?;
+
+        let conns = self.connections(targets).map_err(|e| (e, HashSet::new()))?;
+
+        // every distinct selection gets its batch encoded only once
+        let mut selections = Vec::new();
+        let mut indexes = HashMap::new();
+        let mut sends = Vec::with_capacity(conns.len());
+
+        for (id, conn) in &conns {
+            let quota_action = conn.quota.as_ref().and_then(|quota| quota.exceeded());
+            if quota_action.is_some() {
+                conn.quota_exceeded.fetch_add(1, Ordering::Relaxed);
+            }
+
+            let skip_accounts = match quota_action {
+                Some(QuotaAction::Throttle) => {
+                    conn.dropped_batches.fetch_add(1, Ordering::Relaxed);
+                    conn.skip_batch();
+                    continue;
+                }
+                Some(QuotaAction::Disconnect) => {
+                    conn.closed.store(true, Ordering::Relaxed);
+                    warn!(
+                        "Subscriber {} exceeded its quota, disconnecting",
+                        conn.label()
+                    );
+                    over_quota.push(conn.clone());
+                    continue;
+                }
+                Some(QuotaAction::DropAccounts) => true,
+                None => false,
+            };
+
+            let selection = BatchSelection {
+                filters: conn.filters.as_ref(),
+                skip_accounts,
+                protocol_version: conn.protocol_version,
+                schema_version: conn.schema_version,
+                compression: conn.compression.filter(|codec| {
+                    self.compression
+                        .as_ref()
+                        .is_some_and(|policy| policy.codec.unwrap_or_default() != *codec)
+                }),
+            };
+            let index = *indexes.entry(selection).or_insert_with(|| {
+                selections.push(selection);
+                selections.len() - 1
+            });
+            sends.push((id, conn, index));
+        }
+
+        let batches = self.fan_out(&selections, |selection| {
+            if selection.filters.is_none()
+                && !selection.skip_accounts
+                && selection.compression.is_none()
+                && buffer.versioned == 0
+            {
+                let messages = buffer.data.iter().map(|msg| msg.framed.clone()).collect();
+                (
+                    Some(self.queued_batch(Batch::new(messages, selection.protocol_version))),
+                    0,
+                )
+            } else {
+                let (batch, filtered) = encode_selected_batch(&buffer.data, selection);
+                (batch.map(|batch| self.queued_batch(batch)), filtered)
+            }
+        });
+
+        let published_at = buffer.published_at();
+        // sent to, or filtered out by, at least one connection
+        let mut handled = false;
+        for (id, conn, index) in sends {
+            let (batch, filtered) = &batches[index];
+            conn.filtered_messages
+                .fetch_add(*filtered, Ordering::Relaxed);
+
+            let Some(batch) = batch else {
+                handled = true;
+                continue;
+            };
+            let bytes = batch.len();
+            let messages = buffer.data.len().saturating_sub(*filtered as usize);
+            if Self::try_send(
+                conn,
+                id,
+                batch.clone(),
+                published_at,
+                &mut failed,
+                &mut closed,
+            ) {
+                handled = true;
+                if let Some(quota) = &conn.quota {
+                    quota.add(bytes, messages);
+                }
+            }
+        }
+
+        // the connections with a full buffer are retried for strict delivery
+        let retried = self.strict_delivery && !failed.is_empty();
+        if !handled && targets.is_none() && !retried {
+            if let Some(batch) = replay_batch(buffer) {
+                self.spill_batch(&batch);
+            }
+        }
+
+        for conn in over_quota.iter().chain(&closed) {
+            remove_conn(&self.conns, conn);
+        }
+
+        Self::send_result(failed, closed.len() as u64)
+    }
+
+    /// The `targets` connections, or every one if not set, taken out of the lock so that
+    /// their batches are encoded without holding it. Consumer groups are down to the member
+    /// getting the batch.
+    fn connections(
+        &self,
+        targets: Option<&HashSet<String>>,
+    ) -> Result<Vec<(String, Arc<Connection>)>, GeyserError> {
+        let conns = self
+            .conns
+            .read()
+            .map_err(|_| GeyserError::SenderLockError)?;
+
+        let mut selected = Vec::with_capacity(conns.len());
+        let mut groups = BTreeMap::<&str, Vec<_>>::new();
+        for (id, conn) in conns.iter() {
+            if targets.is_some_and(|targets| !targets.contains(id)) {
+                continue;
+            }
+            match &conn.group {
+                Some(group) => groups.entry(group).or_default().push((id, conn)),
+                None => selected.push((id.clone(), conn.clone())),
+            }
+        }
+
+        let turn = if groups.is_empty() {
+            0
+        } else {
+            self.group_turn.fetch_add(1, Ordering::Relaxed)
+        };
+        for mut members in groups.into_values() {
+            let member = match self.group_balancing {
+                GroupBalancing::LeastLoaded => members
+                    .into_iter()
+                    .filter(|(_, conn)| !conn.closed.load(Ordering::Relaxed))
+                    .min_by_key(|(_, conn)| conn.pending.load(Ordering::Relaxed)),
+                GroupBalancing::RoundRobin => {
+                    // in a stable order for the turns to go around
+                    members.sort_unstable_by_key(|(id, _)| *id);
+                    let len = members.len();
+                    members.into_iter().nth(turn % len)
+                }
+            };
+            if let Some((id, conn)) = member {
+                selected.push((id.clone(), conn.clone()));
+            }
+        }
+
+        Ok(selected)
+    }
+
+    /// Encodes the batch of every distinct selection, on the fan-out threads when there are
+    /// several.
+    fn fan_out<T: Sync, U: Send>(
+        &self,
+        items: &[T],
+        encode: impl Fn(&T) -> U + Sync + Send,
+    ) -> Vec<U> {
+        match &self.fanout_pool {
+            Some(pool) if items.len() > 1 => {
+                pool.install(|| items.par_iter().map(encode).collect())
+            }
+            _ => items.iter().map(encode).collect(),
+        }
+    }
+
+    fn try_send(
+        conn: &Arc<Connection>,
+        id: &str,
+        batch: Arc<Batch>,
+        published_at: Instant,
+        failed: &mut HashSet<String>,
+        closed: &mut Vec<Arc<Connection>>,
+    ) -> bool {
+        if let Err(e) = conn.try_send(batch, published_at) {
+            match e {
+                TrySendError::Full(..) => {
+                    failed.insert(id.to_string());
+                }
+                _ => {
+                    closed.push(conn.clone());
+                }
+            }
+
+            return false;
+        }
+
+        true
+    }
+
+    fn send_result(
+        failed: HashSet<String>,
+        disconnects: u64,
+    ) -> Result<(), (GeyserError, HashSet<String>)> {
+        if !failed.is_empty() {
+            return Err((GeyserError::TcpSend(failed.len() as u64), failed));
+        }
+
+        if disconnects > 0 {
+            return Err((GeyserError::TcpDisconnects(disconnects), failed));
+        }
+
+        Ok(())
+    }
+
+    /// Listens on `port` of every interface of the address family.
+    pub fn bind(&self, port: u16, buffer_size: usize) -> io::Result<()> {
+        self.bind_addr(
+            SocketAddr::new(self.address_family.unspecified(), port),
+            buffer_size,
+        )
+    }
+
+    /// Listens on `addr`, e.g. 127.0.0.1 to only serve consumers on the same host.
+    pub fn bind_addr(&self, addr: SocketAddr, buffer_size: usize) -> io::Result<()> {
+        let listener = bind_tcp(addr, self.address_family.only_v6())?;
+        let endpoint = Endpoint::Tcp(listener.local_addr()?);
+
+        info!(
+            "TCP server listening on {} ({} acceptors, {} writers)",
+            addr, self.acceptor_threads, self.writer_threads
+        );
+
+        self.listen(Listener::Tcp(listener), endpoint, buffer_size)
+    }
+
+    /// Listens on a unix socket instead of a TCP port, for subscribers on the same host.
+    /// Access can be restricted with the permissions of the socket's directory. A socket
+    /// left over at `path` by a previous run is replaced.
+    #[cfg(unix)]
+    pub fn bind_unix(&self, path: &Path, buffer_size: usize) -> io::Result<()> {
+        let endpoint = Endpoint::Unix(path.to_path_buf());
+        let listener = endpoint.bind(false)?;
+
+        info!(
+            "Unix socket server listening on {} ({} acceptors, {} writers)",
+            path.display(),
+            self.acceptor_threads,
+            self.writer_threads
+        );
+
+        self.listen(listener, endpoint, buffer_size)
+    }
+
+    fn listen(&self, listener: Listener, endpoint: Endpoint, buffer_size: usize) -> io::Result<()> {
+        let mut listening = self.listening.lock().unwrap_or_else(|e| e.into_inner());
+        // the connections of a replaced listener keep being written to
+        let writers = match &*listening {
+            Some(listening) => listening.writers.clone(),
+            None => Arc::new(self.writers()?),
+        };
+        let stopped = Arc::new(AtomicBool::new(false));
+        self.acceptors()
+            .spawn(&listener, &endpoint, &writers, buffer_size, &stopped)?;
+
+        if let (None, Some(timeout)) = (&*listening, self.writer_watchdog) {
+            spawn_watchdog(
+                self.conns.clone(),
+                timeout,
+                Arc::downgrade(&self.hung_connections),
+            );
+        }
+        *listening = Some(Listening {
+            endpoint,
+            stopped,
+            writers,
+            buffer_size,
+        });
+
+        Ok(())
+    }
+
+    /// Moves the sender to a new listening address, e.g. after a network reconfiguration.
+    /// Subscribers keep streaming over their established connections, only new ones have to
+    /// connect to the new address. The old listener is closed once the handshakes it started
+    /// are done; if the new address cannot be bound, the old listener is kept.
+    pub fn rebind(&self, addr: SocketAddr) -> io::Result<SocketAddr> {
+        let mut listening = self.listening.lock().unwrap_or_else(|e| e.into_inner());
+        let listening = listening
+            .as_mut()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotConnected, "sender is not bound"))?;
+
+        let listener = bind_tcp(addr, self.address_family.only_v6())?;
+        let addr = listener.local_addr()?;
+        let stopped = Arc::new(AtomicBool::new(false));
+        self.acceptors().spawn(
+            &Listener::Tcp(listener),
+            &Endpoint::Tcp(addr),
+            &listening.writers,
+            listening.buffer_size,
+            &stopped,
+        )?;
+
+        let old_endpoint = std::mem::replace(&mut listening.endpoint, Endpoint::Tcp(addr));
+        let old_stopped = std::mem::replace(&mut listening.stopped, stopped);
+        stop_listener(&old_endpoint, &old_stopped, self.acceptor_threads);
+
+        info!("Server moved from {:?} to {}", old_endpoint, addr);
+
+        Ok(addr)
+    }
+
+    /// Closes the listener, then every connection, abandoning the batches still queued to
+    /// them. Meant to follow `drain` when the plugin is unloaded.
+    pub fn shutdown(&self) {
+        let listening = self
+            .listening
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+        if let Some(listening) = listening {
+            stop_listener(
+                &listening.endpoint,
+                &listening.stopped,
+                self.acceptor_threads,
+            );
+        }
+
+        let conns = std::mem::take(&mut *self.conns.write().unwrap_or_else(|e| e.into_inner()));
+        for conn in conns.values() {
+            conn.closed.store(true, Ordering::Relaxed);
+            if let Some(Err(e)) = conn.closer.as_ref().map(Closer::close) {
+                warn!("Error closing {}: {}", conn.label(), e);
+            }
+        }
+
+        info!("Server shut down, {} subscribers disconnected", conns.len());
+    }
+
+    /// The address new subscribers connect to, None until bound or when bound to a unix
+    /// socket.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        let listening = self.listening.lock().unwrap_or_else(|e| e.into_inner());
+        match listening.as_ref().map(|listening| &listening.endpoint) {
+            Some(Endpoint::Tcp(addr)) => Some(*addr),
+            _ => None,
+        }
+    }
+
+    fn acceptors(&self) -> Acceptors {
+        Acceptors {
+            threads: self.acceptor_threads,
+            conns: self.conns.clone(),
+            handshake: self.handshake.clone(),
+            handshake_timeout: self.handshake_timeout,
+            quotas: self.quotas.clone(),
+            default_quota: self.default_quota,
+            tls: self.tls.clone(),
+            ack_window: self.ack_window,
+            max_connections: self.max_connections,
+            ip_filter: self.ip_filter.clone(),
+            stall_timeout: self.stall_timeout,
+            handshaking: self.handshaking.clone(),
+            rejected_connections: self.rejected_connections.clone(),
+            only_v6: self.address_family.only_v6(),
+            listening: self.listening.clone(),
+            listener_recoveries: self.listener_recoveries.clone(),
+        }
+    }
+
+    fn writers(&self) -> io::Result<Writers> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(self.writer_threads)
+            .thread_name("geyser-tcp-writer")
+            .enable_all()
+            .build()?;
+
+        Ok(Writers {
+            handle: runtime.handle().clone(),
+            runtime: Some(runtime),
+            conns: self.conns.clone(),
+            message_ttl: self.message_ttl,
+            write_timeout: self.write_timeout,
+            stalled_connections: self.stalled_connections.clone(),
+            writer_panics: self.writer_panics.clone(),
+        })
+    }
+}
+
+impl Publisher for TcpSender {
+    fn publish_for_schema(
+        &self,
+        message: Vec<u8>,
+        schema_version: Option<u32>,
+    ) -> Result<(), GeyserError> {
+        TcpSender::publish_for_schema(self, message, schema_version)
+    }
+
+    fn has_subscribers_with_schema(&self, schema_version: u32) -> bool {
+        TcpSender::has_subscribers_with_schema(self, schema_version)
+    }
+
+    fn needs_messages(&self) -> bool {
+        TcpSender::needs_messages(self)
+    }
+
+    fn drain(&self, timeout: Duration) -> Result<usize, GeyserError> {
+        TcpSender::drain(self, timeout)
+    }
+
+    fn shutdown(&self) {
+        TcpSender::shutdown(self)
+    }
+
+    fn subscribers(&self) -> Result<Vec<SubscriberInfo>, GeyserError> {
+        TcpSender::subscribers(self)
+    }
+
+    fn rebind(&self, addr: SocketAddr) -> io::Result<SocketAddr> {
+        TcpSender::rebind(self, addr)
+    }
+
+    fn local_addr(&self) -> Option<SocketAddr> {
+        TcpSender::local_addr(self)
+    }
+
+    fn expired_messages(&self) -> u64 {
+        TcpSender::expired_messages(self)
+    }
+
+    fn rejected_connections(&self) -> u64 {
+        TcpSender::rejected_connections(self)
+    }
+
+    fn stalled_connections(&self) -> u64 {
+        TcpSender::stalled_connections(self)
+    }
+
+    fn abandoned_batches(&self) -> u64 {
+        TcpSender::abandoned_batches(self)
+    }
+
+    fn listener_recoveries(&self) -> u64 {
+        TcpSender::listener_recoveries(self)
+    }
+
+    fn hung_connections(&self) -> u64 {
+        TcpSender::hung_connections(self)
+    }
+
+    fn writer_panics(&self) -> u64 {
+        TcpSender::writer_panics(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::acceptors::{ACCEPT_ERROR_BACKOFF, MAX_ACCEPT_ERRORS};
+    use super::TcpSender;
+    use super::*;
+    use crate::handshake::{ClientHello, PROTOCOL_VERSION_V3};
+    use crate::sync_receiver::SyncTcpReceiver;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::time::Duration;
+    use tokio::time::sleep;
+    use uuid::Uuid;
+
+    /// Polls `condition` until it holds, failing the test if it takes over 5 seconds.
+    fn wait_for(condition: impl Fn() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !condition() {
+            assert!(
+                Instant::now() < deadline,
+                "timed out waiting for a condition"
+            );
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// Connects a receiver to the sender listening on `port`, which greets it with a server
+    /// hello, once the sender added the connection.
+    fn subscribe(
+        sender: &TcpSender,
+        port: u16,
+        client_hello: Option<ClientHello>,
+    ) -> SyncTcpReceiver {
+        let subscribers = sender.subscribers().unwrap().len();
+        let receiver = SyncTcpReceiver::connect(
+            SocketAddr::from(([127, 0, 0, 1], port)),
+            Duration::from_secs(1),
+            client_hello,
+        )
+        .unwrap();
+        receiver
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        wait_for(|| sender.subscribers().unwrap().len() > subscribers);
+
+        receiver
+    }
+
+    #[test]
+    fn test_sender() {
+        let sender = TcpSender::new(10, false, 0).with_handshake_timeout(Duration::from_millis(10));
+        sender.bind(9050, 100).unwrap();
+
+        // without a server hello, connecting waits for the first batch
+        let receiver = thread::spawn(|| {
+            let mut receiver = SyncTcpReceiver::connect(
+                "127.0.0.1:9050".parse().unwrap(),
+                Duration::from_secs(1),
+                None,
+            )
+            .unwrap();
+            receiver
+                .set_read_timeout(Some(Duration::from_secs(5)))
+                .unwrap();
+            (0..100)
+                .map(|_| receiver.recv().unwrap())
+                .collect::<Vec<_>>()
+        });
+        wait_for(|| !sender.subscribers().unwrap().is_empty());
+
+        let msg = b"hello world".to_vec();
+        for _ in 0..100 {
+            sender.publish(msg.clone()).unwrap();
+        }
+
+        assert_eq!(receiver.join().unwrap(), vec![msg; 100]);
+    }
+
+    #[test]
+    fn test_checksummed_batches() {
+        let sender = TcpSender::new(10, false, 0)
+            .with_handshake(&ServerHello::new("test", "test"))
+            .with_handshake_timeout(Duration::from_secs(1));
+        sender.bind(9055, 100).unwrap();
+
+        let mut receiver = subscribe(&sender, 9055, None);
+        assert!(sender
+            .conns
+            .read()
+            .unwrap()
+            .values()
+            .all(|conn| conn.protocol_version == PROTOCOL_VERSION_V3));
+
+        let msg = b"hello world".to_vec();
+        for _ in 0..10 {
+            sender.publish(msg.clone()).unwrap();
+        }
+
+        for _ in 0..10 {
+            assert_eq!(receiver.recv().unwrap(), msg);
+        }
+        assert_eq!(receiver.missed_batches(), 0);
+    }
+
+    #[test]
+    fn test_acked_batches() {
+        let sender = TcpSender::new(10, false, 0)
+            .with_handshake(&ServerHello::new("test", "test"))
+            .with_handshake_timeout(Duration::from_secs(1))
+            .with_ack_window(2);
+        sender.bind(9056, 100).unwrap();
+
+        let client_hello = ClientHello {
+            acks: true,
+            ..Default::default()
+        };
+        let mut receiver = subscribe(&sender, 9056, Some(client_hello));
+
+        let msg = b"hello world".to_vec();
+        for _ in 0..10 {
+            sender.publish(msg.clone()).unwrap();
+        }
+
+        for _ in 0..10 {
+            assert_eq!(receiver.recv().unwrap(), msg);
+        }
+        // the batches past the window were released by the acks
+        wait_for(|| {
+            sender.conns.read().unwrap().values().all(|conn| {
+                conn.acks
+                    .as_ref()
+                    .is_some_and(|acks| acks.acked.load(Ordering::Relaxed) >= 9)
+                    && conn.pending.load(Ordering::Relaxed) == 0
+            })
+        });
+    }
+
+    #[test]
+    fn test_expired_batches_are_acked() {
+        let sender = TcpSender::new(10, false, 0)
+            .with_handshake(&ServerHello::new("test", "test"))
+            .with_handshake_timeout(Duration::from_secs(1))
+            .with_ack_window(1)
+            .with_message_ttl(Duration::from_millis(100));
+        sender.bind(9061, 100).unwrap();
+
+        let client_hello = ClientHello {
+            acks: true,
+            ..Default::default()
+        };
+        let mut receiver = subscribe(&sender, 9061, Some(client_hello));
+
+        for i in 0..5 {
+            sender.publish(vec![i; 11]).unwrap();
+        }
+        // the first batch is acknowledged once read, slower than the TTL
+        thread::sleep(Duration::from_millis(300));
+        assert_eq!(receiver.recv().unwrap(), vec![0; 11]);
+
+        // not held back by the expired ones
+        sender.publish(vec![5; 11]).unwrap();
+        assert_eq!(receiver.recv().unwrap(), vec![5; 11]);
+        assert_eq!(sender.subscribers().unwrap()[0].expired_batches, 4);
+    }
+
+    #[test]
+    fn test_consumer_group() {
+        let sender = TcpSender::new(10, false, 0)
+            .with_handshake(&ServerHello::new("test", "test"))
+            .with_handshake_timeout(Duration::from_secs(1))
+            .with_group_balancing(GroupBalancing::RoundRobin);
+        sender.bind(9064, 100).unwrap();
+
+        // the last one is not in the group and gets every batch
+        let mut receivers = (0..3)
+            .map(|i| {
+                let client_hello = ClientHello {
+                    group: (i < 2).then(|| "workers".to_string()),
+                    ..Default::default()
+                };
+                subscribe(&sender, 9064, Some(client_hello))
+            })
+            .collect::<Vec<_>>();
+
+        for i in 0..10 {
+            sender.publish(vec![i; 11]).unwrap();
+        }
+
+        // each reads until nothing more comes
+        let counts = receivers
+            .iter_mut()
+            .map(|receiver| {
+                receiver
+                    .set_read_timeout(Some(Duration::from_millis(500)))
+                    .unwrap();
+                std::iter::from_fn(|| receiver.recv().ok()).count()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(counts, [5, 5, 10]);
+    }
+
+    #[tokio::test]
+    async fn test_max_connections() {
+        let sender = TcpSender::new(10, false, 0)
+            .with_handshake_timeout(Duration::from_millis(10))
+            .with_max_connections(1);
+        sender.bind(9058, 100).unwrap();
+
+        let _first = TcpStream::connect("127.0.0.1:9058").unwrap();
+        sleep(Duration::from_millis(100)).await;
+        let mut second = TcpStream::connect("127.0.0.1:9058").unwrap();
+        sleep(Duration::from_millis(100)).await;
+
+        assert_eq!(sender.conns.read().unwrap().len(), 1);
+        assert_eq!(sender.rejected_connections(), 1);
+        // closed by the sender
+        assert_eq!(second.read(&mut [0; 1]).unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_stalled_connection_eviction() {
+        let sender = TcpSender::new(10, false, 0)
+            .with_handshake_timeout(Duration::from_millis(10))
+            .with_stall_timeout(Duration::from_millis(200));
+        sender.bind(9059, 1).unwrap();
+
+        // never reads, its socket buffers fill up and its writer blocks
+        let _client = TcpStream::connect("127.0.0.1:9059").unwrap();
+        sleep(Duration::from_millis(100)).await;
+
+        let msg = vec![0; 1024 * 1024];
+        for _ in 0..100 {
+            let _ = sender.publish(msg.clone());
+            if sender.conns.read().unwrap().is_empty() {
+                break;
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+
+        assert!(sender.conns.read().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_write_timeout() {
+        let sender = TcpSender::new(10, false, 0)
+            .with_handshake_timeout(Duration::from_millis(10))
+            .with_write_timeout(Duration::from_millis(200));
+        sender.bind(9062, 1000).unwrap();
+
+        // never reads, a write eventually blocks on its full socket buffers
+        let _client = TcpStream::connect("127.0.0.1:9062").unwrap();
+        sleep(Duration::from_millis(100)).await;
+
+        let msg = vec![0; 1024 * 1024];
+        for _ in 0..100 {
+            let _ = sender.publish(msg.clone());
+            if sender.conns.read().unwrap().is_empty() {
+                break;
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+
+        assert!(sender.conns.read().unwrap().is_empty());
+        assert_eq!(sender.stalled_connections(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_writer_watchdog() {
+        let sender = TcpSender::new(10, false, 0)
+            .with_handshake_timeout(Duration::from_millis(10))
+            .with_writer_watchdog(Duration::from_millis(200));
+        sender.bind(9070, 1000).unwrap();
+
+        // never reads, the writer eventually blocks on its full socket buffers for good
+        let _client = TcpStream::connect("127.0.0.1:9070").unwrap();
+        sleep(Duration::from_millis(100)).await;
+
+        let msg = vec![0; 1024 * 1024];
+        for _ in 0..100 {
+            let _ = sender.publish(msg.clone());
+            if sender.conns.read().unwrap().is_empty() {
+                break;
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+
+        assert!(sender.conns.read().unwrap().is_empty());
+        assert_eq!(sender.hung_connections(), 1);
+        assert_eq!(sender.stalled_connections(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_strict_delivery_timeout() {
+        let dir = std::env::temp_dir().join(format!("dead-{}", uuid::Uuid::new_v4()));
+        let dead_letters = DeadLetters::open(crate::dead_letter::DeadLetterConfig {
+            dir: dir.clone(),
+            max_bytes: None,
+        })
+        .unwrap();
+        let sender = TcpSender::new(10, true, 0)
+            .with_handshake_timeout(Duration::from_millis(10))
+            .with_strict_delivery_timeout(Duration::from_millis(100))
+            .with_dead_letters(dead_letters);
+        sender.bind(9063, 1).unwrap();
+
+        // never reads, its buffer stays full
+        let _client = TcpStream::connect("127.0.0.1:9063").unwrap();
+        sleep(Duration::from_millis(100)).await;
+
+        let msg = vec![0; 1024 * 1024];
+        for _ in 0..20 {
+            if sender.publish(msg.clone()).is_err() {
+                break;
+            }
+        }
+
+        assert_eq!(sender.abandoned_batches(), 1);
+        assert_eq!(crate::dead_letter::dead_letters(&dir).unwrap().len(), 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_publishing_during_strict_retries() {
+        let sender = Arc::new(
+            TcpSender::new(1024, true, 0)
+                .with_handshake_timeout(Duration::from_millis(10))
+                .with_strict_delivery_timeout(Duration::from_secs(2)),
+        );
+        sender.bind(9081, 1).unwrap();
+
+        // never reads, its buffer ends up full and the batch retried
+        let _client = TcpStream::connect("127.0.0.1:9081").unwrap();
+        sleep(Duration::from_millis(100)).await;
+
+        let sender_clone = sender.clone();
+        let retried = thread::spawn(move || {
+            let msg = vec![0; 1024 * 1024];
+            (0..20).any(|_| sender_clone.publish(msg.clone()).is_err())
+        });
+        sleep(Duration::from_millis(500)).await;
+
+        // buffered for the next batch without waiting for the retries
+        let started = Instant::now();
+        sender.publish(vec![1; 10]).unwrap();
+        assert!(started.elapsed() < Duration::from_millis(100));
+
+        assert!(retried.join().unwrap());
+        assert_eq!(sender.abandoned_batches(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown() {
+        let sender = TcpSender::new(10, false, 0).with_handshake_timeout(Duration::from_millis(10));
+        sender.bind(9060, 100).unwrap();
+
+        let mut client = TcpStream::connect("127.0.0.1:9060").unwrap();
+        sleep(Duration::from_millis(100)).await;
+
+        sender.publish(b"hello world".to_vec()).unwrap();
+        assert_eq!(sender.drain(Duration::from_secs(1)).unwrap(), 0);
+        sender.shutdown();
+
+        // the flushed batch, then the end of the stream
+        let mut received = Vec::new();
+        client.read_to_end(&mut received).unwrap();
+        assert_eq!(received.len(), 2 * HEADER_BYTE_SIZE + 11);
+        assert!(sender.local_addr().is_none());
+        sleep(Duration::from_millis(100)).await;
+        assert!(TcpStream::connect("127.0.0.1:9060").is_err());
+    }
+
+    #[test]
+    fn test_dual_stack_listener() {
+        let addr = SocketAddr::new(AddressFamily::DualStack.unspecified(), 9057);
+        let listener = bind_tcp(addr, AddressFamily::DualStack.only_v6()).unwrap();
+
+        TcpStream::connect("127.0.0.1:9057").unwrap();
+        TcpStream::connect("[::1]:9057").unwrap();
+        assert!(listener.accept().unwrap().1.is_ipv6());
+        assert!(listener.accept().unwrap().1.is_ipv6());
+    }
+
+    #[test]
+    fn test_encode_batch_header() {
+        let msg = frame(b"hello world");
+        let batch = encode_batch(
+            std::iter::once(msg.as_slice()),
+            msg.len(),
+            PROTOCOL_VERSION_V3,
+        );
+
+        assert_eq!(
+            batch.len(),
+            batch_header_size(PROTOCOL_VERSION_V3) + msg.len()
+        );
+        assert_eq!(batch[..4], (msg.len() as u32).to_le_bytes());
+        assert_eq!(batch[4..8], crc32fast::hash(&msg).to_le_bytes());
+        assert_eq!(batch[SEQUENCE_OFFSET..SEQUENCE_OFFSET + 8], [0; 8]);
+        let mut buffer = TcpBuffer::new(1);
+        buffer.append(b"hello world".to_vec());
+        assert_eq!(
+            reframe_batch(&buffer.flush_data(), None, PROTOCOL_VERSION_V3),
+            (Some(batch), 0)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_vectored_batch() {
+        let messages = [frame(b"hello"), frame(b"world")];
+        let batch = Batch::new(
+            messages
+                .iter()
+                .map(|msg| Arc::from(msg.as_slice()))
+                .collect(),
+            PROTOCOL_VERSION_V3,
+        );
+
+        let mut written = Vec::new();
+        batch.write_to(&mut written, Some(7)).await.unwrap();
+
+        let mut expected = encode_batch(
+            messages.iter().map(|msg| msg.as_slice()),
+            batch.len() - batch_header_size(PROTOCOL_VERSION_V3),
+            PROTOCOL_VERSION_V3,
+        );
+        expected[SEQUENCE_OFFSET..SEQUENCE_OFFSET + 8].copy_from_slice(&7u64.to_le_bytes());
+        assert_eq!(written, expected);
+
+        // a stream taking a few bytes at a time, splitting the header and the messages
+        struct ShortWrites(Vec<u8>);
+        impl AsyncWrite for ShortWrites {
+            fn poll_write(
+                mut self: std::pin::Pin<&mut Self>,
+                _cx: &mut std::task::Context<'_>,
+                buf: &[u8],
+            ) -> std::task::Poll<io::Result<usize>> {
+                let written = buf.len().min(3);
+                self.0.extend_from_slice(&buf[..written]);
+                std::task::Poll::Ready(Ok(written))
+            }
+
+            fn poll_flush(
+                self: std::pin::Pin<&mut Self>,
+                _cx: &mut std::task::Context<'_>,
+            ) -> std::task::Poll<io::Result<()>> {
+                std::task::Poll::Ready(Ok(()))
+            }
+
+            fn poll_shutdown(
+                self: std::pin::Pin<&mut Self>,
+                _cx: &mut std::task::Context<'_>,
+            ) -> std::task::Poll<io::Result<()>> {
+                std::task::Poll::Ready(Ok(()))
+            }
+        }
+        let mut short_writes = ShortWrites(Vec::new());
+        batch.write_to(&mut short_writes, Some(7)).await.unwrap();
+        assert_eq!(short_writes.0, expected);
+    }
+
+    #[test]
+    fn test_publish_batch_filters() {
+        let mut buffer = TcpBuffer::new(2);
+        buffer.append(vec![MessageType::Account.prefix(), 1, 2, 3]);
+        buffer.append(vec![MessageType::Slot.prefix(), 4, 5, 6]);
+        let batch = buffer.flush_data();
+
+        let slots = SubscriptionFilters {
+            message_types: Some(vec![MessageType::Slot]),
+            ..Default::default()
+        };
+        let (selected, filtered) = reframe_batch(&batch, Some(&slots), PROTOCOL_VERSION_V1);
+        assert_eq!(filtered, 1);
+
+        let mut buffer = TcpBuffer::new(1);
+        buffer.append(vec![MessageType::Slot.prefix(), 4, 5, 6]);
+        assert_eq!(selected, Some(buffer.flush_data()));
+
+        let blocks = SubscriptionFilters {
+            message_types: Some(vec![MessageType::Block]),
+            ..Default::default()
+        };
+        assert_eq!(
+            reframe_batch(&batch, Some(&blocks), PROTOCOL_VERSION_V1),
+            (None, 2)
+        );
+    }
+
+    #[test]
+    fn test_fan_out_keeps_order() {
+        let items = (0..10).collect::<Vec<u32>>();
+        for threads in [1, 3, 16] {
+            let sender = TcpSender::new(1024, false, 0).with_fanout_threads(threads);
+            assert_eq!(
+                sender.fan_out(&items, |i| i * 2),
+                (0..10).map(|i| i * 2).collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_topics_are_flushed_apart() {
+        let sender =
+            TcpSender::new(1024, false, 0).with_topics(HashMap::from([(MessageType::Slot, 0)]));
+        sender
+            .publish(vec![MessageType::Account.prefix(), 1, 2, 3])
+            .unwrap();
+        sender
+            .publish(vec![MessageType::Slot.prefix(), 4, 5, 6])
+            .unwrap();
+
+        assert_eq!(sender.buffer.filling.lock().unwrap().total_bytesize(), 8);
+        let (slots, _) = &sender.topics[&MessageType::Slot];
+        assert_eq!(slots.filling.lock().unwrap().total_bytesize(), 0);
+    }
+
+    #[test]
+    fn test_undeliverable_batches_are_spilled() {
+        let dir = std::env::temp_dir().join(format!("dead-{}", uuid::Uuid::new_v4()));
+        let dead_letters = DeadLetters::open(crate::dead_letter::DeadLetterConfig {
+            dir: dir.clone(),
+            max_bytes: None,
+        })
+        .unwrap();
+        let sender = TcpSender::new(10, false, 0).with_dead_letters(dead_letters);
+
+        // no subscriber to send them to
+        sender.publish(b"hello world".to_vec()).unwrap();
+        sender
+            .publish_batch(encode_batch(
+                [&[0u8; 8][..]].into_iter(),
+                8,
+                PROTOCOL_VERSION_V1,
+            ))
+            .unwrap();
+
+        let letters = crate::dead_letter::dead_letters(&dir).unwrap();
+        assert_eq!(letters.len(), 2);
+        assert_eq!(
+            crate::wal::read_segment(&letters[0].path).unwrap(),
+            vec![b"hello world".to_vec()]
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_min_subscribers_by_type() {
+        let sender = Arc::new(
+            TcpSender::new(1024, false, 0)
+                .with_min_subscribers_by_type(HashMap::from([(MessageType::Account, 1)])),
+        );
+        sender
+            .publish(vec![MessageType::Slot.prefix(), 1, 2, 3])
+            .unwrap();
+
+        let waiting = sender.clone();
+        let account =
+            thread::spawn(move || waiting.publish(vec![MessageType::Account.prefix(), 4, 5, 6]));
+        thread::sleep(Duration::from_millis(100));
+        // held until a subscriber connects, or the sender drains
+        assert!(!account.is_finished());
+        assert_eq!(sender.buffer.filling.lock().unwrap().total_bytesize(), 8);
+
+        sender.draining.store(true, Ordering::Relaxed);
+        assert!(matches!(
+            account.join().unwrap(),
+            Err(GeyserError::SenderDraining)
+        ));
+    }
+
+    #[test]
+    fn test_subscribers_identified_by_name() {
+        let sender = TcpSender::new(1024, false, 0).with_handshake_timeout(Duration::from_secs(1));
+        sender.bind(9067, 100).unwrap();
+
+        let hello = |name: Option<&str>| {
+            let mut stream = TcpStream::connect("127.0.0.1:9067").unwrap();
+            let mut buffer = TcpBuffer::new(1);
+            buffer.append(
+                ClientHello {
+                    name: name.map(str::to_string),
+                    ..Default::default()
+                }
+                .to_message(),
+            );
+            stream.write_all(&buffer.flush_data()).unwrap();
+            thread::sleep(Duration::from_millis(100));
+            stream
+        };
+        let _streams = [hello(Some("indexer")), hello(Some("indexer")), hello(None)];
+
+        let mut ids = sender
+            .subscribers()
+            .unwrap()
+            .into_iter()
+            .map(|subscriber| subscriber.id)
+            .collect::<Vec<_>>();
+        // hex digits sort before names
+        ids.sort();
+        assert!(Uuid::parse_str(&ids[0]).is_ok());
+        assert_eq!(ids[1..], ["indexer", "indexer#2"]);
+    }
+
+    #[test]
+    fn test_needs_messages() {
+        let sender = TcpSender::new(1024, false, 0);
+        sender.bind(9069, 100).unwrap();
+        assert!(!sender.needs_messages());
+        assert!(TcpSender::new(1024, true, 0).needs_messages());
+        assert!(TcpSender::new(1024, false, 1).needs_messages());
+
+        let _stream = TcpStream::connect("127.0.0.1:9069").unwrap();
+        // subscribed to everything once the handshake times out
+        thread::sleep(DEFAULT_HANDSHAKE_TIMEOUT * 3);
+        assert!(sender.needs_messages());
+    }
+
+    #[test]
+    fn test_drain_rejects_new_messages() {
+        let sender = TcpSender::new(1024, false, 0);
+        sender.publish(b"hello world".to_vec()).unwrap();
+
+        assert_eq!(sender.drain(Duration::from_millis(10)).unwrap(), 0);
+        assert!(matches!(
+            sender.publish(b"hello world".to_vec()),
+            Err(GeyserError::SenderDraining)
+        ));
+    }
+
+    #[test]
+    fn test_rebind_closes_old_listener() {
+        let sender = TcpSender::new(1024, false, 0);
+        sender.bind(9051, 100).unwrap();
+
+        let addr = sender.rebind("127.0.0.1:0".parse().unwrap()).unwrap();
+        assert_eq!(sender.local_addr(), Some(addr));
+        assert!(TcpStream::connect(addr).is_ok());
+
+        // the old acceptors return once woken up, dropping the listener
+        thread::sleep(Duration::from_millis(100));
+        assert!(TcpStream::connect("127.0.0.1:9051").is_err());
+    }
+
+    #[test]
+    fn test_listener_recovery() {
+        let sender = TcpSender::new(1024, false, 0);
+        let addr = "127.0.0.1:9066".parse().unwrap();
+        let listener = bind_tcp(addr, false).unwrap();
+        let broken = listener.try_clone().unwrap();
+        sender
+            .listen(Listener::Tcp(listener), Endpoint::Tcp(addr), 100)
+            .unwrap();
+
+        // accepting on a listener shut down fails right away, until the acceptor gives up
+        socket2::SockRef::from(&broken)
+            .shutdown(std::net::Shutdown::Both)
+            .unwrap();
+        drop(broken);
+        thread::sleep(MAX_ACCEPT_ERRORS as u32 * ACCEPT_ERROR_BACKOFF + Duration::from_secs(1));
+
+        assert_eq!(sender.listener_recoveries(), 1);
+        let _stream = TcpStream::connect(addr).unwrap();
+        thread::sleep(Duration::from_millis(500));
+        assert_eq!(sender.subscribers().unwrap().len(), 1);
+    }
+}
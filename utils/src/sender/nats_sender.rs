@@ -0,0 +1,365 @@
+use log::{info, warn};
+use serde::Deserialize;
+use serde_json::json;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::Publisher;
+use crate::compression::CompressionPolicy;
+use crate::errors::GeyserError;
+use crate::flatbuffer::consts::{BYTE_PREFIX_DIAGNOSTIC, COMPRESSED_FLAG};
+use crate::handshake::SCHEMA_VERSION_V1;
+use crate::message_type::MessageType;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+// how long the server is given to answer the connect and stream requests
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+// publishing fails without trying to reconnect in between
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+// JetStream error code of a stream created with another config
+const STREAM_NAME_IN_USE: u64 = 10058;
+
+#[derive(Deserialize)]
+struct ServerInfo {
+    max_payload: usize,
+}
+
+/// A connection to the NATS server, kept alive by a thread answering its pings.
+struct Connection {
+    writer: Arc<Mutex<TcpStream>>,
+    // set by the reader thread once the server closed the connection
+    closed: Arc<AtomicBool>,
+    max_payload: usize,
+}
+
+impl Connection {
+    fn open(addr: &str, stream: Option<(&str, &str)>) -> io::Result<Self> {
+        let addr = addr
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, addr.to_string()))?;
+        let socket = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?;
+        socket.set_nodelay(true)?;
+        socket.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+        let mut reader = BufReader::new(socket.try_clone()?);
+        let mut writer = socket;
+
+        let info = read_line(&mut reader)?;
+        let info = info
+            .strip_prefix("INFO ")
+            .and_then(|info| serde_json::from_str::<ServerInfo>(info).ok())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, info))?;
+
+        let connect = json!({
+            "verbose": false,
+            "pedantic": false,
+            "lang": "rust",
+            "name": "solana-geyser-zmq",
+            "protocol": 1,
+        });
+        writer.write_all(format!("CONNECT {}\r\nPING\r\n", connect).as_bytes())?;
+        expect_pong(&mut reader)?;
+
+        if let Some((name, subjects)) = stream {
+            create_stream(&mut reader, &mut writer, name, subjects)?;
+        }
+
+        let writer = Arc::new(Mutex::new(writer));
+        let closed = Arc::new(AtomicBool::new(false));
+        reader.get_ref().set_read_timeout(None)?;
+        let (pong_writer, reader_closed) = (writer.clone(), closed.clone());
+        thread::spawn(move || {
+            if let Err(e) = answer_pings(reader, &pong_writer) {
+                warn!("NATS connection lost: {}", e);
+            }
+            reader_closed.store(true, Ordering::Relaxed);
+        });
+
+        Ok(Connection {
+            writer,
+            closed,
+            max_payload: info.max_payload,
+        })
+    }
+
+    fn publish(&self, subject: &str, payload: &[u8]) -> io::Result<()> {
+        if payload.len() > self.max_payload {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "message of {} bytes over the max payload of the server",
+                    payload.len()
+                ),
+            ));
+        }
+
+        let mut command = format!("PUB {} {}\r\n", subject, payload.len()).into_bytes();
+        command.reserve(payload.len() + 2);
+        command.extend_from_slice(payload);
+        command.extend_from_slice(b"\r\n");
+
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        writer.write_all(&command)
+    }
+}
+
+impl Drop for Connection {
+    /// Makes the reader thread return.
+    fn drop(&mut self) {
+        let writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = writer.shutdown(Shutdown::Both);
+    }
+}
+
+fn read_line(reader: &mut impl BufRead) -> io::Result<String> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+
+    Ok(line.trim_end().to_string())
+}
+
+fn expect_pong(reader: &mut impl BufRead) -> io::Result<()> {
+    match read_line(reader)?.as_str() {
+        "PONG" => Ok(()),
+        line => Err(io::Error::other(line.to_string())),
+    }
+}
+
+/// Creates the JetStream stream persisting `subjects`, keeping it as is if it exists.
+fn create_stream(
+    reader: &mut impl BufRead,
+    writer: &mut impl Write,
+    name: &str,
+    subjects: &str,
+) -> io::Result<()> {
+    let inbox = format!("_INBOX.{}", uuid::Uuid::new_v4().simple());
+    let request = json!({"name": name, "subjects": [subjects]}).to_string();
+    writer.write_all(
+        format!(
+            "SUB {inbox} 1\r\nPUB $JS.API.STREAM.CREATE.{name} {inbox} {}\r\n{request}\r\n",
+            request.len()
+        )
+        .as_bytes(),
+    )?;
+
+    // MSG <subject> <sid> <size>
+    let header = read_line(reader)?;
+    let size = header
+        .strip_prefix("MSG ")
+        .and_then(|header| header.split(' ').next_back()?.parse::<usize>().ok())
+        .ok_or_else(|| io::Error::other(header.clone()))?;
+    let mut payload = vec![0; size + 2];
+    reader.read_exact(&mut payload)?;
+    writer.write_all(b"UNSUB 1\r\n")?;
+
+    let response: serde_json::Value = serde_json::from_slice(&payload[..size])?;
+    match response.get("error") {
+        None => info!("NATS stream {} created for {}", name, subjects),
+        Some(error) if error["err_code"].as_u64() == Some(STREAM_NAME_IN_USE) => {
+            info!("NATS stream {} already exists", name)
+        }
+        Some(error) => return Err(io::Error::other(error.to_string())),
+    }
+
+    Ok(())
+}
+
+fn answer_pings(mut reader: impl BufRead, writer: &Mutex<TcpStream>) -> io::Result<()> {
+    loop {
+        let line = read_line(&mut reader)?;
+        if line == "PING" {
+            let mut writer = writer.lock().unwrap_or_else(|e| e.into_inner());
+            writer.write_all(b"PONG\r\n")?;
+        } else if let Some(error) = line.strip_prefix("-ERR ") {
+            warn!("NATS server error: {}", error);
+        }
+    }
+}
+
+/// Subject of a message, `<prefix>.<type>`, e.g. "geyser.account".
+fn subject(prefix: &str, message: &[u8]) -> String {
+    let kind = match message.first().copied() {
+        Some(byte) => match MessageType::from_prefix(byte) {
+            Some(message_type) => message_type.name(),
+            None if byte & !COMPRESSED_FLAG == BYTE_PREFIX_DIAGNOSTIC => "diagnostic",
+            None => "other",
+        },
+        None => "other",
+    };
+
+    format!("{}.{}", prefix, kind)
+}
+
+struct State {
+    connection: Option<Connection>,
+    last_attempt: Instant,
+}
+
+/// Publishes every message to a NATS subject named after its type, for consumers relying on
+/// NATS, and on JetStream to replay what they missed. Payloads are the messages as is,
+/// starting with their type prefix.
+///
+/// There is no handshake, subscribers get schema v1 messages. A lost connection is opened
+/// again on a later message, at most once a second, the messages in between failing.
+pub struct NatsSender {
+    // host:port of the server
+    addr: String,
+    subject_prefix: String,
+    // the JetStream stream persisting the subjects, if any
+    stream: Option<String>,
+    compression: Option<CompressionPolicy>,
+    state: Mutex<State>,
+    // set by drain, no message is accepted afterwards
+    draining: AtomicBool,
+}
+
+impl NatsSender {
+    /// Connects to the server at `url`, e.g. "nats://127.0.0.1:4222", publishing to the
+    /// subjects under `subject_prefix`. If `stream` is set, the subjects are persisted in the
+    /// JetStream stream of that name, created with the server defaults unless it exists.
+    pub fn connect(url: &str, subject_prefix: &str, stream: Option<&str>) -> io::Result<Self> {
+        let sender = NatsSender {
+            addr: url.strip_prefix("nats://").unwrap_or(url).to_string(),
+            subject_prefix: subject_prefix.to_string(),
+            stream: stream.map(str::to_string),
+            compression: None,
+            state: Mutex::new(State {
+                connection: None,
+                last_attempt: Instant::now(),
+            }),
+            draining: AtomicBool::new(false),
+        };
+        let connection = sender.open()?;
+        sender
+            .state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .connection = Some(connection);
+
+        info!("NATS publisher connected to {}", url);
+
+        Ok(sender)
+    }
+
+    fn open(&self) -> io::Result<Connection> {
+        let subjects = format!("{}.>", self.subject_prefix);
+        let stream = self.stream.as_deref().map(|name| (name, subjects.as_str()));
+
+        Connection::open(&self.addr, stream)
+    }
+
+    /// Compresses the messages matching the policy, consumers see the compressed flag of
+    /// the prefix.
+    pub fn with_compression(mut self, policy: CompressionPolicy) -> Self {
+        self.compression = Some(policy);
+
+        self
+    }
+}
+
+impl Publisher for NatsSender {
+    fn publish_for_schema(
+        &self,
+        message: Vec<u8>,
+        schema_version: Option<u32>,
+    ) -> Result<(), GeyserError> {
+        if self.draining.load(Ordering::Relaxed) {
+            return Err(GeyserError::SenderDraining);
+        }
+
+        if schema_version.is_some_and(|version| version != SCHEMA_VERSION_V1) {
+            return Ok(());
+        }
+
+        let message = match &self.compression {
+            Some(policy) => policy.apply(message),
+            None => message,
+        };
+
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| GeyserError::SenderLockError)?;
+        if state
+            .connection
+            .as_ref()
+            .map_or(true, |connection| connection.closed.load(Ordering::Relaxed))
+        {
+            state.connection = None;
+            if state.last_attempt.elapsed() < RECONNECT_INTERVAL {
+                return Err(GeyserError::NatsSend(io::ErrorKind::NotConnected.into()));
+            }
+
+            state.last_attempt = Instant::now();
+            let connection = self.open().map_err(GeyserError::NatsSend)?;
+            info!("NATS publisher reconnected to {}", self.addr);
+            state.connection = Some(connection);
+        }
+
+        let connection = state.connection.as_ref().expect("connected above");
+        let subject = subject(&self.subject_prefix, &message);
+        match connection.publish(&subject, &message) {
+            // too large messages are skipped, the connection is still usable
+            Err(e) if e.kind() != io::ErrorKind::InvalidInput => {
+                state.connection = None;
+                Err(GeyserError::NatsSend(e))
+            }
+            result => result.map_err(GeyserError::NatsSend),
+        }
+    }
+
+    fn has_subscribers_with_schema(&self, schema_version: u32) -> bool {
+        schema_version == SCHEMA_VERSION_V1
+    }
+
+    /// Stops accepting new messages. They are written to the socket as they are published,
+    /// so nothing is left to flush and 0 is returned.
+    fn drain(&self, _timeout: Duration) -> Result<usize, GeyserError> {
+        self.draining.store(true, Ordering::Relaxed);
+
+        Ok(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_nats_sender() {
+        // plays the server side of the protocol
+        let listener = TcpListener::bind("127.0.0.1:9054").unwrap();
+        let server = thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            socket
+                .write_all(b"INFO {\"server_id\":\"test\",\"max_payload\":1024}\r\n")
+                .unwrap();
+
+            let mut reader = BufReader::new(socket.try_clone().unwrap());
+            assert!(read_line(&mut reader).unwrap().starts_with("CONNECT "));
+            assert_eq!(read_line(&mut reader).unwrap(), "PING");
+            socket.write_all(b"PONG\r\n").unwrap();
+
+            let header = read_line(&mut reader).unwrap();
+            let mut payload = vec![0; 5];
+            reader.read_exact(&mut payload).unwrap();
+            (header, payload)
+        });
+
+        let sender = NatsSender::connect("nats://127.0.0.1:9054", "geyser", None).unwrap();
+        sender.publish(vec![1, 2, 3]).unwrap();
+        assert!(sender.publish(vec![0; 2048]).is_err());
+
+        let (header, payload) = server.join().unwrap();
+        assert_eq!(header, "PUB geyser.slot 3");
+        assert_eq!(payload, vec![1, 2, 3, b'\r', b'\n']);
+    }
+}
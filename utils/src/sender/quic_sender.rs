@@ -0,0 +1,307 @@
+use log::{info, warn};
+use rustls::ServerConfig;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::runtime::Runtime;
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+use super::{Publisher, SubscriberInfo, HEADER_BYTE_SIZE};
+use crate::compression::CompressionPolicy;
+use crate::errors::GeyserError;
+use crate::flatbuffer::consts::COMPRESSED_FLAG;
+use crate::handshake::SCHEMA_VERSION_V1;
+
+/// Application protocol negotiated by the QUIC sender and receiver.
+pub const QUIC_ALPN: &[u8] = b"geyser-zmq";
+/// Keeps idle connections open and detects the dead ones, on both sides.
+pub const QUIC_KEEP_ALIVE: Duration = Duration::from_secs(5);
+
+type Clients = Arc<Mutex<HashMap<String, Client>>>;
+
+#[derive(Default)]
+struct ClientStats {
+    // bytes queued and not written yet
+    pending_bytes: AtomicUsize,
+    sent_batches: AtomicU64,
+    sent_bytes: AtomicU64,
+    dropped_batches: AtomicU64,
+}
+
+/// A QUIC subscriber, with a stream and a writing task per message type.
+struct Client {
+    connection: quinn::Connection,
+    // queues of the streams, by message prefix without the compressed flag
+    streams: HashMap<u8, mpsc::Sender<Vec<u8>>>,
+    stats: Arc<ClientStats>,
+}
+
+impl Client {
+    fn info(&self, id: &str) -> SubscriberInfo {
+        SubscriberInfo {
+            id: id.to_string(),
+            name: None,
+            labels: BTreeMap::new(),
+            group: None,
+            peer: Some(self.connection.remote_address()),
+            sent_batches: self.stats.sent_batches.load(Ordering::Relaxed),
+            sent_bytes: self.stats.sent_bytes.load(Ordering::Relaxed),
+            dropped_batches: self.stats.dropped_batches.load(Ordering::Relaxed),
+            expired_batches: 0,
+            pending_batches: self
+                .streams
+                .values()
+                .map(|queue| queue.max_capacity() - queue.capacity())
+                .sum(),
+            filtered_messages: 0,
+            quota_exceeded: 0,
+        }
+    }
+}
+
+/// Publishes to subscribers over QUIC, which copes better than TCP with lossy links. Every
+/// message type gets a unidirectional stream of its own, so a lost packet only holds back
+/// the messages of its type. Messages are framed as in the TCP batches, prefixed with
+/// their size as 4 bytes little endian.
+///
+/// There is no handshake, subscribers get schema v1 messages. A stream with `buffer_size`
+/// messages queued misses the next ones until it catches up.
+pub struct QuicSender {
+    clients: Clients,
+    endpoint: quinn::Endpoint,
+    buffer_size: usize,
+    compression: Option<CompressionPolicy>,
+    // set by drain, no message is accepted afterwards
+    draining: AtomicBool,
+    // runs the endpoint and the writing tasks, stopped with the sender
+    runtime: Runtime,
+}
+
+impl QuicSender {
+    /// Serves QUIC connections on the UDP `addr`, with the certificate of `tls`.
+    pub fn bind(addr: SocketAddr, tls: Arc<ServerConfig>, buffer_size: usize) -> io::Result<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("geyser-quic")
+            .enable_all()
+            .build()?;
+
+        let mut crypto = (*tls).clone();
+        crypto.alpn_protocols = vec![QUIC_ALPN.to_vec()];
+        let mut config = quinn::ServerConfig::with_crypto(Arc::new(crypto));
+        let mut transport = quinn::TransportConfig::default();
+        transport.keep_alive_interval(Some(QUIC_KEEP_ALIVE));
+        config.transport_config(Arc::new(transport));
+
+        let endpoint = {
+            let _guard = runtime.enter();
+            quinn::Endpoint::server(config, addr)?
+        };
+        let clients = Clients::default();
+        runtime.spawn(accept(endpoint.clone(), clients.clone()));
+
+        info!("QUIC publisher bound to {}", endpoint.local_addr()?);
+
+        Ok(QuicSender {
+            clients,
+            endpoint,
+            buffer_size: buffer_size.max(1),
+            compression: None,
+            draining: AtomicBool::new(false),
+            runtime,
+        })
+    }
+
+    /// Compresses the messages matching the policy, consumers see the compressed flag of
+    /// the prefix.
+    pub fn with_compression(mut self, policy: CompressionPolicy) -> Self {
+        self.compression = Some(policy);
+
+        self
+    }
+}
+
+impl Drop for QuicSender {
+    fn drop(&mut self) {
+        self.endpoint.close(0u32.into(), b"shutdown");
+    }
+}
+
+async fn accept(endpoint: quinn::Endpoint, clients: Clients) {
+    while let Some(connecting) = endpoint.accept().await {
+        let clients = clients.clone();
+        tokio::spawn(async move {
+            let connection = match connecting.await {
+                Ok(connection) => connection,
+                Err(e) => {
+                    info!("QUIC handshake error: {}", e);
+                    return;
+                }
+            };
+
+            let id = uuid::Uuid::new_v4().to_string();
+            if let Ok(mut clients) = clients.lock() {
+                let client = Client {
+                    connection: connection.clone(),
+                    streams: HashMap::new(),
+                    stats: Arc::new(ClientStats::default()),
+                };
+                clients.insert(id.clone(), client);
+            }
+            info!(
+                "QUIC subscriber {} connected from {}",
+                id,
+                connection.remote_address()
+            );
+
+            let reason = connection.closed().await;
+            // dropping the queues ends the writing tasks
+            if let Ok(mut clients) = clients.lock() {
+                clients.remove(&id);
+            }
+            info!("QUIC subscriber {} disconnected: {}", id, reason);
+        });
+    }
+}
+
+/// Opens a stream and writes the queued messages to it.
+async fn write_stream(
+    connection: quinn::Connection,
+    stats: Arc<ClientStats>,
+    mut queued: mpsc::Receiver<Vec<u8>>,
+) {
+    let mut stream = match connection.open_uni().await {
+        Ok(stream) => stream,
+        Err(e) => {
+            warn!("QUIC stream error: {}", e);
+            return;
+        }
+    };
+
+    while let Some(message) = queued.recv().await {
+        let bytes = message.len();
+        let mut framed = Vec::with_capacity(HEADER_BYTE_SIZE + bytes);
+        framed.extend_from_slice(&(bytes as u32).to_le_bytes());
+        framed.extend_from_slice(&message);
+
+        let result = stream.write_all(&framed).await;
+        stats.pending_bytes.fetch_sub(bytes, Ordering::Relaxed);
+        if let Err(e) = result {
+            warn!("QUIC write error: {}", e);
+            return;
+        }
+
+        stats.sent_batches.fetch_add(1, Ordering::Relaxed);
+        stats
+            .sent_bytes
+            .fetch_add(framed.len() as u64, Ordering::Relaxed);
+    }
+}
+
+impl Publisher for QuicSender {
+    fn publish_for_schema(
+        &self,
+        message: Vec<u8>,
+        schema_version: Option<u32>,
+    ) -> Result<(), GeyserError> {
+        if self.draining.load(Ordering::Relaxed) {
+            return Err(GeyserError::SenderDraining);
+        }
+
+        if schema_version.is_some_and(|version| version != SCHEMA_VERSION_V1) {
+            return Ok(());
+        }
+
+        let message = match &self.compression {
+            Some(policy) => policy.apply(message),
+            None => message,
+        };
+        let stream_key = message
+            .first()
+            .map_or(0, |prefix| prefix & !COMPRESSED_FLAG);
+
+        let mut clients = self
+            .clients
+            .lock()
+            .map_err(|_| GeyserError::SenderLockError)?;
+        for client in clients.values_mut() {
+            let queue = client.streams.entry(stream_key).or_insert_with(|| {
+                let (queue, queued) = mpsc::channel(self.buffer_size);
+                self.runtime.spawn(write_stream(
+                    client.connection.clone(),
+                    client.stats.clone(),
+                    queued,
+                ));
+                queue
+            });
+
+            let bytes = message.len();
+            client
+                .stats
+                .pending_bytes
+                .fetch_add(bytes, Ordering::Relaxed);
+            if let Err(e) = queue.try_send(message.clone()) {
+                client
+                    .stats
+                    .pending_bytes
+                    .fetch_sub(bytes, Ordering::Relaxed);
+                // closed queues belong to failed streams, their connection closing
+                if let TrySendError::Full(_) = e {
+                    client.stats.dropped_batches.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn has_subscribers_with_schema(&self, schema_version: u32) -> bool {
+        schema_version == SCHEMA_VERSION_V1
+            && self.clients.lock().is_ok_and(|clients| !clients.is_empty())
+    }
+
+    /// Messages are not kept for the clients to come.
+    fn needs_messages(&self) -> bool {
+        self.clients.lock().is_ok_and(|clients| !clients.is_empty())
+    }
+
+    /// Stops accepting new messages and waits up to `timeout` for the queued ones to be
+    /// written. Returns the number of bytes still queued.
+    fn drain(&self, timeout: Duration) -> Result<usize, GeyserError> {
+        let deadline = Instant::now() + timeout;
+        self.draining.store(true, Ordering::Relaxed);
+
+        loop {
+            let pending = self
+                .clients
+                .lock()
+                .map_err(|_| GeyserError::SenderLockError)?
+                .values()
+                .map(|client| client.stats.pending_bytes.load(Ordering::Relaxed))
+                .sum::<usize>();
+
+            if pending == 0 || Instant::now() >= deadline {
+                return Ok(pending);
+            }
+
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    fn subscribers(&self) -> Result<Vec<SubscriberInfo>, GeyserError> {
+        let clients = self
+            .clients
+            .lock()
+            .map_err(|_| GeyserError::SenderLockError)?;
+
+        Ok(clients.iter().map(|(id, client)| client.info(id)).collect())
+    }
+
+    fn local_addr(&self) -> Option<SocketAddr> {
+        self.endpoint.local_addr().ok()
+    }
+}
@@ -0,0 +1,233 @@
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use super::{Publisher, SubscriberInfo};
+use crate::errors::GeyserError;
+use crate::message_type::MessageType;
+
+/// A publisher, and the message types routed to it, every one if not set.
+struct Route {
+    message_types: Option<Vec<MessageType>>,
+    publisher: Box<dyn Publisher>,
+}
+
+impl Route {
+    fn matches(&self, message: &[u8]) -> bool {
+        match (&self.message_types, message.first()) {
+            (Some(types), Some(&prefix)) => MessageType::from_prefix(prefix)
+                .map_or(true, |message_type| types.contains(&message_type)),
+            _ => true,
+        }
+    }
+}
+
+/// Publishes every message through the listeners routed its type, so one plugin can serve,
+/// e.g., accounts on one port and transactions on another. Control messages, which have no
+/// type, go to every listener.
+///
+/// The first listener is the main one, which is moved by `rebind` and reported by
+/// `local_addr`.
+pub struct Router {
+    routes: Vec<Route>,
+}
+
+impl Router {
+    /// Routes every message to `main`.
+    pub fn new(main: Box<dyn Publisher>) -> Self {
+        Router {
+            routes: vec![Route {
+                message_types: None,
+                publisher: main,
+            }],
+        }
+    }
+
+    /// Adds a listener, getting the messages of `message_types`, or every one if not set.
+    pub fn with_route(
+        mut self,
+        message_types: Option<Vec<MessageType>>,
+        publisher: Box<dyn Publisher>,
+    ) -> Self {
+        self.routes.push(Route {
+            message_types,
+            publisher,
+        });
+
+        self
+    }
+}
+
+impl Publisher for Router {
+    /// Publishes the message through every matching listener, even if one of them fails,
+    /// returning the first error.
+    fn publish_for_schema(
+        &self,
+        message: Vec<u8>,
+        schema_version: Option<u32>,
+    ) -> Result<(), GeyserError> {
+        let routes = self
+            .routes
+            .iter()
+            .filter(|route| route.matches(&message))
+            .collect::<Vec<_>>();
+        let Some((last, routes)) = routes.split_last() else {
+            return Ok(());
+        };
+
+        let mut res = Ok(());
+        for route in routes {
+            let published = route
+                .publisher
+                .publish_for_schema(message.clone(), schema_version);
+            res = res.and(published);
+        }
+        // the last listener gets the message itself
+        res.and(last.publisher.publish_for_schema(message, schema_version))
+    }
+
+    fn has_subscribers_with_schema(&self, schema_version: u32) -> bool {
+        self.routes
+            .iter()
+            .any(|route| route.publisher.has_subscribers_with_schema(schema_version))
+    }
+
+    fn needs_messages(&self) -> bool {
+        self.routes
+            .iter()
+            .any(|route| route.publisher.needs_messages())
+    }
+
+    /// Drains every listener, each given up to `timeout`.
+    fn drain(&self, timeout: Duration) -> Result<usize, GeyserError> {
+        self.routes.iter().try_fold(0, |abandoned, route| {
+            Ok(abandoned + route.publisher.drain(timeout)?)
+        })
+    }
+
+    fn shutdown(&self) {
+        for route in &self.routes {
+            route.publisher.shutdown();
+        }
+    }
+
+    fn subscribers(&self) -> Result<Vec<SubscriberInfo>, GeyserError> {
+        let mut subscribers = Vec::new();
+        for route in &self.routes {
+            subscribers.extend(route.publisher.subscribers()?);
+        }
+
+        Ok(subscribers)
+    }
+
+    fn rebind(&self, addr: SocketAddr) -> io::Result<SocketAddr> {
+        self.routes[0].publisher.rebind(addr)
+    }
+
+    fn local_addr(&self) -> Option<SocketAddr> {
+        self.routes[0].publisher.local_addr()
+    }
+
+    fn expired_messages(&self) -> u64 {
+        self.routes
+            .iter()
+            .map(|route| route.publisher.expired_messages())
+            .sum()
+    }
+
+    fn rejected_connections(&self) -> u64 {
+        self.routes
+            .iter()
+            .map(|route| route.publisher.rejected_connections())
+            .sum()
+    }
+
+    fn stalled_connections(&self) -> u64 {
+        self.routes
+            .iter()
+            .map(|route| route.publisher.stalled_connections())
+            .sum()
+    }
+
+    fn abandoned_batches(&self) -> u64 {
+        self.routes
+            .iter()
+            .map(|route| route.publisher.abandoned_batches())
+            .sum()
+    }
+
+    fn listener_recoveries(&self) -> u64 {
+        self.routes
+            .iter()
+            .map(|route| route.publisher.listener_recoveries())
+            .sum()
+    }
+
+    fn hung_connections(&self) -> u64 {
+        self.routes
+            .iter()
+            .map(|route| route.publisher.hung_connections())
+            .sum()
+    }
+
+    fn writer_panics(&self) -> u64 {
+        self.routes
+            .iter()
+            .map(|route| route.publisher.writer_panics())
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flatbuffer::consts::{BYTE_PREFIX_ACCOUNT, BYTE_PREFIX_DIAGNOSTIC, BYTE_PREFIX_TX};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default, Clone)]
+    struct Recorder(Arc<Mutex<Vec<Vec<u8>>>>);
+
+    impl Publisher for Recorder {
+        fn publish_for_schema(&self, message: Vec<u8>, _: Option<u32>) -> Result<(), GeyserError> {
+            self.0.lock().unwrap().push(message);
+            Ok(())
+        }
+
+        fn has_subscribers_with_schema(&self, _: u32) -> bool {
+            true
+        }
+
+        fn drain(&self, _: Duration) -> Result<usize, GeyserError> {
+            Ok(0)
+        }
+    }
+
+    #[test]
+    fn test_router() {
+        let (main, accounts, txs) = (
+            Recorder::default(),
+            Recorder::default(),
+            Recorder::default(),
+        );
+        let router = Router::new(Box::new(main.clone()))
+            .with_route(Some(vec![MessageType::Account]), Box::new(accounts.clone()))
+            .with_route(Some(vec![MessageType::Transaction]), Box::new(txs.clone()));
+
+        router.publish(vec![BYTE_PREFIX_ACCOUNT, 1]).unwrap();
+        router.publish(vec![BYTE_PREFIX_TX, 2]).unwrap();
+        router.publish(vec![BYTE_PREFIX_DIAGNOSTIC, 3]).unwrap();
+
+        assert_eq!(main.0.lock().unwrap().len(), 3);
+        assert_eq!(
+            *accounts.0.lock().unwrap(),
+            vec![
+                vec![BYTE_PREFIX_ACCOUNT, 1],
+                vec![BYTE_PREFIX_DIAGNOSTIC, 3]
+            ]
+        );
+        assert_eq!(
+            *txs.0.lock().unwrap(),
+            vec![vec![BYTE_PREFIX_TX, 2], vec![BYTE_PREFIX_DIAGNOSTIC, 3]]
+        );
+    }
+}
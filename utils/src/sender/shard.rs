@@ -0,0 +1,182 @@
+use std::time::Duration;
+
+use super::{Publisher, SubscriberInfo};
+use crate::errors::GeyserError;
+use crate::event::message_shard_key;
+
+/// The shard of a key out of `shards`: the CRC32 of the key, as a string, modulo `shards`,
+/// so consumers can tell which shard any account or transaction is published on.
+pub fn shard_of(key: &str, shards: usize) -> usize {
+    crc32fast::hash(key.as_bytes()) as usize % shards.max(1)
+}
+
+/// Partitions accounts by pubkey and transactions by signature across its shards, each a
+/// listener of its own, so a pool of consumers each handles a deterministic part of the
+/// stream, see `shard_of`. The other messages, e.g. slots and blocks, go to every shard.
+pub struct Sharder {
+    shards: Vec<Box<dyn Publisher>>,
+}
+
+impl Sharder {
+    pub fn new(shards: Vec<Box<dyn Publisher>>) -> Self {
+        Sharder { shards }
+    }
+}
+
+impl Publisher for Sharder {
+    /// Publishes the message through its shard, or through every one if it has none,
+    /// even if one of them fails, returning the first error.
+    fn publish_for_schema(
+        &self,
+        message: Vec<u8>,
+        schema_version: Option<u32>,
+    ) -> Result<(), GeyserError> {
+        if let Some(key) = message_shard_key(&message) {
+            let shard = &self.shards[shard_of(&key, self.shards.len())];
+            return shard.publish_for_schema(message, schema_version);
+        }
+
+        let Some((last, shards)) = self.shards.split_last() else {
+            return Ok(());
+        };
+
+        let mut res = Ok(());
+        for shard in shards {
+            res = res.and(shard.publish_for_schema(message.clone(), schema_version));
+        }
+        // the last shard gets the message itself
+        res.and(last.publish_for_schema(message, schema_version))
+    }
+
+    fn has_subscribers_with_schema(&self, schema_version: u32) -> bool {
+        self.shards
+            .iter()
+            .any(|shard| shard.has_subscribers_with_schema(schema_version))
+    }
+
+    fn needs_messages(&self) -> bool {
+        self.shards.iter().any(|shard| shard.needs_messages())
+    }
+
+    /// Drains every shard, each given up to `timeout`.
+    fn drain(&self, timeout: Duration) -> Result<usize, GeyserError> {
+        self.shards
+            .iter()
+            .try_fold(0, |abandoned, shard| Ok(abandoned + shard.drain(timeout)?))
+    }
+
+    fn shutdown(&self) {
+        for shard in &self.shards {
+            shard.shutdown();
+        }
+    }
+
+    fn subscribers(&self) -> Result<Vec<SubscriberInfo>, GeyserError> {
+        let mut subscribers = Vec::new();
+        for shard in &self.shards {
+            subscribers.extend(shard.subscribers()?);
+        }
+
+        Ok(subscribers)
+    }
+
+    fn expired_messages(&self) -> u64 {
+        self.shards.iter().map(|s| s.expired_messages()).sum()
+    }
+
+    fn rejected_connections(&self) -> u64 {
+        self.shards.iter().map(|s| s.rejected_connections()).sum()
+    }
+
+    fn stalled_connections(&self) -> u64 {
+        self.shards.iter().map(|s| s.stalled_connections()).sum()
+    }
+
+    fn abandoned_batches(&self) -> u64 {
+        self.shards.iter().map(|s| s.abandoned_batches()).sum()
+    }
+
+    fn listener_recoveries(&self) -> u64 {
+        self.shards.iter().map(|s| s.listener_recoveries()).sum()
+    }
+
+    fn hung_connections(&self) -> u64 {
+        self.shards.iter().map(|s| s.hung_connections()).sum()
+    }
+
+    fn writer_panics(&self) -> u64 {
+        self.shards.iter().map(|s| s.writer_panics()).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flatbuffer::{
+        account_info_generated::account_info::{AccountInfo, AccountInfoArgs},
+        consts::{BYTE_PREFIX_ACCOUNT, BYTE_PREFIX_SLOT},
+    };
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default, Clone)]
+    struct Recorder(Arc<Mutex<Vec<Vec<u8>>>>);
+
+    impl Publisher for Recorder {
+        fn publish_for_schema(&self, message: Vec<u8>, _: Option<u32>) -> Result<(), GeyserError> {
+            self.0.lock().unwrap().push(message);
+            Ok(())
+        }
+
+        fn has_subscribers_with_schema(&self, _: u32) -> bool {
+            true
+        }
+
+        fn drain(&self, _: Duration) -> Result<usize, GeyserError> {
+            Ok(0)
+        }
+    }
+
+    fn account(pubkey: &str) -> Vec<u8> {
+        let mut builder = flatbuffers::FlatBufferBuilder::new();
+        let pubkey = builder.create_string(pubkey);
+        let info = AccountInfo::create(
+            &mut builder,
+            &AccountInfoArgs {
+                pubkey: Some(pubkey),
+                ..Default::default()
+            },
+        );
+        builder.finish(info, None);
+
+        let mut message = vec![BYTE_PREFIX_ACCOUNT];
+        message.extend_from_slice(builder.finished_data());
+        message
+    }
+
+    #[test]
+    fn test_sharder() {
+        let shards = [Recorder::default(), Recorder::default()];
+        let sharder = Sharder::new(
+            shards
+                .iter()
+                .map(|shard| Box::new(shard.clone()) as Box<dyn Publisher>)
+                .collect(),
+        );
+
+        let pubkeys = ["a", "b", "c", "d"];
+        for pubkey in pubkeys {
+            sharder.publish(account(pubkey)).unwrap();
+        }
+        sharder.publish(vec![BYTE_PREFIX_SLOT, 1]).unwrap();
+
+        for (i, shard) in shards.iter().enumerate() {
+            let mut expected = pubkeys
+                .iter()
+                .filter(|pubkey| shard_of(pubkey, 2) == i)
+                .map(|pubkey| account(pubkey))
+                .collect::<Vec<_>>();
+            expected.push(vec![BYTE_PREFIX_SLOT, 1]);
+            assert_eq!(*shard.0.lock().unwrap(), expected);
+        }
+    }
+}
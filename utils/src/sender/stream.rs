@@ -0,0 +1,387 @@
+//! Subscriber connections, over TCP, TLS or a unix socket.
+use rustls::{ServerConfig, ServerConnection, StreamOwned};
+use socket2::{Domain, Protocol, Socket, Type};
+use std::io::{self, IoSlice, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, TcpListener, TcpStream};
+#[cfg(unix)]
+use std::os::unix::{
+    fs::FileTypeExt,
+    net::{UnixListener, UnixStream},
+};
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::tcp;
+#[cfg(unix)]
+use tokio::net::unix;
+use tokio::runtime::Handle;
+
+/// Where a listener is bound.
+#[derive(Debug, Clone)]
+pub(super) enum Endpoint {
+    Tcp(SocketAddr),
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+impl Endpoint {
+    /// Connects to the listener and disconnects right away.
+    pub(super) fn touch(&self, timeout: Duration) -> io::Result<()> {
+        match self {
+            Endpoint::Tcp(addr) => {
+                // a listener on every interface is reached through the loopback one
+                let ip = match addr.ip() {
+                    IpAddr::V4(ip) if ip.is_unspecified() => Ipv4Addr::LOCALHOST.into(),
+                    IpAddr::V6(ip) if ip.is_unspecified() => Ipv6Addr::LOCALHOST.into(),
+                    ip => ip,
+                };
+                TcpStream::connect_timeout(&SocketAddr::new(ip, addr.port()), timeout).map(drop)
+            }
+            #[cfg(unix)]
+            Endpoint::Unix(path) => UnixStream::connect(path).map(drop),
+        }
+    }
+
+    /// Binds a listener to the endpoint, replacing the socket a previous listener left at
+    /// a unix one.
+    pub(super) fn bind(&self, only_v6: bool) -> io::Result<Listener> {
+        match self {
+            Endpoint::Tcp(addr) => bind_tcp(*addr, only_v6).map(Listener::Tcp),
+            #[cfg(unix)]
+            Endpoint::Unix(path) => {
+                if std::fs::symlink_metadata(path)
+                    .is_ok_and(|metadata| metadata.file_type().is_socket())
+                {
+                    std::fs::remove_file(path)?;
+                }
+                UnixListener::bind(path).map(Listener::Unix)
+            }
+        }
+    }
+}
+
+/// Binds a TCP listener to `addr`, accepting IPv4 connections too if it's an IPv6 address and
+/// `only_v6` is not set.
+pub(super) fn bind_tcp(addr: SocketAddr, only_v6: bool) -> io::Result<TcpListener> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+    if addr.is_ipv6() {
+        socket.set_only_v6(only_v6)?;
+    }
+    // as std does, so a restarted sender can bind while old connections linger
+    #[cfg(unix)]
+    socket.set_reuse_address(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(128)?;
+
+    Ok(socket.into())
+}
+
+pub(super) enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener),
+}
+
+impl Listener {
+    pub(super) fn try_clone(&self) -> io::Result<Self> {
+        match self {
+            Listener::Tcp(listener) => listener.try_clone().map(Listener::Tcp),
+            #[cfg(unix)]
+            Listener::Unix(listener) => listener.try_clone().map(Listener::Unix),
+        }
+    }
+
+    pub(super) fn accept(&self) -> io::Result<Stream> {
+        match self {
+            Listener::Tcp(listener) => listener.accept().map(|(stream, _)| Stream::Tcp(stream)),
+            #[cfg(unix)]
+            Listener::Unix(listener) => listener.accept().map(|(stream, _)| Stream::Unix(stream)),
+        }
+    }
+}
+
+pub(super) enum Stream {
+    Tcp(TcpStream),
+    Tls(Box<StreamOwned<ServerConnection, TcpStream>>),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl Stream {
+    /// Wraps a TCP stream in TLS, completing the TLS handshake within `timeout`. Other
+    /// streams are returned as is.
+    pub(super) fn into_tls(self, config: Arc<ServerConfig>, timeout: Duration) -> io::Result<Self> {
+        let stream = match self {
+            Stream::Tcp(stream) => stream,
+            stream => return Ok(stream),
+        };
+
+        let conn = ServerConnection::new(config).map_err(io::Error::other)?;
+        let mut stream = StreamOwned::new(conn, stream);
+        stream.sock.set_read_timeout(Some(timeout))?;
+        while stream.conn.is_handshaking() {
+            stream.conn.complete_io(&mut stream.sock)?;
+        }
+
+        Ok(Stream::Tls(Box::new(stream)))
+    }
+
+    pub(super) fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        match self {
+            Stream::Tcp(stream) => stream.set_read_timeout(timeout),
+            Stream::Tls(stream) => stream.sock.set_read_timeout(timeout),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.set_read_timeout(timeout),
+        }
+    }
+
+    /// Moves the stream to `runtime` once the handshake is done, split into the half it's
+    /// written to and the one acks are read from. TLS streams have no read half, their session
+    /// can't be shared.
+    pub(super) fn into_async(self, runtime: &Handle) -> io::Result<(WriteHalf, Option<ReadHalf>)> {
+        let _runtime = runtime.enter();
+        match self {
+            Stream::Tcp(stream) => {
+                stream.set_nonblocking(true)?;
+                let (read, write) = tokio::net::TcpStream::from_std(stream)?.into_split();
+                Ok((WriteHalf::Tcp(write), Some(ReadHalf::Tcp(read))))
+            }
+            Stream::Tls(stream) => {
+                let StreamOwned { conn, sock } = *stream;
+                sock.set_nonblocking(true)?;
+                let sock = tokio::net::TcpStream::from_std(sock)?;
+                Ok((WriteHalf::Tls(Box::new(conn), sock), None))
+            }
+            #[cfg(unix)]
+            Stream::Unix(stream) => {
+                stream.set_nonblocking(true)?;
+                let (read, write) = tokio::net::UnixStream::from_std(stream)?.into_split();
+                Ok((WriteHalf::Unix(write), Some(ReadHalf::Unix(read))))
+            }
+        }
+    }
+
+    /// A handle to the socket of the stream, TLS ones included, to close it while its writer
+    /// waits on it.
+    pub(super) fn closer(&self) -> io::Result<Closer> {
+        match self {
+            Stream::Tcp(stream) => stream.try_clone().map(Closer::Tcp),
+            Stream::Tls(stream) => stream.sock.try_clone().map(Closer::Tcp),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.try_clone().map(Closer::Unix),
+        }
+    }
+
+    /// Common name of the certificate a TLS client authenticated with.
+    pub(super) fn peer_common_name(&self) -> Option<String> {
+        match self {
+            Stream::Tls(stream) => stream
+                .conn
+                .peer_certificates()
+                .and_then(|certs| certs.first())
+                .and_then(crate::tls::common_name),
+            _ => None,
+        }
+    }
+
+    /// The remote address, None for unix sockets.
+    pub(super) fn peer_addr(&self) -> Option<SocketAddr> {
+        match self {
+            Stream::Tcp(stream) => stream.peer_addr().ok(),
+            Stream::Tls(stream) => stream.sock.peer_addr().ok(),
+            #[cfg(unix)]
+            Stream::Unix(_) => None,
+        }
+    }
+}
+
+pub(super) enum Closer {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl Closer {
+    /// Shuts the socket down, reads and writes blocked on it return.
+    pub(super) fn close(&self) -> io::Result<()> {
+        match self {
+            Closer::Tcp(stream) => stream.shutdown(Shutdown::Both),
+            #[cfg(unix)]
+            Closer::Unix(stream) => stream.shutdown(Shutdown::Both),
+        }
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.read(buf),
+            Stream::Tls(stream) => stream.read(buf),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.write(buf),
+            Stream::Tls(stream) => stream.write(buf),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.write(buf),
+        }
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.write_vectored(bufs),
+            Stream::Tls(stream) => stream.write_vectored(bufs),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.write_vectored(bufs),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Stream::Tcp(stream) => stream.flush(),
+            Stream::Tls(stream) => stream.flush(),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.flush(),
+        }
+    }
+}
+
+/// The half of a stream its batches are written to, see `Stream::into_async`.
+pub(super) enum WriteHalf {
+    Tcp(tcp::OwnedWriteHalf),
+    // the session encrypts what's written into records, written to the socket as it takes them
+    Tls(Box<ServerConnection>, tokio::net::TcpStream),
+    #[cfg(unix)]
+    Unix(unix::OwnedWriteHalf),
+}
+
+/// The half of a stream its acks are read from, see `Stream::into_async`.
+pub(super) enum ReadHalf {
+    Tcp(tcp::OwnedReadHalf),
+    #[cfg(unix)]
+    Unix(unix::OwnedReadHalf),
+}
+
+/// Writes the records the TLS session has ready to the socket.
+fn poll_write_tls(
+    conn: &mut ServerConnection,
+    sock: &mut tokio::net::TcpStream,
+    cx: &mut Context<'_>,
+) -> Poll<io::Result<()>> {
+    while conn.wants_write() {
+        match conn.write_tls(&mut AsyncSocket { sock, cx }) {
+            Ok(_) => {}
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Poll::Pending,
+            Err(e) => return Poll::Ready(Err(e)),
+        }
+    }
+
+    Poll::Ready(Ok(()))
+}
+
+/// The socket of a TLS stream as rustls writes records to it, failing with `WouldBlock` when
+/// it's not ready, once the task is registered to be woken up.
+struct AsyncSocket<'a, 'b> {
+    sock: &'a mut tokio::net::TcpStream,
+    cx: &'a mut Context<'b>,
+}
+
+impl Write for AsyncSocket<'_, '_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match Pin::new(&mut *self.sock).poll_write(self.cx, buf) {
+            Poll::Ready(res) => res,
+            Poll::Pending => Err(io::ErrorKind::WouldBlock.into()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl AsyncWrite for WriteHalf {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            WriteHalf::Tcp(stream) => Pin::new(stream).poll_write(cx, buf),
+            WriteHalf::Tls(conn, sock) => {
+                // the session only buffers so much, the records of the previous writes go first
+                ready!(poll_write_tls(conn, sock, cx))?;
+                Poll::Ready(conn.writer().write(buf))
+            }
+            #[cfg(unix)]
+            WriteHalf::Unix(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            WriteHalf::Tcp(stream) => Pin::new(stream).poll_write_vectored(cx, bufs),
+            WriteHalf::Tls(conn, sock) => {
+                ready!(poll_write_tls(conn, sock, cx))?;
+                Poll::Ready(conn.writer().write_vectored(bufs))
+            }
+            #[cfg(unix)]
+            WriteHalf::Unix(stream) => Pin::new(stream).poll_write_vectored(cx, bufs),
+        }
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        true
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            WriteHalf::Tcp(stream) => Pin::new(stream).poll_flush(cx),
+            WriteHalf::Tls(conn, sock) => {
+                ready!(poll_write_tls(conn, sock, cx))?;
+                Pin::new(sock).poll_flush(cx)
+            }
+            #[cfg(unix)]
+            WriteHalf::Unix(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            WriteHalf::Tcp(stream) => Pin::new(stream).poll_shutdown(cx),
+            WriteHalf::Tls(conn, sock) => {
+                ready!(poll_write_tls(conn, sock, cx))?;
+                Pin::new(sock).poll_shutdown(cx)
+            }
+            #[cfg(unix)]
+            WriteHalf::Unix(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
+
+impl AsyncRead for ReadHalf {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            ReadHalf::Tcp(stream) => Pin::new(stream).poll_read(cx, buf),
+            #[cfg(unix)]
+            ReadHalf::Unix(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
@@ -0,0 +1,345 @@
+//! Writing the batches queued to the subscribers, and reading their acks, on the writers'
+//! runtime.
+use futures_util::FutureExt;
+use log::{error, warn};
+use std::collections::VecDeque;
+use std::io;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::runtime::{Handle, Runtime};
+use tokio::sync::Notify;
+
+use super::connection::{remove_conn, Connection, ConnectionMap, QueuedBatch};
+use super::stream::{Closer, ReadHalf, WriteHalf};
+use super::{Batch, HEADER_BYTE_SIZE, SEQUENCE_BYTE_SIZE};
+use crate::handshake::parse_ack;
+
+// how often the task reading the acks of a connection checks that it's still open
+const ACK_READ_TIMEOUT: Duration = Duration::from_secs(1);
+// an ack message framed as a batch
+const ACK_BATCH_BYTE_SIZE: usize = 2 * HEADER_BYTE_SIZE + 1 + SEQUENCE_BYTE_SIZE;
+
+// a batch held until acknowledgements make room for it, as it was queued
+type HeldBatch = (Arc<Batch>, Instant, u64);
+
+/// Runtime of the tasks writing to the connections and reading their acks, so that
+/// connections don't take a thread each. The tasks are abandoned once it's dropped.
+pub(super) struct Writers {
+    // only ever taken by drop, a runtime can't be dropped from an async context
+    pub(super) runtime: Option<Runtime>,
+    pub(super) handle: Handle,
+    pub(super) conns: Arc<RwLock<ConnectionMap>>,
+    pub(super) message_ttl: Option<Duration>,
+    pub(super) write_timeout: Duration,
+    pub(super) stalled_connections: Arc<AtomicU64>,
+    pub(super) writer_panics: Arc<AtomicU64>,
+}
+
+impl Drop for Writers {
+    fn drop(&mut self) {
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+impl Writers {
+    /// Spawns the task writing the batches queued to the connection, and the one reading its
+    /// acks if it asked for them. Both return once the connection is dropped or closed.
+    pub(super) fn spawn(
+        &self,
+        conn: &Arc<Connection>,
+        queued: tokio::sync::mpsc::Receiver<QueuedBatch>,
+        stream: WriteHalf,
+        ack_stream: Option<ReadHalf>,
+    ) {
+        let writer = Writer {
+            conns: self.conns.clone(),
+            stream,
+            held: VecDeque::new(),
+            message_ttl: self.message_ttl,
+            write_timeout: self.write_timeout,
+            stalled_connections: self.stalled_connections.clone(),
+            writer_panics: self.writer_panics.clone(),
+        };
+        let released = conn.acks.as_ref().map(|acks| acks.released.clone());
+        self.handle
+            .spawn(writer.run(Arc::downgrade(conn), queued, released));
+
+        if let Some(ack_stream) = ack_stream {
+            self.handle.spawn(read_acks(
+                self.conns.clone(),
+                Arc::downgrade(conn),
+                ack_stream,
+            ));
+        }
+    }
+}
+
+/// Writes the batches queued to a connection from a task of the writers' runtime.
+struct Writer {
+    conns: Arc<RwLock<ConnectionMap>>,
+    stream: WriteHalf,
+    // batches held until acknowledgements make room in the window, in order
+    held: VecDeque<HeldBatch>,
+    message_ttl: Option<Duration>,
+    write_timeout: Duration,
+    stalled_connections: Arc<AtomicU64>,
+    writer_panics: Arc<AtomicU64>,
+}
+
+impl Writer {
+    /// Writes the batches queued to the connection, and those its acks release, until it's
+    /// dropped or a write fails, which closes it. The task only holds the connection weakly,
+    /// so that the queue is closed once it's removed from the map.
+    async fn run(
+        mut self,
+        conn: Weak<Connection>,
+        mut queued: tokio::sync::mpsc::Receiver<QueuedBatch>,
+        released: Option<Arc<Notify>>,
+    ) {
+        loop {
+            let queued_batch = tokio::select! {
+                queued_batch = queued.recv() => match queued_batch {
+                    Some(queued_batch) => Some(queued_batch),
+                    None => break,
+                },
+                _ = Self::released(released.as_deref()) => None,
+            };
+            let Some(conn) = conn.upgrade() else {
+                break;
+            };
+
+            // a panic only costs the connection it happened on, rather than leaving it in the
+            // map with nothing writing to it
+            let res = AssertUnwindSafe(async {
+                match queued_batch {
+                    Some((batch, published_at, sequence)) => {
+                        self.deliver_or_hold(&conn, batch, published_at, sequence)
+                            .await
+                    }
+                    None => self.deliver_released(&conn).await,
+                }
+            })
+            .catch_unwind()
+            .await
+            .unwrap_or_else(|panic| {
+                self.writer_panics.fetch_add(1, Ordering::Relaxed);
+                let reason = panic
+                    .downcast_ref::<&str>()
+                    .copied()
+                    .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+                    .unwrap_or("unknown reason");
+                Err(io::Error::other(format!("writer panicked: {}", reason)))
+            });
+            conn.progressed();
+            if let Err(e) = res {
+                // the write timeout expired, the peer stopped reading
+                if e.kind() == io::ErrorKind::TimedOut {
+                    self.stalled_connections.fetch_add(1, Ordering::Relaxed);
+                    warn!("Write to {} timed out, disconnecting", conn.label());
+                } else {
+                    error!("Error writing data to {}: {}", conn.label(), e);
+                }
+
+                // drop connection, a partially written batch leaves it unusable anyway
+                conn.closed.store(true, Ordering::Relaxed);
+                if let Some(closer) = &conn.closer {
+                    let _ = closer.close();
+                }
+                remove_conn(&self.conns, &conn);
+                break;
+            }
+        }
+    }
+
+    /// Waits for acks to release held batches, forever for connections not sending any.
+    async fn released(released: Option<&Notify>) {
+        match released {
+            Some(released) => released.notified().await,
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Delivers the batch, or holds it if it's past the acknowledgement window or other
+    /// batches are held already.
+    async fn deliver_or_hold(
+        &mut self,
+        conn: &Connection,
+        batch: Arc<Batch>,
+        published_at: Instant,
+        sequence: Option<u64>,
+    ) -> io::Result<()> {
+        let (Some(acks), Some(sequence)) = (&conn.acks, sequence) else {
+            return self.deliver(conn, &batch, published_at, sequence).await;
+        };
+
+        if self.held.is_empty() && sequence < acks.acked.load(Ordering::Relaxed) + acks.window {
+            acks.ack_expired(sequence, published_at, self.message_ttl);
+            return self
+                .deliver(conn, &batch, published_at, Some(sequence))
+                .await;
+        }
+
+        self.held.push_back((batch, published_at, sequence));
+        Ok(())
+    }
+
+    /// Delivers the held batches as far as the acknowledgement window allows.
+    async fn deliver_released(&mut self, conn: &Connection) -> io::Result<()> {
+        let Some(acks) = &conn.acks else {
+            return Ok(());
+        };
+
+        while self.held.front().is_some_and(|(_, _, sequence)| {
+            *sequence < acks.acked.load(Ordering::Relaxed) + acks.window
+        }) {
+            if let Some((batch, published_at, sequence)) = self.held.pop_front() {
+                acks.ack_expired(sequence, published_at, self.message_ttl);
+                self.deliver(conn, &batch, published_at, Some(sequence))
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes a batch queued to the connection, unless closed or expired, with its sequence
+    /// number in its header if set.
+    async fn deliver(
+        &mut self,
+        conn: &Connection,
+        batch: &Batch,
+        published_at: Instant,
+        sequence: Option<u64>,
+    ) -> io::Result<()> {
+        let res = self.write_batch(conn, batch, published_at, sequence).await;
+        // only once the write is done, so that `TcpSender::drain` waits for it
+        conn.pending.fetch_sub(1, Ordering::Relaxed);
+        conn.pending_bytes.fetch_sub(batch.len(), Ordering::Relaxed);
+
+        res
+    }
+
+    async fn write_batch(
+        &mut self,
+        conn: &Connection,
+        batch: &Batch,
+        published_at: Instant,
+        sequence: Option<u64>,
+    ) -> io::Result<()> {
+        if conn.closed.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        if self
+            .message_ttl
+            .is_some_and(|ttl| published_at.elapsed() > ttl)
+        {
+            conn.expired_batches.fetch_add(1, Ordering::Relaxed);
+            return Ok(());
+        }
+
+        let write = async {
+            batch.write_to(&mut self.stream, sequence).await?;
+            // TLS records may be left buffered by the write
+            self.stream.flush().await
+        };
+        tokio::time::timeout(self.write_timeout, write)
+            .await
+            .unwrap_or_else(|_| Err(io::Error::new(io::ErrorKind::TimedOut, "write timed out")))?;
+
+        conn.sent_batches.fetch_add(1, Ordering::Relaxed);
+        conn.sent_bytes
+            .fetch_add(batch.len() as u64, Ordering::Relaxed);
+        *conn.stalled_since.lock().unwrap_or_else(|e| e.into_inner()) = None;
+
+        Ok(())
+    }
+}
+
+/// Reads the acks of a connection until it's closed, its writer delivering the batches
+/// they release.
+pub(super) async fn read_acks(
+    conns: Arc<RwLock<ConnectionMap>>,
+    conn: Weak<Connection>,
+    mut stream: ReadHalf,
+) {
+    let mut frame = [0; ACK_BATCH_BYTE_SIZE];
+    let mut filled = 0;
+    loop {
+        // the connection is only referenced by the map, the timeout lets the task notice
+        // it's gone
+        match tokio::time::timeout(ACK_READ_TIMEOUT, stream.read(&mut frame[filled..])).await {
+            Ok(Ok(0)) => break,
+            Ok(Ok(read)) => filled += read,
+            Ok(Err(_)) => break,
+            Err(_) => {}
+        }
+
+        let Some(conn) = conn
+            .upgrade()
+            .filter(|conn| !conn.closed.load(Ordering::Relaxed))
+        else {
+            break;
+        };
+        if filled < frame.len() {
+            continue;
+        }
+        filled = 0;
+
+        match parse_ack(&frame[2 * HEADER_BYTE_SIZE..]) {
+            Some(sequence) => conn.ack(sequence),
+            None => {
+                error!("Error handling the acks of {}: invalid ack", conn.label());
+
+                conn.closed.store(true, Ordering::Relaxed);
+                remove_conn(&conns, &conn);
+                break;
+            }
+        }
+    }
+}
+
+/// Closes the connections the writers made no progress on for longer than `timeout`,
+/// which fails the write their writer waits on and lets it release their queued batches.
+pub(super) fn spawn_watchdog(
+    conns: Arc<RwLock<ConnectionMap>>,
+    timeout: Duration,
+    hung_connections: Weak<AtomicU64>,
+) {
+    let interval = (timeout / 4).max(Duration::from_millis(10));
+
+    thread::spawn(move || loop {
+        thread::sleep(interval);
+        let Some(hung_connections) = hung_connections.upgrade() else {
+            break;
+        };
+
+        let hung = conns
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .filter(|conn| conn.hung(timeout))
+            .cloned()
+            .collect::<Vec<_>>();
+        for conn in hung {
+            hung_connections.fetch_add(1, Ordering::Relaxed);
+            warn!(
+                "No batch written to {} for more than {:?}, disconnecting",
+                conn.label(),
+                timeout
+            );
+
+            conn.closed.store(true, Ordering::Relaxed);
+            if let Some(Err(e)) = conn.closer.as_ref().map(Closer::close) {
+                warn!("Error closing {}: {}", conn.label(), e);
+            }
+            remove_conn(&conns, &conn);
+        }
+    });
+}
@@ -0,0 +1,298 @@
+use futures_util::{SinkExt, StreamExt};
+use log::{info, warn};
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::runtime::Runtime;
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tokio_tungstenite::tungstenite::Message;
+
+use super::{Publisher, SubscriberInfo};
+use crate::compression::CompressionPolicy;
+use crate::errors::GeyserError;
+use crate::handshake::SCHEMA_VERSION_V1;
+
+/// How long a new connection is given to complete the WebSocket upgrade.
+const WS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+type Clients = Arc<Mutex<HashMap<String, Arc<Client>>>>;
+
+/// A WebSocket subscriber, written to by its own task.
+struct Client {
+    peer: SocketAddr,
+    queue: mpsc::Sender<Vec<u8>>,
+    // bytes queued and not written yet
+    pending_bytes: AtomicUsize,
+    sent_batches: AtomicU64,
+    sent_bytes: AtomicU64,
+    dropped_batches: AtomicU64,
+}
+
+impl Client {
+    fn info(&self, id: &str) -> SubscriberInfo {
+        SubscriberInfo {
+            id: id.to_string(),
+            name: None,
+            labels: BTreeMap::new(),
+            group: None,
+            peer: Some(self.peer),
+            sent_batches: self.sent_batches.load(Ordering::Relaxed),
+            sent_bytes: self.sent_bytes.load(Ordering::Relaxed),
+            dropped_batches: self.dropped_batches.load(Ordering::Relaxed),
+            expired_batches: 0,
+            pending_batches: self.queue.max_capacity() - self.queue.capacity(),
+            filtered_messages: 0,
+            quota_exceeded: 0,
+        }
+    }
+}
+
+/// Publishes every message as a binary WebSocket frame, for browsers and tools without a
+/// parser of the framed TCP protocol. Frames hold a single message, starting with its type
+/// prefix.
+///
+/// There is no handshake, subscribers get schema v1 messages. A subscriber with
+/// `buffer_size` messages queued misses the next ones until it catches up.
+pub struct WsSender {
+    clients: Clients,
+    local_addr: SocketAddr,
+    compression: Option<CompressionPolicy>,
+    // set by drain, no message is accepted afterwards
+    draining: AtomicBool,
+    // runs the acceptor and the subscriber tasks, stopped with the sender
+    _runtime: Runtime,
+}
+
+impl WsSender {
+    /// Serves WebSocket upgrades on `addr`, queuing up to `buffer_size` messages per
+    /// subscriber.
+    pub fn bind(addr: SocketAddr, buffer_size: usize) -> io::Result<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("geyser-ws")
+            .enable_all()
+            .build()?;
+
+        let listener = runtime.block_on(TcpListener::bind(addr))?;
+        let local_addr = listener.local_addr()?;
+        let clients = Clients::default();
+        runtime.spawn(accept(listener, clients.clone(), buffer_size.max(1)));
+
+        info!("WebSocket publisher bound to {}", local_addr);
+
+        Ok(WsSender {
+            clients,
+            local_addr,
+            compression: None,
+            draining: AtomicBool::new(false),
+            _runtime: runtime,
+        })
+    }
+
+    /// Compresses the messages matching the policy, consumers see the compressed flag of
+    /// the prefix.
+    pub fn with_compression(mut self, policy: CompressionPolicy) -> Self {
+        self.compression = Some(policy);
+
+        self
+    }
+}
+
+async fn accept(listener: TcpListener, clients: Clients, buffer_size: usize) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, peer)) => {
+                tokio::spawn(serve(stream, peer, clients.clone(), buffer_size));
+            }
+            Err(e) => warn!("WebSocket accept error: {}", e),
+        }
+    }
+}
+
+/// Upgrades the connection and writes the queued messages until the subscriber leaves.
+async fn serve(stream: TcpStream, peer: SocketAddr, clients: Clients, buffer_size: usize) {
+    let ws = tokio::time::timeout(
+        WS_HANDSHAKE_TIMEOUT,
+        tokio_tungstenite::accept_async(stream),
+    )
+    .await;
+    let ws = match ws {
+        Ok(Ok(ws)) => ws,
+        Ok(Err(e)) => {
+            info!("WebSocket handshake error from {}: {}", peer, e);
+            return;
+        }
+        Err(_) => {
+            info!("WebSocket handshake timeout from {}", peer);
+            return;
+        }
+    };
+
+    let (queue, mut queued) = mpsc::channel(buffer_size);
+    let client = Arc::new(Client {
+        peer,
+        queue,
+        pending_bytes: AtomicUsize::new(0),
+        sent_batches: AtomicU64::new(0),
+        sent_bytes: AtomicU64::new(0),
+        dropped_batches: AtomicU64::new(0),
+    });
+    let id = uuid::Uuid::new_v4().to_string();
+    if let Ok(mut clients) = clients.lock() {
+        clients.insert(id.clone(), client.clone());
+    }
+    info!("WebSocket subscriber {} connected from {}", id, peer);
+
+    let (mut outgoing, mut incoming) = ws.split();
+    loop {
+        tokio::select! {
+            message = queued.recv() => {
+                let message = match message {
+                    Some(message) => message,
+                    None => break,
+                };
+
+                let bytes = message.len();
+                let result = outgoing.send(Message::Binary(message)).await;
+                client.pending_bytes.fetch_sub(bytes, Ordering::Relaxed);
+                if let Err(e) = result {
+                    info!("WebSocket subscriber {} write error: {}", id, e);
+                    break;
+                }
+
+                client.sent_batches.fetch_add(1, Ordering::Relaxed);
+                client.sent_bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+            }
+            // pings are answered while reading, other frames from subscribers are ignored
+            frame = incoming.next() => match frame {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+
+    if let Ok(mut clients) = clients.lock() {
+        clients.remove(&id);
+    }
+    info!("WebSocket subscriber {} disconnected", id);
+}
+
+impl Publisher for WsSender {
+    fn publish_for_schema(
+        &self,
+        message: Vec<u8>,
+        schema_version: Option<u32>,
+    ) -> Result<(), GeyserError> {
+        if self.draining.load(Ordering::Relaxed) {
+            return Err(GeyserError::SenderDraining);
+        }
+
+        if schema_version.is_some_and(|version| version != SCHEMA_VERSION_V1) {
+            return Ok(());
+        }
+
+        let message = match &self.compression {
+            Some(policy) => policy.apply(message),
+            None => message,
+        };
+
+        let clients = self
+            .clients
+            .lock()
+            .map_err(|_| GeyserError::SenderLockError)?;
+        for client in clients.values() {
+            let bytes = message.len();
+            client.pending_bytes.fetch_add(bytes, Ordering::Relaxed);
+            match client.queue.try_send(message.clone()) {
+                Ok(()) => {}
+                Err(e) => {
+                    client.pending_bytes.fetch_sub(bytes, Ordering::Relaxed);
+                    // closed queues belong to subscribers being removed
+                    if let TrySendError::Full(_) = e {
+                        client.dropped_batches.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn has_subscribers_with_schema(&self, schema_version: u32) -> bool {
+        schema_version == SCHEMA_VERSION_V1
+            && self.clients.lock().is_ok_and(|clients| !clients.is_empty())
+    }
+
+    /// Messages are not kept for the clients to come.
+    fn needs_messages(&self) -> bool {
+        self.clients.lock().is_ok_and(|clients| !clients.is_empty())
+    }
+
+    /// Stops accepting new messages and waits up to `timeout` for the queued ones to be
+    /// written. Returns the number of bytes still queued.
+    fn drain(&self, timeout: Duration) -> Result<usize, GeyserError> {
+        let deadline = Instant::now() + timeout;
+        self.draining.store(true, Ordering::Relaxed);
+
+        loop {
+            let pending = self
+                .clients
+                .lock()
+                .map_err(|_| GeyserError::SenderLockError)?
+                .values()
+                .map(|client| client.pending_bytes.load(Ordering::Relaxed))
+                .sum::<usize>();
+
+            if pending == 0 || Instant::now() >= deadline {
+                return Ok(pending);
+            }
+
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    fn subscribers(&self) -> Result<Vec<SubscriberInfo>, GeyserError> {
+        let clients = self
+            .clients
+            .lock()
+            .map_err(|_| GeyserError::SenderLockError)?;
+
+        Ok(clients.iter().map(|(id, client)| client.info(id)).collect())
+    }
+
+    fn local_addr(&self) -> Option<SocketAddr> {
+        Some(self.local_addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ws_sender() {
+        let sender = WsSender::bind("127.0.0.1:9053".parse().unwrap(), 100).unwrap();
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let (mut ws, _) = tokio_tungstenite::connect_async("ws://127.0.0.1:9053")
+                .await
+                .unwrap();
+
+            // the subscriber is registered once the upgrade is done on the sender side
+            while sender.subscribers().unwrap().is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+
+            sender.publish(vec![1, 2, 3]).unwrap();
+
+            let frame = ws.next().await.unwrap().unwrap();
+            assert_eq!(frame, Message::Binary(vec![1, 2, 3]));
+        });
+    }
+}
@@ -0,0 +1,128 @@
+use log::info;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::Publisher;
+use crate::compression::CompressionPolicy;
+use crate::errors::GeyserError;
+use crate::handshake::SCHEMA_VERSION_V1;
+
+/// Publishes every message as a single frame on a ZeroMQ PUB socket, for SUB consumers that
+/// don't speak the framed TCP protocol. Messages start with their type prefix, so consumers
+/// can subscribe to a topic of one byte to get a single message type. With compression, a
+/// compressed message has `COMPRESSED_FLAG` set on its prefix, a consumer wanting type `p`
+/// has to subscribe to both `p` and `p | COMPRESSED_FLAG`.
+///
+/// There is no handshake, subscribers get schema v1 messages. A subscriber falling behind by
+/// more than the high water mark misses messages, the PUB socket never blocks.
+pub struct ZmqSender {
+    socket: Mutex<zmq::Socket>,
+    // kept for the lifetime of the socket
+    _context: zmq::Context,
+    compression: Option<CompressionPolicy>,
+    // set by drain, no message is accepted afterwards
+    draining: AtomicBool,
+}
+
+impl ZmqSender {
+    /// Binds a PUB socket to `endpoint`, e.g. "tcp://0.0.0.0:8800" or "tcp://[::]:8800",
+    /// queuing up to `send_hwm` messages per subscriber.
+    pub fn bind(endpoint: &str, send_hwm: i32) -> io::Result<Self> {
+        let context = zmq::Context::new();
+        let socket = context.socket(zmq::PUB)?;
+        socket.set_sndhwm(send_hwm)?;
+        // IPv6 endpoints are refused otherwise, IPv4 ones still work
+        socket.set_ipv6(true)?;
+        socket.bind(endpoint)?;
+
+        info!("ZMQ publisher bound to {}", endpoint);
+
+        Ok(ZmqSender {
+            socket: Mutex::new(socket),
+            _context: context,
+            compression: None,
+            draining: AtomicBool::new(false),
+        })
+    }
+
+    /// Compresses the messages matching the policy, their prefix then has `COMPRESSED_FLAG`
+    /// set, 0x80, changing the topic they're published on, see `ZmqSender`.
+    pub fn with_compression(mut self, policy: CompressionPolicy) -> Self {
+        self.compression = Some(policy);
+
+        self
+    }
+}
+
+impl Publisher for ZmqSender {
+    fn publish_for_schema(
+        &self,
+        message: Vec<u8>,
+        schema_version: Option<u32>,
+    ) -> Result<(), GeyserError> {
+        if self.draining.load(Ordering::Relaxed) {
+            return Err(GeyserError::SenderDraining);
+        }
+
+        if schema_version.is_some_and(|version| version != SCHEMA_VERSION_V1) {
+            return Ok(());
+        }
+
+        let message = match &self.compression {
+            Some(policy) => policy.apply(message),
+            None => message,
+        };
+
+        let socket = self
+            .socket
+            .lock()
+            .map_err(|_| GeyserError::SenderLockError)?;
+        socket.send(message, 0).map_err(GeyserError::ZmqSend)
+    }
+
+    fn has_subscribers_with_schema(&self, schema_version: u32) -> bool {
+        schema_version == SCHEMA_VERSION_V1
+    }
+
+    /// Stops accepting new messages and gives the queued ones up to `timeout` to be sent
+    /// when the socket is closed. ZeroMQ doesn't tell how much is left, 0 is returned.
+    fn drain(&self, timeout: Duration) -> Result<usize, GeyserError> {
+        self.draining.store(true, Ordering::Relaxed);
+
+        let socket = self
+            .socket
+            .lock()
+            .map_err(|_| GeyserError::SenderLockError)?;
+        socket
+            .set_linger(timeout.as_millis().min(i32::MAX as u128) as i32)
+            .map_err(GeyserError::ZmqSend)?;
+
+        Ok(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zmq_sender() {
+        let sender = ZmqSender::bind("tcp://127.0.0.1:9052", 100).unwrap();
+
+        let context = zmq::Context::new();
+        let subscriber = context.socket(zmq::SUB).unwrap();
+        subscriber.connect("tcp://127.0.0.1:9052").unwrap();
+        subscriber.set_subscribe(&[1]).unwrap();
+        subscriber.set_rcvtimeo(1000).unwrap();
+
+        // the subscription takes a moment to reach the publisher
+        std::thread::sleep(Duration::from_millis(200));
+
+        sender.publish(vec![0, 1, 2]).unwrap();
+        sender.publish(vec![1, 2, 3]).unwrap();
+
+        assert_eq!(subscriber.recv_bytes(0).unwrap(), vec![1, 2, 3]);
+    }
+}
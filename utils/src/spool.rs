@@ -0,0 +1,205 @@
+//! Disk queue of received messages, letting `TcpReceiver` keep reading from the network while
+//! its callback falls behind.
+use std::{
+    collections::VecDeque,
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+pub const DEFAULT_SEGMENT_MAX_BYTES: u64 = 64 * 1024 * 1024;
+const HEADER_BYTE_SIZE: usize = 4;
+
+struct Segment {
+    id: u64,
+    // messages not read yet
+    messages: usize,
+}
+
+/// Messages are appended to numbered segment files and read back in order, a segment being
+/// deleted once fully read. Segments left over by a previous run are read first, in full: the
+/// messages already read from a segment before a restart are read again.
+pub struct DiskSpool {
+    dir: PathBuf,
+    segment_max_bytes: u64,
+    // oldest first
+    segments: VecDeque<Segment>,
+    // appends to the newest segment, with the bytes written so far
+    writer: Option<(BufWriter<File>, u64)>,
+    // reads the oldest segment
+    reader: Option<BufReader<File>>,
+    len: usize,
+}
+
+impl DiskSpool {
+    pub fn open(dir: PathBuf, segment_max_bytes: u64) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+
+        let mut ids = fs::read_dir(&dir)?
+            .filter_map(|entry| {
+                let name = entry.ok()?.file_name();
+                name.to_str()?
+                    .strip_prefix("spool-")?
+                    .strip_suffix(".bin")?
+                    .parse::<u64>()
+                    .ok()
+            })
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+
+        let mut segments = VecDeque::with_capacity(ids.len());
+        for id in ids {
+            let messages = count_messages(&segment_path(&dir, id))?;
+            segments.push_back(Segment { id, messages });
+        }
+
+        Ok(DiskSpool {
+            len: segments.iter().map(|s| s.messages).sum(),
+            dir,
+            segment_max_bytes,
+            segments,
+            writer: None,
+            reader: None,
+        })
+    }
+
+    /// Number of messages spooled and not read yet.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push(&mut self, message: &[u8]) -> io::Result<()> {
+        let writer = match &mut self.writer {
+            Some((writer, bytes)) if *bytes < self.segment_max_bytes => writer,
+            _ => {
+                // segments left over by a previous run may end with a partial message, so
+                // they are never appended to
+                let id = self.segments.back().map_or(0, |s| s.id + 1);
+                let file = OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(segment_path(&self.dir, id))?;
+
+                if let Some((mut writer, _)) = self.writer.take() {
+                    writer.flush()?;
+                }
+                self.segments.push_back(Segment { id, messages: 0 });
+
+                &mut self.writer.insert((BufWriter::new(file), 0)).0
+            }
+        };
+
+        writer.write_all(&(message.len() as u32).to_le_bytes())?;
+        writer.write_all(message)?;
+
+        if let Some((_, bytes)) = &mut self.writer {
+            *bytes += (HEADER_BYTE_SIZE + message.len()) as u64;
+        }
+        if let Some(segment) = self.segments.back_mut() {
+            segment.messages += 1;
+        }
+        self.len += 1;
+
+        Ok(())
+    }
+
+    /// The oldest message, None if the spool is empty.
+    pub fn pop(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let segment = match self.segments.front() {
+            Some(segment) if self.len > 0 => segment,
+            _ => return Ok(None),
+        };
+        let id = segment.id;
+        let writing = self.segments.len() == 1 && self.writer.is_some();
+
+        // the buffered writes have to reach the file before they are read
+        if writing {
+            if let Some((writer, _)) = &mut self.writer {
+                writer.flush()?;
+            }
+        }
+
+        let reader = match &mut self.reader {
+            Some(reader) => reader,
+            None => self
+                .reader
+                .insert(BufReader::new(File::open(segment_path(&self.dir, id))?)),
+        };
+
+        let mut header = [0; HEADER_BYTE_SIZE];
+        reader.read_exact(&mut header)?;
+        let mut message = vec![0; u32::from_le_bytes(header) as usize];
+        reader.read_exact(&mut message)?;
+
+        self.len -= 1;
+        if let Some(segment) = self.segments.front_mut() {
+            segment.messages -= 1;
+
+            if segment.messages == 0 {
+                self.segments.pop_front();
+                self.reader = None;
+                if writing {
+                    self.writer = None;
+                }
+                fs::remove_file(segment_path(&self.dir, id))?;
+            }
+        }
+
+        Ok(Some(message))
+    }
+}
+
+fn segment_path(dir: &Path, id: u64) -> PathBuf {
+    dir.join(format!("spool-{:010}.bin", id))
+}
+
+/// Complete messages in a segment, a partially written one at the end is ignored.
+fn count_messages(path: &Path) -> io::Result<usize> {
+    let data = fs::read(path)?;
+
+    let mut messages = 0;
+    let mut i = 0;
+    while i + HEADER_BYTE_SIZE <= data.len() {
+        let size = u32::from_le_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]) as usize;
+        i += HEADER_BYTE_SIZE + size;
+        if i > data.len() {
+            break;
+        }
+        messages += 1;
+    }
+
+    Ok(messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spool_keeps_order_across_segments_and_restarts() {
+        let dir = std::env::temp_dir().join(format!("spool-{}", uuid::Uuid::new_v4()));
+
+        let mut spool = DiskSpool::open(dir.clone(), 16).unwrap();
+        for i in 0..5u8 {
+            spool.push(&[i; 10]).unwrap();
+        }
+        assert_eq!(spool.pop().unwrap(), Some(vec![0; 10]));
+        drop(spool);
+
+        // the first segment held two messages, both are read again
+        let mut spool = DiskSpool::open(dir.clone(), 16).unwrap();
+        assert_eq!(spool.len(), 5);
+        spool.push(&[5; 10]).unwrap();
+        for i in 0..6u8 {
+            assert_eq!(spool.pop().unwrap(), Some(vec![i; 10]));
+        }
+        assert_eq!(spool.pop().unwrap(), None);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
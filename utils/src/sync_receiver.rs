@@ -0,0 +1,311 @@
+//! Blocking receiver on `std::net`, for tools and tests which don't run a tokio runtime. It
+//! reads the same framing as `TcpReceiver`, from a single connection: it doesn't reconnect,
+//! nor speaks TLS or decrypts batches. A batch read in part when a read times out is kept,
+//! and completed by the next call.
+use std::collections::VecDeque;
+use std::io::{self, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+use crate::chunk::Reassembler;
+use crate::event::{decode_event, DecodeError, Event};
+use crate::handshake::{
+    ack_message, ClientHello, ServerHello, PROTOCOL_VERSION_V1, PROTOCOL_VERSION_V3,
+};
+use crate::receiver::{FrameHeader, TcpReceiver, DEFAULT_MAX_BATCH_BYTES};
+use crate::sender::TcpBuffer;
+
+pub struct SyncTcpReceiver {
+    stream: BufReader<TcpStream>,
+    protocol_version: u32,
+    acks: bool,
+    // messages of the last batch read not returned yet
+    messages: VecDeque<Vec<u8>>,
+    next_sequence: u64,
+    missed_batches: u64,
+    reassembler: Reassembler,
+    max_batch_bytes: usize,
+    // header of the batch being read, once read in full
+    header: Option<FrameHeader>,
+    // bytes read so far of the header or body being read, kept across timed out reads
+    partial: Vec<u8>,
+    // set once reading failed, ending the iterator
+    closed: bool,
+}
+
+impl SyncTcpReceiver {
+    /// Connects to the sender, answering its `ServerHello`, if it sends one, with
+    /// `client_hello`, or a default one selecting the newest protocol version both sides
+    /// speak.
+    pub fn connect(
+        addr: SocketAddr,
+        connect_timeout: Duration,
+        client_hello: Option<ClientHello>,
+    ) -> io::Result<Self> {
+        let stream = TcpStream::connect_timeout(&addr, connect_timeout)?;
+        let mut receiver = SyncTcpReceiver {
+            stream: BufReader::new(stream),
+            protocol_version: PROTOCOL_VERSION_V1,
+            acks: false,
+            messages: VecDeque::new(),
+            next_sequence: 0,
+            missed_batches: 0,
+            reassembler: Reassembler::default(),
+            max_batch_bytes: DEFAULT_MAX_BATCH_BYTES,
+            header: None,
+            partial: Vec::new(),
+            closed: false,
+        };
+
+        // the server hello, if the sender sends one, comes first and is framed as V1
+        let (body, _) = receiver.read_batch()?;
+        let messages = TcpReceiver::split_batch(&body)?;
+        let hello = messages.first().and_then(|m| ServerHello::from_message(m));
+        if let Some(hello) = &hello {
+            let mut client_hello = client_hello.unwrap_or_default();
+            let protocol_version = *client_hello
+                .protocol_version
+                .get_or_insert(TcpReceiver::check_server_hello(hello)?);
+
+            let mut buffer = TcpBuffer::new(1);
+            buffer.append(client_hello.to_message());
+            receiver.stream.get_mut().write_all(&buffer.flush_data())?;
+
+            receiver.protocol_version = protocol_version;
+            receiver.acks = client_hello.acks && protocol_version >= PROTOCOL_VERSION_V3;
+        }
+        // the hello is consumed here, like `TcpReceiver` does
+        receiver.messages.extend(
+            messages
+                .into_iter()
+                .skip(hello.is_some() as usize)
+                .map(<[u8]>::to_vec),
+        );
+
+        Ok(receiver)
+    }
+
+    /// Makes `recv` fail with `WouldBlock` or `TimedOut` if nothing is read for `timeout`. The
+    /// receiver stays usable, `recv` can be called again to wait further. The iterator ends on
+    /// the timeout though.
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.stream.get_ref().set_read_timeout(timeout)
+    }
+
+    /// See `TcpReceiver::with_max_batch_bytes`.
+    pub fn set_max_batch_bytes(&mut self, max_bytes: usize) {
+        self.max_batch_bytes = max_bytes;
+    }
+
+    /// See `TcpReceiver::missed_batches`.
+    pub fn missed_batches(&self) -> u64 {
+        self.missed_batches
+    }
+
+    /// The next message, as `TcpReceiver` hands them to its callback: data messages only,
+    /// chunked ones put back together.
+    pub fn recv(&mut self) -> io::Result<Vec<u8>> {
+        loop {
+            while let Some(message) = self.messages.pop_front() {
+                if let Some(message) = self.reassembler.push(message) {
+                    return Ok(message);
+                }
+            }
+
+            let (body, sequence) = self.read_batch()?;
+            if let Some(sequence) = sequence {
+                self.missed_batches += sequence.saturating_sub(self.next_sequence);
+                self.next_sequence = sequence + 1;
+            }
+            self.messages.extend(
+                TcpReceiver::split_batch(&body)?
+                    .into_iter()
+                    .map(<[u8]>::to_vec),
+            );
+
+            if let Some(sequence) = sequence.filter(|_| self.acks) {
+                let mut buffer = TcpBuffer::new(1);
+                buffer.append(ack_message(sequence));
+                self.stream.get_mut().write_all(&buffer.flush_data())?;
+            }
+        }
+    }
+
+    /// Reads a batch framed as the negotiated protocol version, see `TcpReceiver::read_batch`.
+    /// What was read of it before an error stays in `partial`, to resume from there.
+    fn read_batch(&mut self) -> io::Result<(Vec<u8>, Option<u64>)> {
+        let header = match self.header.take() {
+            Some(header) => header,
+            None => {
+                self.fill(FrameHeader::len(self.protocol_version))?;
+                let header = FrameHeader::parse(&self.partial, self.max_batch_bytes)?;
+                self.partial.clear();
+                header
+            }
+        };
+
+        if let Err(e) = self.fill(header.body_len) {
+            self.header = Some(header);
+            return Err(e);
+        }
+        let body = std::mem::take(&mut self.partial);
+        header.check(&body)?;
+
+        Ok((body, header.sequence))
+    }
+
+    /// Reads into `partial` until it holds `len` bytes.
+    fn fill(&mut self, len: usize) -> io::Result<()> {
+        while self.partial.len() < len {
+            let start = self.partial.len();
+            self.partial.resize(len, 0);
+            let read = self.stream.read(&mut self.partial[start..]);
+            self.partial
+                .truncate(start + read.as_ref().map_or(0, |read| *read));
+
+            match read {
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The decoded events, skipping the messages without typed decoding such as diagnostics.
+/// Ends once the connection is closed, after yielding the error if it failed.
+impl Iterator for SyncTcpReceiver {
+    type Item = io::Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.closed {
+            let message = match self.recv() {
+                Ok(message) => message,
+                Err(e) => {
+                    self.closed = true;
+                    return (e.kind() != io::ErrorKind::UnexpectedEof).then_some(Err(e));
+                }
+            };
+
+            match decode_event(&message) {
+                Ok(event) => return Some(Ok(event)),
+                Err(DecodeError::UnsupportedPrefix(_)) => continue,
+                Err(e) => return Some(Err(io::Error::new(io::ErrorKind::InvalidData, e))),
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::{SlotEvent, SlotStatus};
+    use crate::flatbuffer::{
+        consts::BYTE_PREFIX_SLOT,
+        slot_generated::slot::{Slot, SlotArgs, Status},
+    };
+    use crate::sender::TcpSender;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Instant;
+
+    #[test]
+    fn test_sync_receiver() {
+        let sender = TcpSender::new(10, false, 0)
+            .with_handshake(&ServerHello::new("test", "test"))
+            .with_handshake_timeout(Duration::from_secs(1));
+        sender.bind(9077, 100).unwrap();
+
+        let mut receiver = SyncTcpReceiver::connect(
+            "127.0.0.1:9077".parse().unwrap(),
+            Duration::from_secs(1),
+            None,
+        )
+        .unwrap();
+        receiver
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .unwrap();
+        // the sender adds the connection once it has read the client hello
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while sender.subscribers().unwrap().is_empty() {
+            assert!(Instant::now() < deadline, "the receiver never subscribed");
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let mut builder = flatbuffers::FlatBufferBuilder::new();
+        let slot = Slot::create(
+            &mut builder,
+            &SlotArgs {
+                slot: 42,
+                status: Status::Confirmed,
+                parent: None,
+            },
+        );
+        builder.finish(slot, None);
+        let mut msg = vec![BYTE_PREFIX_SLOT];
+        msg.extend_from_slice(builder.finished_data());
+        sender.publish(msg).unwrap();
+
+        assert_eq!(
+            receiver.next().unwrap().unwrap(),
+            Event::Slot(SlotEvent {
+                slot: 42,
+                status: SlotStatus::Confirmed,
+                parent: None,
+            })
+        );
+        assert_eq!(receiver.missed_batches(), 0);
+
+        // nothing else was published, the read times out
+        assert!(receiver.next().unwrap().is_err());
+        assert!(receiver.next().is_none());
+    }
+
+    #[test]
+    fn test_resumes_after_timeout() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:9082").unwrap();
+        let (sent, written) = mpsc::channel::<()>();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let frame = |message: &[u8]| {
+                let mut buffer = TcpBuffer::new(1);
+                buffer.append(message.to_vec());
+                buffer.flush_data()
+            };
+            stream.write_all(&frame(b"first")).unwrap();
+
+            // the second batch arrives in two parts, with a timeout in between
+            let second = frame(b"second");
+            stream.write_all(&second[..6]).unwrap();
+            written.recv().unwrap();
+            stream.write_all(&second[6..]).unwrap();
+            written.recv().unwrap();
+        });
+
+        let mut receiver = SyncTcpReceiver::connect(
+            "127.0.0.1:9082".parse().unwrap(),
+            Duration::from_secs(1),
+            None,
+        )
+        .unwrap();
+        receiver
+            .set_read_timeout(Some(Duration::from_millis(200)))
+            .unwrap();
+
+        assert_eq!(receiver.recv().unwrap(), b"first");
+        let e = receiver.recv().unwrap_err();
+        assert!(matches!(
+            e.kind(),
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+        ));
+
+        sent.send(()).unwrap();
+        assert_eq!(receiver.recv().unwrap(), b"second");
+        sent.send(()).unwrap();
+    }
+}
@@ -0,0 +1,67 @@
+//! `TcpReceiver` running on a thread of its own, its messages read from a blocking queue.
+//! Used by the language bindings, whose callers don't run a tokio runtime.
+use crate::receiver::{Callback, TcpReceiver};
+use std::{
+    io,
+    net::SocketAddr,
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+use tokio::sync::oneshot;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub(crate) struct ThreadedReceiver {
+    messages: Receiver<Vec<u8>>,
+    // stops the receiver thread once dropped
+    _shutdown: oneshot::Sender<()>,
+}
+
+impl ThreadedReceiver {
+    /// Connects to the sender, reconnecting when the connection drops. Reading from the
+    /// socket waits once `queue_size` messages are queued.
+    pub(crate) fn spawn(
+        addr: SocketAddr,
+        reconnect_interval: Duration,
+        queue_size: usize,
+    ) -> io::Result<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()?;
+
+        let (sender, messages) = mpsc::sync_channel(queue_size);
+        let callback: Callback = Box::new(move |message| {
+            let sender = sender.clone();
+            Box::pin(async move {
+                let _ = tokio::task::block_in_place(|| sender.send(message));
+            })
+        });
+        let receiver = TcpReceiver::new(callback, CONNECT_TIMEOUT, reconnect_interval);
+
+        let (shutdown, stopped) = oneshot::channel::<()>();
+        thread::spawn(move || {
+            runtime.block_on(async move {
+                tokio::select! {
+                    _ = receiver.connect(addr) => {}
+                    _ = stopped => {}
+                }
+            })
+        });
+
+        Ok(ThreadedReceiver {
+            messages,
+            _shutdown: shutdown,
+        })
+    }
+
+    pub(crate) fn recv_timeout(&mut self, timeout: Duration) -> Result<Vec<u8>, RecvTimeoutError> {
+        self.messages.recv_timeout(timeout)
+    }
+
+    #[cfg(feature = "ffi")]
+    pub(crate) fn recv(&mut self) -> Result<Vec<u8>, mpsc::RecvError> {
+        self.messages.recv()
+    }
+}
@@ -0,0 +1,114 @@
+//! TLS configs of the sender and the receiver, loaded from PEM files.
+use rustls::{
+    server::AllowAnyAuthenticatedClient, Certificate, ClientConfig, PrivateKey, RootCertStore,
+    ServerConfig,
+};
+use rustls_pemfile::Item;
+use std::{
+    fs::File,
+    io::{self, BufReader},
+    path::Path,
+    sync::Arc,
+};
+
+/// Server config presenting the certificate chain of `cert_path`, with the private key of
+/// `key_path` (PKCS#8, PKCS#1 or SEC1). If `client_ca_path` is set, clients have to present
+/// a certificate signed by one of its certificates.
+pub fn server_config(
+    cert_path: &Path,
+    key_path: &Path,
+    client_ca_path: Option<&Path>,
+) -> io::Result<Arc<ServerConfig>> {
+    let certs = read_certs(cert_path)?;
+    let key = read_key(key_path)?;
+
+    let builder = ServerConfig::builder().with_safe_defaults();
+    let builder = match client_ca_path {
+        Some(path) => builder
+            .with_client_cert_verifier(AllowAnyAuthenticatedClient::new(read_roots(path)?).boxed()),
+        None => builder.with_no_client_auth(),
+    };
+    let config = builder
+        .with_single_cert(certs, key)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    Ok(Arc::new(config))
+}
+
+/// Client config trusting the certificates of `ca_path`, or the system's ones if not set.
+/// If `identity` is set, the certificate chain and private key paths are presented to a
+/// sender requiring client certificates.
+pub fn client_config(
+    ca_path: Option<&Path>,
+    identity: Option<(&Path, &Path)>,
+) -> io::Result<Arc<ClientConfig>> {
+    let roots = match ca_path {
+        Some(path) => read_roots(path)?,
+        None => {
+            let mut roots = RootCertStore::empty();
+            for cert in rustls_native_certs::load_native_certs()? {
+                roots
+                    .add(&Certificate(cert.0))
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            }
+            roots
+        }
+    };
+
+    let builder = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots);
+    let config = match identity {
+        Some((cert_path, key_path)) => builder
+            .with_client_auth_cert(read_certs(cert_path)?, read_key(key_path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?,
+        None => builder.with_no_client_auth(),
+    };
+
+    Ok(Arc::new(config))
+}
+
+/// Common name of the subject of a DER certificate.
+pub(crate) fn common_name(cert: &Certificate) -> Option<String> {
+    let (_, cert) = x509_parser::parse_x509_certificate(&cert.0).ok()?;
+    let common_name = cert.subject().iter_common_name().next()?;
+
+    common_name.as_str().ok().map(str::to_string)
+}
+
+fn read_roots(path: &Path) -> io::Result<RootCertStore> {
+    let mut roots = RootCertStore::empty();
+    for cert in read_certs(path)? {
+        roots
+            .add(&cert)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    }
+
+    Ok(roots)
+}
+
+fn read_certs(path: &Path) -> io::Result<Vec<Certificate>> {
+    let certs = rustls_pemfile::certs(&mut BufReader::new(File::open(path)?))?;
+    if certs.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("no certificate in {}", path.display()),
+        ));
+    }
+
+    Ok(certs.into_iter().map(Certificate).collect())
+}
+
+fn read_key(path: &Path) -> io::Result<PrivateKey> {
+    let mut reader = BufReader::new(File::open(path)?);
+    while let Some(item) = rustls_pemfile::read_one(&mut reader)? {
+        if let Item::PKCS8Key(key) | Item::RSAKey(key) | Item::ECKey(key) = item {
+            return Ok(PrivateKey(key));
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("no private key in {}", path.display()),
+    ))
+}
@@ -0,0 +1,237 @@
+//! Write-ahead log of the batches published by `TcpSender`, kept on disk for consumers which
+//! were down for longer than their buffer on the sender allows, and for offline replay.
+//!
+//! Batches are appended to numbered segment files as they are sent to subscribers, framed
+//! as `PROTOCOL_VERSION_V1`, so a segment reads like a stream from a sender with no handshake.
+use serde::Deserialize;
+use std::{
+    collections::VecDeque,
+    convert::TryInto,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+pub const DEFAULT_SEGMENT_MAX_BYTES: u64 = 256 * 1024 * 1024;
+const HEADER_BYTE_SIZE: usize = 4;
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct WalConfig {
+    pub dir: PathBuf,
+    /// Size past which a new segment is started, `DEFAULT_SEGMENT_MAX_BYTES` if not set
+    #[serde(default)]
+    pub segment_bytes: Option<u64>,
+    /// Oldest segments are deleted once the log is larger than this
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+    /// Segments last written to longer ago than this are deleted
+    #[serde(default)]
+    pub max_age_secs: Option<u64>,
+}
+
+struct Segment {
+    id: u64,
+    bytes: u64,
+    modified: SystemTime,
+}
+
+/// Appends batches to the newest segment, enforcing the retention whenever a segment is
+/// started. Writes are not synced to disk, a crash of the host may lose the last ones.
+pub struct WriteAheadLog {
+    config: WalConfig,
+    // oldest first, the newest one being written to
+    segments: VecDeque<Segment>,
+    file: Option<File>,
+}
+
+impl WriteAheadLog {
+    /// Opens the log in `config.dir`, segments left by a previous run are kept and new
+    /// batches go to a new segment.
+    pub fn open(config: WalConfig) -> io::Result<Self> {
+        fs::create_dir_all(&config.dir)?;
+
+        let mut segments = segment_ids(&config.dir)?
+            .into_iter()
+            .map(|id| {
+                let metadata = fs::metadata(segment_path(&config.dir, id))?;
+                Ok(Segment {
+                    id,
+                    bytes: metadata.len(),
+                    modified: metadata.modified()?,
+                })
+            })
+            .collect::<io::Result<VecDeque<_>>>()?;
+        segments.make_contiguous().sort_unstable_by_key(|s| s.id);
+
+        Ok(WriteAheadLog {
+            config,
+            segments,
+            file: None,
+        })
+    }
+
+    /// Appends an encoded batch.
+    pub fn append(&mut self, batch: &[u8]) -> io::Result<()> {
+        let segment_max_bytes = self
+            .config
+            .segment_bytes
+            .unwrap_or(DEFAULT_SEGMENT_MAX_BYTES);
+
+        let file = match &mut self.file {
+            Some(file)
+                if self
+                    .segments
+                    .back()
+                    .is_some_and(|s| s.bytes < segment_max_bytes) =>
+            {
+                file
+            }
+            _ => {
+                let id = self.segments.back().map_or(0, |s| s.id + 1);
+                let file = OpenOptions::new()
+                    .append(true)
+                    .create_new(true)
+                    .open(segment_path(&self.config.dir, id))?;
+                self.segments.push_back(Segment {
+                    id,
+                    bytes: 0,
+                    modified: SystemTime::now(),
+                });
+                self.enforce_retention()?;
+
+                self.file.insert(file)
+            }
+        };
+
+        file.write_all(batch)?;
+
+        if let Some(segment) = self.segments.back_mut() {
+            segment.bytes += batch.len() as u64;
+            segment.modified = SystemTime::now();
+        }
+
+        Ok(())
+    }
+
+    /// Deletes the oldest segments past the size or age limits, never the one written to.
+    fn enforce_retention(&mut self) -> io::Result<()> {
+        let max_age = self.config.max_age_secs.map(Duration::from_secs);
+        let mut total_bytes = self.segments.iter().map(|s| s.bytes).sum::<u64>();
+
+        while self.segments.len() > 1 {
+            let oldest = &self.segments[0];
+            let too_large = self.config.max_bytes.is_some_and(|max| total_bytes > max);
+            let too_old = max_age.is_some_and(|max_age| {
+                oldest
+                    .modified
+                    .elapsed()
+                    .is_ok_and(|elapsed| elapsed > max_age)
+            });
+            if !too_large && !too_old {
+                break;
+            }
+
+            total_bytes -= oldest.bytes;
+            fs::remove_file(segment_path(&self.config.dir, oldest.id))?;
+            self.segments.pop_front();
+        }
+
+        Ok(())
+    }
+}
+
+/// Segments of the log in `dir`, oldest first.
+pub fn segments(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut ids = segment_ids(dir)?;
+    ids.sort_unstable();
+
+    Ok(ids.into_iter().map(|id| segment_path(dir, id)).collect())
+}
+
+/// Messages of the batches in a segment, a partially written batch at the end is ignored.
+pub fn read_segment(path: &Path) -> io::Result<Vec<Vec<u8>>> {
+    let data = fs::read(path)?;
+    let mut messages = Vec::new();
+
+    let mut i = 0;
+    while let Some(size) = read_size(&data, i) {
+        let end = i + HEADER_BYTE_SIZE + size;
+        if end > data.len() {
+            break;
+        }
+
+        let mut j = i + HEADER_BYTE_SIZE;
+        while let Some(size) = read_size(&data[..end], j) {
+            j += HEADER_BYTE_SIZE;
+            messages.push(data[j..(j + size).min(end)].to_vec());
+            j += size;
+        }
+        i = end;
+    }
+
+    Ok(messages)
+}
+
+fn read_size(data: &[u8], i: usize) -> Option<usize> {
+    let bytes = data.get(i..i + HEADER_BYTE_SIZE)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?) as usize)
+}
+
+fn segment_ids(dir: &Path) -> io::Result<Vec<u64>> {
+    Ok(fs::read_dir(dir)?
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name();
+            name.to_str()?
+                .strip_prefix("wal-")?
+                .strip_suffix(".bin")?
+                .parse::<u64>()
+                .ok()
+        })
+        .collect())
+}
+
+fn segment_path(dir: &Path, id: u64) -> PathBuf {
+    dir.join(format!("wal-{:010}.bin", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sender::TcpBuffer;
+
+    #[test]
+    fn test_wal_rotates_and_enforces_retention() {
+        let dir = std::env::temp_dir().join(format!("wal-{}", uuid::Uuid::new_v4()));
+        let config = WalConfig {
+            dir: dir.clone(),
+            segment_bytes: Some(20),
+            max_bytes: Some(40),
+            max_age_secs: None,
+        };
+
+        let mut wal = WriteAheadLog::open(config.clone()).unwrap();
+        for i in 0..5u8 {
+            let mut buffer = TcpBuffer::new(2);
+            buffer.append(vec![i; 4]);
+            buffer.append(vec![i; 4]);
+            wal.append(&buffer.flush_data()).unwrap();
+        }
+        drop(wal);
+
+        // a batch of 20 bytes per segment, the oldest two went past 40 bytes
+        let segments = segments(&dir).unwrap();
+        assert_eq!(segments.len(), 3);
+        assert_eq!(
+            read_segment(&segments[0]).unwrap(),
+            vec![vec![2; 4], vec![2; 4]]
+        );
+
+        // a new run keeps the segments and starts another one
+        let mut wal = WriteAheadLog::open(config).unwrap();
+        wal.append(&TcpBuffer::new(1).flush_data()).unwrap();
+        assert_eq!(super::segments(&dir).unwrap().len(), 3);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}